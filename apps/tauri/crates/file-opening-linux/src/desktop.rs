@@ -0,0 +1,490 @@
+//! # Desktop entries
+//!
+//! Parses the subset of the freedesktop.org Desktop Entry spec needed to list
+//! and launch applications: `Name`, `Exec`, `MimeType` and the visibility
+//! flags. Desktop file IDs follow the spec, so `kde4/okular.desktop` under an
+//! `applications` directory has the id `kde4-okular.desktop`.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::xdg;
+
+#[derive(Debug, Clone)]
+pub(crate) struct DesktopEntry {
+	pub id: String,
+	pub path: PathBuf,
+	pub name: String,
+	pub exec: Option<String>,
+	pub icon: Option<String>,
+	pub mime_types: Vec<String>,
+	pub no_display: bool,
+	pub hidden: bool,
+}
+
+impl DesktopEntry {
+	/// Parses the `[Desktop Entry]` group, returning `None` for anything that
+	/// isn't a launchable application.
+	pub fn parse(id: &str, path: &Path, contents: &str) -> Option<Self> {
+		let mut in_entry_group = false;
+		let mut kind = None;
+		let mut entry = Self {
+			id: id.to_string(),
+			path: path.to_path_buf(),
+			name: String::new(),
+			exec: None,
+			icon: None,
+			mime_types: Vec::new(),
+			no_display: false,
+			hidden: false,
+		};
+
+		for line in contents.lines().map(str::trim) {
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+
+			if line.starts_with('[') {
+				in_entry_group = line == "[Desktop Entry]";
+				continue;
+			}
+
+			if !in_entry_group {
+				continue;
+			}
+
+			let Some((key, value)) = line.split_once('=') else {
+				continue;
+			};
+			let value = unescape(value.trim());
+
+			match key.trim() {
+				"Type" => kind = Some(value),
+				"Name" => entry.name = value,
+				"Exec" => entry.exec = Some(value),
+				"Icon" => entry.icon = Some(value),
+				"MimeType" => {
+					entry.mime_types = split_list(&value);
+				}
+				"NoDisplay" => entry.no_display = value == "true",
+				"Hidden" => entry.hidden = value == "true",
+				_ => {}
+			}
+		}
+
+		if kind.as_deref() != Some("Application") || entry.name.is_empty() {
+			return None;
+		}
+
+		Some(entry)
+	}
+
+	pub fn load(id: &str, path: &Path) -> Option<Self> {
+		let contents = fs::read_to_string(path).ok()?;
+		Self::parse(id, path, &contents)
+	}
+
+	pub fn supports(&self, mime: &str) -> bool {
+		self.mime_types.iter().any(|m| m == mime)
+	}
+
+	/// The binary `Exec` starts, with bare names looked up on `$PATH`.
+	pub fn executable(&self) -> Option<PathBuf> {
+		find_program(Path::new(
+			&tokenize(self.exec.as_deref()?)?.into_iter().next()?,
+		))
+	}
+
+	/// Expands the `Exec` line into an argv for the given targets.
+	///
+	/// Field codes are substituted per the spec (`%f`/`%F` paths, `%u`/`%U`
+	/// `file://` URIs, `%i`/`%c`/`%k` entry metadata); deprecated codes are
+	/// dropped. When the line takes no file argument at all the targets are
+	/// appended, matching GLib's behaviour for entries that forgot a code.
+	///
+	/// `args` are inserted just before the first file argument, so wrappers
+	/// such as `flatpak run app %U` forward them to the app rather than
+	/// interpreting them as their own options.
+	pub fn command_line(&self, targets: &[&Path], args: &[&str]) -> Option<Vec<String>> {
+		let tokens = tokenize(self.exec.as_deref()?)?;
+		let mut argv = Vec::with_capacity(tokens.len() + args.len() + targets.len());
+		let mut consumed_targets = false;
+		let extra = || args.iter().map(|arg| arg.to_string());
+
+		for token in tokens {
+			match token.as_str() {
+				"%F" | "%U" => {
+					if !consumed_targets {
+						argv.extend(extra());
+					}
+					if token == "%F" {
+						argv.extend(targets.iter().map(|t| t.to_string_lossy().to_string()));
+					} else {
+						argv.extend(targets.iter().map(|t| file_uri(t)));
+					}
+					consumed_targets = true;
+				}
+				"%i" => {
+					if let Some(icon) = &self.icon {
+						argv.push("--icon".to_string());
+						argv.push(icon.clone());
+					}
+				}
+				_ => {
+					let (expanded, used_target) =
+						self.expand_token(&token, targets.first().copied());
+					if used_target && !consumed_targets {
+						argv.extend(extra());
+					}
+					consumed_targets |= used_target;
+					if !expanded.is_empty() || !token.contains('%') {
+						argv.push(expanded);
+					}
+				}
+			}
+		}
+
+		if !consumed_targets {
+			argv.extend(extra());
+			argv.extend(targets.iter().map(|t| t.to_string_lossy().to_string()));
+		}
+
+		(!argv.is_empty()).then_some(argv)
+	}
+
+	fn expand_token(&self, token: &str, target: Option<&Path>) -> (String, bool) {
+		let mut out = String::with_capacity(token.len());
+		let mut used_target = false;
+		let mut chars = token.chars();
+
+		while let Some(c) = chars.next() {
+			if c != '%' {
+				out.push(c);
+				continue;
+			}
+
+			match chars.next() {
+				Some('%') => out.push('%'),
+				Some('f') | Some('F') => {
+					used_target = true;
+					if let Some(target) = target {
+						out.push_str(&target.to_string_lossy());
+					}
+				}
+				Some('u') | Some('U') => {
+					used_target = true;
+					if let Some(target) = target {
+						out.push_str(&file_uri(target));
+					}
+				}
+				Some('c') => out.push_str(&self.name),
+				Some('k') => out.push_str(&self.path.to_string_lossy()),
+				// %d, %D, %n, %N, %v, %m are deprecated and expand to nothing.
+				_ => {}
+			}
+		}
+
+		(out, used_target)
+	}
+}
+
+/// All installed applications, with earlier data dirs shadowing later ones.
+pub(crate) fn all() -> Vec<DesktopEntry> {
+	let mut seen = HashSet::new();
+	let mut entries = Vec::new();
+
+	for dir in xdg::data_dirs() {
+		let root = dir.join("applications");
+		let mut files = Vec::new();
+		collect_desktop_files(&root, &root, &mut files);
+
+		for (id, path) in files {
+			// The first dir to define an id wins even when its copy is
+			// Hidden=true; that's how users remove system-wide entries.
+			if !seen.insert(id.clone()) {
+				continue;
+			}
+
+			if let Some(entry) = DesktopEntry::load(&id, &path) {
+				if !entry.hidden {
+					entries.push(entry);
+				}
+			}
+		}
+	}
+
+	entries
+}
+
+/// Resolves a desktop file id to its entry.
+pub(crate) fn find(id: &str) -> Option<DesktopEntry> {
+	for dir in xdg::data_dirs() {
+		let root = dir.join("applications");
+
+		// Ids flatten subdirectories with '-', so try the direct file first and
+		// only walk the tree when that misses.
+		let direct = root.join(id);
+		if direct.is_file() {
+			return DesktopEntry::load(id, &direct).filter(|entry| !entry.hidden);
+		}
+
+		let mut files = Vec::new();
+		collect_desktop_files(&root, &root, &mut files);
+		if let Some((_, path)) = files.into_iter().find(|(file_id, _)| file_id == id) {
+			return DesktopEntry::load(id, &path).filter(|entry| !entry.hidden);
+		}
+	}
+
+	None
+}
+
+fn collect_desktop_files(root: &Path, dir: &Path, out: &mut Vec<(String, PathBuf)>) {
+	let Ok(read_dir) = fs::read_dir(dir) else {
+		return;
+	};
+
+	for entry in read_dir.flatten() {
+		let path = entry.path();
+
+		if path.is_dir() {
+			collect_desktop_files(root, &path, out);
+		} else if path.extension().is_some_and(|ext| ext == "desktop") {
+			if let Ok(relative) = path.strip_prefix(root) {
+				let id = relative.to_string_lossy().replace('/', "-");
+				out.push((id, path));
+			}
+		}
+	}
+}
+
+/// Splits a `;`-separated list value, ignoring the trailing separator.
+/// `program` if it's an absolute path to a file, or the first match for a
+/// bare name on `$PATH`.
+pub(crate) fn find_program(program: &Path) -> Option<PathBuf> {
+	if program.is_absolute() {
+		return program.is_file().then(|| program.to_path_buf());
+	}
+
+	std::env::split_paths(&std::env::var_os("PATH")?)
+		.map(|dir| dir.join(program))
+		.find(|candidate| candidate.is_file())
+}
+
+pub(crate) fn split_list(value: &str) -> Vec<String> {
+	value
+		.split(';')
+		.map(str::trim)
+		.filter(|item| !item.is_empty())
+		.map(str::to_string)
+		.collect()
+}
+
+/// Applies the value-level escapes (`\s`, `\n`, `\t`, `\r`, `\\`).
+fn unescape(value: &str) -> String {
+	let mut out = String::with_capacity(value.len());
+	let mut chars = value.chars();
+
+	while let Some(c) = chars.next() {
+		if c != '\\' {
+			out.push(c);
+			continue;
+		}
+
+		match chars.next() {
+			Some('s') => out.push(' '),
+			Some('n') => out.push('\n'),
+			Some('t') => out.push('\t'),
+			Some('r') => out.push('\r'),
+			Some('\\') => out.push('\\'),
+			Some(other) => {
+				out.push('\\');
+				out.push(other);
+			}
+			None => out.push('\\'),
+		}
+	}
+
+	out
+}
+
+/// Splits an `Exec` value into arguments using the spec's quoting rules.
+///
+/// Returns `None` for unterminated quotes, which the spec treats as invalid.
+fn tokenize(exec: &str) -> Option<Vec<String>> {
+	let mut tokens = Vec::new();
+	let mut current = String::new();
+	let mut in_token = false;
+	let mut quoted = false;
+	let mut chars = exec.chars();
+
+	while let Some(c) = chars.next() {
+		match c {
+			'"' => {
+				quoted = !quoted;
+				in_token = true;
+			}
+			'\\' if quoted => match chars.next() {
+				Some(escaped @ ('"' | '`' | '$' | '\\')) => current.push(escaped),
+				Some(other) => {
+					current.push('\\');
+					current.push(other);
+				}
+				None => return None,
+			},
+			c if c.is_whitespace() && !quoted => {
+				if in_token {
+					tokens.push(std::mem::take(&mut current));
+					in_token = false;
+				}
+			}
+			c => {
+				current.push(c);
+				in_token = true;
+			}
+		}
+	}
+
+	if quoted {
+		return None;
+	}
+
+	if in_token {
+		tokens.push(current);
+	}
+
+	Some(tokens)
+}
+
+/// Builds a percent-encoded `file://` URI for a local path.
+pub(crate) fn file_uri(path: &Path) -> String {
+	use std::os::unix::ffi::OsStrExt;
+
+	let mut uri = String::from("file://");
+	for &byte in path.as_os_str().as_bytes() {
+		match byte {
+			b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+				uri.push(byte as char)
+			}
+			_ => uri.push_str(&format!("%{:02X}", byte)),
+		}
+	}
+
+	uri
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn entry(exec: &str) -> DesktopEntry {
+		DesktopEntry::parse(
+			"org.example.App.desktop",
+			Path::new("/usr/share/applications/org.example.App.desktop"),
+			&format!(
+				"[Desktop Entry]\nType=Application\nName=Example\nIcon=example\nExec={exec}\n"
+			),
+		)
+		.unwrap()
+	}
+
+	#[test]
+	fn parses_application_entries_only() {
+		let contents = "\
+[Desktop Entry]
+Type=Application
+Name=Text Editor
+Exec=gedit %U
+MimeType=text/plain;text/x-c;
+
+[Desktop Action new-window]
+Name=New Window
+Exec=gedit --new-window
+";
+		let entry =
+			DesktopEntry::parse("org.gnome.gedit.desktop", Path::new("/x"), contents).unwrap();
+		assert_eq!(entry.name, "Text Editor");
+		assert_eq!(entry.exec.as_deref(), Some("gedit %U"));
+		assert_eq!(entry.mime_types, ["text/plain", "text/x-c"]);
+
+		let link = "[Desktop Entry]\nType=Link\nName=Site\nURL=https://example.com\n";
+		assert!(DesktopEntry::parse("site.desktop", Path::new("/x"), link).is_none());
+	}
+
+	#[test]
+	fn substitutes_field_codes() {
+		let file = Path::new("/home/user/My Notes.txt");
+
+		assert_eq!(
+			entry("gedit %f").command_line(&[file], &[]).unwrap(),
+			["gedit", "/home/user/My Notes.txt"]
+		);
+		assert_eq!(
+			entry("gedit %U").command_line(&[file], &[]).unwrap(),
+			["gedit", "file:///home/user/My%20Notes.txt"]
+		);
+		assert_eq!(
+			entry("app %i --title=%c %k %f")
+				.command_line(&[file], &[])
+				.unwrap(),
+			[
+				"app",
+				"--icon",
+				"example",
+				"--title=Example",
+				"/usr/share/applications/org.example.App.desktop",
+				"/home/user/My Notes.txt",
+			]
+		);
+	}
+
+	#[test]
+	fn appends_targets_when_exec_has_no_field_code() {
+		assert_eq!(
+			entry("viewer --fullscreen")
+				.command_line(&[Path::new("/a.png")], &[])
+				.unwrap(),
+			["viewer", "--fullscreen", "/a.png"]
+		);
+	}
+
+	#[test]
+	fn inserts_args_before_first_target() {
+		let file = Path::new("/src/main.rs");
+
+		assert_eq!(
+			entry("flatpak run --command=gedit org.gnome.gedit @@u %U @@")
+				.command_line(&[file], &["+42"])
+				.unwrap(),
+			[
+				"flatpak",
+				"run",
+				"--command=gedit",
+				"org.gnome.gedit",
+				"@@u",
+				"+42",
+				"file:///src/main.rs",
+				"@@"
+			]
+		);
+		assert_eq!(
+			entry("kate --tempfile")
+				.command_line(&[file], &["--line", "42"])
+				.unwrap(),
+			["kate", "--tempfile", "--line", "42", "/src/main.rs"]
+		);
+	}
+
+	#[test]
+	fn honours_quoting_and_escapes() {
+		assert_eq!(
+			entry(r#""/opt/My App/bin" --literal=100%% "a \"b\"" %d %f"#)
+				.command_line(&[Path::new("/f")], &[])
+				.unwrap(),
+			["/opt/My App/bin", "--literal=100%", "a \"b\"", "/f"]
+		);
+		assert!(entry(r#"app "unterminated"#)
+			.command_line(&[], &[])
+			.is_none());
+	}
+}
@@ -0,0 +1,269 @@
+//! # Launching
+//!
+//! Apps are started the way the desktop itself would start them whenever a
+//! helper is installed: `gio launch` (or `gtk-launch`) runs a desktop entry
+//! through GLib, which handles D-Bus activated apps and startup notification
+//! that spawning `Exec=` by hand misses. Spawning the `Exec=` line is the
+//! fallback, and the only way to pass extra arguments or to keep a D-Bus
+//! activated app from opening the file in the instance it already has.
+//!
+//! A launch's working directory and extra environment are set on whichever
+//! process is spawned, and an app started by a helper inherits them from it.
+//!
+//! Whatever gets spawned is detached from Spacedrive so it outlives it: it
+//! gets its own process group, out of reach of a terminal hangup or Ctrl+C
+//! aimed at Spacedrive, and, when a systemd user manager is running, its own
+//! transient scope, so stopping Spacedrive's unit doesn't stop it too.
+//!
+//! The session environment (`WAYLAND_DISPLAY`, `DISPLAY`,
+//! `DBUS_SESSION_BUS_ADDRESS`, ...) is passed on untouched. Only entries that
+//! point into an AppImage's mount are dropped, since they name Spacedrive's
+//! bundled libraries and break any app that isn't Spacedrive.
+
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use file_opening::{FileOpenError, LaunchSpec, OpenResult};
+
+use crate::desktop::{self, DesktopEntry};
+
+/// Opens `spec.path` with `entry`, passing `spec.args` before it.
+pub(crate) fn launch(entry: &DesktopEntry, spec: &LaunchSpec) -> Result<OpenResult, FileOpenError> {
+	// A helper that is missing, too old, or can't run the entry falls
+	// through to the Exec line, whose errors say more.
+	if spec.args.is_empty() && !spec.new_instance && launch_with_helper(entry, spec) {
+		return Ok(OpenResult::success());
+	}
+
+	let Some(argv) = entry.command_line(&[&spec.path], &spec.arg_strs()) else {
+		return Ok(OpenResult::PlatformError {
+			message: format!("{} has no usable Exec line", entry.id),
+			code: 0,
+		});
+	};
+
+	// Checked up front: under systemd-run, a missing binary would only show
+	// up as the wrapper's exit status.
+	let Some(program) = desktop::find_program(Path::new(&argv[0])) else {
+		return Ok(OpenResult::AppNotFound {
+			app_id: entry.id.clone(),
+		});
+	};
+
+	let mut command = detached(&program);
+	command.args(&argv[1..]);
+	apply_spec(&mut command, spec);
+
+	match command.spawn() {
+		Ok(mut child) => {
+			// Reap in the background so long-lived GUI apps don't linger as zombies.
+			std::thread::spawn(move || {
+				let _ = child.wait();
+			});
+			Ok(OpenResult::success())
+		}
+		Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(OpenResult::AppNotFound {
+			app_id: entry.id.clone(),
+		}),
+		Err(e) => Ok(OpenResult::PlatformError {
+			message: e.to_string(),
+			code: e.raw_os_error().unwrap_or(0),
+		}),
+	}
+}
+
+/// Opens `path` with whatever GLib considers its default, for types that
+/// `mimeapps.list` has no default for, falling back to `xdg-open`.
+pub(crate) fn open_default(path: &Path) -> OpenResult {
+	if let Some(gio) = desktop::find_program(Path::new("gio")) {
+		let mut command = detached(&gio);
+		command.arg("open").arg(path);
+		if succeeds(command) {
+			return OpenResult::success();
+		}
+	}
+
+	crate::open_that(path)
+}
+
+/// Runs `entry` through `gio launch` or `gtk-launch`, whichever is installed.
+///
+/// Both exit once the app has been started, so their status is the launch's.
+fn launch_with_helper(entry: &DesktopEntry, spec: &LaunchSpec) -> bool {
+	let mut command = if let Some(gio) = desktop::find_program(Path::new("gio")) {
+		let mut command = detached(&gio);
+		command.arg("launch").arg(&entry.path).arg(&spec.path);
+		command
+	} else if let Some(gtk_launch) = desktop::find_program(Path::new("gtk-launch")) {
+		let mut command = detached(&gtk_launch);
+		command.arg(&entry.id).arg(&spec.path);
+		command
+	} else {
+		return false;
+	};
+
+	apply_spec(&mut command, spec);
+	succeeds(command)
+}
+
+/// Starts `command` in the spec's working directory, with its variables set
+/// over whatever [`detached`] left of the session environment.
+fn apply_spec(command: &mut Command, spec: &LaunchSpec) {
+	if let Some(dir) = &spec.working_dir {
+		command.current_dir(dir);
+	}
+	command.envs(&spec.env);
+}
+
+fn succeeds(mut command: Command) -> bool {
+	command.status().is_ok_and(|status| status.success())
+}
+
+/// A command for `program` that is detached from Spacedrive as described in
+/// the module docs, with no standard streams.
+fn detached(program: &Path) -> Command {
+	let mut command = match systemd_run() {
+		Some(systemd_run) => {
+			// --scope execs the program in place once the scope exists, so
+			// the child is still the program itself.
+			let mut command = Command::new(systemd_run);
+			command
+				.args(["--user", "--scope", "--quiet", "--collect", "--"])
+				.arg(program);
+			command
+		}
+		None => Command::new(program),
+	};
+
+	command
+		.stdin(Stdio::null())
+		.stdout(Stdio::null())
+		.stderr(Stdio::null())
+		.process_group(0);
+
+	if let Some(appdir) = std::env::var_os("APPDIR").filter(|dir| !dir.is_empty()) {
+		for (key, value) in std::env::vars_os() {
+			match without_bundle_entries(&value, Path::new(&appdir)) {
+				Some(kept) if kept == value => {}
+				Some(kept) => {
+					command.env(key, kept);
+				}
+				None => {
+					command.env_remove(key);
+				}
+			}
+		}
+	}
+
+	command
+}
+
+/// `systemd-run`, if there is a user manager for it to talk to.
+fn systemd_run() -> Option<PathBuf> {
+	let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")?;
+	if !Path::new(&runtime_dir).join("systemd/private").exists() {
+		return None;
+	}
+
+	desktop::find_program(Path::new("systemd-run"))
+}
+
+/// `value` with the `:`-separated entries under `appdir` removed, or `None`
+/// if nothing is left.
+fn without_bundle_entries(value: &OsStr, appdir: &Path) -> Option<OsString> {
+	let kept = value
+		.as_bytes()
+		.split(|&byte| byte == b':')
+		.filter(|entry| !Path::new(OsStr::from_bytes(entry)).starts_with(appdir))
+		.collect::<Vec<_>>();
+
+	if kept.is_empty() {
+		return None;
+	}
+
+	Some(OsString::from_vec(kept.join(&b':')))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn strips_appimage_entries_from_the_environment() {
+		let appdir = Path::new("/tmp/.mount_SpacedXYZ");
+		let scrub = |value: &str| {
+			without_bundle_entries(OsStr::new(value), appdir)
+				.map(|kept| kept.to_string_lossy().to_string())
+		};
+
+		assert_eq!(
+			scrub("/tmp/.mount_SpacedXYZ/usr/bin:/usr/local/bin:/usr/bin"),
+			Some("/usr/local/bin:/usr/bin".to_string())
+		);
+		assert_eq!(scrub("/tmp/.mount_SpacedXYZ/usr/lib"), None);
+		assert_eq!(scrub("wayland-0"), Some("wayland-0".to_string()));
+		assert_eq!(
+			scrub("unix:path=/run/user/1000/bus"),
+			Some("unix:path=/run/user/1000/bus".to_string())
+		);
+		// A sibling mount with a longer name is a different bundle.
+		assert_eq!(
+			scrub("/tmp/.mount_SpacedXYZ2/lib"),
+			Some("/tmp/.mount_SpacedXYZ2/lib".to_string())
+		);
+	}
+
+	#[test]
+	fn apps_start_in_the_requested_working_directory() {
+		use std::os::unix::fs::PermissionsExt;
+		use std::time::{Duration, Instant};
+
+		let root = std::env::temp_dir().join(format!("sd-launch-cwd-{}", std::process::id()));
+		let cwd = root.join("cwd");
+		std::fs::create_dir_all(&cwd).unwrap();
+
+		// Stands in for an app: it overwrites the file it's opened with with
+		// the directory it was started in.
+		let script = root.join("record-cwd.sh");
+		std::fs::write(&script, "#!/bin/sh\npwd > \"$1\"\n").unwrap();
+		std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+		let entry_path = root.join("record-cwd.desktop");
+		let contents = format!(
+			"[Desktop Entry]\nType=Application\nName=Record\nExec={} %f\n",
+			script.display()
+		);
+		std::fs::write(&entry_path, &contents).unwrap();
+		let entry = DesktopEntry::parse("record-cwd.desktop", &entry_path, &contents).unwrap();
+
+		let opened = root.join("opened.txt");
+		std::fs::write(&opened, "").unwrap();
+		let spec = LaunchSpec {
+			working_dir: Some(cwd.clone()),
+			..LaunchSpec::new(&opened, "record-cwd.desktop")
+		};
+		assert!(matches!(
+			launch(&entry, &spec),
+			Ok(OpenResult::Success { .. })
+		));
+
+		let deadline = Instant::now() + Duration::from_secs(10);
+		let recorded = loop {
+			let recorded = std::fs::read_to_string(&opened).unwrap();
+			if !recorded.is_empty() {
+				break recorded;
+			}
+			assert!(Instant::now() < deadline, "the app never ran");
+			std::thread::sleep(Duration::from_millis(20));
+		};
+		assert_eq!(
+			Path::new(recorded.trim()).canonicalize().unwrap(),
+			cwd.canonicalize().unwrap()
+		);
+
+		std::fs::remove_dir_all(root).unwrap();
+	}
+}
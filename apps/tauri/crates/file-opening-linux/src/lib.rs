@@ -1,61 +1,371 @@
-use file_opening::{FileOpener, OpenResult, OpenWithApp};
+use file_opening::{
+	check_app_id, reveal_target, AppKind, FileOpenError, FileOpener, LaunchSpec, OpenResult,
+	OpenWithApp, OpenerCapabilities, RevealTarget, UriPolicy,
+};
 use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 
+mod desktop;
+mod launcher;
+mod mime;
+mod mimeapps;
+mod xdg;
+
+use desktop::DesktopEntry;
+use mimeapps::MimeApps;
+
+/// XDG MIME backed opener.
+///
+/// App ids are desktop file ids (e.g. `org.gnome.gedit.desktop`). Types are
+/// resolved through shared-mime-info and defaults through `mimeapps.list`, so
+/// results match what the desktop's own file manager offers.
 pub struct LinuxFileOpener;
 
 impl FileOpener for LinuxFileOpener {
-	fn get_apps_for_file(&self, _path: &Path) -> Result<Vec<OpenWithApp>, String> {
-		// Simple implementation - return empty list
-		// Full implementation would require parsing freedesktop.org desktop entries
-		Ok(vec![])
+	fn get_apps_for_file(&self, path: &Path) -> Result<Vec<OpenWithApp>, FileOpenError> {
+		Ok(match mime_type(path)? {
+			Some(mime) => apps_for_mime(&mime, false),
+			None => vec![],
+		})
+	}
+
+	fn get_all_apps_for_file(&self, path: &Path) -> Result<Vec<OpenWithApp>, FileOpenError> {
+		Ok(match mime_type(path)? {
+			Some(mime) => apps_for_mime(&mime, true),
+			None => vec![],
+		})
+	}
+
+	fn get_apps_for_mime_type(&self, mime: &str) -> Result<Vec<OpenWithApp>, FileOpenError> {
+		Ok(apps_for_mime(&mime::canonical(mime), false))
+	}
+
+	fn get_default_app(&self, path: &Path) -> Result<Option<OpenWithApp>, FileOpenError> {
+		Ok(default_entry(path).map(|entry| OpenWithApp {
+			is_default: true,
+			..to_app(&entry, true)
+		}))
+	}
+
+	fn can_open(&self, path: &Path) -> Result<bool, FileOpenError> {
+		// A default in mimeapps.list answers without scanning every desktop entry.
+		if default_entry(path).is_some() {
+			return Ok(true);
+		}
+
+		Ok(!self.get_apps_for_file(path)?.is_empty())
 	}
 
-	fn open_with_default(&self, path: &Path) -> Result<OpenResult, String> {
+	fn open_with_default(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
 		if !path.exists() {
 			return Ok(OpenResult::FileNotFound {
 				path: path.to_string_lossy().to_string(),
 			});
 		}
 
-		match open::that(path) {
-			Ok(_) => Ok(OpenResult::Success),
-			Err(e) => Ok(OpenResult::PlatformError {
-				message: e.to_string(),
-			}),
+		if let Some(entry) = default_entry(path) {
+			return launcher::launch(&entry, &LaunchSpec::new(path, &entry.id));
 		}
+
+		// Nothing in mimeapps.list; GLib or xdg-open apply the desktop's own
+		// fallbacks.
+		Ok(launcher::open_default(path))
 	}
 
-	fn open_with_app(&self, path: &Path, app_id: &str) -> Result<OpenResult, String> {
-		if !path.exists() {
+	fn open_with_app(&self, path: &Path, app_id: &str) -> Result<OpenResult, FileOpenError> {
+		self.launch(&LaunchSpec::new(path, app_id))
+	}
+
+	fn open_with_app_args(
+		&self,
+		path: &Path,
+		app_id: &str,
+		args: &[&str],
+	) -> Result<OpenResult, FileOpenError> {
+		self.launch(&LaunchSpec {
+			args: args.iter().map(|arg| arg.to_string()).collect(),
+			..LaunchSpec::new(path, app_id)
+		})
+	}
+
+	fn launch(&self, spec: &LaunchSpec) -> Result<OpenResult, FileOpenError> {
+		check_app_id(&spec.app_id)?;
+		spec.check_working_dir()?;
+
+		if !spec.path.exists() {
 			return Ok(OpenResult::FileNotFound {
-				path: path.to_string_lossy().to_string(),
+				path: spec.path.to_string_lossy().to_string(),
 			});
 		}
 
-		// Use xdg-open with specific app
-		let output = std::process::Command::new("gtk-launch")
-			.arg(app_id)
-			.arg(path)
-			.output()
-			.map_err(|e| e.to_string())?;
+		let Some(entry) = desktop::find(&spec.app_id) else {
+			return Ok(OpenResult::AppNotFound {
+				app_id: spec.app_id.clone(),
+			});
+		};
 
-		if output.status.success() {
-			Ok(OpenResult::Success)
-		} else {
-			Ok(OpenResult::PlatformError {
-				message: String::from_utf8_lossy(&output.stderr).to_string(),
-			})
-		}
+		launcher::launch(&entry, spec)
 	}
 
 	fn open_files_with_app(
 		&self,
 		paths: &[PathBuf],
 		app_id: &str,
-	) -> Result<Vec<OpenResult>, String> {
+	) -> Result<Vec<OpenResult>, FileOpenError> {
 		paths
 			.iter()
 			.map(|path| self.open_with_app(path, app_id))
 			.collect()
 	}
+
+	fn open_uri_with_policy(
+		&self,
+		uri: &str,
+		policy: &UriPolicy,
+	) -> Result<OpenResult, FileOpenError> {
+		policy.check(uri)?;
+
+		// xdg-open dispatches on the scheme through x-scheme-handler/* types.
+		Ok(open_that(uri))
+	}
+
+	fn reveal(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+		Ok(match reveal_target(path) {
+			RevealTarget::Folder(folder) => open_that(&folder),
+			RevealTarget::Select(item) if show_items(&[&item]) => OpenResult::success(),
+			// No FileManager1 service: the folder is the closest we can get.
+			RevealTarget::Select(item) => open_that(item.parent().unwrap_or(&item)),
+			RevealTarget::Missing => OpenResult::FileNotFound {
+				path: path.to_string_lossy().to_string(),
+			},
+		})
+	}
+
+	fn reveal_many(&self, paths: &[&Path]) -> Result<Vec<OpenResult>, FileOpenError> {
+		// One ShowItems call gets a window per parent folder with all of its
+		// items selected; if it fails, each item falls back as in `reveal`.
+		let mut results = Vec::with_capacity(paths.len());
+		let mut selected = Vec::new();
+		for (index, path) in paths.iter().enumerate() {
+			match reveal_target(path) {
+				RevealTarget::Select(item) => {
+					selected.push(index);
+					results.push((item, None));
+				}
+				_ => results.push((path.to_path_buf(), Some(self.reveal(path)?))),
+			}
+		}
+
+		let items = selected
+			.iter()
+			.map(|&index| results[index].0.as_path())
+			.collect::<Vec<_>>();
+		if !items.is_empty() && show_items(&items) {
+			for &index in &selected {
+				results[index].1 = Some(OpenResult::success());
+			}
+		}
+
+		results
+			.into_iter()
+			.map(|(item, result)| match result {
+				Some(result) => Ok(result),
+				None => self.reveal(&item),
+			})
+			.collect()
+	}
+
+	fn capabilities(&self) -> OpenerCapabilities {
+		OpenerCapabilities {
+			reveal: true,
+			elevated: false,
+			verbs: false,
+			dialog: false,
+			executable: false,
+			launch: false,
+			wait: false,
+			shell_items: false,
+			print: false,
+			edit: false,
+			properties: false,
+			icons: false,
+			thumbnails: false,
+			set_default: false,
+			uri: true,
+			trash: false,
+			mail: false,
+		}
+	}
+}
+
+/// Desktop entries that handle `mime` or one of its parents, default first,
+/// then by name.
+///
+/// With `include_others`, every other visible entry that declares some MIME
+/// type is added as not recommended, like the "other applications" section of
+/// desktop Open With dialogs. Entries the user removed for this type stay out.
+fn apps_for_mime(mime: &str, include_others: bool) -> Vec<OpenWithApp> {
+	let mimes = mime::with_parents(mime);
+	let associations = MimeApps::load();
+	let default_id = default_for(&mimes, &associations).map(|entry| entry.id);
+
+	let mut apps = desktop::all()
+		.into_iter()
+		.filter(|entry| !entry.no_display)
+		.filter_map(|entry| {
+			let recommended = mimes.iter().any(|mime| {
+				!associations.is_removed(mime, &entry.id)
+					&& (entry.supports(mime)
+						|| associations.added_for(mime).any(|id| id == entry.id))
+			});
+			let other = include_others
+				&& !entry.mime_types.is_empty()
+				&& !mimes
+					.iter()
+					.any(|mime| associations.is_removed(mime, &entry.id));
+
+			(recommended || other).then(|| OpenWithApp {
+				is_default: default_id.as_deref() == Some(entry.id.as_str()),
+				..to_app(&entry, recommended)
+			})
+		})
+		.collect::<Vec<_>>();
+
+	// Entries come in directory order, so recommended ones are put in name
+	// order before the default is pulled to the front.
+	apps.sort_by(|a, b| a.name.cmp(&b.name));
+	file_opening::sort_by_relevance(&mut apps);
+	apps
+}
+
+fn to_app(entry: &DesktopEntry, recommended: bool) -> OpenWithApp {
+	OpenWithApp {
+		id: entry.id.clone(),
+		name: entry.name.clone(),
+		exe_path: entry.executable(),
+		recommended,
+		is_default: false,
+		kind: AppKind::Unknown,
+		category: None,
+		progid: None,
+		clsid: None,
+		icon: None,
+		icons: Vec::new(),
+		accelerator: None,
+	}
+}
+
+/// Resolves the effective default for the file's type, walking up the MIME
+/// hierarchy so a `text/plain` default covers source files too.
+fn default_entry(path: &Path) -> Option<DesktopEntry> {
+	let mime = mime::mime_type_for(path)?;
+
+	default_for(&mime::with_parents(&mime), &MimeApps::load())
+}
+
+/// The default for the first of `mimes` (a type and its parents) that has one.
+fn default_for(mimes: &[String], associations: &MimeApps) -> Option<DesktopEntry> {
+	mimes.iter().find_map(|mime| {
+		associations
+			.defaults_for(mime)
+			.chain(associations.added_for(mime))
+			.find_map(desktop::find)
+	})
+}
+
+pub(crate) fn open_that(target: impl AsRef<std::ffi::OsStr>) -> OpenResult {
+	match open::that(target) {
+		Ok(_) => OpenResult::success(),
+		Err(e) => OpenResult::PlatformError {
+			message: e.to_string(),
+			code: e.raw_os_error().unwrap_or(0),
+		},
+	}
+}
+
+/// Asks the running file manager to select `items` through the freedesktop
+/// `org.freedesktop.FileManager1` interface that Nautilus, Dolphin, Nemo and
+/// friends implement. `--print-reply` makes a missing service fail the call.
+fn show_items(items: &[&Path]) -> bool {
+	let uris = items
+		.iter()
+		.map(|item| desktop::file_uri(item))
+		.collect::<Vec<_>>()
+		.join(",");
+
+	Command::new("dbus-send")
+		.args([
+			"--session",
+			"--print-reply",
+			"--type=method_call",
+			"--dest=org.freedesktop.FileManager1",
+			"/org/freedesktop/FileManager1",
+			"org.freedesktop.FileManager1.ShowItems",
+		])
+		// URIs are percent-encoded, so they can't contain the commas that
+		// dbus-send uses to separate array elements.
+		.arg(format!("array:string:{uris}"))
+		.arg("string:")
+		.stdin(Stdio::null())
+		.stdout(Stdio::null())
+		.stderr(Stdio::null())
+		.status()
+		.is_ok_and(|status| status.success())
+}
+
+/// The MIME type of `path`, or `None` for an extension shared-mime-info
+/// doesn't know.
+///
+/// A name without an extension that couldn't be typed from content either
+/// is [`FileOpenError::NoExtension`], so the UI can ask for an app instead of
+/// reporting that none is installed.
+fn mime_type(path: &Path) -> Result<Option<String>, FileOpenError> {
+	match mime::mime_type_for(path) {
+		Some(mime) => Ok(Some(mime)),
+		None if path.extension().is_none() => Err(FileOpenError::NoExtension),
+		None => Ok(None),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn missing_directories_are_not_found() {
+		let missing = std::env::temp_dir().join(format!("sd-missing-{}", std::process::id()));
+
+		for path in [
+			missing.join("dir"),
+			PathBuf::from(format!("{}/dir/", missing.display())),
+		] {
+			assert!(matches!(
+				LinuxFileOpener.open_with_default(&path),
+				Ok(OpenResult::FileNotFound { .. })
+			));
+			assert!(matches!(
+				LinuxFileOpener.reveal(&path),
+				Ok(OpenResult::FileNotFound { .. })
+			));
+		}
+	}
+
+	#[test]
+	fn bulk_reveal_keeps_results_in_input_order() {
+		let missing = std::env::temp_dir().join(format!("sd-missing-{}", std::process::id()));
+		let (first, second) = (missing.join("a"), missing.join("b"));
+
+		let results = LinuxFileOpener
+			.reveal_many(&[first.as_path(), second.as_path()])
+			.unwrap();
+
+		let paths = results
+			.iter()
+			.map(|result| match result {
+				OpenResult::FileNotFound { path } => path.clone(),
+				other => panic!("{other:?}"),
+			})
+			.collect::<Vec<_>>();
+		assert_eq!(paths, [first.to_string_lossy(), second.to_string_lossy()]);
+	}
 }
@@ -0,0 +1,234 @@
+//! # MIME type resolution
+//!
+//! Matches file names against shared-mime-info's `globs2` database first,
+//! since that needs no subprocess, and only falls back to `xdg-mime query
+//! filetype` (which sniffs content) when no glob matches. Parent types come
+//! from the `subclasses` database so an app registered for `text/plain` is
+//! offered for `text/x-python` too.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use crate::xdg;
+
+struct Glob {
+	weight: u32,
+	mime: String,
+	pattern: String,
+	case_sensitive: bool,
+}
+
+/// Resolves the MIME type for a file, or `None` if nothing recognises it.
+pub(crate) fn mime_type_for(path: &Path) -> Option<String> {
+	let name = path.file_name()?.to_string_lossy();
+
+	let globs = xdg::data_dirs()
+		.into_iter()
+		.filter_map(|dir| fs::read_to_string(dir.join("mime/globs2")).ok())
+		.flat_map(|contents| parse_globs(&contents))
+		.collect::<Vec<_>>();
+
+	best_glob_match(&globs, &name).or_else(|| query_filetype(path))
+}
+
+/// The type itself followed by every ancestor, nearest first.
+pub(crate) fn with_parents(mime: &str) -> Vec<String> {
+	let subclasses = xdg::data_dirs()
+		.into_iter()
+		.filter_map(|dir| fs::read_to_string(dir.join("mime/subclasses")).ok())
+		.collect::<Vec<_>>()
+		.join("\n");
+
+	ancestors(mime, &subclasses)
+}
+
+/// Resolves a caller-supplied type to its canonical name through the
+/// `aliases` database, so `application/x-pdf` finds apps registered for
+/// `application/pdf`.
+pub(crate) fn canonical(mime: &str) -> String {
+	let mime = mime.trim().to_ascii_lowercase();
+	let aliases = xdg::data_dirs()
+		.into_iter()
+		.filter_map(|dir| fs::read_to_string(dir.join("mime/aliases")).ok())
+		.collect::<Vec<_>>()
+		.join("\n");
+
+	resolve_alias(&mime, &aliases).unwrap_or(mime)
+}
+
+fn parse_globs(contents: &str) -> Vec<Glob> {
+	contents
+		.lines()
+		.filter(|line| !line.starts_with('#'))
+		.filter_map(|line| {
+			let mut fields = line.split(':');
+			let weight = fields.next()?.parse().ok()?;
+			let mime = fields.next()?.to_string();
+			let pattern = fields.next()?.to_string();
+			let case_sensitive = fields.next().is_some_and(|flags| flags.contains("cs"));
+
+			Some(Glob {
+				weight,
+				mime,
+				pattern,
+				case_sensitive,
+			})
+		})
+		.collect()
+}
+
+/// Picks the highest-weight match, breaking ties on the longest pattern as
+/// shared-mime-info recommends (so `*.tar.gz` beats `*.gz`).
+fn best_glob_match(globs: &[Glob], name: &str) -> Option<String> {
+	let lowered = name.to_lowercase();
+
+	globs
+		.iter()
+		.filter(|glob| {
+			let candidate = if glob.case_sensitive { name } else { &lowered };
+			let pattern = if glob.case_sensitive {
+				glob.pattern.clone()
+			} else {
+				glob.pattern.to_lowercase()
+			};
+			glob_matches(&pattern, candidate)
+		})
+		.max_by_key(|glob| (glob.weight, glob.pattern.len()))
+		.map(|glob| glob.mime.clone())
+}
+
+/// Supports the literal and `*suffix` patterns that make up nearly all of
+/// `globs2`; anything more exotic is skipped rather than misinterpreted.
+fn glob_matches(pattern: &str, name: &str) -> bool {
+	match pattern.strip_prefix('*') {
+		Some(suffix) if !suffix.contains(['*', '?', '[']) => name.ends_with(suffix),
+		Some(_) => false,
+		None if pattern.contains(['*', '?', '[']) => false,
+		None => pattern == name,
+	}
+}
+
+fn ancestors(mime: &str, subclasses: &str) -> Vec<String> {
+	let mut result = vec![mime.to_string()];
+	let mut seen = HashSet::from([mime.to_string()]);
+	let mut index = 0;
+
+	while index < result.len() {
+		let current = result[index].clone();
+		index += 1;
+
+		let mut parents = subclasses
+			.lines()
+			.filter_map(|line| line.split_once(' '))
+			.filter(|(child, _)| *child == current)
+			.map(|(_, parent)| parent.trim().to_string())
+			.collect::<Vec<_>>();
+
+		// Every text type is implicitly a subclass of text/plain.
+		if current.starts_with("text/") && current != "text/plain" {
+			parents.push("text/plain".to_string());
+		}
+
+		for parent in parents {
+			if seen.insert(parent.clone()) {
+				result.push(parent);
+			}
+		}
+	}
+
+	result
+}
+
+fn resolve_alias(mime: &str, aliases: &str) -> Option<String> {
+	aliases
+		.lines()
+		.filter_map(|line| line.split_once(' '))
+		.find(|(alias, _)| *alias == mime)
+		.map(|(_, canonical)| canonical.trim().to_string())
+}
+
+fn query_filetype(path: &Path) -> Option<String> {
+	let output = Command::new("xdg-mime")
+		.args(["query", "filetype"])
+		.arg(path)
+		.output()
+		.ok()?;
+
+	let mime = String::from_utf8_lossy(&output.stdout).trim().to_string();
+	(output.status.success() && mime.contains('/')).then_some(mime)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const GLOBS: &str = "\
+# This file was automatically generated
+50:application/gzip:*.gz
+50:application/x-compressed-tar:*.tar.gz
+50:text/x-c:*.c:cs
+50:text/x-c++src:*.C:cs
+50:image/png:*.png
+10:text/x-readme:README*
+";
+
+	#[test]
+	fn longest_glob_wins_at_equal_weight() {
+		let globs = parse_globs(GLOBS);
+		assert_eq!(
+			best_glob_match(&globs, "backup.tar.gz").as_deref(),
+			Some("application/x-compressed-tar")
+		);
+		assert_eq!(
+			best_glob_match(&globs, "notes.gz").as_deref(),
+			Some("application/gzip")
+		);
+	}
+
+	#[test]
+	fn respects_case_sensitivity_flag() {
+		let globs = parse_globs(GLOBS);
+		assert_eq!(
+			best_glob_match(&globs, "Photo.PNG").as_deref(),
+			Some("image/png")
+		);
+		assert_eq!(
+			best_glob_match(&globs, "main.c").as_deref(),
+			Some("text/x-c")
+		);
+		assert_eq!(
+			best_glob_match(&globs, "main.C").as_deref(),
+			Some("text/x-c++src")
+		);
+		assert_eq!(best_glob_match(&globs, "README.md"), None);
+	}
+
+	#[test]
+	fn resolves_aliases() {
+		let aliases = "application/x-pdf application/pdf\nimage/jpg image/jpeg\n";
+		assert_eq!(
+			resolve_alias("application/x-pdf", aliases).as_deref(),
+			Some("application/pdf")
+		);
+		assert_eq!(resolve_alias("application/pdf", aliases), None);
+	}
+
+	#[test]
+	fn walks_subclass_chain() {
+		let subclasses = "application/x-shellscript application/x-executable\napplication/x-shellscript text/plain\n";
+		assert_eq!(
+			ancestors("application/x-shellscript", subclasses),
+			[
+				"application/x-shellscript",
+				"application/x-executable",
+				"text/plain"
+			]
+		);
+		assert_eq!(
+			ancestors("text/x-python", ""),
+			["text/x-python", "text/plain"]
+		);
+	}
+}
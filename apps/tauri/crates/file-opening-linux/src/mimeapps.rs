@@ -0,0 +1,171 @@
+//! # `mimeapps.list` associations
+//!
+//! Merges every `mimeapps.list` (and desktop-specific `$DESKTOP-mimeapps.list`)
+//! in spec precedence order. Each list keeps its entries in that order, so the
+//! first installed entry in `defaults` is the effective default.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::desktop::split_list;
+use crate::xdg;
+
+#[derive(Clone, Copy)]
+enum Section {
+	Defaults,
+	Added,
+	Removed,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct MimeApps {
+	defaults: HashMap<String, Vec<String>>,
+	added: HashMap<String, Vec<String>>,
+	removed: HashMap<String, Vec<String>>,
+}
+
+impl MimeApps {
+	pub fn load() -> Self {
+		let mut merged = Self::default();
+
+		for path in list_paths() {
+			if let Ok(contents) = fs::read_to_string(path) {
+				merged.merge(Self::parse(&contents));
+			}
+		}
+
+		merged
+	}
+
+	pub fn parse(contents: &str) -> Self {
+		let mut apps = Self::default();
+		let mut section = None;
+
+		for line in contents.lines().map(str::trim) {
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+
+			if line.starts_with('[') {
+				section = match line {
+					"[Default Applications]" => Some(Section::Defaults),
+					"[Added Associations]" => Some(Section::Added),
+					"[Removed Associations]" => Some(Section::Removed),
+					_ => None,
+				};
+				continue;
+			}
+
+			let (Some(section), Some((mime, ids))) = (section, line.split_once('=')) else {
+				continue;
+			};
+
+			let map = match section {
+				Section::Defaults => &mut apps.defaults,
+				Section::Added => &mut apps.added,
+				Section::Removed => &mut apps.removed,
+			};
+			map.entry(mime.trim().to_string())
+				.or_default()
+				.extend(split_list(ids));
+		}
+
+		apps
+	}
+
+	fn merge(&mut self, other: Self) {
+		for (target, source) in [
+			(&mut self.defaults, other.defaults),
+			(&mut self.added, other.added),
+			(&mut self.removed, other.removed),
+		] {
+			for (mime, ids) in source {
+				target.entry(mime).or_default().extend(ids);
+			}
+		}
+	}
+
+	/// Default candidates for `mime`, best first.
+	pub fn defaults_for<'a>(&'a self, mime: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+		self.defaults
+			.get(mime)
+			.into_iter()
+			.flatten()
+			.map(String::as_str)
+			.filter(move |id| !self.is_removed(mime, id))
+	}
+
+	/// Entries associated with `mime` by the user rather than the app itself.
+	pub fn added_for<'a>(&'a self, mime: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+		self.added
+			.get(mime)
+			.into_iter()
+			.flatten()
+			.map(String::as_str)
+			.filter(move |id| !self.is_removed(mime, id))
+	}
+
+	pub fn is_removed(&self, mime: &str, id: &str) -> bool {
+		self.removed
+			.get(mime)
+			.is_some_and(|ids| ids.iter().any(|removed| removed == id))
+	}
+}
+
+fn list_paths() -> Vec<PathBuf> {
+	let desktops = xdg::current_desktops();
+	let config = xdg::config_dirs();
+	let data = xdg::data_dirs()
+		.into_iter()
+		.map(|dir| dir.join("applications"));
+
+	config
+		.into_iter()
+		.chain(data)
+		.flat_map(|dir| {
+			desktops
+				.iter()
+				.map(|desktop| dir.join(format!("{desktop}-mimeapps.list")))
+				.chain(std::iter::once(dir.join("mimeapps.list")))
+				.collect::<Vec<_>>()
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn merges_sections_in_precedence_order() {
+		let mut apps = MimeApps::parse(
+			"\
+[Default Applications]
+text/plain=org.gnome.gedit.desktop;
+
+[Removed Associations]
+text/plain=libreoffice-writer.desktop;
+",
+		);
+		apps.merge(MimeApps::parse(
+			"\
+[Default Applications]
+text/plain=libreoffice-writer.desktop;org.kde.kate.desktop;
+
+[Added Associations]
+text/plain=code.desktop;
+",
+		));
+
+		assert_eq!(
+			apps.defaults_for("text/plain").collect::<Vec<_>>(),
+			["org.gnome.gedit.desktop", "org.kde.kate.desktop"]
+		);
+		assert_eq!(
+			apps.added_for("text/plain").collect::<Vec<_>>(),
+			["code.desktop"]
+		);
+		assert!(apps.defaults_for("image/png").next().is_none());
+	}
+}
@@ -0,0 +1,51 @@
+//! XDG base directory lookup, in precedence order (user dirs first).
+
+use std::env;
+use std::path::PathBuf;
+
+fn home_relative(var: &str, fallback: &str) -> Option<PathBuf> {
+	// The spec says relative paths in these variables are invalid and must be ignored.
+	env::var_os(var)
+		.map(PathBuf::from)
+		.filter(|path| path.is_absolute())
+		.or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(fallback)))
+}
+
+fn dir_list(var: &str, fallback: &str) -> Vec<PathBuf> {
+	let value = env::var(var)
+		.ok()
+		.filter(|value| !value.is_empty())
+		.unwrap_or_else(|| fallback.to_string());
+
+	value
+		.split(':')
+		.map(PathBuf::from)
+		.filter(|path| path.is_absolute())
+		.collect()
+}
+
+/// `$XDG_DATA_HOME` followed by `$XDG_DATA_DIRS`.
+pub(crate) fn data_dirs() -> Vec<PathBuf> {
+	home_relative("XDG_DATA_HOME", ".local/share")
+		.into_iter()
+		.chain(dir_list("XDG_DATA_DIRS", "/usr/local/share:/usr/share"))
+		.collect()
+}
+
+/// `$XDG_CONFIG_HOME` followed by `$XDG_CONFIG_DIRS`.
+pub(crate) fn config_dirs() -> Vec<PathBuf> {
+	home_relative("XDG_CONFIG_HOME", ".config")
+		.into_iter()
+		.chain(dir_list("XDG_CONFIG_DIRS", "/etc/xdg"))
+		.collect()
+}
+
+/// Lowercased `$XDG_CURRENT_DESKTOP` entries, used for desktop-specific overrides.
+pub(crate) fn current_desktops() -> Vec<String> {
+	env::var("XDG_CURRENT_DESKTOP")
+		.unwrap_or_default()
+		.split(':')
+		.filter(|desktop| !desktop.is_empty())
+		.map(str::to_lowercase)
+		.collect()
+}
@@ -1,11 +1,25 @@
 import Foundation
 import AppKit
+import CoreServices
 import SwiftRs
+import UniformTypeIdentifiers
 
 struct OpenWithApp: Codable {
     let id: String
     let name: String
+    let exePath: String?
+    let recommended: Bool
+    var isDefault: Bool
     let icon: String?
+
+    enum CodingKeys: String, CodingKey {
+        case id
+        case name
+        case exePath = "exe_path"
+        case recommended
+        case isDefault = "is_default"
+        case icon
+    }
 }
 
 enum OpenResult: Codable {
@@ -13,13 +27,15 @@ enum OpenResult: Codable {
     case fileNotFound(path: String)
     case appNotFound(appId: String)
     case permissionDenied(path: String)
-    case platformError(message: String)
+    case platformError(message: String, code: Int32 = 0)
+    case cancelled
     
     enum CodingKeys: String, CodingKey {
         case status
         case path
         case appId = "app_id"
         case message
+        case code
     }
     
     func encode(to encoder: Encoder) throws {
@@ -37,9 +53,12 @@ enum OpenResult: Codable {
         case .permissionDenied(let path):
             try container.encode("permission_denied", forKey: .status)
             try container.encode(path, forKey: .path)
-        case .platformError(let message):
+        case .platformError(let message, let code):
             try container.encode("platform_error", forKey: .status)
             try container.encode(message, forKey: .message)
+            try container.encode(code, forKey: .code)
+        case .cancelled:
+            try container.encode("cancelled", forKey: .status)
         }
     }
     
@@ -61,7 +80,10 @@ enum OpenResult: Codable {
             self = .permissionDenied(path: path)
         case "platform_error":
             let message = try container.decode(String.self, forKey: .message)
-            self = .platformError(message: message)
+            let code = try container.decodeIfPresent(Int32.self, forKey: .code) ?? 0
+            self = .platformError(message: message, code: code)
+        case "cancelled":
+            self = .cancelled
         default:
             throw DecodingError.dataCorruptedError(
                 forKey: .status,
@@ -85,6 +107,30 @@ func getAppsForPath(path: SRString) -> SRString {
         appURLs = getAppsLegacy(for: url)
     }
 
+    let defaultURL = NSWorkspace.shared.urlForApplication(toOpen: url)
+    return encodeApps(appURLs, defaultURL: defaultURL)
+}
+
+@_cdecl("get_apps_for_mime_type")
+func getAppsForMimeType(mime: SRString) -> SRString {
+    // Launch Services only takes content types by UTType before macOS 12,
+    // and unknown MIME types have no UTType to ask about.
+    guard #available(macOS 12.0, *),
+          let type = UTType(mimeType: mime.toString()) else {
+        return SRString("[]")
+    }
+
+    return encodeApps(
+        NSWorkspace.shared.urlsForApplications(toOpen: type),
+        defaultURL: NSWorkspace.shared.urlForApplication(toOpen: type)
+    )
+}
+
+func encodeApps(_ appURLs: [URL], defaultURL: URL?) -> SRString {
+    // Matched by bundle ID, since the default may be a copy installed
+    // somewhere other than the one that survives the dedup below.
+    let defaultId = defaultURL.flatMap { Bundle(url: $0)?.bundleIdentifier }
+
     // Filter to standard app directories
     // /Applications/ - user/admin installed apps
     // /System/Applications/ - system apps (macOS 10.15+)
@@ -92,29 +138,73 @@ func getAppsForPath(path: SRString) -> SRString {
     let homeDir = FileManager.default.homeDirectoryForCurrentUser.path
     let validPrefixes = ["/Applications/", "/System/Applications/", "\(homeDir)/Applications/"]
 
+    // The same bundle can be installed in several places; keep the first,
+    // which Launch Services returns in preference order.
+    var seenBundleIds = Set<String>()
     let apps: [OpenWithApp] = appURLs
         .filter { appURL in
             validPrefixes.contains { appURL.path.hasPrefix($0) }
         }
-        .compactMap { appURL in
-            guard let bundle = Bundle(url: appURL),
-                  let bundleId = bundle.bundleIdentifier,
-                  let displayName = bundle.infoDictionary?["CFBundleDisplayName"] as? String
-                    ?? bundle.infoDictionary?["CFBundleName"] as? String else {
-                return nil
-            }
-
-            return OpenWithApp(id: bundleId, name: displayName, icon: nil)
+        .compactMap(openWithApp(for:))
+        .filter { seenBundleIds.insert($0.id).inserted }
+        .map { app in
+            var app = app
+            app.isDefault = app.id == defaultId
+            return app
         }
 
     let json = (try? JSONEncoder().encode(apps)) ?? Data()
     return SRString(String(data: json, encoding: .utf8) ?? "[]")
 }
 
+@_cdecl("get_default_app_for_path")
+func getDefaultAppForPath(path: SRString) -> SRString {
+    let url = URL(fileURLWithPath: path.toString())
+
+    guard let appURL = NSWorkspace.shared.urlForApplication(toOpen: url),
+          var app = openWithApp(for: appURL) else {
+        return SRString("null")
+    }
+    app.isDefault = true
+
+    let json = (try? JSONEncoder().encode(app)) ?? Data()
+    return SRString(String(data: json, encoding: .utf8) ?? "null")
+}
+
+func openWithApp(for appURL: URL) -> OpenWithApp? {
+    guard let bundle = Bundle(url: appURL),
+          let bundleId = bundle.bundleIdentifier else {
+        return nil
+    }
+
+    // Prefer the localized bundle name so the menu matches Finder in the
+    // user's language, then the raw Info.plist, then the file name.
+    let displayName = bundle.localizedInfoDictionary?["CFBundleDisplayName"] as? String
+        ?? bundle.localizedInfoDictionary?["CFBundleName"] as? String
+        ?? bundle.infoDictionary?["CFBundleDisplayName"] as? String
+        ?? bundle.infoDictionary?["CFBundleName"] as? String
+        ?? appURL.deletingPathExtension().lastPathComponent
+
+    return OpenWithApp(
+        id: bundleId,
+        name: displayName,
+        exePath: bundle.executableURL?.path,
+        recommended: true,
+        isDefault: false,
+        icon: nil
+    )
+}
+
 @_cdecl("open_path_with_default")
 func openPathWithDefault(path: SRString) -> SRString {
     let url = URL(fileURLWithPath: path.toString())
-    
+
+    guard FileManager.default.fileExists(atPath: url.path) else {
+        let result = OpenResult.fileNotFound(path: url.path)
+        let json = (try? JSONEncoder().encode(result)) ?? Data()
+        return SRString(String(data: json, encoding: .utf8) ?? "{}")
+    }
+
     let success = NSWorkspace.shared.open(url)
     let result = success
         ? OpenResult.success
@@ -126,22 +216,65 @@ func openPathWithDefault(path: SRString) -> SRString {
 
 @_cdecl("open_path_with_app")
 func openPathWithApp(path: SRString, appId: SRString) -> SRString {
-    let fileURL = URL(fileURLWithPath: path.toString())
-    let bundleId = appId.toString()
-    
+    let result = openFile(path.toString(), withBundleId: appId.toString(), arguments: [], environment: [:])
+    let json = (try? JSONEncoder().encode(result)) ?? Data()
+    return SRString(String(data: json, encoding: .utf8) ?? "{}")
+}
+
+@_cdecl("open_path_with_app_args")
+func openPathWithAppArgs(path: SRString, appId: SRString, args: SRString) -> SRString {
+    let arguments = args.toString().split(separator: "\0").map(String.init)
+    let result = openFile(path.toString(), withBundleId: appId.toString(), arguments: arguments, environment: [:])
+    let json = (try? JSONEncoder().encode(result)) ?? Data()
+    return SRString(String(data: json, encoding: .utf8) ?? "{}")
+}
+
+/// `env` holds null-delimited `KEY=VALUE` pairs, like the arguments.
+@_cdecl("open_path_with_launch_spec")
+func openPathWithLaunchSpec(path: SRString, appId: SRString, args: SRString, env: SRString, newInstance: Bool) -> SRString {
+    let arguments = args.toString().split(separator: "\0").map(String.init)
+    var environment: [String: String] = [:]
+    for pair in env.toString().split(separator: "\0") {
+        let parts = pair.split(separator: "=", maxSplits: 1).map(String.init)
+        if parts.count == 2 {
+            environment[parts[0]] = parts[1]
+        }
+    }
+
+    let result = openFile(path.toString(), withBundleId: appId.toString(), arguments: arguments, environment: environment, newInstance: newInstance)
+    let json = (try? JSONEncoder().encode(result)) ?? Data()
+    return SRString(String(data: json, encoding: .utf8) ?? "{}")
+}
+
+/// Opens one file with the app for `bundleId`. Launch Services only hands
+/// `arguments` and `environment` to the app when this call launches it; an
+/// app that is already running just receives the file, unless `newInstance`
+/// starts another copy of it as `open -n` does.
+func openFile(_ path: String, withBundleId bundleId: String, arguments: [String], environment: [String: String], newInstance: Bool = false) -> OpenResult {
+    let fileURL = URL(fileURLWithPath: path)
+
+    guard FileManager.default.fileExists(atPath: fileURL.path) else {
+        return OpenResult.fileNotFound(path: fileURL.path)
+    }
+
+    // Bundle ids are stable across installs and locales; resolve to the
+    // current install location at launch time.
     guard let appURL = NSWorkspace.shared.urlForApplication(withBundleIdentifier: bundleId) else {
-        let result = OpenResult.appNotFound(appId: bundleId)
-        let json = (try? JSONEncoder().encode(result)) ?? Data()
-        return SRString(String(data: json, encoding: .utf8) ?? "{}")
+        return OpenResult.appNotFound(appId: bundleId)
     }
     
     let config = NSWorkspace.OpenConfiguration()
+    config.arguments = arguments
+    config.createsNewApplicationInstance = newInstance
+    if !environment.isEmpty {
+        config.environment = environment
+    }
     var openResult = OpenResult.success
     let semaphore = DispatchSemaphore(value: 0)
     
     NSWorkspace.shared.open([fileURL], withApplicationAt: appURL, configuration: config) { _, error in
         if let error = error {
-            openResult = OpenResult.platformError(message: error.localizedDescription)
+            openResult = openResult(for: error)
         }
         semaphore.signal()
     }
@@ -152,8 +285,7 @@ func openPathWithApp(path: SRString, appId: SRString) -> SRString {
         openResult = OpenResult.platformError(message: "Operation timed out after 5 seconds")
     }
     
-    let json = (try? JSONEncoder().encode(openResult)) ?? Data()
-    return SRString(String(data: json, encoding: .utf8) ?? "{}")
+    return openResult
 }
 
 @_cdecl("open_paths_with_app")
@@ -174,7 +306,7 @@ func openPathsWithApp(paths: SRString, appId: SRString) -> SRString {
     
     NSWorkspace.shared.open(fileURLs, withApplicationAt: appURL, configuration: config) { _, error in
         if let error = error {
-            openResult = OpenResult.platformError(message: error.localizedDescription)
+            openResult = openResult(for: error)
         }
         semaphore.signal()
     }
@@ -190,8 +322,72 @@ func openPathsWithApp(paths: SRString, appId: SRString) -> SRString {
     return SRString(String(data: json, encoding: .utf8) ?? "{}")
 }
 
+@_cdecl("open_url")
+func openURL(url: SRString) -> SRString {
+    let result: OpenResult
+    if let parsed = URL(string: url.toString()) {
+        result = NSWorkspace.shared.open(parsed)
+            ? OpenResult.success
+            : OpenResult.platformError(message: "No application can open \(url.toString())")
+    } else {
+        result = OpenResult.platformError(message: "Invalid URL: \(url.toString())")
+    }
+
+    let json = (try? JSONEncoder().encode(result)) ?? Data()
+    return SRString(String(data: json, encoding: .utf8) ?? "{}")
+}
+
+@_cdecl("reveal_path")
+func revealPath(path: SRString) -> SRString {
+    let url = URL(fileURLWithPath: path.toString())
+    var isDirectory: ObjCBool = false
+    let result: OpenResult
+
+    if FileManager.default.fileExists(atPath: url.path, isDirectory: &isDirectory) {
+        if isDirectory.boolValue {
+            result = showFolder(url)
+        } else {
+            NSWorkspace.shared.activateFileViewerSelecting([url])
+            result = OpenResult.success
+        }
+    } else {
+        // The item is gone, but landing in its folder is still the closest match.
+        let parent = url.deletingLastPathComponent()
+        result = FileManager.default.fileExists(atPath: parent.path)
+            ? showFolder(parent)
+            : OpenResult.fileNotFound(path: url.path)
+    }
+
+    let json = (try? JSONEncoder().encode(result)) ?? Data()
+    return SRString(String(data: json, encoding: .utf8) ?? "{}")
+}
+
+/// Opens a Finder window on the folder with nothing selected. Rooting the
+/// viewer avoids NSWorkspace.open, which would launch bundles like .app.
+func showFolder(_ url: URL) -> OpenResult {
+    NSWorkspace.shared.selectFile(nil, inFileViewerRootedAtPath: url.path)
+        ? OpenResult.success
+        : OpenResult.platformError(message: "Failed to show folder")
+}
+
+/// Dismissing a Launch Services prompt (e.g. "are you sure you want to open
+/// this downloaded app?") is the user's choice, not a failure.
+func openResult(for error: Error) -> OpenResult {
+    let nsError = error as NSError
+    if nsError.domain == NSCocoaErrorDomain && nsError.code == NSUserCancelledError {
+        return OpenResult.cancelled
+    }
+    return OpenResult.platformError(
+        message: error.localizedDescription,
+        code: Int32(truncatingIfNeeded: nsError.code)
+    )
+}
+
 func getAppsLegacy(for url: URL) -> [URL] {
-    // Legacy implementation for macOS < 12
-    // For now, return empty array - can implement LSCopyAllRoleHandlersForContentType if needed
-    return []
+    guard let appURLs = LSCopyApplicationURLsForURL(url as CFURL, .all)?
+        .takeRetainedValue() as? [URL] else {
+        return []
+    }
+
+    return appURLs
 }
@@ -1,43 +1,135 @@
-use file_opening::{FileOpener, OpenResult, OpenWithApp};
+use file_opening::{
+	check_app_id, FileOpenError, FileOpener, LaunchSpec, OpenResult, OpenWithApp,
+	OpenerCapabilities, UriPolicy,
+};
 use std::path::{Path, PathBuf};
 use swift_rs::*;
 
 swift!(fn get_apps_for_path(path: &SRString) -> SRString);
+swift!(fn get_apps_for_mime_type(mime: &SRString) -> SRString);
+swift!(fn get_default_app_for_path(path: &SRString) -> SRString);
 swift!(fn open_path_with_default(path: &SRString) -> SRString);
 swift!(fn open_path_with_app(path: &SRString, app_id: &SRString) -> SRString);
+swift!(fn open_path_with_app_args(path: &SRString, app_id: &SRString, args: &SRString) -> SRString);
+swift!(fn open_path_with_launch_spec(
+	path: &SRString,
+	app_id: &SRString,
+	args: &SRString,
+	env: &SRString,
+	new_instance: Bool
+) -> SRString);
 swift!(fn open_paths_with_app(paths: &SRString, app_id: &SRString) -> SRString);
+swift!(fn open_url(url: &SRString) -> SRString);
+swift!(fn reveal_path(path: &SRString) -> SRString);
 
+/// Launch Services backed opener.
+///
+/// App ids are bundle identifiers (e.g. `com.apple.TextEdit`), resolved back
+/// to an install location on every launch so moved or updated apps still work.
 pub struct MacFileOpener;
 
 impl FileOpener for MacFileOpener {
-	fn get_apps_for_file(&self, path: &Path) -> Result<Vec<OpenWithApp>, String> {
+	fn get_apps_for_file(&self, path: &Path) -> Result<Vec<OpenWithApp>, FileOpenError> {
 		let path_str = path.to_string_lossy().to_string();
 		let sr_path = SRString::from(path_str.as_str());
 
 		unsafe {
 			let result = get_apps_for_path(&sr_path).to_string();
-			serde_json::from_str(&result).map_err(|e| e.to_string())
+			decode_apps(&result)
 		}
 	}
 
-	fn open_with_default(&self, path: &Path) -> Result<OpenResult, String> {
+	fn get_apps_for_mime_type(&self, mime: &str) -> Result<Vec<OpenWithApp>, FileOpenError> {
+		let sr_mime = SRString::from(mime);
+
+		unsafe {
+			let result = get_apps_for_mime_type(&sr_mime).to_string();
+			decode_apps(&result)
+		}
+	}
+
+	fn get_default_app(&self, path: &Path) -> Result<Option<OpenWithApp>, FileOpenError> {
+		let path_str = path.to_string_lossy().to_string();
+		let sr_path = SRString::from(path_str.as_str());
+
+		unsafe {
+			let result = get_default_app_for_path(&sr_path).to_string();
+			serde_json::from_str(&result).map_err(|e| FileOpenError::Other(e.to_string()))
+		}
+	}
+
+	fn can_open(&self, path: &Path) -> Result<bool, FileOpenError> {
+		// One Launch Services lookup instead of listing every app.
+		Ok(self.get_default_app(path)?.is_some())
+	}
+
+	fn open_with_default(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
 		let path_str = path.to_string_lossy().to_string();
 		let sr_path = SRString::from(path_str.as_str());
 
 		unsafe {
 			let result = open_path_with_default(&sr_path).to_string();
-			serde_json::from_str(&result).map_err(|e| e.to_string())
+			serde_json::from_str(&result).map_err(|e| FileOpenError::Other(e.to_string()))
 		}
 	}
 
-	fn open_with_app(&self, path: &Path, app_id: &str) -> Result<OpenResult, String> {
+	fn open_with_app(&self, path: &Path, app_id: &str) -> Result<OpenResult, FileOpenError> {
+		check_app_id(app_id)?;
 		let path_str = path.to_string_lossy().to_string();
 		let sr_path = SRString::from(path_str.as_str());
 		let sr_app_id = SRString::from(app_id);
 
 		unsafe {
 			let result = open_path_with_app(&sr_path, &sr_app_id).to_string();
-			serde_json::from_str(&result).map_err(|e| e.to_string())
+			serde_json::from_str(&result).map_err(|e| FileOpenError::Other(e.to_string()))
+		}
+	}
+
+	fn open_with_app_args(
+		&self,
+		path: &Path,
+		app_id: &str,
+		args: &[&str],
+	) -> Result<OpenResult, FileOpenError> {
+		check_app_id(app_id)?;
+		let path_str = path.to_string_lossy().to_string();
+		let sr_path = SRString::from(path_str.as_str());
+		let sr_app_id = SRString::from(app_id);
+		// Null-delimited like the multi-file paths
+		let sr_args = SRString::from(args.join("\0").as_str());
+
+		unsafe {
+			let result = open_path_with_app_args(&sr_path, &sr_app_id, &sr_args).to_string();
+			serde_json::from_str(&result).map_err(|e| FileOpenError::Other(e.to_string()))
+		}
+	}
+
+	fn launch(&self, spec: &LaunchSpec) -> Result<OpenResult, FileOpenError> {
+		check_app_id(&spec.app_id)?;
+		spec.check_working_dir()?;
+
+		let path_str = spec.path.to_string_lossy().to_string();
+		let sr_path = SRString::from(path_str.as_str());
+		let sr_app_id = SRString::from(spec.app_id.as_str());
+		let sr_args = SRString::from(spec.args.join("\0").as_str());
+		let env = spec
+			.env
+			.iter()
+			.map(|(key, value)| format!("{key}={value}"))
+			.collect::<Vec<_>>()
+			.join("\0");
+		let sr_env = SRString::from(env.as_str());
+
+		unsafe {
+			let result = open_path_with_launch_spec(
+				&sr_path,
+				&sr_app_id,
+				&sr_args,
+				&sr_env,
+				spec.new_instance,
+			)
+			.to_string();
+			serde_json::from_str(&result).map_err(|e| FileOpenError::Other(e.to_string()))
 		}
 	}
 
@@ -45,7 +137,8 @@ impl FileOpener for MacFileOpener {
 		&self,
 		paths: &[PathBuf],
 		app_id: &str,
-	) -> Result<Vec<OpenResult>, String> {
+	) -> Result<Vec<OpenResult>, FileOpenError> {
+		check_app_id(app_id)?;
 		// Use null-delimited paths for multiple files
 		let paths_str = paths
 			.iter()
@@ -57,7 +150,79 @@ impl FileOpener for MacFileOpener {
 
 		unsafe {
 			let result = open_paths_with_app(&sr_paths, &sr_app_id).to_string();
-			serde_json::from_str(&result).map_err(|e| e.to_string())
+			serde_json::from_str(&result).map_err(|e| FileOpenError::Other(e.to_string()))
+		}
+	}
+
+	/// `open_paths_with_app` already hands the app every file in one go.
+	fn open_many_with_app(
+		&self,
+		paths: &[&Path],
+		app_id: &str,
+	) -> Result<OpenResult, FileOpenError> {
+		let paths = paths
+			.iter()
+			.map(|path| path.to_path_buf())
+			.collect::<Vec<_>>();
+		Ok(self
+			.open_files_with_app(&paths, app_id)?
+			.into_iter()
+			.find(|result| !matches!(result, OpenResult::Success { .. }))
+			.unwrap_or_else(OpenResult::success))
+	}
+
+	fn open_uri_with_policy(
+		&self,
+		uri: &str,
+		policy: &UriPolicy,
+	) -> Result<OpenResult, FileOpenError> {
+		policy.check(uri)?;
+		let sr_uri = SRString::from(uri);
+
+		unsafe {
+			let result = open_url(&sr_uri).to_string();
+			serde_json::from_str(&result).map_err(|e| FileOpenError::Other(e.to_string()))
+		}
+	}
+
+	fn reveal(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+		let path_str = path.to_string_lossy().to_string();
+		let sr_path = SRString::from(path_str.as_str());
+
+		unsafe {
+			let result = reveal_path(&sr_path).to_string();
+			serde_json::from_str(&result).map_err(|e| FileOpenError::Other(e.to_string()))
 		}
 	}
+
+	fn capabilities(&self) -> OpenerCapabilities {
+		OpenerCapabilities {
+			reveal: true,
+			elevated: false,
+			verbs: false,
+			dialog: false,
+			executable: false,
+			launch: false,
+			wait: false,
+			shell_items: false,
+			print: false,
+			edit: false,
+			properties: false,
+			icons: false,
+			thumbnails: false,
+			set_default: false,
+			uri: true,
+			trash: false,
+			mail: false,
+		}
+	}
+}
+
+/// Parses an app list from Swift and puts the default first. Launch Services
+/// already lists apps in its preference order, which the stable sort keeps.
+fn decode_apps(json: &str) -> Result<Vec<OpenWithApp>, FileOpenError> {
+	let mut apps: Vec<OpenWithApp> =
+		serde_json::from_str(json).map_err(|e| FileOpenError::Other(e.to_string()))?;
+	file_opening::sort_by_relevance(&mut apps);
+	Ok(apps)
 }
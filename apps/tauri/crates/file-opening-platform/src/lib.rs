@@ -0,0 +1,55 @@
+//! # Platform opener
+//!
+//! Picks the `file-opening` backend for the target OS so consumers don't
+//! have to `#[cfg]` between the concrete openers themselves. This lives
+//! apart from the core crate because every backend depends on that one.
+
+use file_opening::FileOpener;
+
+/// The file opener for the current platform, in its default configuration.
+///
+/// Targets without a backend get a [`file_opening::NoopFileOpener`], whose
+/// launches all report that they aren't supported.
+///
+/// The opener is `Send + Sync` and can be shared across threads freely. On
+/// Windows that holds because no COM object ever leaves the thread that
+/// created it: each call initializes COM on its own thread and handlers are
+/// cached per thread, so nothing needs to be marshalled between apartments.
+/// Backends added here must keep to the same rule.
+pub fn default_opener() -> Box<dyn FileOpener> {
+	opener_for_window(None)
+}
+
+/// The file opener for the current platform, with the shell's dialogs
+/// parented to `owner`, a raw window handle.
+///
+/// Only Windows uses the handle, as the `HWND` that Open With, UAC and error
+/// dialogs are owned by. `None` (or `Some(0)`) gives [`default_opener`].
+pub fn opener_for_window(owner: Option<isize>) -> Box<dyn FileOpener> {
+	#[cfg(target_os = "windows")]
+	{
+		Box::new(
+			file_opening_windows::WindowsFileOpener::builder()
+				.owner_window(owner.unwrap_or(0))
+				.build(),
+		)
+	}
+
+	#[cfg(not(target_os = "windows"))]
+	let _ = owner;
+
+	#[cfg(target_os = "macos")]
+	{
+		Box::new(file_opening_macos::MacFileOpener)
+	}
+
+	#[cfg(target_os = "linux")]
+	{
+		Box::new(file_opening_linux::LinuxFileOpener)
+	}
+
+	#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+	{
+		Box::new(file_opening::NoopFileOpener)
+	}
+}
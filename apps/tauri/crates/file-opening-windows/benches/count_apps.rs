@@ -0,0 +1,50 @@
+//! # Counting versus listing
+//!
+//! Compares what an "Open With (3)" badge costs against building the list it
+//! counts, with every cache cleared before each call so the shell is asked
+//! afresh each time. Run with `cargo bench -p file-opening-windows`.
+
+#[cfg(windows)]
+fn main() {
+	use std::path::Path;
+	use std::time::{Duration, Instant};
+
+	use file_opening::FileOpener;
+	use file_opening_windows::WindowsFileOpener;
+
+	const ROUNDS: u32 = 20;
+
+	let opener = WindowsFileOpener::default();
+	let time = |label: &str, path: &Path, call: &dyn Fn(&Path)| {
+		let mut total = Duration::ZERO;
+		for _ in 0..ROUNDS {
+			opener.clear_cache();
+			opener.clear_icon_cache();
+
+			let started = Instant::now();
+			call(path);
+			total += started.elapsed();
+		}
+		println!(
+			"{:<8} {label:<28} {:>10.2?}",
+			path.display(),
+			total / ROUNDS
+		);
+	};
+
+	for name in ["a.txt", "a.png", "a.html", "a.mp4"] {
+		let path = Path::new(name);
+		time("get_apps_for_file", path, &|path| {
+			let _ = opener.get_apps_for_file(path);
+		});
+		time("count_apps_for_file", path, &|path| {
+			let _ = opener.count_apps_for_file(path);
+		});
+		time("count_apps_for_file_up_to 1", path, &|path| {
+			let _ = opener.count_apps_for_file_up_to(path, 1);
+		});
+	}
+}
+
+#[cfg(not(windows))]
+fn main() {}
@@ -0,0 +1,239 @@
+//! # Association queries
+//!
+//! Thin wrappers over `AssocQueryStringW` for the cheap single-value lookups
+//! (default executable, friendly names, commands) that don't need a full
+//! `SHAssocEnumHandlers` walk.
+
+use std::path::{Path, PathBuf};
+use windows::core::{HSTRING, PCWSTR, PWSTR};
+use windows::Win32::System::Registry::HKEY_CLASSES_ROOT;
+use windows::Win32::UI::Shell::{
+	AssocQueryStringW, IAssocHandler, ASSOCF, ASSOCF_NONE, ASSOCF_OPEN_BYEXENAME, ASSOCSTR,
+	ASSOCSTR_COMMAND, ASSOCSTR_DELEGATEEXECUTE, ASSOCSTR_EXECUTABLE, ASSOCSTR_FRIENDLYAPPNAME,
+	ASSOCSTR_PROGID,
+};
+
+use crate::{registry, take_co_string};
+
+/// Queries a single association string for `ext` (e.g. `.txt`), or for an
+/// executable name when `flags` include `ASSOCF_OPEN_BYEXENAME`.
+///
+/// Returns `None` for any failure, including `ERROR_NO_ASSOCIATION`, because
+/// callers treat "nothing registered" and "couldn't read it" the same way.
+pub(crate) fn query_string(flags: ASSOCF, kind: ASSOCSTR, ext: &str) -> Option<String> {
+	query_verb_string(flags, kind, ext, None)
+}
+
+/// [`query_string`] for a specific shell verb (e.g. `print`) instead of the
+/// type's default one.
+fn query_verb_string(
+	flags: ASSOCF,
+	kind: ASSOCSTR,
+	ext: &str,
+	verb: Option<&str>,
+) -> Option<String> {
+	let assoc = HSTRING::from(ext);
+	let verb = verb.map(HSTRING::from);
+	let extra = verb.as_ref().map_or(PCWSTR::null(), |v| PCWSTR(v.as_ptr()));
+	let mut len = 0u32;
+
+	unsafe {
+		// A null output buffer makes the call report the required length
+		// (including the terminator) and return S_FALSE.
+		let hr = AssocQueryStringW(flags, kind, &assoc, extra, PWSTR::null(), &mut len);
+		if hr.is_err() || len == 0 {
+			return None;
+		}
+
+		let mut buffer = vec![0u16; len as usize];
+		let hr = AssocQueryStringW(
+			flags,
+			kind,
+			&assoc,
+			extra,
+			PWSTR(buffer.as_mut_ptr()),
+			&mut len,
+		);
+		if hr.is_err() {
+			return None;
+		}
+
+		let end = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+		let value = String::from_utf16(&buffer[..end]).ok()?;
+
+		if value.is_empty() {
+			None
+		} else {
+			Some(value)
+		}
+	}
+}
+
+/// Whether anything is registered to open `ext` by default.
+///
+/// Packaged apps have no executable to report and launch through a
+/// `DelegateExecute` COM server instead, so either counts.
+pub(crate) fn has_default(ext: &str) -> bool {
+	query_string(ASSOCF_NONE, ASSOCSTR_EXECUTABLE, ext).is_some()
+		|| query_string(ASSOCF_NONE, ASSOCSTR_DELEGATEEXECUTE, ext).is_some()
+}
+
+/// Every extension under `HKCR` with a default, lowercased and sorted.
+///
+/// Types that only list apps under `OpenWithProgids` open with nothing by
+/// default, so they're left out.
+pub(crate) fn extensions() -> Vec<String> {
+	let mut extensions = registry::subkey_names(HKEY_CLASSES_ROOT, "")
+		.into_iter()
+		.filter(|key| key.len() > 1 && key.starts_with('.'))
+		.map(|key| key.to_lowercase())
+		.filter(|ext| has_default(ext))
+		.collect::<Vec<_>>();
+
+	extensions.sort();
+	extensions.dedup();
+	extensions
+}
+
+/// Whether the association for `ext` defines `verb`, i.e. has a command for
+/// it under its `shell` key.
+pub(crate) fn has_verb(ext: &str, verb: &str) -> bool {
+	query_verb_string(ASSOCF_NONE, ASSOCSTR_COMMAND, ext, Some(verb)).is_some()
+}
+
+/// Whether the verb a double-click on an `ext` file runs has the same
+/// command as `open`, e.g. not a media type whose default is `play`.
+pub(crate) fn default_verb_is_open(ext: &str) -> bool {
+	let default = query_string(ASSOCF_NONE, ASSOCSTR_COMMAND, ext);
+	default.is_some()
+		&& default == query_verb_string(ASSOCF_NONE, ASSOCSTR_COMMAND, ext, Some("open"))
+}
+
+/// The verbs registered for `ext`, in the order Explorer would find them:
+/// the ProgID's own, then those shared by the extension and by its perceived
+/// type (`image`, `video`, ...) under `SystemFileAssociations`.
+///
+/// Verbs marked `LegacyDisable` or `ProgrammaticAccessOnly` are skipped, as
+/// Explorer hides them too. Names compare case-insensitively, like the
+/// registry does.
+pub(crate) fn verbs(ext: &str) -> Vec<String> {
+	let mut classes = Vec::new();
+	if let Some(prog_id) = query_string(ASSOCF_NONE, ASSOCSTR_PROGID, ext) {
+		classes.push(prog_id);
+	}
+	classes.push(format!(r"SystemFileAssociations\{ext}"));
+	if let Some(perceived) = registry::read_string(HKEY_CLASSES_ROOT, ext, Some("PerceivedType")) {
+		classes.push(format!(r"SystemFileAssociations\{perceived}"));
+	}
+
+	let mut verbs: Vec<String> = Vec::new();
+	for class in classes {
+		let shell = format!(r"{class}\shell");
+		for verb in registry::subkey_names(HKEY_CLASSES_ROOT, &shell) {
+			let key = format!(r"{shell}\{verb}");
+			let hidden = ["LegacyDisable", "ProgrammaticAccessOnly"]
+				.iter()
+				.any(|flag| registry::has_value(HKEY_CLASSES_ROOT, &key, flag));
+
+			if !hidden && !verbs.iter().any(|v| v.eq_ignore_ascii_case(&verb)) {
+				verbs.push(verb);
+			}
+		}
+	}
+	verbs
+}
+
+/// Resolves the binary behind an enumerated handler's `GetName`.
+///
+/// Classic handlers report the executable's full path. Others report a bare
+/// exe name registered under `App Paths` or `Applications`, which the shell
+/// can still resolve; packaged apps report neither and get `None`.
+pub(crate) fn handler_executable(name: &str) -> Option<PathBuf> {
+	handler_target(name).filter(|path| path.is_file())
+}
+
+/// The executable a handler was registered with when it no longer exists,
+/// e.g. after an uninstall that left its registration behind.
+pub(crate) fn missing_handler_executable(name: &str) -> Option<PathBuf> {
+	handler_target(name).filter(|path| {
+		std::fs::metadata(path).is_err_and(|e| e.kind() == std::io::ErrorKind::NotFound)
+	})
+}
+
+/// The path a handler's `GetName` points at, whether or not it exists.
+fn handler_target(name: &str) -> Option<PathBuf> {
+	let path = Path::new(name);
+	if path.is_absolute() {
+		return Some(path.to_path_buf());
+	}
+
+	query_string(ASSOCF_OPEN_BYEXENAME, ASSOCSTR_EXECUTABLE, name).map(PathBuf::from)
+}
+
+/// The name to show for an enumerated handler whose `GetName` is `name`.
+///
+/// `GetName` is an executable path or an AUMID, so it's only the last
+/// resort: the handler's `GetUIName` comes first, then the friendly app name
+/// registered for the executable, both of which are localized.
+pub(crate) fn handler_display_name(name: &str, handler: &IAssocHandler) -> String {
+	let ui_name = unsafe { handler.GetUIName() }
+		.ok()
+		.and_then(|ui_name| unsafe { take_co_string(ui_name) }.ok());
+
+	display_name(ui_name, || friendly_app_name(name), name)
+}
+
+/// The localized name registered for the executable `exe`, e.g. `Notepad`
+/// for `C:\Windows\System32\notepad.exe`.
+pub(crate) fn friendly_app_name(exe: &str) -> Option<String> {
+	query_string(ASSOCF_OPEN_BYEXENAME, ASSOCSTR_FRIENDLYAPPNAME, exe)
+}
+
+/// The first non-blank of `ui_name` and `friendly`, else `name`.
+fn display_name(
+	ui_name: Option<String>,
+	friendly: impl FnOnce() -> Option<String>,
+	name: &str,
+) -> String {
+	let usable = |candidate: &String| !candidate.trim().is_empty();
+
+	ui_name
+		.filter(usable)
+		.or_else(|| friendly().filter(usable))
+		.unwrap_or_else(|| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn uninstalled_handlers_are_missing() {
+		let gone = r"C:\Program Files\Uninstalled App\app.exe";
+		assert_eq!(missing_handler_executable(gone), Some(PathBuf::from(gone)));
+		assert_eq!(handler_executable(gone), None);
+
+		let notepad = r"C:\Windows\System32\notepad.exe";
+		assert_eq!(missing_handler_executable(notepad), None);
+		assert_eq!(handler_executable(notepad), Some(PathBuf::from(notepad)));
+	}
+
+	#[test]
+	fn handlers_are_shown_by_their_friendly_name() {
+		let raw = r"C:\Program Files\Editor\ed.exe";
+
+		assert_eq!(
+			display_name(Some("Editor".into()), || unreachable!(), raw),
+			"Editor"
+		);
+		assert_eq!(
+			display_name(None, || Some("Editor Pro".into()), raw),
+			"Editor Pro"
+		);
+		assert_eq!(display_name(Some(" ".into()), || None, raw), raw);
+
+		let notepad = r"C:\Windows\System32\notepad.exe";
+		let friendly = friendly_app_name(notepad).unwrap();
+		assert_ne!(friendly, notepad);
+	}
+}
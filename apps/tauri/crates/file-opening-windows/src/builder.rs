@@ -0,0 +1,147 @@
+//! # Opener configuration
+//!
+//! Icon extraction and content sniffing both cost I/O on every uncached
+//! listing, so callers that don't show icons or don't trust file content can
+//! turn them off per opener. How long a launch waits on an unresponsive
+//! network path is set here too.
+
+use std::time::Duration;
+
+use windows::Win32::UI::Shell::{ASSOC_FILTER, ASSOC_FILTER_NONE, ASSOC_FILTER_RECOMMENDED};
+
+use crate::dry_run::DryRunLog;
+use crate::WindowsFileOpener;
+
+/// Which handlers `get_apps_for_file` lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AssocFilter {
+	/// The handlers the shell's own Open With menu shows.
+	#[default]
+	Recommended,
+	/// Every registered handler, as `get_all_apps_for_file` returns.
+	All,
+}
+
+impl AssocFilter {
+	pub(crate) fn as_raw(self) -> ASSOC_FILTER {
+		match self {
+			Self::Recommended => ASSOC_FILTER_RECOMMENDED,
+			Self::All => ASSOC_FILTER_NONE,
+		}
+	}
+}
+
+/// Builds a configured [`WindowsFileOpener`]; see
+/// [`WindowsFileOpener::builder`].
+#[derive(Debug, Clone, Default)]
+pub struct WindowsFileOpenerBuilder {
+	opener: WindowsFileOpener,
+}
+
+impl WindowsFileOpenerBuilder {
+	/// Whether listed apps carry icons. Defaults to `true`.
+	pub fn load_icons(mut self, load: bool) -> Self {
+		self.opener.load_icons = load;
+		self
+	}
+
+	/// Whether files without an extension are sniffed for their type.
+	/// Defaults to `true`.
+	pub fn sniff_extensionless(mut self, sniff: bool) -> Self {
+		self.opener.sniff_extensionless = sniff;
+		self
+	}
+
+	/// Whether `open_with_default` opens a `.lnk` shortcut's target rather
+	/// than the shortcut. Defaults to `false`.
+	pub fn follow_shortcuts(mut self, follow: bool) -> Self {
+		self.opener.follow_shortcuts = follow;
+		self
+	}
+
+	/// Whether `open_with_default` always uses the `open` verb rather than
+	/// the file's default one. Defaults to `false`, so a type whose default
+	/// action is `play` or `edit` is opened as a double-click in Explorer
+	/// would open it.
+	pub fn force_open_verb(mut self, force: bool) -> Self {
+		self.opener.force_open_verb = force;
+		self
+	}
+
+	/// Whether listings leave out handlers whose executable no longer exists.
+	/// Defaults to `false`, listing them like the shell does.
+	pub fn filter_unavailable(mut self, filter: bool) -> Self {
+		self.opener.filter_unavailable = filter;
+		self
+	}
+
+	/// Which handlers `get_apps_for_file` lists. Defaults to
+	/// [`AssocFilter::Recommended`].
+	pub fn filter(mut self, filter: AssocFilter) -> Self {
+		self.opener.filter = filter;
+		self
+	}
+
+	/// Icon edge length in pixels. Defaults to 32.
+	///
+	/// Shorthand for `icon_sizes(&[size])`.
+	pub fn icon_size(self, size: u32) -> Self {
+		self.icon_sizes(&[size])
+	}
+
+	/// Icon sizes in pixels to extract for every app, e.g. `&[16, 32, 256]`.
+	///
+	/// Each is snapped to the nearest system image list (16, 32, 48 or 256
+	/// px) and all of them land in `OpenWithApp::icons`; the first also fills
+	/// `OpenWithApp::icon`. An empty list is the same as `load_icons(false)`.
+	pub fn icon_sizes(mut self, sizes: &[u32]) -> Self {
+		self.opener.icon_sizes = sizes.to_vec();
+		self
+	}
+
+	/// Whether a launch refused with `OpenResult::RequiresElevation` is
+	/// retried through the shell's `runas` verb, which shows the UAC prompt.
+	/// Defaults to `false`, leaving it to the caller.
+	///
+	/// Only launches that start an executable directly (`launch` with
+	/// `new_instance`, say) are ever refused; the shell prompts for the rest
+	/// by itself.
+	pub fn elevate_when_required(mut self, elevate: bool) -> Self {
+		self.opener.elevate_when_required = elevate;
+		self
+	}
+
+	/// The window, as a raw `HWND`, that dialogs shown for a launch (Open
+	/// With, UAC prompts, shell errors) are parented to, so they stay in
+	/// front of it. Defaults to `0`: no owner, so they may open behind the
+	/// app's window.
+	pub fn owner_window(mut self, hwnd: isize) -> Self {
+		self.opener.owner = hwnd;
+		self
+	}
+
+	/// How long a file on a share or mapped drive gets to answer before a
+	/// launch gives up on it with `OpenResult::Unreachable`. Defaults to 3
+	/// seconds.
+	///
+	/// Without the check an offline server holds the launch for the SMB
+	/// client's own timeout, often half a minute. Local paths aren't affected.
+	pub fn network_timeout(mut self, timeout: Duration) -> Self {
+		self.opener.network_timeout = timeout;
+		self
+	}
+
+	/// Whether launches are resolved but not performed, so nothing is
+	/// started. Defaults to `false`.
+	///
+	/// See [`dry_run`](crate::dry_run) for what is still checked, and
+	/// [`WindowsFileOpener::dry_run_launches`] for what would have run.
+	pub fn dry_run(mut self, dry_run: bool) -> Self {
+		self.opener.dry_run = dry_run.then(DryRunLog::default);
+		self
+	}
+
+	pub fn build(self) -> WindowsFileOpener {
+		self.opener
+	}
+}
@@ -0,0 +1,502 @@
+//! # Handler cache
+//!
+//! `SHAssocEnumHandlers` walks the registry and instantiates every handler, so
+//! enumerations are kept per extension and filter, and reused by listing,
+//! lookup by name and default resolution alike. Only the handlers themselves
+//! are kept eagerly: names, apps and icons are each worked out the first time
+//! something needs them, so a lookup by name stops reading names at the match.
+//!
+//! `IAssocHandler` pointers belong to the apartment that created them, so each
+//! thread keeps its own small LRU. Invalidation is process-wide instead: a
+//! shared generation counter is bumped by `clear_cache` and by the watcher in
+//! [`crate::watcher`], and every thread drops its entries the next time it
+//! notices the counter moved. The process-wide [`crate::icon_cache`] follows
+//! the same counter.
+
+use file_opening::{AppIcon, AppKind, CancellationToken, FileOpenError, OpenWithApp};
+use std::cell::{OnceCell, RefCell};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use windows::Win32::Foundation::S_OK;
+use windows::Win32::UI::Shell::{IAssocHandler, ASSOC_FILTER};
+
+use crate::{
+	assoc, clients, icon, icon_cache, packaged, progid, stable_id, take_co_string, watcher,
+	LOG_TARGET,
+};
+
+/// Extensions remembered per thread; a file browser rarely juggles more.
+const CAPACITY: usize = 32;
+
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+	static CACHE: RefCell<Lru<Rc<Handlers>>> = const { RefCell::new(Lru::new(CAPACITY)) };
+}
+
+/// One enumeration of the handlers for an extension.
+pub(crate) struct Handlers {
+	ext: String,
+	entries: Vec<Entry>,
+	apps: OnceCell<Vec<(OpenWithApp, IAssocHandler)>>,
+	/// Icons per system image list, aligned with `apps`.
+	icons: RefCell<HashMap<u32, Vec<Option<AppIcon>>>>,
+}
+
+/// An enumerated handler whose name is only read once something asks for it.
+struct Entry {
+	handler: IAssocHandler,
+	name: OnceCell<Option<String>>,
+}
+
+impl Entry {
+	/// `None` for a handler that can't report a name, which nothing can pick.
+	///
+	/// Such a handler is skipped rather than failing the whole listing: one
+	/// broken registration shouldn't hide every other app for the type.
+	fn name(&self) -> Option<&str> {
+		self.name
+			.get_or_init(|| match unsafe { self.handler.GetName() } {
+				Ok(name) => {
+					let name = unsafe { take_co_string(name) }.ok();
+					tracing::trace!(target: LOG_TARGET, name, "Resolved handler name");
+					name
+				}
+				Err(e) => {
+					tracing::warn!(
+						target: LOG_TARGET,
+						"Skipping a file handler that can't report its name: {e}"
+					);
+					None
+				}
+			})
+			.as_deref()
+	}
+}
+
+impl Handlers {
+	pub fn new(ext: &str, handlers: Vec<IAssocHandler>) -> Self {
+		Self {
+			ext: ext.to_string(),
+			entries: handlers
+				.into_iter()
+				.map(|handler| Entry {
+					handler,
+					name: OnceCell::new(),
+				})
+				.collect(),
+			apps: OnceCell::new(),
+			icons: RefCell::new(HashMap::new()),
+		}
+	}
+
+	/// Whether the enumeration came back without a single handler, as it
+	/// does for a type that's known but has nothing registered to open it.
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	/// The first handler `matches` accepts. Names past it are never read.
+	pub fn find(
+		&self,
+		matches: impl Fn(&str, &IAssocHandler) -> bool,
+	) -> Option<(String, IAssocHandler)> {
+		self.named()
+			.find(|(name, handler)| matches(name, handler))
+			.map(|(name, handler)| (name.to_string(), handler.clone()))
+	}
+
+	/// How many apps [`Handlers::apps`] lists, up to `cap`, leaving out
+	/// those `skip` rejects by name.
+	///
+	/// Until the apps are built this only reads names and resolves their
+	/// executables, and stops reading at `cap`.
+	pub fn app_count(&self, cap: Option<usize>, skip: impl Fn(&str) -> bool) -> usize {
+		let cap = cap.unwrap_or(usize::MAX);
+
+		if let Some(apps) = self.apps.get() {
+			return apps
+				.iter()
+				.filter(|(app, _)| !skip(&app.id))
+				.take(cap)
+				.count();
+		}
+
+		// The same dedup as `resolved`, on the executable alone.
+		let mut seen = HashSet::new();
+		self.named()
+			.filter(|(name, _)| !skip(name))
+			.filter(|(name, _)| match assoc::handler_executable(name) {
+				Some(exe) => seen.insert(exe.to_string_lossy().to_lowercase()),
+				None => true,
+			})
+			.take(cap)
+			.count()
+	}
+
+	/// The handlers as apps in enumeration order, one per executable, with
+	/// icons at `icon_sizes` (none if empty). Icons are only extracted the
+	/// first time a size is asked for, since opening by name never needs them.
+	///
+	/// `cancel` is checked before each icon. A size whose icons weren't all
+	/// extracted isn't kept, so a later call starts it over.
+	pub fn apps(
+		&self,
+		icon_sizes: &[u32],
+		cancel: &CancellationToken,
+	) -> Result<Vec<OpenWithApp>, FileOpenError> {
+		let apps = self.resolved();
+
+		if icon_sizes.is_empty() {
+			return Ok(apps.iter().map(|(app, _)| app.clone()).collect());
+		}
+
+		let mut icons = self.icons.borrow_mut();
+		for list in icon_sizes.iter().map(|&size| icon::image_list_for(size)) {
+			if icons.contains_key(&list) {
+				continue;
+			}
+
+			let mut extracted = Vec::with_capacity(apps.len());
+			for (app, handler) in apps {
+				cancel.check()?;
+				extracted.push(icon_cache::get_or_extract(
+					app.exe_path.as_deref(),
+					list,
+					|| icon::icon_for_handler(handler, list),
+				));
+			}
+			icons.insert(list, extracted);
+		}
+
+		Ok(apps
+			.iter()
+			.enumerate()
+			.map(|(index, (app, _))| {
+				let (icon, renditions) =
+					icon::assemble(icon_sizes, |list| icons[&list][index].clone());
+
+				OpenWithApp {
+					icon,
+					icons: renditions,
+					accelerator: None,
+					..app.clone()
+				}
+			})
+			.collect())
+	}
+
+	/// Fills in icons at `icon_sizes` for `apps`, taken from
+	/// [`Handlers::apps`] without any, extracting only theirs.
+	///
+	/// `cancel` is checked before each app.
+	pub fn add_icons(
+		&self,
+		apps: &mut [OpenWithApp],
+		icon_sizes: &[u32],
+		cancel: &CancellationToken,
+	) -> Result<(), FileOpenError> {
+		if icon_sizes.is_empty() {
+			return Ok(());
+		}
+
+		let resolved = self.resolved();
+		for app in apps {
+			cancel.check()?;
+			let Some((_, handler)) = resolved.iter().find(|(known, _)| known.id == app.id) else {
+				continue;
+			};
+
+			(app.icon, app.icons) = icon::assemble(icon_sizes, |list| {
+				icon_cache::get_or_extract(app.exe_path.as_deref(), list, || {
+					icon::icon_for_handler(handler, list)
+				})
+			});
+		}
+		Ok(())
+	}
+
+	/// The handlers that report a name, reading names as the iteration
+	/// reaches them.
+	fn named(&self) -> impl Iterator<Item = (&str, &IAssocHandler)> {
+		self.entries
+			.iter()
+			.filter_map(|entry| Some((entry.name()?, &entry.handler)))
+	}
+
+	/// The apps behind [`Handlers::apps`], built on first use.
+	fn resolved(&self) -> &[(OpenWithApp, IAssocHandler)] {
+		self.apps.get_or_init(|| {
+			let categories = clients::categories();
+			let apps = self
+				.named()
+				.map(|(name, handler)| {
+					let exe_path = assoc::handler_executable(name);
+					let category = exe_path.as_deref().and_then(|exe| {
+						categories
+							.get(&exe.to_string_lossy().to_lowercase())
+							.map(|category| category.to_string())
+					});
+					let aumid = match exe_path {
+						Some(_) => None,
+						None => packaged::app_user_model_id(&self.ext, name, handler),
+					};
+					let kind = if exe_path.is_some() {
+						AppKind::Desktop
+					} else if aumid.is_some() {
+						AppKind::Packaged
+					} else {
+						AppKind::Unknown
+					};
+					let progid = progid::for_handler(&self.ext, name, aumid.as_deref());
+					let clsid = progid.as_deref().and_then(progid::delegate_clsid);
+
+					let app = OpenWithApp {
+						id: stable_id::of(name, exe_path.as_deref(), aumid.as_deref()),
+						name: assoc::handler_display_name(name, handler),
+						exe_path,
+						// S_FALSE means enumerated but not recommended.
+						recommended: unsafe { handler.IsRecommended() } == S_OK,
+						is_default: false,
+						kind,
+						category,
+						progid,
+						clsid,
+						icon: None,
+						icons: Vec::new(),
+						accelerator: None,
+					};
+					(app, handler.clone())
+				})
+				.collect::<Vec<_>>();
+
+			dedup_by_executable(apps, |(app, _)| app)
+		})
+	}
+}
+
+/// Drops later entries that launch the same executable as an earlier one.
+///
+/// One app can register several verbs that the shell enumerates as separate
+/// handlers under slightly different names, so names can't be the key. Paths
+/// compare case-insensitively like the file system does, and entries without a
+/// resolvable executable are always kept.
+fn dedup_by_executable<T>(items: Vec<T>, app: impl Fn(&T) -> &OpenWithApp) -> Vec<T> {
+	let mut seen = HashSet::new();
+
+	items
+		.into_iter()
+		.filter(|item| match &app(item).exe_path {
+			Some(exe) => seen.insert(exe.to_string_lossy().to_lowercase()),
+			None => true,
+		})
+		.collect()
+}
+
+/// Returns the cached handlers for `ext` under `filter`, running `enumerate`
+/// on a miss.
+pub(crate) fn handlers_for(
+	ext: &str,
+	filter: ASSOC_FILTER,
+	enumerate: impl FnOnce(&str, ASSOC_FILTER) -> Result<Handlers, FileOpenError>,
+) -> Result<Rc<Handlers>, FileOpenError> {
+	watcher::ensure_started();
+
+	let key = format!("{}|{}", ext.to_lowercase(), filter.0);
+	// Read before enumerating so a change that lands mid-enumeration still
+	// marks the result stale.
+	let generation = GENERATION.load(Ordering::Acquire);
+
+	if let Some(hit) = CACHE.with_borrow_mut(|cache| cache.get(&key, generation)) {
+		return Ok(hit);
+	}
+
+	let handlers = Rc::new(enumerate(ext, filter)?);
+	CACHE.with_borrow_mut(|cache| cache.insert(key, Rc::clone(&handlers), generation));
+	Ok(handlers)
+}
+
+/// Drops the current thread's entries, releasing their handlers now.
+///
+/// Does nothing if the cache itself has already been torn down.
+pub(crate) fn clear_thread() {
+	let _ = CACHE.try_with(|cache| cache.borrow_mut().clear());
+}
+
+/// Marks every thread's cache stale.
+pub(crate) fn invalidate() {
+	GENERATION.fetch_add(1, Ordering::AcqRel);
+}
+
+/// The counter [`invalidate`] bumps.
+pub(crate) fn generation() -> u64 {
+	GENERATION.load(Ordering::Acquire)
+}
+
+/// Least-recently-used map with the most recent entry at the back.
+pub(crate) struct Lru<T> {
+	capacity: usize,
+	generation: u64,
+	entries: VecDeque<(String, T)>,
+}
+
+impl<T: Clone> Lru<T> {
+	pub(crate) const fn new(capacity: usize) -> Self {
+		Self {
+			capacity,
+			generation: 0,
+			entries: VecDeque::new(),
+		}
+	}
+
+	pub(crate) fn get(&mut self, key: &str, generation: u64) -> Option<T> {
+		self.sync(generation);
+
+		let index = self.entries.iter().position(|(k, _)| k == key)?;
+		let entry = self.entries.remove(index)?;
+		let value = entry.1.clone();
+		self.entries.push_back(entry);
+		Some(value)
+	}
+
+	pub(crate) fn insert(&mut self, key: String, value: T, generation: u64) {
+		// Results enumerated under an older generation are already stale.
+		if generation < self.generation {
+			return;
+		}
+		self.sync(generation);
+
+		self.entries.retain(|(k, _)| *k != key);
+		if self.entries.len() == self.capacity {
+			self.entries.pop_front();
+		}
+		self.entries.push_back((key, value));
+	}
+
+	pub(crate) fn clear(&mut self) {
+		self.entries.clear();
+	}
+
+	fn sync(&mut self, generation: u64) {
+		if generation != self.generation {
+			self.entries.clear();
+			self.generation = generation;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::provider::fake::FakeHandler;
+	use std::path::PathBuf;
+
+	#[test]
+	fn handlers_that_fail_are_skipped() {
+		crate::com::ensure_initialized().unwrap();
+
+		let handlers = Handlers::new(
+			".sdfake",
+			[
+				Some(r"C:\Fake\first.exe"),
+				None,
+				Some(r"C:\Fake\second.exe"),
+				None,
+			]
+			.into_iter()
+			.map(|name| FakeHandler(name.map(str::to_string)).into())
+			.collect(),
+		);
+
+		let ids = handlers
+			.apps(&[], &CancellationToken::new())
+			.unwrap()
+			.into_iter()
+			.map(|app| app.id)
+			.collect::<Vec<_>>();
+		assert_eq!(ids, [r"C:\Fake\first.exe", r"C:\Fake\second.exe"]);
+
+		let found = handlers.find(|name, _| name.ends_with("second.exe"));
+		assert_eq!(
+			found.map(|(name, _)| name).as_deref(),
+			Some(r"C:\Fake\second.exe")
+		);
+	}
+
+	fn app(name: &str, exe: Option<&str>) -> OpenWithApp {
+		OpenWithApp {
+			exe_path: exe.map(PathBuf::from),
+			..OpenWithApp::new(name, name)
+		}
+	}
+
+	#[test]
+	fn keeps_first_handler_per_executable() {
+		let apps = vec![
+			app("Photos Viewer", Some(r"C:\Program Files\Viewer\viewer.exe")),
+			app("Paint", Some(r"C:\Windows\system32\mspaint.exe")),
+			app(
+				"Photos Viewer (Edit)",
+				Some(r"c:\program files\viewer\VIEWER.EXE"),
+			),
+			app("Photos", None),
+			app("Snip", None),
+		];
+
+		let names = dedup_by_executable(apps, |app| app)
+			.into_iter()
+			.map(|app| app.name)
+			.collect::<Vec<_>>();
+		assert_eq!(names, ["Photos Viewer", "Paint", "Photos", "Snip"]);
+	}
+
+	#[test]
+	fn refresh_recomputes_cached_enumerations() {
+		use file_opening::FileOpener;
+		use std::cell::Cell;
+
+		crate::com::ensure_initialized().unwrap();
+		let runs = Cell::new(0);
+		let enumerate = |ext: &str, _| {
+			runs.set(runs.get() + 1);
+			Ok(Handlers::new(ext, Vec::new()))
+		};
+
+		let first = handlers_for(".sdrefresh", ASSOC_FILTER(0), enumerate).unwrap();
+		let cached = handlers_for(".sdrefresh", ASSOC_FILTER(0), enumerate).unwrap();
+		assert!(Rc::ptr_eq(&first, &cached));
+		assert_eq!(runs.get(), 1);
+
+		crate::WindowsFileOpener::default().refresh();
+		let refreshed = handlers_for(".sdrefresh", ASSOC_FILTER(0), enumerate).unwrap();
+		assert!(!Rc::ptr_eq(&first, &refreshed));
+		assert_eq!(runs.get(), 2);
+	}
+
+	#[test]
+	fn evicts_least_recently_used() {
+		let mut lru = Lru::new(2);
+		lru.insert(".txt".to_string(), 1, 0);
+		lru.insert(".png".to_string(), 2, 0);
+
+		assert_eq!(lru.get(".txt", 0), Some(1));
+		lru.insert(".pdf".to_string(), 3, 0);
+
+		assert_eq!(lru.get(".png", 0), None);
+		assert_eq!(lru.get(".txt", 0), Some(1));
+		assert_eq!(lru.get(".pdf", 0), Some(3));
+	}
+
+	#[test]
+	fn generation_change_drops_entries() {
+		let mut lru = Lru::new(4);
+		lru.insert(".txt".to_string(), 1, 0);
+
+		assert_eq!(lru.get(".txt", 1), None);
+
+		// A result enumerated before the bump must not repopulate the cache.
+		lru.insert(".txt".to_string(), 1, 0);
+		assert_eq!(lru.get(".txt", 1), None);
+	}
+}
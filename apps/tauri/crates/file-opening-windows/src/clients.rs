@@ -0,0 +1,105 @@
+//! # Client categories
+//!
+//! Browsers, mail clients and media players register under
+//! `SOFTWARE\Clients\<category>` so Default Programs can offer them per
+//! category. That is the only app category Windows records, so it's what
+//! [`OpenWithApp::category`](file_opening::OpenWithApp::category) reports;
+//! apps outside these categories have none.
+
+use std::collections::HashMap;
+use std::path::Path;
+use windows::Win32::System::Registry::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE};
+
+use crate::registry;
+
+/// Client subkeys and the category id each is reported as.
+const CATEGORIES: &[(&str, &str)] = &[
+	("StartMenuInternet", "browser"),
+	("Mail", "mail"),
+	("Media", "media"),
+	("Calendar", "calendar"),
+	("Contacts", "contacts"),
+	("News", "news"),
+];
+
+/// Registered client executables, lowercased, mapped to their category.
+///
+/// Per-user registrations win over machine-wide ones, and the first category
+/// an executable is found under wins over later ones.
+pub(crate) fn categories() -> HashMap<String, &'static str> {
+	let mut categories = HashMap::new();
+
+	for root in [HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE] {
+		for &(key, category) in CATEGORIES {
+			let clients = format!(r"SOFTWARE\Clients\{key}");
+
+			for client in registry::subkey_names(root, &clients) {
+				let command = registry::read_string(
+					root,
+					&format!(r"{clients}\{client}\shell\open\command"),
+					None,
+				);
+
+				if let Some(exe) = command.as_deref().and_then(command_executable) {
+					categories.entry(exe.to_lowercase()).or_insert(category);
+				}
+			}
+		}
+	}
+
+	categories
+}
+
+/// The category registered for `exe`, if any.
+pub(crate) fn category_for(exe: &Path) -> Option<String> {
+	categories()
+		.get(&exe.to_string_lossy().to_lowercase())
+		.map(|category| category.to_string())
+}
+
+/// The executable a registered command line starts, e.g. `C:\a b\app.exe`
+/// from `"C:\a b\app.exe" --new-window`.
+///
+/// Unquoted commands run up to the end of the first `.exe`, which is how the
+/// shell reads paths with spaces that were registered without quotes.
+pub(crate) fn command_executable(command: &str) -> Option<&str> {
+	let command = command.trim_start();
+
+	let exe = match command.strip_prefix('"') {
+		Some(quoted) => &quoted[..quoted.find('"')?],
+		None => {
+			let end = command
+				.to_ascii_lowercase()
+				.find(".exe")
+				.map(|index| index + ".exe".len())
+				.unwrap_or_else(|| command.find(' ').unwrap_or(command.len()));
+			&command[..end]
+		}
+	};
+
+	(!exe.is_empty()).then_some(exe)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn reads_the_executable_off_a_command_line() {
+		for (command, exe) in [
+			(
+				r#""C:\Program Files\Mozilla Firefox\firefox.exe" -osint -url "%1""#,
+				Some(r"C:\Program Files\Mozilla Firefox\firefox.exe"),
+			),
+			(
+				r"C:\Program Files\Mail\mail.exe /compose",
+				Some(r"C:\Program Files\Mail\mail.exe"),
+			),
+			(r"C:\Tools\player", Some(r"C:\Tools\player")),
+			(r#""C:\unterminated.exe"#, None),
+			("", None),
+		] {
+			assert_eq!(command_executable(command), exe, "{command}");
+		}
+	}
+}
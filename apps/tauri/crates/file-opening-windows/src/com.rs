@@ -0,0 +1,157 @@
+//! # COM apartment
+//!
+//! Every shell call needs COM on the calling thread, and calls arrive on
+//! whatever thread the caller (often a blocking pool) happens to use. Each
+//! thread therefore initializes an apartment on first use and keeps it in a
+//! thread-local [`ComGuard`], which balances the initialization when the
+//! thread exits.
+//!
+//! A thread where COM can't be initialized stays that way, so the failure is
+//! kept too and every call on that thread reports it as
+//! [`FileOpenError::ComInit`], rather than as whatever the first shell call
+//! makes of running without an apartment.
+
+use file_opening::FileOpenError;
+use windows::Win32::Foundation::RPC_E_CHANGED_MODE;
+use windows::Win32::System::Com::{CoInitializeEx, CoUninitialize, COINIT_APARTMENTTHREADED};
+
+use crate::{cache, LOG_TARGET};
+
+thread_local! {
+	static GUARD: ComGuard = ComGuard::new();
+}
+
+/// Makes sure COM is initialized on the current thread.
+pub(crate) fn ensure_initialized() -> Result<(), FileOpenError> {
+	GUARD.with(|guard| match guard.failure {
+		Some(hresult) => Err(FileOpenError::ComInit(hresult)),
+		None => Ok(()),
+	})
+}
+
+/// Owns one `CoInitializeEx` on the current thread.
+pub(crate) struct ComGuard {
+	/// Whether `CoUninitialize` is owed on drop.
+	initialized: bool,
+	/// The `HRESULT` COM couldn't be initialized with, if any.
+	failure: Option<i32>,
+}
+
+impl ComGuard {
+	pub fn new() -> Self {
+		let result = unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED) };
+
+		// RPC_E_CHANGED_MODE means the thread already joined the MTA. COM is
+		// still usable there and the shell APIs tolerate it, but the failed
+		// call must not be balanced. S_FALSE (already initialized) must be.
+		if result == RPC_E_CHANGED_MODE {
+			tracing::debug!(target: LOG_TARGET, "Using this thread's existing multithreaded apartment");
+			return Self {
+				initialized: false,
+				failure: None,
+			};
+		}
+
+		if result.is_err() {
+			tracing::warn!(
+				target: LOG_TARGET,
+				hresult = format_args!("{:#010x}", result.0),
+				"COM initialization failed"
+			);
+		} else {
+			tracing::debug!(target: LOG_TARGET, "Initialized COM on this thread");
+		}
+
+		Self {
+			initialized: result.is_ok(),
+			failure: result.is_err().then_some(result.0),
+		}
+	}
+}
+
+impl Drop for ComGuard {
+	fn drop(&mut self) {
+		// Thread-locals are destroyed in no particular order, so cached
+		// handlers are released here while the apartment still exists.
+		cache::clear_thread();
+
+		if self.initialized {
+			unsafe { CoUninitialize() };
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::provider::{AssocProvider, SystemAssoc};
+	use crate::WindowsFileOpener;
+	use file_opening::{CancellationToken, FileOpener};
+	use windows::Win32::Foundation::{S_FALSE, S_OK};
+	use windows::Win32::System::Com::COINIT_MULTITHREADED;
+	use windows::Win32::UI::Shell::ASSOC_FILTER_RECOMMENDED;
+
+	#[test]
+	fn balances_initialization_on_every_thread() {
+		for _ in 0..8 {
+			std::thread::spawn(|| {
+				let guard = ComGuard::new();
+				let _ = SystemAssoc.enum_handlers(
+					".txt",
+					ASSOC_FILTER_RECOMMENDED,
+					&CancellationToken::new(),
+				);
+				drop(guard);
+
+				// S_OK rather than S_FALSE: nothing was left initialized.
+				let result = unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED) };
+				assert_eq!(result, S_OK);
+				unsafe { CoUninitialize() };
+			})
+			.join()
+			.unwrap();
+		}
+	}
+
+	#[test]
+	fn tolerates_multithreaded_apartment() {
+		std::thread::spawn(|| unsafe {
+			CoInitializeEx(None, COINIT_MULTITHREADED).ok().unwrap();
+
+			let guard = ComGuard::new();
+			assert!(!guard.initialized);
+			assert_eq!(guard.failure, None);
+			let _ = SystemAssoc.enum_handlers(
+				".txt",
+				ASSOC_FILTER_RECOMMENDED,
+				&CancellationToken::new(),
+			);
+			drop(guard);
+
+			// The caller's own initialization is left alone.
+			assert_eq!(CoInitializeEx(None, COINIT_MULTITHREADED), S_FALSE);
+			CoUninitialize();
+			CoUninitialize();
+		})
+		.join()
+		.unwrap();
+	}
+
+	#[test]
+	fn callers_own_apartment_is_used_as_is() {
+		std::thread::spawn(|| unsafe {
+			CoInitializeEx(None, COINIT_APARTMENTTHREADED).ok().unwrap();
+
+			// S_FALSE from the guard's own call is success, not ComInit.
+			assert!(ensure_initialized().is_ok());
+			let opener = WindowsFileOpener::default();
+			let path = std::env::temp_dir().join("sd-com.txt");
+			assert!(opener.get_apps_for_file(&path).is_ok());
+			assert!(opener.can_open(&path).is_ok());
+
+			CoUninitialize();
+		})
+		.join()
+		.unwrap();
+	}
+}
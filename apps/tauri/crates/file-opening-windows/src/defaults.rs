@@ -0,0 +1,105 @@
+//! # Default app changes
+//!
+//! Since Windows 8 the default for a type lives in a `UserChoice` key signed
+//! with a hash that only the shell computes, to stop installers from
+//! hijacking associations. A choice written by anything else fails the check
+//! and the type is reset to its built-in default, so there is no supported
+//! way to change a default silently. All an app can do is send the user to
+//! the Default apps page in Settings, opened on the chosen app where it is
+//! registered, and let them make the change.
+
+use std::path::Path;
+use windows::Win32::System::Registry::{
+	HKEY, HKEY_CLASSES_ROOT, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE,
+};
+
+use crate::{clients, registry};
+
+/// The Default apps page for the app launching `exe`, or the page's start
+/// when the app isn't registered for `ext` under `RegisteredApplications`.
+///
+/// Settings takes no parameter for a single extension; the app's page lists
+/// every type it registered, `ext` among them.
+pub(crate) fn settings_uri(ext: &str, exe: Option<&Path>) -> String {
+	let registered = exe.and_then(|exe| {
+		[
+			(HKEY_CURRENT_USER, "registeredAppUser"),
+			(HKEY_LOCAL_MACHINE, "registeredAppMachine"),
+		]
+		.into_iter()
+		.find_map(|(root, parameter)| {
+			registered_application(root, ext, exe).map(|name| (parameter, name))
+		})
+	});
+
+	match registered {
+		Some((parameter, name)) => format!("ms-settings:defaultapps?{parameter}={}", encode(&name)),
+		None => "ms-settings:defaultapps".to_string(),
+	}
+}
+
+/// The Default apps page for the packaged app `aumid`.
+pub(crate) fn settings_uri_for_package(aumid: &str) -> String {
+	format!("ms-settings:defaultapps?registeredAUMID={}", encode(aumid))
+}
+
+const REGISTERED: &str = r"SOFTWARE\RegisteredApplications";
+
+/// The name under `root`'s `RegisteredApplications` of the app that claims
+/// `ext` in its capabilities with a ProgID that runs `exe`.
+fn registered_application(root: HKEY, ext: &str, exe: &Path) -> Option<String> {
+	let exe = exe.to_string_lossy().to_lowercase();
+
+	registry::value_names(root, REGISTERED)
+		.into_iter()
+		.find(|name| capability_executable(root, name, ext).is_some_and(|claimed| claimed == exe))
+}
+
+/// The lowercased executable the registered app `name` opens `ext` with.
+fn capability_executable(root: HKEY, name: &str, ext: &str) -> Option<String> {
+	let capabilities = registry::read_string(root, REGISTERED, Some(name))?;
+	let prog_id = registry::read_string(
+		root,
+		&format!(r"{capabilities}\FileAssociations"),
+		Some(ext),
+	)?;
+	let command = registry::read_string(
+		HKEY_CLASSES_ROOT,
+		&format!(r"{prog_id}\shell\open\command"),
+		None,
+	)?;
+
+	Some(clients::command_executable(&command)?.to_lowercase())
+}
+
+/// Percent-encodes `value` for a URI query.
+fn encode(value: &str) -> String {
+	value
+		.bytes()
+		.map(|byte| match byte {
+			b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+				(byte as char).to_string()
+			}
+			_ => format!("%{byte:02X}"),
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn encodes_app_names_for_the_query() {
+		assert_eq!(
+			encode("Firefox-308046B0AF4A39CB"),
+			"Firefox-308046B0AF4A39CB"
+		);
+		assert_eq!(encode("Google Chrome"), "Google%20Chrome");
+		assert_eq!(
+			settings_uri_for_package("Microsoft.WindowsNotepad_8wekyb3d8bbwe!App"),
+			"ms-settings:defaultapps?registeredAUMID=Microsoft.WindowsNotepad_8wekyb3d8bbwe%21App"
+		);
+		assert_eq!(settings_uri(".txt", None), "ms-settings:defaultapps");
+	}
+}
@@ -0,0 +1,58 @@
+//! # Dry runs
+//!
+//! An opener built with [`WindowsFileOpenerBuilder::dry_run`] resolves every
+//! launch as usual (the path is checked, the handler found, the executable
+//! and command line worked out) and stops short of the call that would start
+//! anything: `ShellExecuteExW`, `IAssocHandler::Invoke`, `CreateProcessW`,
+//! package activation, MAPI, the shell's dialogs, Explorer or the Recycle
+//! Bin. That launch is reported as `Success` and noted in
+//! [`WindowsFileOpener::dry_run_launches`], so a test can check which handler
+//! a path and app would go to without starting real programs or deleting
+//! anything.
+//!
+//! Whatever resolution reports (`AppNotFound`, `NoAppsForType`, a file with
+//! no extension, a missing path) is reported exactly as in a real run, since
+//! it comes from the same code. What only the launch itself could tell, e.g.
+//! a verb the type turns out not to have or an app that fails to start,
+//! can't be: skipped launches are reported as ones that went through, which
+//! is `RequiresUserAction` for `set_default_app`. Nothing is recorded in the
+//! open history, no association is changed, and nothing is moved to the
+//! Recycle Bin.
+//!
+//! [`WindowsFileOpenerBuilder::dry_run`]: crate::WindowsFileOpenerBuilder::dry_run
+//! [`WindowsFileOpener::dry_run_launches`]: crate::WindowsFileOpener::dry_run_launches
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// A launch a dry run resolved and skipped
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DryRunLaunch {
+	/// The trait method that would have launched, e.g. `open_with_app`
+	pub operation: &'static str,
+	/// The file, URI or app the launch was for, empty for a mail message
+	/// without attachments. Launches of several files are noted once per
+	/// file.
+	pub target: PathBuf,
+	/// What it would have gone to: the handler's name, an executable or a
+	/// package's app id. `None` when the shell would have picked by the
+	/// target's own association.
+	pub handler: Option<String>,
+}
+
+/// The launches an opener skipped, shared by its clones.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DryRunLog(Arc<Mutex<Vec<DryRunLaunch>>>);
+
+impl DryRunLog {
+	pub(crate) fn push(&self, launch: DryRunLaunch) {
+		self.0
+			.lock()
+			.unwrap_or_else(|e| e.into_inner())
+			.push(launch);
+	}
+
+	pub(crate) fn launches(&self) -> Vec<DryRunLaunch> {
+		self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+	}
+}
@@ -0,0 +1,102 @@
+//! # Compound extensions
+//!
+//! `Path::extension` only sees the last segment, so `archive.tar.gz` would be
+//! looked up as `.gz` and miss apps registered for `.tar.gz` itself. Longer
+//! suffixes of the name are tried first, and the first one `HKCR` has a key
+//! for wins. Names with a single dot aren't looked up at all, and the rest
+//! fall back to the plain extension when nothing is registered. A name that
+//! isn't valid Unicode is still looked up by the suffixes that are.
+
+use std::borrow::Cow;
+use std::path::Path;
+
+use windows::Win32::System::Registry::HKEY_CLASSES_ROOT;
+
+use crate::registry;
+
+/// Most segments a compound extension is tried with, e.g. 2 for `.tar.gz`.
+const MAX_SEGMENTS: usize = 3;
+
+/// The extension to look `path` up by, in the `.ext` form the shell
+/// association APIs expect: the longest registered compound one, or the
+/// last segment.
+pub(crate) fn dotted(path: &Path) -> Option<String> {
+	let name = path.file_name()?.to_string_lossy();
+	let lossy = matches!(name, Cow::Owned(_));
+	compound_candidates(&name)
+		.into_iter()
+		.filter(|ext| !(lossy && ext.contains(char::REPLACEMENT_CHARACTER)))
+		.find(|ext| registry::has_key(HKEY_CLASSES_ROOT, ext))
+		.or_else(|| file_opening::mime::derive_extension(path))
+}
+
+/// The suffixes of `name` spanning more than one segment, longest first.
+///
+/// A leading dot belongs to the name (`.config.json` is a hidden `config`
+/// file), and suffixes with an empty segment aren't extensions.
+fn compound_candidates(name: &str) -> Vec<String> {
+	let start = name.len() - name.trim_start_matches('.').len();
+	let dots = name[start..]
+		.match_indices('.')
+		.map(|(index, _)| start + index)
+		.collect::<Vec<_>>();
+	let first = dots.len().saturating_sub(MAX_SEGMENTS);
+
+	dots[first..dots.len().saturating_sub(1)]
+		.iter()
+		.map(|&dot| &name[dot..])
+		.filter(|ext| !ext[1..].split('.').any(str::is_empty))
+		.map(str::to_string)
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn compound_extensions_are_tried_longest_first() {
+		assert_eq!(compound_candidates("foo.tar.gz"), [".tar.gz"]);
+		assert_eq!(compound_candidates("foo.user.js"), [".user.js"]);
+		assert_eq!(
+			compound_candidates("backup.2024.tar.gz"),
+			[".2024.tar.gz", ".tar.gz"]
+		);
+		assert_eq!(
+			compound_candidates("a.b.c.d.e"),
+			[".c.d.e", ".d.e"],
+			"at most {MAX_SEGMENTS} segments"
+		);
+
+		assert!(compound_candidates("notes.txt").is_empty());
+		assert!(compound_candidates(".config.json").is_empty());
+		assert!(compound_candidates("foo..gz").is_empty());
+		assert!(compound_candidates("README").is_empty());
+	}
+
+	#[test]
+	fn unregistered_compounds_fall_back_to_the_last_segment() {
+		// Nothing registers these, so only the plain extension is left.
+		assert_eq!(
+			dotted(Path::new(r"C:\downloads\foo.sd-not-a-type.gz")).as_deref(),
+			Some(".gz")
+		);
+		assert_eq!(
+			dotted(Path::new(r"C:\scripts\foo.sd-not-a-type.js")).as_deref(),
+			Some(".js")
+		);
+		assert_eq!(dotted(Path::new(r"C:\notes\README")), None);
+	}
+
+	#[test]
+	fn names_that_are_not_unicode_keep_their_extension() {
+		use std::ffi::OsString;
+		use std::os::windows::ffi::OsStringExt;
+		use std::path::PathBuf;
+
+		// `s\u{D800}.sd-not-a-type.txt`, with a lone surrogate before the dot.
+		let mut name = OsString::from_wide(&[0x73, 0xD800]);
+		name.push(".sd-not-a-type.txt");
+		assert_eq!(dotted(&PathBuf::from(name)).as_deref(), Some(".txt"));
+	}
+}
@@ -0,0 +1,293 @@
+//! # Handler icon extraction
+//!
+//! Resolves an `IAssocHandler`'s icon location to an `HICON` from one of the
+//! shell's system image lists (16, 32, 48 or 256 px) and re-encodes it as PNG
+//! so it can cross the Tauri boundary. Every failure here maps to
+//! `None`: a missing icon should never cost the user the whole "Open With"
+//! list.
+//!
+//! Extraction and PNG encoding are only built with the `icons` feature.
+//! Without it every handler reports no icon, and only the size bookkeeping
+//! that callers share with icon-free builds remains.
+
+#[cfg(feature = "icons")]
+use std::ffi::c_void;
+#[cfg(feature = "icons")]
+use std::mem::size_of;
+
+use file_opening::AppIcon;
+#[cfg(feature = "icons")]
+use windows::core::{HSTRING, PWSTR};
+#[cfg(feature = "icons")]
+use windows::Win32::Graphics::Gdi::*;
+#[cfg(feature = "icons")]
+use windows::Win32::System::Environment::ExpandEnvironmentStringsW;
+#[cfg(feature = "icons")]
+use windows::Win32::UI::Controls::{IImageList, ILD_TRANSPARENT};
+use windows::Win32::UI::Shell::{
+	IAssocHandler, SHIL_EXTRALARGE, SHIL_JUMBO, SHIL_LARGE, SHIL_SMALL,
+};
+#[cfg(feature = "icons")]
+use windows::Win32::UI::Shell::{SHGetImageList, Shell_GetCachedImageIndexW};
+#[cfg(feature = "icons")]
+use windows::Win32::UI::WindowsAndMessaging::{DestroyIcon, GetIconInfo, HICON, ICONINFO};
+
+#[cfg(feature = "icons")]
+use crate::take_co_string;
+
+/// The system image lists by their nominal edge length. `SHIL_LARGE` follows
+/// the user's icon size setting, so 32 is only its usual value.
+const IMAGE_LISTS: [(u32, u32); 4] = [
+	(16, SHIL_SMALL),
+	(32, SHIL_LARGE),
+	(48, SHIL_EXTRALARGE),
+	(256, SHIL_JUMBO),
+];
+
+/// The image list closest to `size` pixels, preferring the larger of two
+/// equally close ones so icons are scaled down rather than up.
+pub(crate) fn image_list_for(size: u32) -> u32 {
+	IMAGE_LISTS
+		.iter()
+		.min_by_key(|(nominal, _)| (nominal.abs_diff(size), u32::MAX - nominal))
+		.map(|&(_, list)| list)
+		.unwrap_or(SHIL_LARGE)
+}
+
+/// Builds the `icon`/`icons` pair for `OpenWithApp` from `sizes`, the first
+/// of which is the primary. `extract` is called once per distinct image list.
+pub(crate) fn assemble(
+	sizes: &[u32],
+	mut extract: impl FnMut(u32) -> Option<AppIcon>,
+) -> (Option<Vec<u8>>, Vec<AppIcon>) {
+	let mut lists = Vec::new();
+	for &size in sizes {
+		let list = image_list_for(size);
+		if !lists.contains(&list) {
+			lists.push(list);
+		}
+	}
+
+	let renditions = lists.into_iter().map(&mut extract).collect::<Vec<_>>();
+	let primary = renditions.first().cloned().flatten().map(|icon| icon.png);
+
+	let mut icons = renditions.into_iter().flatten().collect::<Vec<_>>();
+	icons.sort_by_key(|icon| icon.size);
+	icons.dedup_by_key(|icon| icon.size);
+
+	(primary, icons)
+}
+
+/// Extracts the handler's icon from the system image list `image_list`
+/// (a `SHIL_*` value), as PNG at whatever size that list holds.
+///
+/// `Shell_GetCachedImageIndexW` takes the icon location exactly as the
+/// registry stores it, including the negative resource IDs DLL locations
+/// often use.
+#[cfg(feature = "icons")]
+pub(crate) fn icon_for_handler(handler: &IAssocHandler, image_list: u32) -> Option<AppIcon> {
+	let mut raw_path = PWSTR::null();
+	let mut index = 0i32;
+
+	let path = unsafe {
+		handler.GetIconLocation(&mut raw_path, &mut index).ok()?;
+		take_co_string(raw_path).ok()?
+	};
+
+	// Packaged apps report indirect "@{...}" resource strings that the image
+	// lists can't load; they fall back to no icon.
+	if path.is_empty() || path.starts_with('@') {
+		return None;
+	}
+
+	let path = expand_environment(&path);
+
+	unsafe {
+		let image = Shell_GetCachedImageIndexW(&HSTRING::from(path), index, 0);
+		if image < 0 {
+			return None;
+		}
+
+		let list: IImageList = SHGetImageList(image_list as i32).ok()?;
+		let hicon = list.GetIcon(image, ILD_TRANSPARENT.0).ok()?;
+		let icon = hicon_to_png(hicon);
+		let _ = DestroyIcon(hicon);
+		icon
+	}
+}
+
+#[cfg(not(feature = "icons"))]
+pub(crate) fn icon_for_handler(handler: &IAssocHandler, image_list: u32) -> Option<AppIcon> {
+	let _ = (handler, image_list);
+	None
+}
+
+/// Expands `%SystemRoot%`-style variables that registry icon paths often use.
+#[cfg(feature = "icons")]
+fn expand_environment(path: &str) -> String {
+	let source = HSTRING::from(path);
+
+	unsafe {
+		let len = ExpandEnvironmentStringsW(&source, None);
+		if len == 0 {
+			return path.to_string();
+		}
+
+		let mut buffer = vec![0u16; len as usize];
+		let written = ExpandEnvironmentStringsW(&source, Some(&mut buffer));
+		if written == 0 || written > len {
+			return path.to_string();
+		}
+
+		String::from_utf16_lossy(&buffer[..written as usize - 1])
+	}
+}
+
+#[cfg(feature = "icons")]
+unsafe fn hicon_to_png(hicon: HICON) -> Option<AppIcon> {
+	let mut info = ICONINFO::default();
+	GetIconInfo(hicon, &mut info).ok()?;
+
+	let pixels = bitmap_rgba(info.hbmColor);
+	let mask = bitmap_rgba(info.hbmMask);
+
+	// GetIconInfo hands us copies of both bitmaps, which we own.
+	let _ = DeleteObject(info.hbmColor);
+	let _ = DeleteObject(info.hbmMask);
+
+	let (width, height, mut pixels) = pixels?;
+	let mask = mask
+		.filter(|&(mask_width, mask_height, _)| (mask_width, mask_height) == (width, height))
+		.map(|(_, _, mask)| mask);
+	apply_mask(&mut pixels, mask.as_deref());
+
+	Some(AppIcon {
+		size: width.max(height),
+		png: encode_png(width, height, &pixels)?,
+	})
+}
+
+/// Gives icons without an alpha channel their transparency from the AND mask.
+///
+/// Legacy icons keep transparency only in the mask and come back with every
+/// alpha byte zero; copied as-is they'd be invisible, and made opaque they'd
+/// sit on a black square. Icons that do have alpha are left alone.
+#[cfg(feature = "icons")]
+pub(crate) fn apply_mask(pixels: &mut [u8], mask: Option<&[u8]>) {
+	if pixels.chunks_exact(4).any(|pixel| pixel[3] != 0) {
+		return;
+	}
+
+	match mask {
+		// Set mask bits (transparent) read back as white.
+		Some(mask) if mask.len() == pixels.len() => {
+			for (pixel, bits) in pixels.chunks_exact_mut(4).zip(mask.chunks_exact(4)) {
+				pixel[3] = if bits[0] == 0 { u8::MAX } else { 0 };
+			}
+		}
+		_ => {
+			for pixel in pixels.chunks_exact_mut(4) {
+				pixel[3] = u8::MAX;
+			}
+		}
+	}
+}
+
+/// Reads a color bitmap as top-down RGBA.
+#[cfg(feature = "icons")]
+pub(crate) unsafe fn bitmap_rgba(bitmap: HBITMAP) -> Option<(u32, u32, Vec<u8>)> {
+	if bitmap.is_invalid() {
+		return None;
+	}
+
+	let mut header = BITMAP::default();
+	let read = GetObjectW(
+		bitmap,
+		size_of::<BITMAP>() as i32,
+		Some(&mut header as *mut BITMAP as *mut c_void),
+	);
+	if read == 0 || header.bmWidth <= 0 || header.bmHeight <= 0 {
+		return None;
+	}
+
+	let (width, height) = (header.bmWidth, header.bmHeight);
+	let mut info = BITMAPINFO {
+		bmiHeader: BITMAPINFOHEADER {
+			biSize: size_of::<BITMAPINFOHEADER>() as u32,
+			biWidth: width,
+			// Negative height requests a top-down DIB, matching PNG row order.
+			biHeight: -height,
+			biPlanes: 1,
+			biBitCount: 32,
+			biCompression: BI_RGB.0,
+			..Default::default()
+		},
+		..Default::default()
+	};
+
+	let mut pixels = vec![0u8; width as usize * height as usize * 4];
+	let hdc = GetDC(None);
+	let lines = GetDIBits(
+		hdc,
+		bitmap,
+		0,
+		height as u32,
+		Some(pixels.as_mut_ptr() as *mut c_void),
+		&mut info,
+		DIB_RGB_COLORS,
+	);
+	ReleaseDC(None, hdc);
+
+	if lines == 0 {
+		return None;
+	}
+
+	for pixel in pixels.chunks_exact_mut(4) {
+		pixel.swap(0, 2);
+	}
+
+	Some((width as u32, height as u32, pixels))
+}
+
+#[cfg(feature = "icons")]
+pub(crate) fn encode_png(width: u32, height: u32, rgba: &[u8]) -> Option<Vec<u8>> {
+	let mut out = Vec::new();
+
+	{
+		let mut encoder = png::Encoder::new(&mut out, width, height);
+		encoder.set_color(png::ColorType::Rgba);
+		encoder.set_depth(png::BitDepth::Eight);
+
+		let mut writer = encoder.write_header().ok()?;
+		writer.write_image_data(rgba).ok()?;
+	}
+
+	Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn picks_nearest_image_list() {
+		assert_eq!(image_list_for(16), SHIL_SMALL);
+		assert_eq!(image_list_for(24), SHIL_LARGE);
+		assert_eq!(image_list_for(40), SHIL_EXTRALARGE);
+		assert_eq!(image_list_for(128), SHIL_EXTRALARGE);
+		assert_eq!(image_list_for(200), SHIL_JUMBO);
+	}
+
+	#[test]
+	#[cfg(feature = "icons")]
+	fn mask_supplies_missing_alpha() {
+		let mut pixels = vec![10, 20, 30, 0, 40, 50, 60, 0];
+		let mask = [0, 0, 0, 0, 255, 255, 255, 0];
+		apply_mask(&mut pixels, Some(&mask));
+		assert_eq!(pixels, [10, 20, 30, 255, 40, 50, 60, 0]);
+
+		let mut translucent = vec![10, 20, 30, 128, 40, 50, 60, 0];
+		apply_mask(&mut translucent, Some(&mask));
+		assert_eq!(translucent, [10, 20, 30, 128, 40, 50, 60, 0]);
+	}
+}
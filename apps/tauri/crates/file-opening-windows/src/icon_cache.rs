@@ -0,0 +1,95 @@
+//! # Icon cache
+//!
+//! The same few apps are listed for many extensions, and without this every
+//! listing would extract their icons again. Icons are plain PNG bytes with no
+//! tie to an apartment, so unlike the handler cache this one is shared by the
+//! whole process, keyed by executable and image list.
+//!
+//! Entries follow the handler cache's generation: an association change,
+//! which an app update that brings a new icon sends as well, or `clear_cache`
+//! drops them along with the handlers. `clear_icon_cache` drops only them.
+
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+use file_opening::AppIcon;
+
+use crate::cache::{self, Lru};
+
+/// Icons remembered across the process; a few per app at most.
+const CAPACITY: usize = 256;
+
+static ICONS: Mutex<Lru<Option<AppIcon>>> = Mutex::new(Lru::new(CAPACITY));
+
+/// The icon of the app launching `exe` from the system image list `list`,
+/// running `extract` only when it isn't cached.
+///
+/// Apps without an executable, like packaged ones, have nothing stable to be
+/// keyed by and are always extracted. Icons that couldn't be extracted are
+/// cached too, so a broken icon isn't retried on every listing.
+pub(crate) fn get_or_extract(
+	exe: Option<&Path>,
+	list: u32,
+	extract: impl FnOnce() -> Option<AppIcon>,
+) -> Option<AppIcon> {
+	let Some(exe) = exe else {
+		return extract();
+	};
+
+	let key = format!("{}|{list}", exe.to_string_lossy().to_lowercase());
+	// Read before extracting, as in `cache::handlers_for`.
+	let generation = cache::generation();
+
+	if let Some(hit) = icons().get(&key, generation) {
+		return hit;
+	}
+
+	// Extracted outside the lock: it's slow, and other threads may be
+	// listing apps of their own meanwhile.
+	let icon = extract();
+	icons().insert(key, icon.clone(), generation);
+	icon
+}
+
+/// Drops every cached icon.
+pub(crate) fn clear() {
+	icons().clear();
+}
+
+fn icons() -> MutexGuard<'static, Lru<Option<AppIcon>>> {
+	ICONS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::cell::Cell;
+
+	#[test]
+	fn second_lookup_reuses_the_extracted_icon() {
+		let exe = Path::new(r"C:\Fake\icon-cache-test.exe");
+		let extractions = Cell::new(0);
+		let extract = || {
+			extractions.set(extractions.get() + 1);
+			Some(AppIcon {
+				size: 32,
+				png: vec![1, 2, 3],
+			})
+		};
+
+		let first = get_or_extract(Some(exe), 1, extract);
+		let second = get_or_extract(Some(Path::new(r"c:\fake\ICON-CACHE-TEST.EXE")), 1, extract);
+		assert_eq!(extractions.get(), 1);
+		assert_eq!(first, second);
+
+		// Another size and an app without an executable are extracted afresh.
+		get_or_extract(Some(exe), 2, extract);
+		get_or_extract(None, 1, extract);
+		get_or_extract(None, 1, extract);
+		assert_eq!(extractions.get(), 4);
+
+		clear();
+		get_or_extract(Some(exe), 1, extract);
+		assert_eq!(extractions.get(), 5);
+	}
+}
@@ -1,158 +1,2809 @@
-use file_opening::{FileOpener, OpenResult, OpenWithApp};
-use std::path::Path;
+use file_opening::{
+	check_app_id, reveal_target, AppKind, CancellationToken, DefaultOverrides, DefaultedOpen,
+	FileOpenError, FileOpener, LaunchSpec, OpenHistory, OpenResult, OpenWithApp,
+	OpenerCapabilities, ProcessOutcome, RevealTarget, UriPolicy, WatchHandle,
+};
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
 use windows::core::*;
-use windows::Win32::Foundation::*;
+use windows::Win32::Foundation::{
+	CloseHandle, GetLastError, SetLastError, ERROR_NO_ASSOCIATION, HWND, WIN32_ERROR,
+};
 use windows::Win32::System::Com::*;
+use windows::Win32::System::Threading::{
+	CreateProcessW, GetProcessId, PROCESS_CREATION_FLAGS, PROCESS_INFORMATION, STARTUPINFOW,
+};
 use windows::Win32::UI::Shell::*;
 use windows::Win32::UI::WindowsAndMessaging::*;
 
-// Thread-local COM initialization
-thread_local! {
-	static COM_INITIALIZED: std::cell::RefCell<bool> = std::cell::RefCell::new(false);
+mod assoc;
+mod builder;
+mod cache;
+mod clients;
+mod com;
+mod defaults;
+mod dry_run;
+mod extension;
+mod icon;
+mod icon_cache;
+mod lock;
+mod mail;
+pub mod mime;
+mod network;
+mod packaged;
+mod paths;
+mod progid;
+mod provider;
+mod registry;
+mod risk;
+mod shell;
+mod shortcut;
+mod sniff;
+mod stable_id;
+mod status;
+#[cfg(feature = "icons")]
+mod thumbnail;
+mod trash;
+mod user_choice;
+mod wait;
+mod watcher;
+
+pub use builder::{AssocFilter, WindowsFileOpenerBuilder};
+pub use dry_run::DryRunLaunch;
+use dry_run::DryRunLog;
+use provider::{AssocProvider, SystemAssoc};
+
+/// The `tracing` target of every span and event this backend records, for
+/// filters like `RUST_LOG=file_opening::windows=debug`.
+///
+/// Handler enumeration and launches log at `debug`, each handler's name at
+/// `trace`, and failures the backend works around at `warn`. Nothing is
+/// formatted unless a subscriber enables the target.
+pub const LOG_TARGET: &str = "file_opening::windows";
+
+/// Shell-backed opener.
+///
+/// The default lists recommended handlers with 32 px icons, sniffs files
+/// without an extension, and gives network paths 3 seconds to answer before
+/// a launch reports them `Unreachable`; [`WindowsFileOpener::builder`]
+/// changes any of that.
+///
+/// The opener holds only its settings, so it can be shared and called from
+/// any thread. COM pointers never leave the thread that created them: each
+/// caller thread gets its own apartment and handler cache, so nothing needs
+/// marshalling through the global interface table.
+#[derive(Debug, Clone)]
+pub struct WindowsFileOpener {
+	load_icons: bool,
+	sniff_extensionless: bool,
+	follow_shortcuts: bool,
+	force_open_verb: bool,
+	elevate_when_required: bool,
+	filter_unavailable: bool,
+	filter: AssocFilter,
+	icon_sizes: Vec<u32>,
+	// Kept as the raw value: an `HWND` holds a pointer, which would make the
+	// opener neither `Send` nor `Sync`.
+	owner: isize,
+	network_timeout: std::time::Duration,
+	/// Set for a dry run, which notes launches here instead of performing them
+	dry_run: Option<DryRunLog>,
+	overrides: DefaultOverrides,
+	history: OpenHistory,
+	assoc: Arc<dyn AssocProvider>,
+}
+
+impl Default for WindowsFileOpener {
+	fn default() -> Self {
+		Self {
+			load_icons: true,
+			sniff_extensionless: true,
+			follow_shortcuts: false,
+			force_open_verb: false,
+			elevate_when_required: false,
+			filter_unavailable: false,
+			filter: AssocFilter::Recommended,
+			icon_sizes: vec![32],
+			owner: 0,
+			network_timeout: network::DEFAULT_TIMEOUT,
+			dry_run: None,
+			overrides: DefaultOverrides::new(),
+			history: OpenHistory::new(),
+			assoc: Arc::new(SystemAssoc),
+		}
+	}
+}
+
+impl WindowsFileOpener {
+	pub fn builder() -> WindowsFileOpenerBuilder {
+		WindowsFileOpenerBuilder::default()
+	}
+
+	/// A default opener that resolves launches without performing them;
+	/// shorthand for `builder().dry_run(true).build()`.
+	pub fn dry_run() -> Self {
+		Self::builder().dry_run(true).build()
+	}
+
+	/// The launches a dry run has skipped so far, oldest first, shared with
+	/// the opener's clones. Always empty outside a dry run.
+	pub fn dry_run_launches(&self) -> Vec<DryRunLaunch> {
+		self.dry_run
+			.as_ref()
+			.map(DryRunLog::launches)
+			.unwrap_or_default()
+	}
+
+	/// Opens the files `data` describes with `app_id`, as
+	/// [`FileOpener::open_with_app`] does for a path
+	///
+	/// For callers that already hold a data object, such as the one built for
+	/// a drag out of Spacedrive, so the files aren't parsed into shell items a
+	/// second time. The app is picked by the first file's extension, and
+	/// nothing is checked for existence first.
+	///
+	/// `data` is only borrowed: a handler that keeps it past the call takes a
+	/// reference of its own, and the caller releases theirs as usual. It must
+	/// be usable on the calling thread, so created there or marshalled to it,
+	/// and come from the same `windows` crate version as this one.
+	pub fn open_with_app_data_object(
+		&self,
+		data: &IDataObject,
+		app_id: &str,
+	) -> std::result::Result<OpenResult, FileOpenError> {
+		check_app_id(app_id)?;
+		com::ensure_initialized()?;
+
+		let items = shell::item_array_for_data_object(data).map_err(other_error)?;
+		let path: &Path = &shell::first_parsing_name(&items).map_err(other_error)?;
+		let ext = self
+			.association_extension(path)
+			.ok_or(FileOpenError::NoExtension)?;
+
+		let (name, handler) = match self.app_handler(&ext, app_id)? {
+			Ok(found) => found,
+			Err(result) => return Ok(result),
+		};
+
+		if let Some(result) = self.skip_launch("open_with_app", path, Some(&name)) {
+			return Ok(result);
+		}
+
+		let result = match invoke_handler(&ext, &name, &handler, data, &items) {
+			Ok(()) => OpenResult::success(),
+			Err(e) => status::from_error(&e, path),
+		};
+		self.history.record(path, app_id, &result);
+		Ok(result)
+	}
+
+	/// The handler `app_id` names for `ext`, or what to report when there's
+	/// none that can be invoked.
+	fn app_handler(
+		&self,
+		ext: &str,
+		app_id: &str,
+	) -> std::result::Result<std::result::Result<(String, IAssocHandler), OpenResult>, FileOpenError>
+	{
+		// app_id is a ProgID, a hash of the executable, or a bare handler name
+		let Some((name, handler)) = find_app_handler(&*self.assoc, ext, app_id)? else {
+			// The full list was just enumerated, so the system's comes from the
+			// thread's cache.
+			let cancel = CancellationToken::new();
+			if handlers(&*self.assoc, ext, ASSOC_FILTER_NONE, &cancel)?.is_empty() {
+				return Ok(Err(OpenResult::NoAppsForType {
+					extension: ext.to_string(),
+				}));
+			}
+			return Ok(Err(OpenResult::AppNotFound {
+				app_id: app_id.to_string(),
+			}));
+		};
+
+		// Invoke would run into a shell error that doesn't say what's wrong.
+		if let Some(exe) = assoc::missing_handler_executable(&name) {
+			return Ok(Err(OpenResult::AppUnavailable {
+				app_id: app_id.to_string(),
+				exe_path: exe.to_string_lossy().to_string(),
+			}));
+		}
+
+		Ok(Ok((name, handler)))
+	}
+
+	/// The default app for `ext` (with or without the leading dot) as
+	/// Explorer resolves it: the user's own choice when its integrity hash
+	/// checks out, otherwise the ProgID the extension is registered with
+	///
+	/// [`FileOpener::get_default_app`] asks the shell, which resolves it the
+	/// same way; this reads the per-user choice and the registration
+	/// directly, so a choice an installer forged without the hash is never
+	/// reported. `None` when neither names an app this machine has.
+	pub fn effective_default_app(
+		&self,
+		ext: &str,
+	) -> std::result::Result<Option<OpenWithApp>, FileOpenError> {
+		com::ensure_initialized()?;
+
+		let ext = format!(".{}", mime::normalize_extension(ext));
+		// Both become part of a registry path.
+		if ext.len() == 1 || ext.contains('\\') {
+			return Ok(None);
+		}
+
+		let Some(prog_id) =
+			user_choice::prog_id(&ext).or_else(|| user_choice::registered_prog_id(&ext))
+		else {
+			return Ok(None);
+		};
+
+		if let Some(aumid) = packaged::app_user_model_id_for(&prog_id) {
+			let found = find_handler_by(&*self.assoc, &ext, |name, handler| {
+				packaged::app_user_model_id(&ext, name, handler)
+					.is_some_and(|candidate| candidate.eq_ignore_ascii_case(&aumid))
+			})?;
+			return Ok(found.map(|(name, handler)| {
+				self.packaged_default(&ext, &name, &handler, Some(&aumid))
+			}));
+		}
+
+		match self
+			.assoc
+			.query_string(ASSOCF_NONE, ASSOCSTR_EXECUTABLE, &prog_id)
+		{
+			Some(exe) => self.desktop_default(&ext, exe).map(Some),
+			None => Ok(None),
+		}
+	}
+
+	/// How many apps `get_apps_for_file` would list, up to `cap`, without
+	/// building them: no display names, ProgIDs or icons.
+	fn count_apps(
+		&self,
+		path: &Path,
+		cap: Option<usize>,
+	) -> std::result::Result<usize, FileOpenError> {
+		com::ensure_initialized()?;
+
+		let ext = self
+			.association_extension(path)
+			.ok_or(FileOpenError::NoExtension)?;
+
+		let handlers = handlers(
+			&*self.assoc,
+			&ext,
+			self.filter.as_raw(),
+			&CancellationToken::new(),
+		)?;
+		Ok(handlers.app_count(cap, |name| {
+			self.filter_unavailable && assoc::missing_handler_executable(name).is_some()
+		}))
+	}
+
+	/// The extension to look up associations for: the real one, compound if
+	/// one like `.tar.gz` is registered, or a guess from the content when the
+	/// name has none and sniffing is on.
+	fn association_extension(&self, path: &Path) -> Option<String> {
+		extension::dotted(path).or_else(|| {
+			if self.sniff_extensionless {
+				sniff::sniffed_extension(path)
+			} else {
+				None
+			}
+		})
+	}
+
+	/// The handlers for `ext` as apps, minus unavailable ones when they're
+	/// filtered out.
+	fn list_apps(
+		&self,
+		ext: &str,
+		filter: ASSOC_FILTER,
+		icon_sizes: &[u32],
+		cancel: &CancellationToken,
+	) -> std::result::Result<Vec<OpenWithApp>, FileOpenError> {
+		let mut apps = list_apps_for_extension(&*self.assoc, ext, filter, icon_sizes, cancel)?;
+		if self.filter_unavailable {
+			apps.retain(|app| assoc::missing_handler_executable(&app.id).is_none());
+		}
+		Ok(apps)
+	}
+
+	/// The default app for `ext` when it's the packaged app behind `handler`.
+	fn packaged_default(
+		&self,
+		ext: &str,
+		name: &str,
+		handler: &IAssocHandler,
+		aumid: Option<&str>,
+	) -> OpenWithApp {
+		let (icon, icons) = icon::assemble(self.icon_sizes(), |list| {
+			icon::icon_for_handler(handler, list)
+		});
+		let progid = progid::for_handler(ext, name, aumid);
+
+		OpenWithApp {
+			id: stable_id::of(name, None, aumid),
+			name: assoc::handler_display_name(name, handler),
+			exe_path: None,
+			recommended: true,
+			is_default: true,
+			kind: AppKind::Packaged,
+			category: None,
+			clsid: progid.as_deref().and_then(progid::delegate_clsid),
+			progid,
+			icon,
+			icons,
+			accelerator: None,
+		}
+	}
+
+	/// The default app for `ext` when it's the desktop app `exe`.
+	fn desktop_default(
+		&self,
+		ext: &str,
+		exe: String,
+	) -> std::result::Result<OpenWithApp, FileOpenError> {
+		// Built from the enumerated handler where there is one, so the id is
+		// the one `get_apps_for_file` lists it under.
+		let app = match find_handler(&*self.assoc, ext, |name| name.eq_ignore_ascii_case(&exe))? {
+			Some((name, handler)) => {
+				let (icon, icons) = icon::assemble(self.icon_sizes(), |list| {
+					icon::icon_for_handler(&handler, list)
+				});
+				let progid = progid::for_handler(ext, &name, None);
+
+				OpenWithApp {
+					id: stable_id::of(&name, Some(Path::new(&exe)), None),
+					name: assoc::handler_display_name(&name, &handler),
+					exe_path: Some(PathBuf::from(&exe)),
+					recommended: true,
+					is_default: true,
+					kind: AppKind::Desktop,
+					category: clients::category_for(Path::new(&exe)),
+					clsid: progid.as_deref().and_then(progid::delegate_clsid),
+					progid,
+					icon,
+					icons,
+					accelerator: None,
+				}
+			}
+			None => OpenWithApp {
+				id: stable_id::of(&exe, Some(Path::new(&exe)), None),
+				name: assoc::friendly_app_name(&exe).unwrap_or_else(|| exe.clone()),
+				category: clients::category_for(Path::new(&exe)),
+				progid: None,
+				clsid: None,
+				exe_path: Some(PathBuf::from(exe)),
+				recommended: true,
+				is_default: true,
+				kind: AppKind::Desktop,
+				icon: None,
+				icons: Vec::new(),
+				accelerator: None,
+			},
+		};
+		Ok(app)
+	}
+
+	/// Registers `app_id` as the default for `ext` through its handler, then
+	/// checks whether that took. Failures are reported against `path`.
+	fn make_default(
+		&self,
+		ext: &str,
+		app_id: &str,
+		path: &Path,
+	) -> std::result::Result<OpenResult, FileOpenError> {
+		let Some((name, handler)) = find_app_handler(&*self.assoc, ext, app_id)? else {
+			return Ok(OpenResult::AppNotFound {
+				app_id: app_id.to_string(),
+			});
+		};
+
+		if let Some(result) = self.skip_launch("open_with_app_and_set_default", path, Some(&name)) {
+			return Ok(result);
+		}
+
+		// Shown as the type's description where nothing else names it.
+		let description = HSTRING::from(assoc::handler_display_name(&name, &handler));
+		if let Err(e) = unsafe { handler.MakeDefault(&description) } {
+			tracing::debug!(target: LOG_TARGET, "MakeDefault failed: {e}");
+			return Ok(status::from_error(&e, path));
+		}
+		cache::invalidate();
+
+		// `Success` if the default is now the app, else off to Settings.
+		self.set_default_app(ext, app_id)
+	}
+
+	/// `FileNotFound` or `Unreachable` for a normalized path that shouldn't
+	/// be handed to the shell, probing network paths for at most
+	/// [`WindowsFileOpenerBuilder::network_timeout`].
+	fn check_path(&self, path: &Path) -> Option<OpenResult> {
+		let path_string = || path.to_string_lossy().to_string();
+		match network::probe(path, self.network_timeout) {
+			network::Probe::Present => None,
+			network::Probe::Missing => Some(OpenResult::FileNotFound {
+				path: path_string(),
+			}),
+			network::Probe::Unreachable => Some(OpenResult::Unreachable {
+				path: path_string(),
+			}),
+		}
+	}
+
+	/// `open_with_default` for a normalized path [`Self::check_path`] has
+	/// already passed.
+	fn open_checked_with_default(
+		&self,
+		path: &Path,
+	) -> std::result::Result<OpenResult, FileOpenError> {
+		if self.follow_shortcuts && shortcut::is_shortcut(path) {
+			if let Some(target) = self.resolve_shortcut(path)? {
+				return self.open_with_default(&target);
+			}
+		}
+
+		if let Some(result) = self.overrides.open(self, path)? {
+			return Ok(result);
+		}
+
+		// ShellExecute only goes by the last segment, so a registered
+		// `.tar.gz` would open with the `.gz` default rather than its own.
+		let compound = extension::dotted(path).filter(|ext| ext.matches('.').count() > 1);
+		if let Some((name, handler)) = compound
+			.map(|ext| default_handler(&*self.assoc, &ext))
+			.transpose()?
+			.flatten()
+		{
+			if let Some(result) = self.skip_launch("open_with_default", path, Some(&name)) {
+				return Ok(result);
+			}
+
+			let result = shell::data_object_for_paths(&[path])
+				.and_then(|data_object| unsafe { handler.Invoke(&data_object) });
+			return Ok(match result {
+				Ok(()) => OpenResult::success(),
+				Err(e) => status::from_error(&e, path),
+			});
+		}
+
+		// No verb is the file's default one, chosen as on a double-click in
+		// Explorer: the ProgID's `shell` key names it, else `open`, else the
+		// first verb listed. For media that is often `play`.
+		let verb = if self.force_open_verb {
+			w!("open")
+		} else {
+			PCWSTR::null()
+		};
+		Ok(self
+			.skip_launch("open_with_default", path, None)
+			.unwrap_or_else(|| shell_open(self.owner(), verb, path)))
+	}
+
+	/// `result`, unless it's `RequiresElevation` and
+	/// [`WindowsFileOpenerBuilder::elevate_when_required`] is on, in which
+	/// case `elevated` is launched instead.
+	fn or_elevated(&self, result: OpenResult, elevated: impl FnOnce() -> OpenResult) -> OpenResult {
+		match result {
+			OpenResult::RequiresElevation { .. } if self.elevate_when_required => {
+				tracing::debug!(target: LOG_TARGET, "Retrying the launch elevated");
+				elevated()
+			}
+			result => result,
+		}
+	}
+
+	/// The window the shell's dialogs are parented to; null for none.
+	fn owner(&self) -> HWND {
+		HWND(self.owner as *mut _)
+	}
+
+	/// In a dry run, notes the launch resolution ended at and reports it as
+	/// done without performing it; `None` in a real run, which goes on to
+	/// launch. See [`dry_run`].
+	fn skip_launch(
+		&self,
+		operation: &'static str,
+		target: &Path,
+		handler: Option<&str>,
+	) -> Option<OpenResult> {
+		let log = self.dry_run.as_ref()?;
+		tracing::debug!(
+			target: LOG_TARGET,
+			operation,
+			target = %target.display(),
+			handler,
+			"Dry run, skipped the launch"
+		);
+		log.push(DryRunLaunch {
+			operation,
+			target: target.to_path_buf(),
+			handler: handler.map(str::to_string),
+		});
+		Some(OpenResult::success())
+	}
+
+	/// The icon sizes to extract, or none when icons are off or not built.
+	fn icon_sizes(&self) -> &[u32] {
+		if self.load_icons && cfg!(feature = "icons") {
+			&self.icon_sizes
+		} else {
+			&[]
+		}
+	}
 }
 
-fn ensure_com_initialized() {
-	COM_INITIALIZED.with(|initialized| {
-		if !*initialized.borrow() {
-			unsafe {
-				let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+impl FileOpener for WindowsFileOpener {
+	fn get_apps_for_file(
+		&self,
+		path: &Path,
+	) -> std::result::Result<Vec<OpenWithApp>, FileOpenError> {
+		self.get_apps_for_file_cancellable(path, &CancellationToken::new())
+	}
+
+	/// Apps are ranked without icons first, then only the page's icons are
+	/// extracted. The ranking comes from the cached enumeration, so it holds
+	/// from page to page until associations change.
+	fn get_apps_for_file_paged(
+		&self,
+		path: &Path,
+		offset: usize,
+		limit: usize,
+	) -> std::result::Result<(Vec<OpenWithApp>, usize), FileOpenError> {
+		com::ensure_initialized()?;
+
+		let ext = self
+			.association_extension(path)
+			.ok_or(FileOpenError::NoExtension)?;
+		let filter = self.filter.as_raw();
+		let cancel = CancellationToken::new();
+
+		let apps = self.list_apps(&ext, filter, &[], &cancel)?;
+		let total = apps.len();
+		let mut page = apps
+			.into_iter()
+			.skip(offset)
+			.take(limit)
+			.collect::<Vec<_>>();
+
+		handlers(&*self.assoc, &ext, filter, &cancel)?.add_icons(
+			&mut page,
+			self.icon_sizes(),
+			&cancel,
+		)?;
+		Ok((page, total))
+	}
+
+	fn get_all_apps_for_file(
+		&self,
+		path: &Path,
+	) -> std::result::Result<Vec<OpenWithApp>, FileOpenError> {
+		self.get_all_apps_for_file_cancellable(path, &CancellationToken::new())
+	}
+
+	/// Checked between `IEnumAssocHandlers::Next` calls and between icons. An
+	/// enumeration cut short isn't cached; the handlers fetched so far are
+	/// released as it unwinds.
+	fn get_apps_for_file_cancellable(
+		&self,
+		path: &Path,
+		cancel: &CancellationToken,
+	) -> std::result::Result<Vec<OpenWithApp>, FileOpenError> {
+		com::ensure_initialized()?;
+
+		let ext = self
+			.association_extension(path)
+			.ok_or(FileOpenError::NoExtension)?;
+
+		self.list_apps(&ext, self.filter.as_raw(), self.icon_sizes(), cancel)
+	}
+
+	fn get_all_apps_for_file_cancellable(
+		&self,
+		path: &Path,
+		cancel: &CancellationToken,
+	) -> std::result::Result<Vec<OpenWithApp>, FileOpenError> {
+		com::ensure_initialized()?;
+
+		let ext = self
+			.association_extension(path)
+			.ok_or(FileOpenError::NoExtension)?;
+
+		self.list_apps(&ext, ASSOC_FILTER_NONE, self.icon_sizes(), cancel)
+	}
+
+	fn get_apps_for_mime_type(
+		&self,
+		mime: &str,
+	) -> std::result::Result<Vec<OpenWithApp>, FileOpenError> {
+		com::ensure_initialized()?;
+
+		match mime::extension_for_mime(mime) {
+			Some(ext) => self.list_apps(
+				&format!(".{ext}"),
+				self.filter.as_raw(),
+				self.icon_sizes(),
+				&CancellationToken::new(),
+			),
+			None => Ok(vec![]),
+		}
+	}
+
+	/// `get_association` is left to the default: the extensions come back
+	/// dotted, and the lookup through a made-up file name never sniffs.
+	fn list_association_extensions(&self) -> std::result::Result<Vec<String>, FileOpenError> {
+		com::ensure_initialized()?;
+		Ok(assoc::extensions())
+	}
+
+	/// A single `AssocQueryStringW` lookup; nothing is enumerated unless the file
+	/// has no extension and must be sniffed.
+	fn can_open(&self, path: &Path) -> std::result::Result<bool, FileOpenError> {
+		com::ensure_initialized()?;
+
+		Ok(self
+			.association_extension(path)
+			.is_some_and(|ext| assoc::has_default(&ext)))
+	}
+
+	/// With [`WindowsFileOpenerBuilder::follow_shortcuts`] on, a `.lnk` opens
+	/// its target with the target's own association. A link that can't be
+	/// resolved is still handed to the shell, which explains what's wrong.
+	fn open_with_default(&self, path: &Path) -> std::result::Result<OpenResult, FileOpenError> {
+		com::ensure_initialized()?;
+		let path: &Path = &paths::normalize(path)?;
+
+		// ShellExecute would report a missing directory as a bare
+		// SE_ERR_FNF/PNF too, but only after trying to resolve it.
+		if let Some(result) = self.check_path(path) {
+			return Ok(result);
+		}
+
+		self.open_checked_with_default(path)
+	}
+
+	fn default_overrides(&self) -> Option<&DefaultOverrides> {
+		Some(&self.overrides)
+	}
+
+	fn open_history(&self) -> Option<&OpenHistory> {
+		Some(&self.history)
+	}
+
+	fn open_elevated(&self, path: &Path) -> std::result::Result<OpenResult, FileOpenError> {
+		com::ensure_initialized()?;
+		let path: &Path = &paths::normalize(path)?;
+
+		if let Some(result) = self.check_path(path) {
+			return Ok(result);
+		}
+
+		if let Some(result) = self.skip_launch("open_elevated", path, None) {
+			return Ok(result);
+		}
+
+		// ShellExecuteExW rather than ShellExecuteW: only the former reports
+		// ERROR_CANCELLED when the UAC prompt is dismissed. NOASYNC because a
+		// blocking-pool thread may exit before the launch completes.
+		let file = HSTRING::from(path);
+		let mut info = SHELLEXECUTEINFOW {
+			cbSize: std::mem::size_of::<SHELLEXECUTEINFOW>() as u32,
+			hwnd: self.owner(),
+			fMask: SEE_MASK_NOASYNC,
+			lpVerb: w!("runas"),
+			lpFile: PCWSTR(file.as_ptr()),
+			nShow: SW_SHOWNORMAL.0,
+			..Default::default()
+		};
+
+		Ok(match unsafe { ShellExecuteExW(&mut info) } {
+			Ok(()) => OpenResult::success(),
+			Err(e) => status::from_error(&e, path),
+		})
+	}
+
+	fn is_risky(&self, path: &Path) -> std::result::Result<bool, FileOpenError> {
+		Ok(self
+			.association_extension(path)
+			.is_some_and(|ext| risk::is_risky(&ext)))
+	}
+
+	fn print(&self, path: &Path) -> std::result::Result<OpenResult, FileOpenError> {
+		self.open_with_verb(path, "print")
+	}
+
+	fn can_print(&self, path: &Path) -> std::result::Result<bool, FileOpenError> {
+		Ok(self
+			.association_extension(path)
+			.is_some_and(|ext| assoc::has_verb(&ext, "print")))
+	}
+
+	/// A type that opens with something but has no `edit` verb can't be
+	/// edited at all, which is `Unsupported`; `NoHandler` is left for types
+	/// nothing is registered for.
+	fn edit(&self, path: &Path) -> std::result::Result<OpenResult, FileOpenError> {
+		Ok(match self.open_with_verb(path, "edit")? {
+			OpenResult::NoHandler { .. }
+				if self
+					.association_extension(path)
+					.is_some_and(|ext| assoc::has_default(&ext)) =>
+			{
+				OpenResult::Unsupported {
+					operation: "edit".to_string(),
+				}
+			}
+			result => result,
+		})
+	}
+
+	fn can_edit(&self, path: &Path) -> std::result::Result<bool, FileOpenError> {
+		Ok(self
+			.association_extension(path)
+			.is_some_and(|ext| assoc::has_verb(&ext, "edit")))
+	}
+
+	/// Verbs implemented by shell extensions rather than registry commands
+	/// aren't listed by `list_verbs` but still work here.
+	fn open_with_verb(
+		&self,
+		path: &Path,
+		verb: &str,
+	) -> std::result::Result<OpenResult, FileOpenError> {
+		com::ensure_initialized()?;
+		let path: &Path = &paths::normalize(path)?;
+
+		if let Some(result) = self.check_path(path) {
+			return Ok(result);
+		}
+
+		// An empty verb would silently run the default one.
+		if verb.is_empty() {
+			return Ok(OpenResult::NoHandler {
+				verb: String::new(),
+			});
+		}
+
+		if let Some(result) = self.skip_launch("open_with_verb", path, None) {
+			return Ok(result);
+		}
+
+		let verb_str = HSTRING::from(verb);
+		Ok(
+			match shell_execute(self.owner(), PCWSTR(verb_str.as_ptr()), path, None) {
+				// SE_ERR_NOASSOC here means the type lacks this verb, not that
+				// its association is broken.
+				OpenResult::PlatformError { code, .. }
+					if code == HRESULT::from_win32(ERROR_NO_ASSOCIATION.0).0 =>
+				{
+					OpenResult::NoHandler {
+						verb: verb.to_string(),
+					}
+				}
+				result => result,
+			},
+		)
+	}
+
+	fn list_verbs(&self, path: &Path) -> std::result::Result<Vec<String>, FileOpenError> {
+		Ok(self
+			.association_extension(path)
+			.map(|ext| assoc::verbs(&ext))
+			.unwrap_or_default())
+	}
+
+	/// Only `.lnk` shell links; anything else is `Ok(None)` without being read.
+	fn resolve_shortcut(&self, path: &Path) -> std::result::Result<Option<PathBuf>, FileOpenError> {
+		com::ensure_initialized()?;
+		let path: &Path = &paths::normalize(path)?;
+
+		if !shortcut::is_shortcut(path) {
+			return Ok(None);
+		}
+
+		shortcut::resolve(path).map_err(other_error)
+	}
+
+	/// Built on [`WindowsFileOpener::open_with_app_data_object`].
+	fn open_with_app(
+		&self,
+		path: &Path,
+		app_id: &str,
+	) -> std::result::Result<OpenResult, FileOpenError> {
+		check_app_id(app_id)?;
+		com::ensure_initialized()?;
+		let path: &Path = &paths::normalize(path)?;
+
+		if let Some(result) = self.check_path(path) {
+			return Ok(result);
+		}
+
+		match shell::data_object_for_paths(&[path]) {
+			Ok(data_object) => self.open_with_app_data_object(&data_object, app_id),
+			Err(e) => Ok(status::from_error(&e, path)),
+		}
+	}
+
+	/// The choice is registered with `IAssocHandler::MakeDefault`, as the
+	/// Open With dialog's "Always use this app" does. Where the shell still
+	/// keeps the user's previous choice, which it does for any program but
+	/// its own dialog on current Windows, the Default apps page is opened as
+	/// in `set_default_app` and the default is `RequiresUserAction`.
+	fn open_with_app_and_set_default(
+		&self,
+		path: &Path,
+		app_id: &str,
+	) -> std::result::Result<DefaultedOpen, FileOpenError> {
+		com::ensure_initialized()?;
+		let path: &Path = &paths::normalize(path)?;
+		let ext = self
+			.association_extension(path)
+			.ok_or(FileOpenError::NoExtension)?;
+
+		let result = self.open_with_app(path, app_id)?;
+		if !matches!(result, OpenResult::Success { .. }) {
+			return Ok(DefaultedOpen {
+				result,
+				default: None,
+			});
+		}
+
+		let default = self.make_default(&ext, app_id, path)?;
+		Ok(DefaultedOpen {
+			result,
+			default: Some(default),
+		})
+	}
+
+	fn open_with_app_args(
+		&self,
+		path: &Path,
+		app_id: &str,
+		args: &[&str],
+	) -> std::result::Result<OpenResult, FileOpenError> {
+		self.launch(&LaunchSpec {
+			args: args.iter().map(|arg| arg.to_string()).collect(),
+			..LaunchSpec::new(path, app_id)
+		})
+	}
+
+	/// Handlers that resolve to an executable are launched directly so the
+	/// arguments and working directory can be passed, with `CreateProcessW`
+	/// for a new instance. Packaged and COM-only handlers can only be given a
+	/// data object, so for those all three are ignored and the handler is
+	/// invoked as in `open_with_app`.
+	///
+	/// `CreateProcessW` can't raise a UAC prompt, so an app that only runs as
+	/// administrator is reported `RequiresElevation`, or with
+	/// [`WindowsFileOpenerBuilder::elevate_when_required`] started through
+	/// the shell's `runas` verb, which may hand the file to a running
+	/// instance after all.
+	fn launch(&self, spec: &LaunchSpec) -> std::result::Result<OpenResult, FileOpenError> {
+		com::ensure_initialized()?;
+		spec.check_working_dir()?;
+		let app_id = spec.app_id.as_str();
+
+		// Nothing the handler's own command line can't do, so it's invoked as
+		// registered: DLL-hosted handlers such as Photo Viewer only work that way.
+		if spec.args.is_empty() && spec.working_dir.is_none() && !spec.new_instance {
+			return self.open_with_app(&spec.path, app_id);
+		}
+
+		let path: &Path = &paths::normalize(&spec.path)?;
+
+		if let Some(result) = self.check_path(path) {
+			return Ok(result);
+		}
+
+		let handler = match self.association_extension(path) {
+			Some(ext) => find_app_handler(&*self.assoc, &ext, app_id)?,
+			None => None,
+		};
+		let Some(exe) = handler.and_then(|(name, _)| assoc::handler_executable(&name)) else {
+			return self.open_with_app(path, app_id);
+		};
+
+		let parameters =
+			shell::join_arguments(spec.args.iter().map(OsStr::new).chain([path.as_os_str()]));
+
+		if let Some(result) = self.skip_launch("launch", path, Some(&exe.to_string_lossy())) {
+			return Ok(result);
+		}
+
+		let shell_launch = |verb| {
+			shell_execute_in(
+				self.owner(),
+				verb,
+				&exe,
+				Some(parameters.as_os_str()),
+				spec.working_dir.as_deref(),
+			)
+		};
+		let result = if spec.new_instance {
+			let created = create_process(&exe, &parameters, spec.working_dir.as_deref(), path);
+			self.or_elevated(created, || shell_launch(w!("runas")))
+		} else {
+			shell_launch(w!("open"))
+		};
+		self.history.record(path, app_id, &result);
+		Ok(result)
+	}
+
+	fn open_with_executable(
+		&self,
+		path: &Path,
+		exe: &Path,
+		args: &[&str],
+	) -> std::result::Result<OpenResult, FileOpenError> {
+		com::ensure_initialized()?;
+		let path: &Path = &paths::normalize(path)?;
+
+		if let Some(result) = self.check_path(path) {
+			return Ok(result);
+		}
+
+		let Some(exe) = executable(exe) else {
+			return Ok(OpenResult::AppNotFound {
+				app_id: exe.to_string_lossy().to_string(),
+			});
+		};
+
+		if let Some(result) =
+			self.skip_launch("open_with_executable", path, Some(&exe.to_string_lossy()))
+		{
+			return Ok(result);
+		}
+
+		let parameters =
+			shell::join_arguments(args.iter().map(OsStr::new).chain([path.as_os_str()]));
+		Ok(shell_execute(
+			self.owner(),
+			w!("open"),
+			&exe,
+			Some(parameters.as_os_str()),
+		))
+	}
+
+	/// An app is started through its ProgID's `open` command where it has
+	/// one, so it gets the arguments it registered, and otherwise as its
+	/// executable with the file as the only argument. Packaged apps are
+	/// activated without a process to wait on.
+	fn open_and_wait(
+		&self,
+		path: &Path,
+		app_id: Option<&str>,
+	) -> std::result::Result<ProcessOutcome, FileOpenError> {
+		com::ensure_initialized()?;
+		let path: &Path = &paths::normalize(path)?;
+		let without_exit = |result| ProcessOutcome {
+			result,
+			exit_code: None,
+		};
+
+		if let Some(result) = self.check_path(path) {
+			return Ok(without_exit(result));
+		}
+
+		let Some(app_id) = app_id else {
+			if let Some(result) = self.skip_launch("open_and_wait", path, None) {
+				return Ok(without_exit(result));
+			}
+
+			let launch = wait::Launch {
+				file: path,
+				parameters: None,
+				class: None,
+			};
+			return Ok(wait::run(self.owner(), &launch, path));
+		};
+
+		let ext = self
+			.association_extension(path)
+			.ok_or(FileOpenError::NoExtension)?;
+		let Some((name, _)) = find_app_handler(&*self.assoc, &ext, app_id)? else {
+			return Ok(without_exit(OpenResult::AppNotFound {
+				app_id: app_id.to_string(),
+			}));
+		};
+		if let Some(exe) = assoc::missing_handler_executable(&name) {
+			return Ok(without_exit(OpenResult::AppUnavailable {
+				app_id: app_id.to_string(),
+				exe_path: exe.to_string_lossy().to_string(),
+			}));
+		}
+		let Some(exe) = assoc::handler_executable(&name) else {
+			return Ok(without_exit(self.open_with_app(path, app_id)?));
+		};
+
+		if let Some(result) = self.skip_launch("open_and_wait", path, Some(&name)) {
+			return Ok(without_exit(result));
+		}
+
+		let progid = progid::for_handler(&ext, &name, None);
+		let parameters = shell::join_arguments([path.as_os_str()]);
+		let launch = match &progid {
+			Some(progid) => wait::Launch {
+				file: path,
+				parameters: None,
+				class: Some(progid),
+			},
+			None => wait::Launch {
+				file: &exe,
+				parameters: Some(parameters.as_os_str()),
+				class: None,
+			},
+		};
+
+		let outcome = wait::run(self.owner(), &launch, path);
+		self.history.record(path, app_id, &outcome.result);
+		Ok(outcome)
+	}
+
+	/// Desktop apps are started through the shell, so one whose manifest
+	/// asks for elevation gets its UAC prompt.
+	fn launch_app(&self, app_id: &str) -> std::result::Result<OpenResult, FileOpenError> {
+		check_app_id(app_id)?;
+		com::ensure_initialized()?;
+
+		if let Some(aumid) = packaged::app_user_model_id_for(app_id) {
+			if let Some(result) = self.skip_launch("launch_app", Path::new(app_id), Some(&aumid)) {
+				return Ok(result);
+			}
+
+			return Ok(match packaged::activate(&aumid) {
+				Ok(()) => OpenResult::success(),
+				Err(e) => status::from_error(&e, Path::new(app_id)),
+			});
+		}
+
+		if let Some(exe) = assoc::missing_handler_executable(app_id) {
+			return Ok(OpenResult::AppUnavailable {
+				app_id: app_id.to_string(),
+				exe_path: exe.to_string_lossy().to_string(),
+			});
+		}
+
+		let Some(exe) = app_executable(&*self.assoc, app_id) else {
+			return Ok(OpenResult::AppNotFound {
+				app_id: app_id.to_string(),
+			});
+		};
+		if let Some(result) = self.skip_launch(
+			"launch_app",
+			Path::new(app_id),
+			Some(&exe.to_string_lossy()),
+		) {
+			return Ok(result);
+		}
+
+		Ok(shell_execute(self.owner(), w!("open"), &exe, None))
+	}
+
+	/// `args` are quoted into the command line of the app's executable.
+	/// Packaged apps are started by activation, which takes none.
+	fn launch_app_with_args(
+		&self,
+		app_id: &str,
+		args: &[&str],
+	) -> std::result::Result<OpenResult, FileOpenError> {
+		check_app_id(app_id)?;
+		com::ensure_initialized()?;
+
+		if packaged::app_user_model_id_for(app_id).is_some() {
+			return Ok(OpenResult::Unsupported {
+				operation: "launch_app_with_args".to_string(),
+			});
+		}
+
+		if let Some(exe) = assoc::missing_handler_executable(app_id) {
+			return Ok(OpenResult::AppUnavailable {
+				app_id: app_id.to_string(),
+				exe_path: exe.to_string_lossy().to_string(),
+			});
+		}
+
+		let Some(exe) = app_executable(&*self.assoc, app_id) else {
+			return Ok(OpenResult::AppNotFound {
+				app_id: app_id.to_string(),
+			});
+		};
+		if let Some(result) = self.skip_launch(
+			"launch_app_with_args",
+			Path::new(app_id),
+			Some(&exe.to_string_lossy()),
+		) {
+			return Ok(result);
+		}
+
+		let parameters = shell::join_arguments(args.iter().map(OsStr::new));
+		Ok(shell_execute(
+			self.owner(),
+			w!("open"),
+			&exe,
+			Some(parameters.as_os_str()),
+		))
+	}
+
+	/// INVOKEIDLIST with no verb runs the item's default context-menu
+	/// command, which is how Explorer opens items a namespace extension
+	/// owns; they have no association of their own to go by.
+	fn open_shell_item(
+		&self,
+		parsing_name: &str,
+	) -> std::result::Result<OpenResult, FileOpenError> {
+		com::ensure_initialized()?;
+
+		let Ok(pidl) = shell::OwnedPidl::from_parsing_name(parsing_name) else {
+			return Err(FileOpenError::InvalidPath {
+				path: parsing_name.to_string(),
+				reason: "no shell item has this parsing name",
+			});
+		};
+		if let Some(result) = self.skip_launch("open_shell_item", Path::new(parsing_name), None) {
+			return Ok(result);
+		}
+
+		let mut info = SHELLEXECUTEINFOW {
+			cbSize: std::mem::size_of::<SHELLEXECUTEINFOW>() as u32,
+			hwnd: self.owner(),
+			fMask: SEE_MASK_INVOKEIDLIST | SEE_MASK_NOASYNC,
+			lpIDList: pidl.as_ptr() as *mut _,
+			nShow: SW_SHOWNORMAL.0,
+			..Default::default()
+		};
+
+		Ok(match unsafe { ShellExecuteExW(&mut info) } {
+			Ok(()) => OpenResult::success(),
+			Err(e) => status::from_error(&e, Path::new(parsing_name)),
+		})
+	}
+
+	/// Both SE_ERR_NOASSOC and an incomplete association lead to the prompt:
+	/// either way ShellExecute found nothing it could run.
+	fn open_with_default_or_prompt(
+		&self,
+		path: &Path,
+	) -> std::result::Result<OpenResult, FileOpenError> {
+		let result = self.open_with_default(path)?;
+		if !status::is_no_association(&result) {
+			return Ok(result);
+		}
+
+		Ok(match self.open_with_dialog(path)? {
+			OpenResult::Success { .. } => OpenResult::OpenedAfterPrompt,
+			result if status::is_no_association(&result) => OpenResult::NoHandler {
+				verb: "open".to_string(),
+			},
+			result => result,
+		})
+	}
+
+	/// The dialog offers "Always use this app", so picking one there can
+	/// change the association; the watcher picks that up like any other
+	/// change.
+	fn open_with_dialog(&self, path: &Path) -> std::result::Result<OpenResult, FileOpenError> {
+		com::ensure_initialized()?;
+		let path: &Path = &paths::normalize(path)?;
+
+		if let Some(result) = self.check_path(path) {
+			return Ok(result);
+		}
+
+		if let Some(result) = self.skip_launch("open_with_dialog", path, None) {
+			return Ok(result);
+		}
+
+		// No class: the dialog works out the file's type itself, and still
+		// lists every app when that type has no association.
+		let file = HSTRING::from(path);
+		let info = OPENASINFO {
+			pcszFile: PCWSTR(file.as_ptr()),
+			pcszClass: PCWSTR::null(),
+			oaifInFlags: OAIF_ALLOW_REGISTRATION | OAIF_EXEC,
+		};
+
+		Ok(match unsafe { SHOpenWithDialog(self.owner(), &info) } {
+			Ok(()) => OpenResult::success(),
+			Err(e) => status::from_error(&e, path),
+		})
+	}
+
+	/// Counts the cached enumeration without building a list or loading icons.
+	fn count_apps_for_file(&self, path: &Path) -> std::result::Result<usize, FileOpenError> {
+		self.count_apps(path, None)
+	}
+
+	fn count_apps_for_file_up_to(
+		&self,
+		path: &Path,
+		cap: usize,
+	) -> std::result::Result<usize, FileOpenError> {
+		self.count_apps(path, Some(cap))
+	}
+
+	fn get_default_app(
+		&self,
+		path: &Path,
+	) -> std::result::Result<Option<OpenWithApp>, FileOpenError> {
+		com::ensure_initialized()?;
+
+		let Some(ext) = self.association_extension(path) else {
+			return Ok(None);
+		};
+
+		// Checked first: for a packaged default, ASSOCSTR_EXECUTABLE names the
+		// activation host rather than the app.
+		if let Some((name, handler)) = default_packaged_handler(&*self.assoc, &ext)? {
+			let aumid = packaged::default_app_user_model_id(&ext);
+			return Ok(Some(self.packaged_default(
+				&ext,
+				&name,
+				&handler,
+				aumid.as_deref(),
+			)));
+		}
+
+		let Some(exe) = self
+			.assoc
+			.query_string(ASSOCF_NONE, ASSOCSTR_EXECUTABLE, &ext)
+		else {
+			return Ok(None);
+		};
+
+		self.desktop_default(&ext, exe).map(Some)
+	}
+
+	/// Files whose type has a desktop app as its default are opened
+	/// together, one `Invoke` per app and folder. The rest, including files
+	/// with an override, followed shortcuts and types whose double-click
+	/// isn't `open`, are opened one by one like `open_with_default`.
+	fn open_many_with_default(
+		&self,
+		paths: &[&Path],
+	) -> std::result::Result<Vec<OpenResult>, FileOpenError> {
+		com::ensure_initialized()?;
+		let normalized = paths
+			.iter()
+			.map(|path| paths::normalize(path))
+			.collect::<std::result::Result<Vec<_>, _>>()?;
+		let paths = normalized.iter().map(PathBuf::as_path).collect::<Vec<_>>();
+
+		let mut results: Vec<Option<OpenResult>> = vec![None; paths.len()];
+		let mut handlers: HashMap<String, Option<(String, IAssocHandler)>> = HashMap::new();
+		// Keyed by (handler name, parent directory): the shell can only build a
+		// data object for siblings, so each folder gets its own Invoke.
+		let mut batches: Vec<((String, PathBuf), IAssocHandler, Vec<usize>)> = Vec::new();
+
+		for (index, path) in paths.iter().enumerate() {
+			if let Some(result) = self.check_path(path) {
+				results[index] = Some(result);
+				continue;
+			}
+
+			// An override or a followed shortcut sends the file elsewhere, so
+			// only files going to the shell default are batched.
+			let redirected = self.overrides.app_for(path).is_some()
+				|| (self.follow_shortcuts && shortcut::is_shortcut(path));
+			let handler = match extension::dotted(path) {
+				Some(_) if redirected => None,
+				Some(ext) => match handlers.get(&ext) {
+					Some(handler) => handler.clone(),
+					None => {
+						// `Invoke` runs the handler's `open` command, which is
+						// only what a launch would do when that's the verb in use.
+						let open_verb = self.force_open_verb || assoc::default_verb_is_open(&ext);
+						let handler = if open_verb {
+							default_handler(&*self.assoc, &ext)?
+						} else {
+							None
+						};
+						handlers.insert(ext, handler.clone());
+						handler
+					}
+				},
+				None => None,
+			};
+
+			// Without a batchable handler, open it the way `open_with_default`
+			// would, letting ShellExecute apply its own rules.
+			let Some((name, handler)) = handler else {
+				results[index] = Some(self.open_checked_with_default(path)?);
+				continue;
+			};
+
+			let key = (
+				name,
+				path.parent().map(Path::to_path_buf).unwrap_or_default(),
+			);
+			match batches
+				.iter_mut()
+				.find(|(batch_key, _, _)| *batch_key == key)
+			{
+				Some((_, _, indices)) => indices.push(index),
+				None => batches.push((key, handler, vec![index])),
+			}
+		}
+
+		for ((name, _), handler, indices) in batches {
+			if self.dry_run.is_some() {
+				for index in indices {
+					results[index] =
+						self.skip_launch("open_many_with_default", paths[index], Some(&name));
+				}
+				continue;
+			}
+
+			let batch_paths = indices.iter().map(|&i| paths[i]).collect::<Vec<_>>();
+
+			let result = shell::data_object_for_paths(&batch_paths)
+				.and_then(|data_object| unsafe { handler.Invoke(&data_object) });
+
+			for index in indices {
+				results[index] = Some(match &result {
+					Ok(()) => OpenResult::success(),
+					Err(e) => status::from_error(e, paths[index]),
+				});
+			}
+		}
+
+		Ok(results.into_iter().flatten().collect())
+	}
+
+	/// The app is found by `app_id` for the first file's type and started the
+	/// way that type opens it. Files of other types must have the same app
+	/// among their handlers, found by `app_id` or by its executable (an app
+	/// registers each type under a ProgID of its own), or nothing is opened.
+	///
+	/// The shell only describes siblings in one data object, so each folder
+	/// among `paths` is one `Invoke`.
+	fn open_many_with_app(
+		&self,
+		paths: &[&Path],
+		app_id: &str,
+	) -> std::result::Result<OpenResult, FileOpenError> {
+		check_app_id(app_id)?;
+		com::ensure_initialized()?;
+		let normalized = paths
+			.iter()
+			.map(|path| paths::normalize(path))
+			.collect::<std::result::Result<Vec<_>, _>>()?;
+
+		if let Some(result) = normalized.iter().find_map(|path| self.check_path(path)) {
+			return Ok(result);
+		}
+
+		// Every type is checked before anything is launched.
+		let mut target: Option<(String, String, IAssocHandler)> = None;
+		let mut checked: Vec<String> = Vec::new();
+		for path in &normalized {
+			let ext = self
+				.association_extension(path)
+				.ok_or(FileOpenError::NoExtension)?;
+			if checked.contains(&ext) {
+				continue;
+			}
+
+			match &target {
+				None => match self.app_handler(&ext, app_id)? {
+					Ok((name, handler)) => target = Some((ext.clone(), name, handler)),
+					Err(result) => return Ok(result),
+				},
+				Some((_, name, _)) => {
+					let found = match find_app_handler(&*self.assoc, &ext, app_id)? {
+						Some(found) => Some(found),
+						None => match assoc::handler_executable(name) {
+							Some(exe) => find_handler(&*self.assoc, &ext, |other| {
+								assoc::handler_executable(other).as_ref() == Some(&exe)
+							})?,
+							None => None,
+						},
+					};
+					if found.is_none() {
+						return Ok(OpenResult::AppNotFound {
+							app_id: app_id.to_string(),
+						});
+					}
+				}
+			}
+			checked.push(ext);
+		}
+		let Some((ext, name, handler)) = target else {
+			return Ok(OpenResult::success());
+		};
+		if self.dry_run.is_some() {
+			for path in &normalized {
+				self.skip_launch("open_many_with_app", path, Some(&name));
+			}
+			return Ok(OpenResult::success());
+		}
+
+		let mut folders: Vec<(&Path, Vec<&Path>)> = Vec::new();
+		for path in &normalized {
+			let folder = path.parent().unwrap_or(Path::new(""));
+			match folders.iter_mut().find(|(known, _)| *known == folder) {
+				Some((_, files)) => files.push(path),
+				None => folders.push((folder, vec![path])),
 			}
-			*initialized.borrow_mut() = true;
 		}
-	});
-}
 
-pub struct WindowsFileOpener;
+		let mut aggregate = OpenResult::success();
+		for (_, files) in folders {
+			let result = shell::item_array_for_paths(&files).and_then(|items| {
+				let data = shell::data_object_for_items(&items)?;
+				invoke_handler(&ext, &name, &handler, &data, &items)
+			});
+			let result = match result {
+				Ok(()) => OpenResult::success(),
+				Err(e) => status::from_error(&e, files[0]),
+			};
 
-impl FileOpener for WindowsFileOpener {
-	fn get_apps_for_file(&self, path: &Path) -> std::result::Result<Vec<OpenWithApp>, String> {
-		ensure_com_initialized();
+			for file in &files {
+				self.history.record(file, app_id, &result);
+			}
+			if matches!(aggregate, OpenResult::Success { .. }) {
+				aggregate = result;
+			}
+		}
+		Ok(aggregate)
+	}
 
-		let ext = path
-			.extension()
-			.and_then(|e| e.to_str())
-			.map(|e| format!(".{}", e))
-			.unwrap_or_default();
+	fn open_uri_with_policy(
+		&self,
+		uri: &str,
+		policy: &UriPolicy,
+	) -> std::result::Result<OpenResult, FileOpenError> {
+		policy.check(uri)?;
+		com::ensure_initialized()?;
 
-		if ext.is_empty() {
-			return Ok(vec![]);
+		if let Some(result) = self.skip_launch("open_uri", Path::new(uri), None) {
+			return Ok(result);
 		}
 
-		list_apps_for_extension(&ext)
+		// Handed over verbatim; the shell resolves the scheme's handler.
+		Ok(shell_execute(
+			self.owner(),
+			w!("open"),
+			Path::new(uri),
+			None,
+		))
 	}
 
-	fn open_with_default(&self, path: &Path) -> std::result::Result<OpenResult, String> {
-		ensure_com_initialized();
+	fn reveal(&self, path: &Path) -> std::result::Result<OpenResult, FileOpenError> {
+		com::ensure_initialized()?;
+		let path: &Path = &paths::normalize(path)?;
+
+		let target = reveal_target(path);
+		if let RevealTarget::Folder(shown) | RevealTarget::Select(shown) = &target {
+			if let Some(result) = self.skip_launch("reveal", shown, None) {
+				return Ok(result);
+			}
+		}
+
+		Ok(match target {
+			// "explore" rather than "open" so a folder always lands in Explorer,
+			// even when another file manager has claimed the folder verb.
+			RevealTarget::Folder(folder) => {
+				shell_execute(self.owner(), w!("explore"), &folder, None)
+			}
+			RevealTarget::Select(item) => {
+				// With no child items, the function opens the item's parent
+				// folder and selects the item itself.
+				let result = shell::OwnedPidl::parse(&item)
+					.and_then(|pidl| unsafe { SHOpenFolderAndSelectItems(pidl.as_ptr(), None, 0) });
 
-		let path_str = path.to_string_lossy();
-		let h_path = HSTRING::from(&*path_str);
+				match result {
+					Ok(()) => OpenResult::success(),
+					Err(e) => status::from_error(&e, &item),
+				}
+			}
+			RevealTarget::Missing => OpenResult::FileNotFound {
+				path: path.to_string_lossy().to_string(),
+			},
+		})
+	}
 
-		unsafe {
-			let result = ShellExecuteW(None, w!("open"), &h_path, None, None, SW_SHOWNORMAL);
+	/// One `IFileOperation` for all of `paths`, so a batch shows one
+	/// confirmation and one progress dialog.
+	fn move_to_trash(
+		&self,
+		paths: &[&Path],
+	) -> std::result::Result<Vec<OpenResult>, FileOpenError> {
+		com::ensure_initialized()?;
+		let normalized = paths
+			.iter()
+			.map(|path| paths::normalize(path))
+			.collect::<std::result::Result<Vec<_>, _>>()?;
+		let paths = normalized.iter().map(PathBuf::as_path).collect::<Vec<_>>();
 
-			if result.0 as isize > 32 {
-				Ok(OpenResult::Success)
-			} else {
-				Ok(OpenResult::PlatformError {
-					message: format!("ShellExecute failed with code {}", result.0 as isize),
+		if self.dry_run.is_some() {
+			return Ok(paths
+				.iter()
+				.filter_map(|path| {
+					self.check_path(path)
+						.or_else(|| self.skip_launch("move_to_trash", path, None))
 				})
-			}
+				.collect());
 		}
+
+		Ok(trash::recycle(self.owner(), &paths))
 	}
 
-	fn open_with_app(&self, path: &Path, app_id: &str) -> std::result::Result<OpenResult, String> {
-		ensure_com_initialized();
+	fn reveal_many(&self, paths: &[&Path]) -> std::result::Result<Vec<OpenResult>, FileOpenError> {
+		com::ensure_initialized()?;
+		let normalized = paths
+			.iter()
+			.map(|path| paths::normalize(path))
+			.collect::<std::result::Result<Vec<_>, _>>()?;
 
-		let ext = path
-			.extension()
-			.and_then(|e| e.to_str())
-			.map(|e| format!(".{}", e))
-			.unwrap_or_default();
+		let mut results: Vec<Option<OpenResult>> = vec![None; normalized.len()];
+		// One window per parent folder, selecting every item revealed in it.
+		let mut folders: Vec<(PathBuf, Vec<(usize, PathBuf)>)> = Vec::new();
 
-		if ext.is_empty() {
-			return Ok(OpenResult::PlatformError {
-				message: "File has no extension".to_string(),
-			});
-		}
+		for (index, path) in normalized.iter().enumerate() {
+			let RevealTarget::Select(item) = reveal_target(path) else {
+				results[index] = Some(self.reveal(path)?);
+				continue;
+			};
 
-		// Find handler by app_id (which is the app name on Windows)
-		unsafe {
-			let handlers =
-				SHAssocEnumHandlers(&HSTRING::from(ext.as_str()), ASSOC_FILTER_RECOMMENDED)
-					.map_err(|e| e.to_string())?;
+			let parent = item.parent().map(Path::to_path_buf).unwrap_or_default();
+			match folders.iter_mut().find(|(folder, _)| *folder == parent) {
+				Some((_, items)) => items.push((index, item)),
+				None => folders.push((parent, vec![(index, item)])),
+			}
+		}
 
-			loop {
-				let mut handler_array: [Option<IAssocHandler>; 1] = [None];
-				let mut fetched = 0u32;
-				
-				if handlers.Next(&mut handler_array, Some(&mut fetched)).is_err() || fetched == 0 {
-					break;
+		for (folder, items) in folders {
+			if self.dry_run.is_some() {
+				for (index, item) in items {
+					results[index] = self.skip_launch("reveal_many", &item, None);
 				}
+				continue;
+			}
+
+			let result =
+				shell::select_in_folder(&folder, items.iter().map(|(_, item)| item.as_path()));
+
+			for (index, item) in items {
+				results[index] = Some(match &result {
+					Ok(()) => OpenResult::success(),
+					Err(e) => status::from_error(e, &item),
+				});
+			}
+		}
 
-				if let Some(handler) = &handler_array[0] {
-					let name = handler
-						.GetName()
-						.map_err(|e| e.to_string())?
-						.to_string()
-						.map_err(|e| e.to_string())?;
+		Ok(results.into_iter().flatten().collect())
+	}
 
-					if name == app_id {
-						// Create shell item from file path
-						let path_str = path.to_string_lossy();
-						let h_path = HSTRING::from(&*path_str);
+	fn show_properties(&self, path: &Path) -> std::result::Result<OpenResult, FileOpenError> {
+		com::ensure_initialized()?;
+		let path: &Path = &paths::normalize(path)?;
 
-						let shell_item: IShellItem =
-							SHCreateItemFromParsingName(&h_path, None).map_err(|e| e.to_string())?;
+		// The shell would show a dialog for a missing file anyway, with every
+		// field blank.
+		if let Some(result) = self.check_path(path) {
+			return Ok(result);
+		}
+		if let Some(result) = self.skip_launch("show_properties", path, None) {
+			return Ok(result);
+		}
 
-						let data_object: IDataObject = shell_item
-							.BindToHandler(None, &BHID_DataObject)
-							.map_err(|e| e.to_string())?;
+		// INVOKEIDLIST routes "properties" through the item's context menu,
+		// which works for folders as well as files. NOASYNC as in
+		// `open_elevated`: the dialog must be up before the pool thread moves on.
+		let file = HSTRING::from(path);
+		let mut info = SHELLEXECUTEINFOW {
+			cbSize: std::mem::size_of::<SHELLEXECUTEINFOW>() as u32,
+			hwnd: self.owner(),
+			fMask: SEE_MASK_INVOKEIDLIST | SEE_MASK_NOASYNC,
+			lpVerb: w!("properties"),
+			lpFile: PCWSTR(file.as_ptr()),
+			nShow: SW_SHOWNORMAL.0,
+			..Default::default()
+		};
 
-						handler.Invoke(&data_object).map_err(|e| e.to_string())?;
+		Ok(match unsafe { ShellExecuteExW(&mut info) } {
+			Ok(()) => OpenResult::success(),
+			Err(e) => status::from_error(&e, path),
+		})
+	}
 
-						return Ok(OpenResult::Success);
-					}
+	#[cfg(feature = "icons")]
+	fn get_thumbnail(
+		&self,
+		path: &Path,
+		size: u32,
+	) -> std::result::Result<Option<Vec<u8>>, FileOpenError> {
+		com::ensure_initialized()?;
+		let path: &Path = &paths::normalize(path)?;
+
+		if !path.exists() {
+			return Ok(None);
+		}
+
+		Ok(thumbnail::for_path(path, size))
+	}
+
+	#[cfg(not(feature = "icons"))]
+	fn get_thumbnail(
+		&self,
+		path: &Path,
+		size: u32,
+	) -> std::result::Result<Option<Vec<u8>>, FileOpenError> {
+		let _ = (path, size);
+		Err(FileOpenError::Unsupported {
+			operation: "get_thumbnail".to_string(),
+		})
+	}
+
+	/// See [`mail`] for how the message is handed over, and the limits on
+	/// attachments that come with it.
+	fn open_mail_with_attachment(
+		&self,
+		attachments: &[&Path],
+		subject: Option<&str>,
+		body: Option<&str>,
+	) -> std::result::Result<OpenResult, FileOpenError> {
+		com::ensure_initialized()?;
+		let normalized = attachments
+			.iter()
+			.map(|path| paths::normalize(path))
+			.collect::<std::result::Result<Vec<_>, _>>()?;
+
+		if let Some(result) = normalized.iter().find_map(|path| self.check_path(path)) {
+			return Ok(result);
+		}
+		if let Some(folder) = normalized.iter().find(|path| path.is_dir()) {
+			return Err(FileOpenError::InvalidPath {
+				path: folder.to_string_lossy().to_string(),
+				reason: "a folder can't be attached",
+			});
+		}
+
+		let attachments = normalized.iter().map(PathBuf::as_path).collect::<Vec<_>>();
+		if let Some(mapi) = mail::mapi() {
+			if self.dry_run.is_some() {
+				let none = [Path::new("")];
+				let targets = if attachments.is_empty() {
+					&none[..]
+				} else {
+					&attachments[..]
+				};
+				for attachment in targets {
+					self.skip_launch("open_mail_with_attachment", attachment, Some(&mapi.client));
 				}
+				return Ok(OpenResult::success());
 			}
+			if let Some(result) = mapi.send(self.owner(), &attachments, subject, body) {
+				return Ok(result);
+			}
+		}
+		if !attachments.is_empty() {
+			return Ok(OpenResult::NoMailClient);
+		}
+
+		let uri = mail::mailto_uri(subject, body);
+		if uri.len() > mail::MAX_MAILTO_LEN {
+			return Ok(OpenResult::PlatformError {
+				message: "The message is too long to hand to the mail client".to_string(),
+				code: 0,
+			});
+		}
+		if let Some(result) = self.skip_launch("open_mail_with_attachment", Path::new(&uri), None) {
+			return Ok(result);
+		}
 
-			Ok(OpenResult::AppNotFound {
+		let result = shell_execute(self.owner(), w!("open"), Path::new(&uri), None);
+		Ok(if status::is_no_association(&result) {
+			OpenResult::NoMailClient
+		} else {
+			result
+		})
+	}
+
+	/// See [`defaults`] for why this can only open Settings.
+	fn set_default_app(
+		&self,
+		extension: &str,
+		app_id: &str,
+	) -> std::result::Result<OpenResult, FileOpenError> {
+		com::ensure_initialized()?;
+		let ext = format!(".{}", mime::normalize_extension(extension));
+
+		let Some((name, handler)) = find_app_handler(&*self.assoc, &ext, app_id)? else {
+			return Ok(OpenResult::AppNotFound {
 				app_id: app_id.to_string(),
-			})
+			});
+		};
+
+		let current = self.get_default_app(Path::new(&format!("file{ext}")))?;
+		if current.is_some_and(|app| app.id == stable_id::of_handler(&ext, &name, &handler)) {
+			return Ok(OpenResult::success());
+		}
+
+		let uri = match packaged::app_user_model_id(&ext, &name, &handler) {
+			Some(aumid) => defaults::settings_uri_for_package(&aumid),
+			None => defaults::settings_uri(&ext, assoc::handler_executable(&name).as_deref()),
+		};
+
+		let result = self
+			.skip_launch("set_default_app", Path::new(&uri), Some(&name))
+			.unwrap_or_else(|| shell_execute(self.owner(), w!("open"), Path::new(&uri), None));
+		Ok(match result {
+			OpenResult::Success { .. } => OpenResult::RequiresUserAction,
+			result => result,
+		})
+	}
+
+	fn clear_cache(&self) {
+		cache::invalidate();
+	}
+
+	fn clear_icon_cache(&self) {
+		icon_cache::clear();
+	}
+
+	/// Resets the handler cache (every thread's enumerations, with the names,
+	/// apps and icons worked out from them) and the process-wide icon cache.
+	/// The calling thread's handlers are released now; other threads drop
+	/// theirs on their next lookup.
+	///
+	/// Kept: the executables behind `exe:` ids, so ids already handed out
+	/// still resolve, and association watchers. Association listings aren't
+	/// cached and need nothing.
+	fn refresh(&self) {
+		cache::invalidate();
+		cache::clear_thread();
+		icon_cache::clear();
+	}
+
+	/// Callbacks run on the watcher's message-pump thread, after the handler
+	/// cache is invalidated, so one that blocks holds up every later
+	/// notification.
+	///
+	/// `WM_SETTINGCHANGE` is broadcast for unrelated settings too, and each
+	/// one is passed on.
+	fn watch_association_changes(
+		&self,
+		callback: Box<dyn Fn() + Send + Sync>,
+	) -> std::result::Result<WatchHandle, FileOpenError> {
+		let id = watcher::subscribe(Arc::from(callback));
+		Ok(WatchHandle::new(move || watcher::unsubscribe(id)))
+	}
+
+	/// Icons and thumbnails depend on the `icons` feature, and icons on the
+	/// builder's icon settings too.
+	fn capabilities(&self) -> OpenerCapabilities {
+		OpenerCapabilities {
+			reveal: true,
+			elevated: true,
+			verbs: true,
+			dialog: true,
+			executable: true,
+			launch: true,
+			wait: true,
+			shell_items: true,
+			print: true,
+			edit: true,
+			properties: true,
+			icons: !self.icon_sizes().is_empty(),
+			thumbnails: cfg!(feature = "icons"),
+			set_default: true,
+			uri: true,
+			trash: true,
+			mail: true,
 		}
 	}
 }
 
-fn list_apps_for_extension(ext: &str) -> std::result::Result<Vec<OpenWithApp>, String> {
+fn shell_execute(owner: HWND, verb: PCWSTR, file: &Path, parameters: Option<&OsStr>) -> OpenResult {
+	shell_execute_in(owner, verb, file, parameters, None)
+}
+
+/// [`shell_execute`] through `ShellExecuteExW`, so the process it started
+/// can be reported. A null `verb` is the file's default verb.
+///
+/// Only a launch that started a process of its own has a pid: a file handed
+/// to a running instance over DDE, or to a COM or packaged handler, has none.
+/// A single-instance app that forwards the file is reported with the pid of
+/// the short-lived process it started to do so.
+fn shell_open(owner: HWND, verb: PCWSTR, file: &Path) -> OpenResult {
+	let _span =
+		tracing::debug_span!(target: LOG_TARGET, "shell_open", file = %file.display()).entered();
+
+	let path = HSTRING::from(file);
+	let mut info = SHELLEXECUTEINFOW {
+		cbSize: std::mem::size_of::<SHELLEXECUTEINFOW>() as u32,
+		hwnd: owner,
+		fMask: SEE_MASK_NOCLOSEPROCESS | SEE_MASK_NOASYNC,
+		lpVerb: verb,
+		lpFile: PCWSTR(path.as_ptr()),
+		nShow: SW_SHOWNORMAL.0,
+		..Default::default()
+	};
+
+	// On failure hInstApp holds the SE_ERR code ShellExecuteW would have
+	// returned, so both are mapped the same way.
+	let launched = unsafe {
+		SetLastError(WIN32_ERROR(0));
+		ShellExecuteExW(&mut info)
+	};
+	if launched.is_err() {
+		let code = info.hInstApp.0 as isize;
+		let last_error = unsafe { GetLastError() };
+		tracing::debug!(
+			target: LOG_TARGET,
+			code,
+			last_error = last_error.0,
+			"ShellExecuteExW failed"
+		);
+		return status::from_shell_execute(code, last_error, file);
+	}
+
+	if info.hProcess.is_invalid() {
+		return OpenResult::success();
+	}
+	let pid = unsafe {
+		let pid = GetProcessId(info.hProcess);
+		let _ = CloseHandle(info.hProcess);
+		pid
+	};
+	tracing::debug!(target: LOG_TARGET, pid, "ShellExecuteExW succeeded");
+	OpenResult::Success {
+		pid: (pid != 0).then_some(pid),
+	}
+}
+
+/// [`shell_execute`] with the launched app started in `directory`, or in
+/// Spacedrive's own working directory for `None`.
+fn shell_execute_in(
+	owner: HWND,
+	verb: PCWSTR,
+	file: &Path,
+	parameters: Option<&OsStr>,
+	directory: Option<&Path>,
+) -> OpenResult {
+	let parameters = parameters.map(HSTRING::from);
+	let parameters = parameters
+		.as_ref()
+		.map_or(PCWSTR::null(), |p| PCWSTR(p.as_ptr()));
+	let directory = directory.map(HSTRING::from);
+	let directory = directory
+		.as_ref()
+		.map_or(PCWSTR::null(), |d| PCWSTR(d.as_ptr()));
+
+	let _span =
+		tracing::debug_span!(target: LOG_TARGET, "shell_execute", file = %file.display()).entered();
+
+	// Cleared first so a stale ERROR_CANCELLED can't be mistaken for this
+	// call's outcome below.
+	let result = unsafe {
+		SetLastError(WIN32_ERROR(0));
+		ShellExecuteW(
+			owner,
+			verb,
+			&HSTRING::from(file),
+			parameters,
+			directory,
+			SW_SHOWNORMAL,
+		)
+	};
+
+	let code = result.0 as isize;
+	if code > 32 {
+		tracing::debug!(target: LOG_TARGET, "ShellExecuteW succeeded");
+		return OpenResult::success();
+	}
+
+	let last_error = unsafe { GetLastError() };
+	tracing::debug!(
+		target: LOG_TARGET,
+		code,
+		last_error = last_error.0,
+		"ShellExecuteW failed"
+	);
+	status::from_shell_execute(code, last_error, file)
+}
+
+/// Starts `exe` with `parameters` in a process of its own, in `directory` or
+/// Spacedrive's working directory for `None`. Failures are reported against
+/// `document`.
+///
+/// The shell may hand a launch to a running instance over DDE; this never
+/// does, though the app itself still can.
+fn create_process(
+	exe: &Path,
+	parameters: &OsStr,
+	directory: Option<&Path>,
+	document: &Path,
+) -> OpenResult {
+	let _span =
+		tracing::debug_span!(target: LOG_TARGET, "CreateProcessW", exe = %exe.display()).entered();
+
+	// Apps read their own name from the front of the command line.
+	let mut command_line = shell::join_arguments([exe.as_os_str()]);
+	command_line.push(" ");
+	command_line.push(parameters);
+	let mut command_line = command_line
+		.encode_wide()
+		.chain(Some(0))
+		.collect::<Vec<_>>();
+	let directory = directory.map(HSTRING::from);
+	let startup = STARTUPINFOW {
+		cb: std::mem::size_of::<STARTUPINFOW>() as u32,
+		..Default::default()
+	};
+	let mut process = PROCESS_INFORMATION::default();
+
+	let created = unsafe {
+		CreateProcessW(
+			&HSTRING::from(exe),
+			PWSTR(command_line.as_mut_ptr()),
+			None,
+			None,
+			false,
+			PROCESS_CREATION_FLAGS(0),
+			None,
+			directory
+				.as_ref()
+				.map_or(PCWSTR::null(), |d| PCWSTR(d.as_ptr())),
+			&startup,
+			&mut process,
+		)
+	};
+	if let Err(e) = created {
+		tracing::debug!(target: LOG_TARGET, "CreateProcessW failed: {e}");
+		return status::from_error(&e, document);
+	}
+
 	unsafe {
-		let handlers = SHAssocEnumHandlers(&HSTRING::from(ext), ASSOC_FILTER_RECOMMENDED)
-			.map_err(|e| e.to_string())?;
+		let _ = CloseHandle(process.hThread);
+		let _ = CloseHandle(process.hProcess);
+	}
+	OpenResult::success_with_pid(process.dwProcessId)
+}
+
+/// Resolves the registered default executable for `ext` to its enumerated handler.
+///
+/// A packaged default resolves to `None`, leaving it to ShellExecute: it
+/// can't be batched into one `Invoke` like a desktop app.
+fn default_handler(
+	assoc: &dyn AssocProvider,
+	ext: &str,
+) -> std::result::Result<Option<(String, IAssocHandler)>, FileOpenError> {
+	if packaged::default_app_user_model_id(ext).is_some() {
+		return Ok(None);
+	}
+
+	let Some(exe) = assoc.query_string(ASSOCF_NONE, ASSOCSTR_EXECUTABLE, ext) else {
+		return Ok(None);
+	};
+
+	find_handler(assoc, ext, |name| name.eq_ignore_ascii_case(&exe))
+}
+
+/// The enumerated handler of the packaged app registered as the default for
+/// `ext`, if the default is one.
+fn default_packaged_handler(
+	assoc: &dyn AssocProvider,
+	ext: &str,
+) -> std::result::Result<Option<(String, IAssocHandler)>, FileOpenError> {
+	let Some(aumid) = packaged::default_app_user_model_id(ext) else {
+		return Ok(None);
+	};
+
+	find_handler_by(assoc, ext, |name, handler| {
+		packaged::app_user_model_id(ext, name, handler).as_deref() == Some(aumid.as_str())
+	})
+}
+
+/// The program an app id stands for: a handler's executable, the one a
+/// ProgID opens files with, or the one behind a hashed id listed earlier.
+fn app_executable(assoc: &dyn AssocProvider, app_id: &str) -> Option<PathBuf> {
+	assoc::handler_executable(app_id)
+		.or_else(|| {
+			assoc
+				.query_string(ASSOCF_NONE, ASSOCSTR_EXECUTABLE, app_id)
+				.map(PathBuf::from)
+				.filter(|exe| exe.is_file())
+		})
+		.or_else(|| stable_id::executable(app_id).filter(|exe| exe.is_file()))
+}
+
+/// `exe` normalized, if it is an existing file Windows runs as a program,
+/// going by the extensions listed in `PATHEXT`.
+///
+/// Anything else would be opened with its own association instead of run.
+fn executable(exe: &Path) -> Option<PathBuf> {
+	let exe = paths::normalize(exe).ok()?;
+	let ext = exe.extension()?.to_str()?;
+	let runnable = std::env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string());
+
+	let runnable = runnable
+		.split(';')
+		.any(|known| known.trim_start_matches('.').eq_ignore_ascii_case(ext));
+	(runnable && exe.is_file()).then_some(exe)
+}
+
+/// Hands `items`, which `data` describes, to the app behind `handler`.
+fn invoke_handler(
+	ext: &str,
+	name: &str,
+	handler: &IAssocHandler,
+	data: &IDataObject,
+	items: &IShellItemArray,
+) -> Result<()> {
+	match packaged::app_user_model_id(ext, name, handler) {
+		Some(aumid) => packaged::activate_for_items(&aumid, items),
+		None => unsafe { handler.Invoke(data) },
+	}
+}
+
+/// Finds the handler for `ext` that `app_id` names: by its `GetName`, the
+/// hash of its executable or its AUMID, any of which `OpenWithApp::id` may
+/// be (see [`stable_id`]), or else its ProgID.
+fn find_app_handler(
+	assoc: &dyn AssocProvider,
+	ext: &str,
+	app_id: &str,
+) -> std::result::Result<Option<(String, IAssocHandler)>, FileOpenError> {
+	// Every caller gets this, however it came by the id.
+	check_app_id(app_id)?;
+
+	// Bare handler names are what ids were before they were made stable, and
+	// may still be saved somewhere.
+	if let Some(found) = find_handler(assoc, ext, |name| name == app_id)? {
+		return Ok(Some(found));
+	}
+
+	if let Some(found) = find_handler(assoc, ext, |name| {
+		assoc::handler_executable(name).is_some_and(|exe| stable_id::is_hash_of(app_id, &exe))
+	})? {
+		return Ok(Some(found));
+	}
+
+	if let Some(found) = find_handler_by(assoc, ext, |name, handler| {
+		assoc::handler_executable(name).is_none()
+			&& packaged::app_user_model_id(ext, name, handler)
+				.is_some_and(|aumid| aumid.eq_ignore_ascii_case(app_id))
+	})? {
+		return Ok(Some(found));
+	}
+
+	// Registry keys, ProgIDs included, are case-insensitive.
+	find_handler_by(assoc, ext, |name, handler| {
+		progid::of_handler(ext, name, handler)
+			.is_some_and(|prog_id| prog_id.eq_ignore_ascii_case(app_id))
+	})
+}
+
+/// Finds the first handler for `ext` whose name `matches` accepts.
+///
+/// Recommended handlers are searched first; the full list is only enumerated
+/// when none match, e.g. for an app picked from "More apps…".
+fn find_handler(
+	assoc: &dyn AssocProvider,
+	ext: &str,
+	matches: impl Fn(&str) -> bool,
+) -> std::result::Result<Option<(String, IAssocHandler)>, FileOpenError> {
+	find_handler_by(assoc, ext, |name, _| matches(name))
+}
+
+/// Like [`find_handler`], for matches that need the handler itself.
+fn find_handler_by(
+	assoc: &dyn AssocProvider,
+	ext: &str,
+	matches: impl Fn(&str, &IAssocHandler) -> bool,
+) -> std::result::Result<Option<(String, IAssocHandler)>, FileOpenError> {
+	let cancel = CancellationToken::new();
+	if let Some(found) = handlers(assoc, ext, ASSOC_FILTER_RECOMMENDED, &cancel)?.find(&matches) {
+		return Ok(Some(found));
+	}
+
+	Ok(handlers(assoc, ext, ASSOC_FILTER_NONE, &cancel)?.find(matches))
+}
+
+/// The handlers for `ext` as apps, default first; see
+/// [`file_opening::sort_by_relevance`].
+fn list_apps_for_extension(
+	assoc: &dyn AssocProvider,
+	ext: &str,
+	filter: ASSOC_FILTER,
+	icon_sizes: &[u32],
+	cancel: &CancellationToken,
+) -> std::result::Result<Vec<OpenWithApp>, FileOpenError> {
+	let _span =
+		tracing::debug_span!(target: LOG_TARGET, "list_apps", ext, filter = filter.0).entered();
+
+	// A type can be known and still have nothing to open it, in which case
+	// the enumeration succeeds with no handlers and has no default to mark.
+	let handlers = handlers(assoc, ext, filter, cancel)?;
+	if handlers.is_empty() {
+		tracing::debug!(target: LOG_TARGET, "No handlers are registered for {ext}");
+		return Ok(Vec::new());
+	}
+	let mut apps = handlers.apps(icon_sizes, cancel)?;
+
+	// Marked per call rather than cached: the lookup is cheap, and dedup may
+	// have kept a sibling entry of the default under another name.
+	if let Some((name, handler)) = default_packaged_handler(assoc, ext)? {
+		let id = stable_id::of_handler(ext, &name, &handler);
+		for app in &mut apps {
+			app.is_default = app.id == id;
+		}
+	} else if let Some(exe) = assoc.query_string(ASSOCF_NONE, ASSOCSTR_EXECUTABLE, ext) {
+		for app in &mut apps {
+			app.is_default = app
+				.exe_path
+				.as_deref()
+				.is_some_and(|path| path.as_os_str().eq_ignore_ascii_case(&exe));
+		}
+	}
+
+	file_opening::sort_by_relevance(&mut apps);
+	tracing::debug!(target: LOG_TARGET, apps = apps.len(), "Listed apps");
+	Ok(apps)
+}
+
+/// The handlers `assoc` lists for `ext` under `filter`, from the thread's
+/// cache when `assoc` may use it.
+fn handlers(
+	assoc: &dyn AssocProvider,
+	ext: &str,
+	filter: ASSOC_FILTER,
+	cancel: &CancellationToken,
+) -> std::result::Result<Rc<cache::Handlers>, FileOpenError> {
+	let enumerate = |ext: &str, filter| {
+		let handlers = assoc.enum_handlers(ext, filter, cancel)?;
+		Ok(cache::Handlers::new(ext, handlers))
+	};
+
+	if assoc.cacheable() {
+		cache::handlers_for(ext, filter, enumerate)
+	} else {
+		enumerate(ext, filter).map(Rc::new)
+	}
+}
+
+/// The handlers `SHAssocEnumHandlers` lists for an extension, fetched one at
+/// a time.
+///
+/// Yields bare handlers: reading a name costs a COM call and an allocation,
+/// so that's left to whoever consumes them.
+pub(crate) struct HandlerIter {
+	handlers: IEnumAssocHandlers,
+}
+
+impl HandlerIter {
+	pub fn new(ext: &str, filter: ASSOC_FILTER) -> std::result::Result<Self, FileOpenError> {
+		let handlers =
+			unsafe { SHAssocEnumHandlers(&HSTRING::from(ext), filter) }.map_err(|e| {
+				tracing::warn!(
+					target: LOG_TARGET,
+					hresult = format_args!("{:#010x}", e.code().0),
+					"SHAssocEnumHandlers failed for {ext}"
+				);
+				FileOpenError::EnumHandlers(e.code().0)
+			})?;
+
+		Ok(Self { handlers })
+	}
+}
 
-		let mut apps = Vec::new();
-		
+impl Iterator for HandlerIter {
+	type Item = IAssocHandler;
+
+	fn next(&mut self) -> Option<IAssocHandler> {
 		loop {
 			let mut handler_array: [Option<IAssocHandler>; 1] = [None];
 			let mut fetched = 0u32;
-			
-			if handlers.Next(&mut handler_array, Some(&mut fetched)).is_err() || fetched == 0 {
-				break;
-			}
-
-			if let Some(handler) = &handler_array[0] {
-				let name = handler
-					.GetName()
-					.map_err(|e| e.to_string())?
-					.to_string()
-					.map_err(|e| e.to_string())?;
-
-				apps.push(OpenWithApp {
-					id: name.clone(),
-					name,
-					icon: None,
-				});
+
+			// A failed fetch can't be skipped past: the enumerator may not have
+			// advanced, so what was listed so far is what's returned.
+			let result = unsafe { self.handlers.Next(&mut handler_array, Some(&mut fetched)) };
+			if let Err(e) = &result {
+				tracing::warn!(target: LOG_TARGET, "Stopped enumerating file handlers early: {e}");
+			}
+			if result.is_err() || fetched == 0 {
+				return None;
 			}
+
+			if let Some(handler) = handler_array[0].take() {
+				return Some(handler);
+			}
+		}
+	}
+}
+
+fn other_error(error: impl std::fmt::Display) -> FileOpenError {
+	FileOpenError::Other(error.to_string())
+}
+
+/// Converts a shell-allocated string to an owned `String` and frees it.
+///
+/// `IAssocHandler` getters return `CoTaskMemAlloc`'d buffers that the caller
+/// owns, so converting without freeing leaks on every enumeration.
+pub(crate) unsafe fn take_co_string(
+	value: PWSTR,
+) -> std::result::Result<String, std::string::FromUtf16Error> {
+	let result = value.to_string();
+	CoTaskMemFree(Some(value.0 as *const _));
+	result
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use provider::fake::FakeAssoc;
+	use std::thread;
+
+	fn names(apps: Vec<OpenWithApp>) -> Vec<String> {
+		apps.into_iter().map(|app| app.name).collect()
+	}
+
+	#[test]
+	fn only_existing_programs_count_as_executables() {
+		let exe = std::env::current_exe().unwrap();
+		assert!(executable(&exe).is_some());
+
+		let text =
+			std::env::temp_dir().join(format!("sd-not-a-program-{}.txt", std::process::id()));
+		std::fs::write(&text, b"").unwrap();
+		assert_eq!(executable(&text), None);
+		std::fs::remove_file(&text).unwrap();
+
+		assert_eq!(executable(&exe.with_file_name("missing.exe")), None);
+		assert_eq!(executable(Path::new("")), None);
+	}
+
+	#[test]
+	fn apps_are_found_by_handler_name_or_prog_id() {
+		let exe = std::env::current_exe().unwrap();
+		assert_eq!(
+			app_executable(&SystemAssoc, &exe.to_string_lossy()),
+			Some(exe)
+		);
+		assert!(app_executable(&SystemAssoc, "txtfile").is_some_and(|exe| exe.is_file()));
+		assert_eq!(app_executable(&SystemAssoc, "sd-not-an-app"), None);
+
+		assert!(matches!(
+			WindowsFileOpener::default().launch_app("sd-not-an-app"),
+			Ok(OpenResult::AppNotFound { app_id }) if app_id == "sd-not-an-app"
+		));
+	}
+
+	#[test]
+	fn listings_are_built_from_the_provider() {
+		com::ensure_initialized().unwrap();
+		let exe = std::env::current_exe().unwrap();
+		let cmd = PathBuf::from(std::env::var("SystemRoot").unwrap()).join(r"System32\cmd.exe");
+		let name = |path: &Path| Some(path.to_string_lossy().to_string());
+		let opener = WindowsFileOpener {
+			assoc: Arc::new(FakeAssoc {
+				handlers: vec![
+					name(&cmd),
+					None,
+					name(&exe),
+					Some(cmd.to_string_lossy().to_uppercase()),
+				],
+				default: name(&exe),
+				..FakeAssoc::default()
+			}),
+			..WindowsFileOpener::default()
+		};
+		let apps = opener.get_apps_for_file(Path::new("notes.sdfake")).unwrap();
+
+		// The default first, the failing handler skipped, and cmd.exe once.
+		let listed = apps
+			.iter()
+			.map(|app| (app.exe_path.clone(), app.is_default))
+			.collect::<Vec<_>>();
+		assert_eq!(listed, [(Some(exe), true), (Some(cmd), false)]);
+		assert_eq!(
+			opener.count_apps(Path::new("notes.sdfake"), None).unwrap(),
+			2
+		);
+	}
+
+	#[test]
+	fn provider_failures_surface_as_enumeration_errors() {
+		let opener = WindowsFileOpener {
+			assoc: Arc::new(FakeAssoc {
+				failing: true,
+				..FakeAssoc::default()
+			}),
+			..WindowsFileOpener::default()
+		};
+		assert!(matches!(
+			opener.get_apps_for_file(Path::new("notes.sdfake")),
+			Err(FileOpenError::EnumHandlers(_))
+		));
+	}
+
+	#[test]
+	fn malformed_app_ids_fail_before_any_enumeration() {
+		// Any enumeration would fail, so only an up-front check gets past it.
+		let opener = WindowsFileOpener {
+			assoc: Arc::new(FakeAssoc {
+				failing: true,
+				..FakeAssoc::default()
+			}),
+			..WindowsFileOpener::default()
+		};
+		let path = std::env::current_exe().unwrap();
+		for app_id in ["", " ", "cmd\0.exe"] {
+			assert!(matches!(
+				opener.open_with_app(&path, app_id),
+				Err(FileOpenError::InvalidAppId { .. })
+			));
+			assert!(matches!(
+				opener.launch_app(app_id),
+				Err(FileOpenError::InvalidAppId { .. })
+			));
 		}
+	}
 
-		apps.sort_by(|a, b| a.name.cmp(&b.name));
-		Ok(apps)
+	#[test]
+	fn app_searches_stop_at_the_first_match() {
+		com::ensure_initialized().unwrap();
+		let target = r"C:\Windows\System32\cmd.exe";
+		let handlers = std::iter::once(Some(target.to_string()))
+			.chain((0..10_000).map(|i| Some(format!(r"C:\sd-fake\app{i}.exe"))))
+			.collect();
+		let assoc = FakeAssoc {
+			handlers,
+			..FakeAssoc::default()
+		};
+
+		provider::fake::NAMES_READ.with(|read| read.set(0));
+		let (name, _) = find_app_handler(&assoc, ".sdfake", target)
+			.unwrap()
+			.unwrap();
+		assert_eq!(name, target);
+		assert_eq!(provider::fake::NAMES_READ.with(|read| read.get()), 1);
+	}
+
+	#[test]
+	fn dry_runs_resolve_like_real_ones_but_launch_nothing() {
+		com::ensure_initialized().unwrap();
+		let dir = std::env::temp_dir().join(format!("sd-dry-run-{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		let (file, extensionless) = (dir.join("notes.sdfake"), dir.join("notes"));
+		std::fs::write(&file, b"dry").unwrap();
+		std::fs::write(&extensionless, b"").unwrap();
+		let cmd = r"C:\Windows\System32\cmd.exe";
+		let opener = WindowsFileOpener {
+			assoc: Arc::new(FakeAssoc {
+				handlers: vec![Some(cmd.to_string())],
+				..FakeAssoc::default()
+			}),
+			sniff_extensionless: false,
+			..WindowsFileOpener::dry_run()
+		};
+
+		assert!(matches!(
+			opener.open_with_app(&file, "sd-not-an-app"),
+			Ok(OpenResult::AppNotFound { .. })
+		));
+		assert!(matches!(
+			opener.open_with_app(&dir.join("missing.sdfake"), cmd),
+			Ok(OpenResult::FileNotFound { .. })
+		));
+		assert!(matches!(
+			opener.open_many_with_app(&[&file, &extensionless], cmd),
+			Err(FileOpenError::NoExtension)
+		));
+		assert!(opener.dry_run_launches().is_empty());
+
+		// The fake handler can't be invoked, so only a skipped launch succeeds.
+		assert!(matches!(
+			opener.open_with_app(&file, cmd),
+			Ok(OpenResult::Success { .. })
+		));
+		let launches = opener.dry_run_launches();
+		assert_eq!(launches.len(), 1);
+		assert_eq!(launches[0].operation, "open_with_app");
+		assert!(launches[0].target.ends_with("notes.sdfake"));
+		assert_eq!(launches[0].handler.as_deref(), Some(cmd));
+		assert!(opener.history.entries().is_empty());
+
+		let missing = dir.join("missing.sdfake");
+		let trashed = opener.move_to_trash(&[&file, &missing]).unwrap();
+		assert!(matches!(trashed[0], OpenResult::Success { .. }));
+		assert!(matches!(trashed[1], OpenResult::FileNotFound { .. }));
+		assert!(file.exists());
+		assert!(matches!(
+			opener.reveal(&file),
+			Ok(OpenResult::Success { .. })
+		));
+		assert!(matches!(
+			opener.reveal(&missing),
+			Ok(OpenResult::FileNotFound { .. })
+		));
+		let operations = opener
+			.dry_run_launches()
+			.iter()
+			.map(|launch| launch.operation)
+			.collect::<Vec<_>>();
+		assert_eq!(operations, ["open_with_app", "move_to_trash", "reveal"]);
+
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn plain_launch_specs_go_through_the_handler() {
+		com::ensure_initialized().unwrap();
+		let path = std::env::temp_dir().join(format!("sd-launch-{}.sdfake", std::process::id()));
+		std::fs::write(&path, b"launch").unwrap();
+		let cmd = r"C:\Windows\System32\cmd.exe";
+		let opener = WindowsFileOpener {
+			assoc: Arc::new(FakeAssoc {
+				handlers: vec![Some(cmd.to_string())],
+				..FakeAssoc::default()
+			}),
+			..WindowsFileOpener::dry_run()
+		};
+
+		opener.launch(&LaunchSpec::new(&path, cmd)).unwrap();
+		opener
+			.launch(&LaunchSpec {
+				args: vec!["/c".to_string()],
+				..LaunchSpec::new(&path, cmd)
+			})
+			.unwrap();
+		let operations = opener
+			.dry_run_launches()
+			.iter()
+			.map(|launch| launch.operation)
+			.collect::<Vec<_>>();
+		assert_eq!(operations, ["open_with_app", "launch"]);
+
+		std::fs::remove_file(&path).unwrap();
+	}
+
+	#[test]
+	fn types_without_handlers_are_told_apart_from_missing_apps() {
+		com::ensure_initialized().unwrap();
+		let path =
+			std::env::temp_dir().join(format!("sd-no-handlers-{}.sdfake", std::process::id()));
+		std::fs::write(&path, b"unassociated").unwrap();
+		let opener = |handlers| WindowsFileOpener {
+			assoc: Arc::new(FakeAssoc {
+				handlers,
+				..FakeAssoc::default()
+			}),
+			..WindowsFileOpener::default()
+		};
+
+		let unassociated = opener(Vec::new());
+		assert!(unassociated.get_apps_for_file(&path).unwrap().is_empty());
+		assert!(matches!(
+			unassociated.open_with_app(&path, "sd-not-an-app"),
+			Ok(OpenResult::NoAppsForType { extension }) if extension == ".sdfake"
+		));
+
+		let associated = opener(vec![Some(r"C:\Windows\System32\cmd.exe".to_string())]);
+		assert!(matches!(
+			associated.open_with_app(&path, "sd-not-an-app"),
+			Ok(OpenResult::AppNotFound { .. })
+		));
+
+		std::fs::remove_file(&path).unwrap();
+	}
+
+	#[test]
+	fn data_objects_pick_the_app_by_their_first_file() {
+		com::ensure_initialized().unwrap();
+		let path = std::env::temp_dir().join(format!("sd-data-object-{}.txt", std::process::id()));
+		std::fs::write(&path, b"dragged").unwrap();
+
+		let data = shell::data_object_for_paths(&[&path]).unwrap();
+		let items = shell::item_array_for_data_object(&data).unwrap();
+		assert_eq!(shell::first_parsing_name(&items).unwrap(), path);
+		assert!(matches!(
+			WindowsFileOpener::default().open_with_app_data_object(&data, "sd-not-an-app"),
+			Ok(OpenResult::AppNotFound { app_id }) if app_id == "sd-not-an-app"
+		));
+
+		std::fs::remove_file(&path).unwrap();
+	}
+
+	#[test]
+	fn unresolvable_shell_items_are_invalid_paths() {
+		let missing = std::env::temp_dir()
+			.join("sd-no-such.zip")
+			.join("inside.txt");
+		assert!(matches!(
+			WindowsFileOpener::default().open_shell_item(&missing.to_string_lossy()),
+			Err(FileOpenError::InvalidPath { .. })
+		));
+	}
+
+	#[test]
+	fn usable_from_threads_other_than_its_creator() {
+		let path = Path::new("notes.txt");
+
+		// Created and first used on a thread that is gone by the time the
+		// others call in, taking its apartment and cache with it.
+		let (opener, expected) = thread::spawn(move || {
+			let opener = Arc::new(WindowsFileOpener::default());
+			let apps = names(opener.get_apps_for_file(path).unwrap());
+			(opener, apps)
+		})
+		.join()
+		.unwrap();
+
+		let workers = (0..4)
+			.map(|_| {
+				let opener: Arc<dyn FileOpener> = opener.clone();
+				thread::spawn(move || {
+					let apps = names(opener.get_apps_for_file(path).unwrap());
+					opener.get_default_app(path).unwrap();
+					apps
+				})
+			})
+			.collect::<Vec<_>>();
+
+		for worker in workers {
+			assert_eq!(worker.join().unwrap(), expected);
+		}
+	}
+
+	#[test]
+	fn prog_ids_open_the_app_they_belong_to() {
+		com::ensure_initialized().unwrap();
+		let apps = WindowsFileOpener::default()
+			.get_all_apps_for_file(Path::new("notes.txt"))
+			.unwrap();
+
+		let with_prog_id = apps
+			.iter()
+			.filter_map(|app| Some((app.progid.as_deref()?, app)))
+			.collect::<Vec<_>>();
+		assert!(!with_prog_id.is_empty());
+
+		for (prog_id, app) in with_prog_id {
+			let (name, _) = find_app_handler(&SystemAssoc, ".txt", prog_id)
+				.unwrap()
+				.unwrap();
+			assert_eq!(assoc::handler_executable(&name), app.exe_path, "{prog_id}");
+		}
+	}
+
+	#[test]
+	fn waited_launches_report_the_exit_code() {
+		let script = std::env::temp_dir().join(format!("sd-wait-{}.cmd", std::process::id()));
+		std::fs::write(&script, "@exit /b 7\r\n").unwrap();
+		let opener = WindowsFileOpener::default();
+
+		let outcome = opener.open_and_wait(&script, None).unwrap();
+		assert!(matches!(
+			outcome.result,
+			OpenResult::Success { pid: Some(_) }
+		));
+		assert_eq!(outcome.exit_code, Some(7));
+
+		let outcome = opener
+			.open_and_wait(&script, Some("sd-not-an-app"))
+			.unwrap();
+		assert!(matches!(outcome.result, OpenResult::AppNotFound { .. }));
+		assert_eq!(outcome.exit_code, None);
+
+		std::fs::remove_file(&script).unwrap();
+	}
+
+	#[test]
+	fn listed_ids_are_stable_and_open_their_app() {
+		com::ensure_initialized().unwrap();
+		let opener = WindowsFileOpener::default();
+		let apps = opener
+			.get_all_apps_for_file(Path::new("notes.txt"))
+			.unwrap();
+
+		for app in &apps {
+			// Never the display name, which changes with the UI language.
+			if let Some(exe) = &app.exe_path {
+				assert!(stable_id::is_hash_of(&app.id, exe), "{}", app.id);
+			}
+
+			for id in [Some(&app.id), app.progid.as_ref()].into_iter().flatten() {
+				let (name, _) = find_app_handler(&SystemAssoc, ".txt", id).unwrap().unwrap();
+				assert_eq!(assoc::handler_executable(&name), app.exe_path, "{id}");
+			}
+		}
+
+		opener.clear_cache();
+		let relisted = opener
+			.get_all_apps_for_file(Path::new("notes.txt"))
+			.unwrap();
+		let ids = |apps: &[OpenWithApp]| apps.iter().map(|app| app.id.clone()).collect::<Vec<_>>();
+		assert_eq!(ids(&relisted), ids(&apps));
+	}
+
+	#[test]
+	fn pages_add_up_to_the_full_listing() {
+		let opener = WindowsFileOpener::default();
+		let path = Path::new("notes.txt");
+		let listed = opener.get_apps_for_file(path).unwrap();
+
+		let mut paged = Vec::new();
+		for offset in (0..listed.len() + 2).step_by(2) {
+			let (page, total) = opener.get_apps_for_file_paged(path, offset, 2).unwrap();
+			assert_eq!(total, listed.len());
+			paged.extend(page);
+		}
+
+		let ids = |apps: &[OpenWithApp]| apps.iter().map(|app| app.id.clone()).collect::<Vec<_>>();
+		assert_eq!(ids(&paged), ids(&listed));
+		assert_eq!(
+			paged
+				.iter()
+				.map(|app| app.icon.is_some())
+				.collect::<Vec<_>>(),
+			listed
+				.iter()
+				.map(|app| app.icon.is_some())
+				.collect::<Vec<_>>()
+		);
+	}
+
+	#[test]
+	fn elevation_is_only_retried_when_opted_into() {
+		let refused = || OpenResult::RequiresElevation {
+			path: r"C:\notes.txt".to_string(),
+		};
+
+		let cautious = WindowsFileOpener::default();
+		assert!(matches!(
+			cautious.or_elevated(refused(), || unreachable!()),
+			OpenResult::RequiresElevation { .. }
+		));
+
+		let elevating = WindowsFileOpener::builder()
+			.elevate_when_required(true)
+			.build();
+		assert!(matches!(
+			elevating.or_elevated(refused(), OpenResult::success),
+			OpenResult::Success { .. }
+		));
+		assert!(matches!(
+			elevating.or_elevated(OpenResult::Cancelled, || unreachable!()),
+			OpenResult::Cancelled
+		));
+	}
+
+	#[test]
+	fn capabilities_match_what_is_implemented() {
+		let unsupported = |result: std::result::Result<OpenResult, FileOpenError>| {
+			matches!(result, Ok(OpenResult::Unsupported { .. }))
+		};
+		// Neither the file nor its folder exists, so nothing gets opened.
+		let missing = std::env::temp_dir()
+			.join(format!("sd-capabilities-{}", std::process::id()))
+			.join("notes.txt");
+
+		for opener in [
+			WindowsFileOpener::default(),
+			WindowsFileOpener::builder().load_icons(false).build(),
+		] {
+			let capabilities = opener.capabilities();
+
+			assert_eq!(capabilities.reveal, !unsupported(opener.reveal(&missing)));
+			assert_eq!(
+				capabilities.elevated,
+				!unsupported(opener.open_elevated(&missing))
+			);
+			assert_eq!(
+				capabilities.verbs,
+				!unsupported(opener.open_with_verb(&missing, "play"))
+			);
+			assert_eq!(
+				capabilities.dialog,
+				!unsupported(opener.open_with_dialog(&missing))
+			);
+			assert_eq!(
+				capabilities.executable,
+				!unsupported(opener.open_with_executable(&missing, Path::new("notepad.exe"), &[]))
+			);
+			assert_eq!(
+				capabilities.launch,
+				!unsupported(opener.launch_app("sd-not-an-app"))
+			);
+			assert_eq!(
+				capabilities.wait,
+				!unsupported(
+					opener
+						.open_and_wait(&missing, None)
+						.map(|outcome| outcome.result)
+				)
+			);
+			assert_eq!(
+				capabilities.shell_items,
+				!unsupported(opener.open_shell_item(r"C:\sd-not-a-shell-item"))
+			);
+			assert_eq!(capabilities.print, !unsupported(opener.print(&missing)));
+			assert_eq!(capabilities.edit, !unsupported(opener.edit(&missing)));
+			assert_eq!(
+				capabilities.properties,
+				!unsupported(opener.show_properties(&missing))
+			);
+			assert_eq!(
+				capabilities.set_default,
+				!unsupported(opener.set_default_app("txt", "sd-not-an-app"))
+			);
+			assert_eq!(
+				capabilities.thumbnails,
+				opener.get_thumbnail(&missing, 32).is_ok()
+			);
+			assert_eq!(
+				capabilities.icons,
+				opener
+					.get_apps_for_file(Path::new("notes.txt"))
+					.unwrap()
+					.iter()
+					.any(|app| app.icon.is_some())
+			);
+			// Any URI that passes the policy would be launched for real.
+			assert!(capabilities.uri);
+			assert_eq!(
+				capabilities.trash,
+				!opener
+					.move_to_trash(&[&missing])
+					.unwrap()
+					.iter()
+					.any(|result| matches!(result, OpenResult::Unsupported { .. }))
+			);
+		}
+	}
+
+	#[test]
+	fn counts_match_the_listed_apps() {
+		let opener = WindowsFileOpener::default();
+
+		for path in ["notes.txt", "photo.png"] {
+			let path = Path::new(path);
+			// Counted before listing, so before the apps are built.
+			let capped = opener.count_apps_for_file_up_to(path, 1).unwrap();
+			let count = opener.count_apps_for_file(path).unwrap();
+			let listed = opener.get_apps_for_file(path).unwrap().len();
+
+			assert_eq!(count, listed, "{}", path.display());
+			assert_eq!(capped, count.min(1), "{}", path.display());
+			assert_eq!(opener.count_apps_for_file_up_to(path, 1).unwrap(), capped);
+		}
+
+		// Nothing to sniff either: the file doesn't exist.
+		let untyped = Path::new("no-extension");
+		assert!(matches!(
+			opener.count_apps_for_file(untyped),
+			Err(FileOpenError::NoExtension)
+		));
+		assert!(matches!(
+			opener.get_apps_for_file(untyped),
+			Err(FileOpenError::NoExtension)
+		));
+	}
+
+	#[test]
+	fn cancelled_listings_are_not_cached() {
+		// A fresh thread, so the listing can't be served from its cache.
+		thread::spawn(|| {
+			let opener = WindowsFileOpener::default();
+			let path = Path::new("notes.txt");
+
+			let cancel = CancellationToken::new();
+			cancel.cancel();
+			assert!(matches!(
+				opener.get_apps_for_file_cancellable(path, &cancel),
+				Err(FileOpenError::Cancelled)
+			));
+
+			assert!(!opener.get_apps_for_file(path).unwrap().is_empty());
+		})
+		.join()
+		.unwrap();
 	}
 }
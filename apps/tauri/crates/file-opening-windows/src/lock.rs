@@ -0,0 +1,97 @@
+//! # Locked files
+//!
+//! A handler that can't get at a file another process holds exclusively
+//! fails with a sharing violation, which on its own only says "in use". The
+//! Restart Manager knows which processes have a file open, so the result can
+//! name the app. Asking it is best effort: any failure just leaves the
+//! holder out.
+
+use std::path::Path;
+
+use windows::core::{HSTRING, PCWSTR, PWSTR};
+use windows::Win32::Foundation::{ERROR_MORE_DATA, ERROR_SUCCESS};
+use windows::Win32::System::RestartManager::{
+	RmEndSession, RmGetList, RmRegisterResources, RmStartSession, CCH_RM_SESSION_KEY,
+	RM_PROCESS_INFO,
+};
+
+/// The display name of an app that has `path` open, if one can be found.
+pub(crate) fn holder(path: &Path) -> Option<String> {
+	let mut session = 0u32;
+	let mut key = [0u16; CCH_RM_SESSION_KEY as usize + 1];
+	if unsafe { RmStartSession(&mut session, 0, PWSTR(key.as_mut_ptr())) } != ERROR_SUCCESS {
+		return None;
+	}
+
+	let name = session_holder(session, path);
+	unsafe {
+		let _ = RmEndSession(session);
+	}
+	name
+}
+
+fn session_holder(session: u32, path: &Path) -> Option<String> {
+	let file = HSTRING::from(path);
+	let files = [PCWSTR(file.as_ptr())];
+	if unsafe { RmRegisterResources(session, Some(&files), None, None) } != ERROR_SUCCESS {
+		return None;
+	}
+
+	// Asked for the count first; a process that opens the file in between
+	// makes the second call fail too, and there's no holder to report then.
+	let (mut needed, mut count, mut reasons) = (0u32, 0u32, 0u32);
+	let status = unsafe { RmGetList(session, &mut needed, &mut count, None, &mut reasons) };
+	if status != ERROR_MORE_DATA || needed == 0 {
+		return None;
+	}
+
+	let mut processes = vec![RM_PROCESS_INFO::default(); needed as usize];
+	count = needed;
+	let status = unsafe {
+		RmGetList(
+			session,
+			&mut needed,
+			&mut count,
+			Some(processes.as_mut_ptr()),
+			&mut reasons,
+		)
+	};
+	if status != ERROR_SUCCESS {
+		return None;
+	}
+
+	processes[..count as usize]
+		.iter()
+		.map(|process| wide_to_string(&process.strAppName))
+		.find(|name| !name.is_empty())
+}
+
+fn wide_to_string(wide: &[u16]) -> String {
+	let len = wide.iter().position(|&c| c == 0).unwrap_or(wide.len());
+	String::from_utf16_lossy(&wide[..len])
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::fs::{self, OpenOptions};
+	use std::os::windows::fs::OpenOptionsExt;
+
+	#[test]
+	fn files_held_open_name_their_holder() {
+		let path = std::env::temp_dir().join(format!("sd-locked-{}.txt", std::process::id()));
+		fs::write(&path, b"locked").unwrap();
+		assert_eq!(holder(&path), None);
+
+		// No sharing at all, as Office does with documents it has open.
+		let locked = OpenOptions::new()
+			.read(true)
+			.share_mode(0)
+			.open(&path)
+			.unwrap();
+		assert!(holder(&path).is_some());
+
+		drop(locked);
+		let _ = fs::remove_file(&path);
+	}
+}
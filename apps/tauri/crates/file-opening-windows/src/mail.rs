@@ -0,0 +1,265 @@
+//! # Mail
+//!
+//! Files are handed to the default mail client with Simple MAPI's
+//! `MAPISendMailW`, the call behind Explorer's "Send to > Mail recipient".
+//! With `MAPI_DIALOG` the client opens a new message with the files attached
+//! and the user addresses and sends it; nothing goes out without them. The
+//! call returns once the client has taken the message, which for some
+//! clients is only when its compose window closes.
+//!
+//! `mapi32.dll` is a stub that forwards to the client registered as the
+//! default under `SOFTWARE\Clients\Mail`, so it's loaded on first use rather
+//! than linked. It isn't called at all without a default that has a MAPI
+//! provider, since the stub would show an error box of its own.
+//!
+//! Without MAPI, a message with no attachments falls back to a `mailto:`
+//! URI, which any mail handler takes, webmail included. One with attachments
+//! is reported as [`OpenResult::NoMailClient`]: `mailto:` (RFC 6068) has no
+//! way to carry files.
+//!
+//! Limits:
+//! - Simple MAPI sets none of its own on how many files or how large they
+//!   are. The client does, and its refusal (`MAPI_E_TOO_MANY_FILES`,
+//!   `MAPI_E_ATTACHMENT_TOO_LARGE`) is reported as `PlatformError` with the
+//!   MAPI code. Outlook, for one, refuses messages over its account's size
+//!   limit, 20 MB by default for internet accounts.
+//! - A `mailto:` URI carries no files, and at most [`MAX_MAILTO_LEN`]
+//!   characters once encoded.
+
+use std::path::Path;
+use std::sync::OnceLock;
+
+use file_opening::OpenResult;
+use windows::core::{s, w, HSTRING, PWSTR};
+use windows::Win32::Foundation::HWND;
+use windows::Win32::System::LibraryLoader::{
+	GetProcAddress, LoadLibraryExW, LOAD_LIBRARY_SEARCH_SYSTEM32,
+};
+use windows::Win32::System::Mapi::{
+	MapiFileDescW, MapiMessageW, MAPI_DIALOG, MAPI_E_ATTACHMENT_NOT_FOUND,
+	MAPI_E_ATTACHMENT_OPEN_FAILURE, MAPI_E_ATTACHMENT_TOO_LARGE, MAPI_E_LOGIN_FAILURE,
+	MAPI_E_NOT_SUPPORTED, MAPI_E_TOO_MANY_FILES, MAPI_E_USER_ABORT, MAPI_LOGON_UI, SUCCESS_SUCCESS,
+};
+use windows::Win32::System::Registry::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE};
+
+use crate::{registry, LOG_TARGET};
+
+/// Longest `mailto:` URI handed over. `INTERNET_MAX_URL_LENGTH` is 2083, and
+/// handlers that enforce it cut longer ones off instead of failing.
+pub(crate) const MAX_MAILTO_LEN: usize = 2_048;
+
+const CLIENTS: &str = r"SOFTWARE\Clients\Mail";
+
+type SendMail = unsafe extern "system" fn(usize, usize, *const MapiMessageW, u32, u32) -> u32;
+
+/// The default client, reached through Simple MAPI.
+pub(crate) struct Mapi {
+	send_mail: SendMail,
+	/// Its name under `SOFTWARE\Clients\Mail`
+	pub client: String,
+}
+
+/// The default client, unless there's no MAPI to reach it through: no
+/// `MAPISendMailW`, no default client, or one without a provider.
+pub(crate) fn mapi() -> Option<Mapi> {
+	Some(Mapi {
+		send_mail: send_mail()?,
+		client: default_client()?,
+	})
+}
+
+impl Mapi {
+	/// Hands `attachments` to the client's new message dialog.
+	///
+	/// `None` when the stub couldn't reach the client after all.
+	pub(crate) fn send(
+		&self,
+		owner: HWND,
+		attachments: &[&Path],
+		subject: Option<&str>,
+		body: Option<&str>,
+	) -> Option<OpenResult> {
+		let _span = tracing::debug_span!(
+			target: LOG_TARGET,
+			"MAPISendMailW",
+			client = self.client.as_str(),
+			attachments = attachments.len()
+		)
+		.entered();
+
+		let Ok(count) = u32::try_from(attachments.len()) else {
+			return result(MAPI_E_TOO_MANY_FILES);
+		};
+
+		// MAPI only reads these, for as long as the call lasts.
+		let paths = attachments
+			.iter()
+			.map(|path| HSTRING::from(*path))
+			.collect::<Vec<_>>();
+		let mut files = paths
+			.iter()
+			.map(|path| MapiFileDescW {
+				// Attached rather than placed in the text.
+				nPosition: u32::MAX,
+				lpszPathName: PWSTR(path.as_ptr().cast_mut()),
+				..Default::default()
+			})
+			.collect::<Vec<_>>();
+		let subject = subject.map(HSTRING::from);
+		let body = body.map(HSTRING::from);
+		let text = |text: &Option<HSTRING>| {
+			text.as_ref()
+				.map_or(PWSTR::null(), |text| PWSTR(text.as_ptr().cast_mut()))
+		};
+		let message = MapiMessageW {
+			lpszSubject: text(&subject),
+			lpszNoteText: text(&body),
+			nFileCount: count,
+			lpFiles: if files.is_empty() {
+				std::ptr::null_mut()
+			} else {
+				files.as_mut_ptr()
+			},
+			..Default::default()
+		};
+
+		let code = unsafe {
+			(self.send_mail)(
+				0,
+				owner.0 as usize,
+				&message,
+				MAPI_LOGON_UI | MAPI_DIALOG,
+				0,
+			)
+		};
+		tracing::debug!(target: LOG_TARGET, code, "MAPISendMailW returned");
+		result(code)
+	}
+}
+
+/// What a `MAPISendMailW` code means for the caller, `None` for the ones
+/// that say no client could be reached.
+fn result(code: u32) -> Option<OpenResult> {
+	let message = match code {
+		SUCCESS_SUCCESS => return Some(OpenResult::success()),
+		MAPI_E_USER_ABORT => return Some(OpenResult::Cancelled),
+		MAPI_E_NOT_SUPPORTED | MAPI_E_LOGIN_FAILURE => return None,
+		MAPI_E_TOO_MANY_FILES => "Too many attachments for the mail client",
+		MAPI_E_ATTACHMENT_TOO_LARGE => "The attachments are too large for the mail client",
+		MAPI_E_ATTACHMENT_NOT_FOUND | MAPI_E_ATTACHMENT_OPEN_FAILURE => {
+			"The mail client couldn't read an attachment"
+		}
+		_ => "The mail client couldn't create the message",
+	};
+	Some(OpenResult::PlatformError {
+		message: message.to_string(),
+		code: code as i32,
+	})
+}
+
+/// `MAPISendMailW` from the system's `mapi32.dll`, loaded once and kept.
+fn send_mail() -> Option<SendMail> {
+	static SEND_MAIL: OnceLock<Option<SendMail>> = OnceLock::new();
+
+	*SEND_MAIL.get_or_init(|| unsafe {
+		// From System32 only, so a mapi32.dll next to a document isn't loaded.
+		let module = LoadLibraryExW(w!("mapi32.dll"), None, LOAD_LIBRARY_SEARCH_SYSTEM32).ok()?;
+		let proc = GetProcAddress(module, s!("MAPISendMailW"))?;
+		Some(std::mem::transmute::<
+			unsafe extern "system" fn() -> isize,
+			SendMail,
+		>(proc))
+	})
+}
+
+/// The default mail client's name, if it has a MAPI provider for the stub to
+/// forward to. Per-user defaults win, as they do for the stub.
+fn default_client() -> Option<String> {
+	let client = [HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE]
+		.into_iter()
+		.find_map(|root| registry::read_string(root, CLIENTS, None))
+		.filter(|client| !client.trim().is_empty())?;
+
+	let key = format!(r"{CLIENTS}\{client}");
+	let has_provider = [HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE]
+		.into_iter()
+		.any(|root| {
+			registry::has_value(root, &key, "DLLPath")
+				|| registry::has_value(root, &key, "DLLPathEx")
+		});
+	has_provider.then_some(client)
+}
+
+/// A `mailto:` URI with no recipient, for the user to fill in.
+pub(crate) fn mailto_uri(subject: Option<&str>, body: Option<&str>) -> String {
+	let fields = [("subject", subject), ("body", body)]
+		.into_iter()
+		.filter_map(|(name, value)| Some((name, value.filter(|value| !value.is_empty())?)))
+		.map(|(name, value)| format!("{name}={}", encode(value)))
+		.collect::<Vec<_>>();
+
+	if fields.is_empty() {
+		"mailto:".to_string()
+	} else {
+		format!("mailto:?{}", fields.join("&"))
+	}
+}
+
+/// `value` percent-encoded for a `mailto:` field, line breaks as CRLF as RFC
+/// 6068 asks.
+fn encode(value: &str) -> String {
+	let mut encoded = String::new();
+	for line in value.split_inclusive('\n') {
+		let (text, newline) = match line.strip_suffix('\n') {
+			Some(text) => (text.strip_suffix('\r').unwrap_or(text), true),
+			None => (line, false),
+		};
+		for byte in text.bytes() {
+			if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+				encoded.push(byte as char);
+			} else {
+				encoded.push_str(&format!("%{byte:02X}"));
+			}
+		}
+		if newline {
+			encoded.push_str("%0D%0A");
+		}
+	}
+	encoded
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn mailto_fields_are_encoded() {
+		assert_eq!(mailto_uri(None, Some("")), "mailto:");
+		assert_eq!(
+			mailto_uri(Some("Q3 & Q4 report"), None),
+			"mailto:?subject=Q3%20%26%20Q4%20report"
+		);
+		assert_eq!(
+			mailto_uri(Some("Hi"), Some("line one\nline two\r\nbis")),
+			"mailto:?subject=Hi&body=line%20one%0D%0Aline%20two%0D%0Abis"
+		);
+		assert_eq!(mailto_uri(None, Some("café")), "mailto:?body=caf%C3%A9");
+	}
+
+	#[test]
+	fn unreachable_clients_fall_back() {
+		assert!(matches!(
+			result(SUCCESS_SUCCESS),
+			Some(OpenResult::Success { .. })
+		));
+		assert!(matches!(
+			result(MAPI_E_USER_ABORT),
+			Some(OpenResult::Cancelled)
+		));
+		assert!(result(MAPI_E_NOT_SUPPORTED).is_none());
+		assert!(matches!(
+			result(MAPI_E_ATTACHMENT_TOO_LARGE),
+			Some(OpenResult::PlatformError { code, .. }) if code == MAPI_E_ATTACHMENT_TOO_LARGE as i32
+		));
+	}
+}
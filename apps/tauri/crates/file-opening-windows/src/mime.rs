@@ -0,0 +1,60 @@
+//! # MIME types from the registry
+//!
+//! [`file_opening::mime`] with the registry consulted first: apps register
+//! the types they handle under `HKCR\MIME\Database\Content Type` and as each
+//! extension's `Content Type`, which covers vendor types no built-in table
+//! knows. Anything the registry leaves out comes from that table.
+
+use windows::Win32::System::Registry::HKEY_CLASSES_ROOT;
+
+use crate::registry;
+
+pub use file_opening::mime::normalize_extension;
+
+/// The extension registered for `mime`, normalized, e.g. `png` for
+/// `image/png`
+pub fn extension_for_mime(mime: &str) -> Option<String> {
+	let essence = mime.split(';').next().unwrap_or_default().trim();
+	// The type becomes part of a registry path, so anything that could step
+	// into another key is rejected outright.
+	if essence.is_empty() || essence.contains('\\') {
+		return None;
+	}
+
+	let subkey = format!(r"MIME\Database\Content Type\{essence}");
+	registry::read_string(HKEY_CLASSES_ROOT, &subkey, Some("Extension"))
+		.filter(|ext| ext.starts_with('.'))
+		.map(|ext| normalize_extension(&ext))
+		.or_else(|| file_opening::mime::extension_for_mime(essence))
+}
+
+/// The `Content Type` registered for `extension` (with or without the
+/// leading dot)
+pub fn mime_for_extension(extension: &str) -> Option<String> {
+	let ext = format!(".{}", normalize_extension(extension));
+	if ext.contains('\\') {
+		return None;
+	}
+
+	registry::read_string(HKEY_CLASSES_ROOT, &ext, Some("Content Type"))
+		.filter(|mime| mime.contains('/'))
+		.or_else(|| file_opening::mime::mime_for_extension(&ext))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn registered_types_round_trip() {
+		assert_eq!(extension_for_mime("image/png").as_deref(), Some("png"));
+		assert_eq!(mime_for_extension(".PNG").as_deref(), Some("image/png"));
+
+		// Not every Windows install registers these; the table still knows.
+		assert_eq!(extension_for_mime("image/webp").as_deref(), Some("webp"));
+		assert_eq!(mime_for_extension("webp").as_deref(), Some("image/webp"));
+
+		assert_eq!(extension_for_mime(r"image\..\png"), None);
+		assert_eq!(mime_for_extension(r"..\png"), None);
+	}
+}
@@ -0,0 +1,122 @@
+//! # Network paths
+//!
+//! A file on a share whose server is offline doesn't fail fast: the first
+//! call that touches it, `Path::exists` included, blocks for the SMB client's
+//! own timeout, tens of seconds, and ShellExecute then reports an error that
+//! doesn't say the network was the problem. Paths on shares and mapped
+//! drives are probed on a worker thread first, and given up on after the
+//! opener's network timeout.
+
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use windows::core::HSTRING;
+use windows::Win32::Foundation::{
+	GetLastError, ERROR_BAD_NETPATH, ERROR_BAD_NET_NAME, ERROR_BAD_NET_RESP, ERROR_FILE_NOT_FOUND,
+	ERROR_HOST_UNREACHABLE, ERROR_NETNAME_DELETED, ERROR_NETWORK_UNREACHABLE,
+	ERROR_NO_NET_OR_BAD_PATH, ERROR_PATH_NOT_FOUND, ERROR_REM_NOT_LIST, ERROR_SEM_TIMEOUT,
+	ERROR_UNEXP_NET_ERR,
+};
+use windows::Win32::Storage::FileSystem::{
+	GetDriveTypeW, GetFileAttributesW, INVALID_FILE_ATTRIBUTES,
+};
+use windows::Win32::System::WindowsProgramming::DRIVE_REMOTE;
+
+/// How long a network path gets to answer by default.
+pub(crate) const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// What a probe found out about a path before it's handed to the shell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Probe {
+	/// It exists, or failed in a way the shell explains better.
+	Present,
+	Missing,
+	/// Its server didn't answer, or not within the timeout.
+	Unreachable,
+}
+
+/// Checks that `path`, already normalized, is there.
+///
+/// Local paths are checked directly. For remote ones the check runs on its
+/// own thread, which is left behind when `timeout` passes and ends once the
+/// SMB client gives up.
+pub(crate) fn probe(path: &Path, timeout: Duration) -> Probe {
+	if !is_remote(path) {
+		return match path.exists() {
+			true => Probe::Present,
+			false => Probe::Missing,
+		};
+	}
+
+	let (sender, receiver) = mpsc::channel();
+	let file = HSTRING::from(path);
+	let spawned = std::thread::Builder::new()
+		.name("file-opening-probe".to_string())
+		.spawn(move || {
+			let _ = sender.send(attributes_probe(&file));
+		});
+
+	if spawned.is_err() {
+		return attributes_probe(&HSTRING::from(path));
+	}
+
+	receiver.recv_timeout(timeout).unwrap_or(Probe::Unreachable)
+}
+
+/// Whether `path` is on a share, by UNC name or through a mapped drive.
+fn is_remote(path: &Path) -> bool {
+	let raw = path.to_string_lossy();
+	if raw.starts_with(r"\\") {
+		return true;
+	}
+
+	match raw.as_bytes() {
+		[drive, b':', ..] if drive.is_ascii_alphabetic() => {
+			let root = HSTRING::from(format!(r"{}:\", *drive as char));
+			// Answered from the drive mapping, without asking the server.
+			unsafe { GetDriveTypeW(&root) == DRIVE_REMOTE }
+		}
+		_ => false,
+	}
+}
+
+fn attributes_probe(file: &HSTRING) -> Probe {
+	if unsafe { GetFileAttributesW(file) } != INVALID_FILE_ATTRIBUTES {
+		return Probe::Present;
+	}
+
+	match unsafe { GetLastError() } {
+		ERROR_FILE_NOT_FOUND | ERROR_PATH_NOT_FOUND => Probe::Missing,
+		ERROR_BAD_NETPATH
+		| ERROR_BAD_NET_NAME
+		| ERROR_BAD_NET_RESP
+		| ERROR_NETNAME_DELETED
+		| ERROR_UNEXP_NET_ERR
+		| ERROR_REM_NOT_LIST
+		| ERROR_NETWORK_UNREACHABLE
+		| ERROR_HOST_UNREACHABLE
+		| ERROR_NO_NET_OR_BAD_PATH
+		| ERROR_SEM_TIMEOUT => Probe::Unreachable,
+		_ => Probe::Present,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn shares_are_remote_and_local_drives_are_not() {
+		assert!(is_remote(Path::new(r"\\server\share\notes.txt")));
+
+		let system_drive = std::env::var("SystemDrive").unwrap_or_else(|_| "C:".to_string());
+		assert!(!is_remote(&Path::new(&system_drive).join("notes.txt")));
+	}
+
+	#[test]
+	fn servers_that_dont_exist_are_unreachable() {
+		let path = Path::new(r"\\spacedrive-test.invalid\share\notes.txt");
+		assert_eq!(probe(path, Duration::from_millis(500)), Probe::Unreachable);
+	}
+}
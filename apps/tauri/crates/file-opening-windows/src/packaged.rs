@@ -0,0 +1,142 @@
+//! # Packaged apps
+//!
+//! UWP and MSIX apps have no executable to report and are identified by an
+//! AppUserModelID (AUMID) instead. `IAssocHandler::Invoke` is unreliable for
+//! them (Store apps in particular fail to activate through it), so they are
+//! launched with `IApplicationActivationManager::ActivateForFile`.
+//!
+//! An enumerated handler is packaged when its `GetName` is itself an AUMID,
+//! or when one of the extension's `OpenWithProgids` is an `AppX` ProgID whose
+//! friendly app name matches the handler's UI name.
+
+use windows::core::{w, Result, HSTRING, PCWSTR};
+use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_LOCAL_SERVER};
+use windows::Win32::System::Registry::HKEY_CLASSES_ROOT;
+use windows::Win32::UI::Shell::{
+	ApplicationActivationManager, IApplicationActivationManager, IAssocHandler, IShellItemArray,
+	AO_NONE, ASSOCF_NONE, ASSOCSTR_APPID, ASSOCSTR_FRIENDLYAPPNAME,
+};
+
+use crate::{assoc, registry, take_co_string};
+
+/// The AUMID behind an enumerated handler for `ext`, if it's a packaged app.
+pub(crate) fn app_user_model_id(ext: &str, name: &str, handler: &IAssocHandler) -> Option<String> {
+	if is_app_user_model_id(name) {
+		return Some(name.to_string());
+	}
+
+	let ui_name = unsafe { take_co_string(handler.GetUIName().ok()?).ok()? };
+
+	registry::value_names(HKEY_CLASSES_ROOT, &format!(r"{ext}\OpenWithProgids"))
+		.into_iter()
+		.find_map(|prog_id| {
+			let aumid = registry::read_string(
+				HKEY_CLASSES_ROOT,
+				&format!(r"{prog_id}\Application"),
+				Some("AppUserModelID"),
+			)
+			.filter(|aumid| is_app_user_model_id(aumid))?;
+
+			assoc::query_string(ASSOCF_NONE, ASSOCSTR_FRIENDLYAPPNAME, &prog_id)
+				.filter(|friendly| friendly.eq_ignore_ascii_case(&ui_name))
+				.map(|_| aumid)
+		})
+}
+
+/// The AUMID of the packaged app registered as the default for `ext`, if the
+/// default is one.
+pub(crate) fn default_app_user_model_id(ext: &str) -> Option<String> {
+	assoc::query_string(ASSOCF_NONE, ASSOCSTR_APPID, ext)
+		.filter(|aumid| is_app_user_model_id(aumid))
+}
+
+/// The AUMID an app id names on its own: itself if it is one, or the one
+/// registered for an `AppX` ProgID.
+pub(crate) fn app_user_model_id_for(app_id: &str) -> Option<String> {
+	if is_app_user_model_id(app_id) {
+		return Some(app_id.to_string());
+	}
+
+	registry::read_string(
+		HKEY_CLASSES_ROOT,
+		&format!(r"{app_id}\Application"),
+		Some("AppUserModelID"),
+	)
+	.filter(|aumid| is_app_user_model_id(aumid))
+}
+
+/// Starts the packaged app `aumid` without a file.
+pub(crate) fn activate(aumid: &str) -> Result<()> {
+	unsafe {
+		let manager: IApplicationActivationManager =
+			CoCreateInstance(&ApplicationActivationManager, None, CLSCTX_LOCAL_SERVER)?;
+		manager.ActivateApplication(&HSTRING::from(aumid), PCWSTR::null(), AO_NONE)?;
+	}
+	Ok(())
+}
+
+/// Launches the packaged app `aumid` with every one of `items`.
+pub(crate) fn activate_for_items(aumid: &str, items: &IShellItemArray) -> Result<()> {
+	unsafe {
+		let manager: IApplicationActivationManager =
+			CoCreateInstance(&ApplicationActivationManager, None, CLSCTX_LOCAL_SERVER)?;
+
+		// The process id is of no use: packaged apps may hand the file to an
+		// instance that was already running.
+		manager.ActivateForFile(&HSTRING::from(aumid), items, w!("open"))?;
+	}
+	Ok(())
+}
+
+/// Whether `value` has the `PackageFamilyName!AppId` shape of an AUMID, e.g.
+/// `Microsoft.WindowsNotepad_8wekyb3d8bbwe!App`.
+///
+/// The family name is the package name, an underscore, and the 13-character
+/// publisher id. Desktop apps can set arbitrary explicit AUMIDs on their
+/// windows, but those never take this shape, so it only matches packages.
+pub(crate) fn is_app_user_model_id(value: &str) -> bool {
+	let Some((family, app_id)) = value.split_once('!') else {
+		return false;
+	};
+	let Some((package, publisher)) = family.rsplit_once('_') else {
+		return false;
+	};
+
+	!package.is_empty()
+		&& publisher.len() == 13
+		&& publisher
+			.chars()
+			.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+		&& !app_id.is_empty()
+		&& app_id
+			.chars()
+			.all(|c| c.is_ascii_alphanumeric() || c == '.')
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn recognizes_package_app_ids() {
+		for aumid in [
+			"Microsoft.WindowsNotepad_8wekyb3d8bbwe!App",
+			"Microsoft.Windows.Photos_8wekyb3d8bbwe!App",
+			"Microsoft.ZuneVideo_8wekyb3d8bbwe!Microsoft.ZuneVideo",
+		] {
+			assert!(is_app_user_model_id(aumid), "{aumid}");
+		}
+
+		for other in [
+			r"C:\Windows\system32\notepad.exe",
+			"notepad.exe",
+			"Microsoft.WindowsNotepad_8wekyb3d8bbwe",
+			"Microsoft.WindowsNotepad!App",
+			"Microsoft.WindowsNotepad_8WEKYB3D8BBWE!App",
+			"Microsoft.WindowsNotepad_8wekyb3d8bbwe!",
+			"Contoso.App_8wekyb3d8bbwe!App!Extra",
+		] {
+			assert!(!is_app_user_model_id(other), "{other}");
+		}
+	}
+}
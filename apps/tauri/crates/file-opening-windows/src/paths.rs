@@ -0,0 +1,184 @@
+//! # Path normalization
+//!
+//! The shell APIs expect a plain absolute Windows path. They resolve relative
+//! paths against whatever the process's working directory happens to be,
+//! reject the `\\?\` prefix that `std::fs::canonicalize` adds, and stop at
+//! `MAX_PATH` regardless of the system's long path setting. Every path is
+//! passed through [`normalize`] before it reaches them.
+//!
+//! Paths stay UTF-16 throughout: NTFS names may hold unpaired surrogates,
+//! which a round trip through `String` would replace and so point at another
+//! file, or at none.
+
+use std::ffi::OsString;
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
+use std::path::{Path, PathBuf};
+use windows::core::HSTRING;
+use windows::Win32::Foundation::MAX_PATH;
+use windows::Win32::Storage::FileSystem::GetShortPathNameW;
+
+use file_opening::FileOpenError;
+
+/// Makes `path` absolute, with backslashes and without a `\\?\` prefix.
+///
+/// A path too long for the shell is replaced by its 8.3 short form when the
+/// volume has one. Empty paths, device paths (`\\.\`), and paths with
+/// characters no file name can hold fail with [`FileOpenError::InvalidPath`].
+pub(crate) fn normalize(path: &Path) -> Result<PathBuf, FileOpenError> {
+	// Replacement characters pass validation like the surrogates they stand
+	// in for, so the lossy form is good enough to check.
+	let raw = path.to_string_lossy();
+	validate(&raw).map_err(|reason| invalid(path, reason))?;
+
+	// GetFullPathNameW, which also turns forward slashes into backslashes and
+	// collapses `.` and `..`, without touching the file system.
+	let absolute = std::path::absolute(path).map_err(|_| invalid(path, "not a valid path"))?;
+	let plain = strip_verbatim(&absolute.as_os_str().encode_wide().collect::<Vec<_>>());
+
+	if plain.len() < MAX_PATH as usize {
+		return Ok(PathBuf::from(OsString::from_wide(&plain)));
+	}
+
+	short_path(&plain)
+		.filter(|short| short.len() < MAX_PATH as usize)
+		.map(|short| PathBuf::from(OsString::from_wide(&short)))
+		.ok_or_else(|| invalid(path, "path is too long for the shell"))
+}
+
+fn invalid(path: &Path, reason: &'static str) -> FileOpenError {
+	FileOpenError::InvalidPath {
+		path: path.to_string_lossy().to_string(),
+		reason,
+	}
+}
+
+/// Rejects input that can't name a file whatever the working directory.
+fn validate(path: &str) -> Result<(), &'static str> {
+	if path.is_empty() {
+		return Err("path is empty");
+	}
+	if path.starts_with(r"\\.\") || path.starts_with("//./") {
+		return Err("device paths can't be opened");
+	}
+
+	// `?` is allowed only as part of the `\\?\` prefix, and `:` only after
+	// a drive letter.
+	let rest = path.strip_prefix(r"\\?\").unwrap_or(path);
+	let rest = match rest.as_bytes() {
+		[drive, b':', ..] if drive.is_ascii_alphabetic() => &rest[2..],
+		_ => rest,
+	};
+	if rest
+		.chars()
+		.any(|c| c.is_control() || matches!(c, '<' | '>' | '"' | '|' | '?' | '*' | ':'))
+	{
+		return Err("path contains characters file names can't hold");
+	}
+
+	Ok(())
+}
+
+/// `\\?\C:\dir` to `C:\dir` and `\\?\UNC\server\share` to `\\server\share`.
+fn strip_verbatim(path: &[u16]) -> Vec<u16> {
+	if let Some(unc) = strip_prefix(path, r"\\?\UNC\") {
+		[wide(r"\\"), unc.to_vec()].concat()
+	} else if let Some(local) = strip_prefix(path, r"\\?\") {
+		local.to_vec()
+	} else {
+		path.to_vec()
+	}
+}
+
+/// The 8.3 form of an existing long path, if the volume generates them.
+fn short_path(path: &[u16]) -> Option<Vec<u16>> {
+	// The long form only gets past MAX_PATH with the verbatim prefix.
+	let verbatim = match strip_prefix(path, r"\\") {
+		Some(unc) => [wide(r"\\?\UNC\"), unc.to_vec()].concat(),
+		None => [wide(r"\\?\"), path.to_vec()].concat(),
+	};
+	let verbatim = HSTRING::from_wide(&verbatim).ok()?;
+
+	unsafe {
+		let len = GetShortPathNameW(&verbatim, None);
+		if len == 0 {
+			return None;
+		}
+
+		let mut buffer = vec![0u16; len as usize];
+		let written = GetShortPathNameW(&verbatim, Some(&mut buffer));
+		if written == 0 || written as usize >= buffer.len() {
+			return None;
+		}
+
+		Some(strip_verbatim(&buffer[..written as usize]))
+	}
+}
+
+fn wide(s: &str) -> Vec<u16> {
+	s.encode_utf16().collect()
+}
+
+fn strip_prefix<'a>(path: &'a [u16], prefix: &str) -> Option<&'a [u16]> {
+	path.strip_prefix(wide(prefix).as_slice())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn relative_paths_become_absolute() {
+		let normalized = normalize(Path::new(r"docs/notes.txt")).unwrap();
+
+		assert!(normalized.is_absolute());
+		assert!(normalized.ends_with(r"docs\notes.txt"));
+		assert!(!normalized.to_string_lossy().contains('/'));
+	}
+
+	#[test]
+	fn verbatim_prefixes_are_stripped() {
+		assert_eq!(
+			normalize(Path::new(r"\\?\C:\Users\me\notes.txt")).unwrap(),
+			PathBuf::from(r"C:\Users\me\notes.txt")
+		);
+		assert_eq!(
+			normalize(Path::new(r"\\?\UNC\server\share\notes.txt")).unwrap(),
+			PathBuf::from(r"\\server\share\notes.txt")
+		);
+		assert_eq!(
+			normalize(Path::new(r"\\server\share\notes.txt")).unwrap(),
+			PathBuf::from(r"\\server\share\notes.txt")
+		);
+	}
+
+	#[test]
+	fn unpaired_surrogates_are_kept() {
+		let name = OsString::from_wide(&[0x61, 0xD800, 0x2E, 0x74, 0x78, 0x74]);
+		let path = PathBuf::from(r"\\?\C:\dir").join(&name);
+
+		let normalized = normalize(&path).unwrap();
+		assert_eq!(normalized, PathBuf::from(r"C:\dir").join(&name));
+	}
+
+	#[test]
+	fn too_long_or_malformed_paths_fail_cleanly() {
+		// Doesn't exist, so there is no short name to fall back on.
+		let long = format!(r"C:\{}\notes.txt", "d".repeat(300));
+
+		for path in [
+			long.as_str(),
+			"",
+			r"\\.\PhysicalDrive0",
+			r"C:\a|b.txt",
+			r"C:\dir\x:y",
+		] {
+			assert!(
+				matches!(
+					normalize(Path::new(path)),
+					Err(FileOpenError::InvalidPath { .. })
+				),
+				"{path} should be rejected"
+			);
+		}
+	}
+}
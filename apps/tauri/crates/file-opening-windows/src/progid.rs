@@ -0,0 +1,84 @@
+//! # ProgIDs
+//!
+//! `IAssocHandler` doesn't say which registration it was built from, so a
+//! handler's ProgID is found by matching it against the ProgIDs its
+//! extension lists: a packaged app by the AUMID its ProgID names, a desktop
+//! app by the executable its ProgID launches. Desktop apps that registered
+//! no ProgID of their own are found through `Applications\<exe>`, the key
+//! the shell's Open With list falls back to.
+
+use windows::Win32::System::Registry::HKEY_CLASSES_ROOT;
+use windows::Win32::UI::Shell::{
+	IAssocHandler, ASSOCF_NONE, ASSOCSTR_DELEGATEEXECUTE, ASSOCSTR_EXECUTABLE,
+};
+
+use crate::{assoc, packaged, registry};
+
+/// The ProgID behind an enumerated handler for `ext` whose `GetName` is
+/// `name`.
+pub(crate) fn of_handler(ext: &str, name: &str, handler: &IAssocHandler) -> Option<String> {
+	let aumid = match assoc::handler_executable(name) {
+		Some(_) => None,
+		None => packaged::app_user_model_id(ext, name, handler),
+	};
+
+	for_handler(ext, name, aumid.as_deref())
+}
+
+/// The ProgID behind a handler for `ext`, given its `GetName` and, for a
+/// packaged app, its AUMID.
+pub(crate) fn for_handler(ext: &str, name: &str, aumid: Option<&str>) -> Option<String> {
+	let candidates = candidates(ext);
+
+	if let Some(aumid) = aumid {
+		return candidates.into_iter().find(|prog_id| {
+			registry::read_string(
+				HKEY_CLASSES_ROOT,
+				&format!(r"{prog_id}\Application"),
+				Some("AppUserModelID"),
+			)
+			.is_some_and(|candidate| candidate.eq_ignore_ascii_case(aumid))
+		});
+	}
+
+	let exe = assoc::handler_executable(name)?;
+	let launches_exe = |prog_id: &String| {
+		assoc::query_string(ASSOCF_NONE, ASSOCSTR_EXECUTABLE, prog_id)
+			.is_some_and(|candidate| candidate.eq_ignore_ascii_case(&exe.to_string_lossy()))
+	};
+
+	candidates.into_iter().find(launches_exe).or_else(|| {
+		let application = format!(r"Applications\{}", exe.file_name()?.to_string_lossy());
+		registry::read_string(
+			HKEY_CLASSES_ROOT,
+			&format!(r"{application}\shell\open\command"),
+			None,
+		)
+		.map(|_| application)
+	})
+}
+
+/// The CLSID in braces that `prog_id`'s default verb delegates to, if it is
+/// run by a COM object rather than a command line.
+pub(crate) fn delegate_clsid(prog_id: &str) -> Option<String> {
+	assoc::query_string(ASSOCF_NONE, ASSOCSTR_DELEGATEEXECUTE, prog_id)
+		.filter(|clsid| clsid.starts_with('{') && clsid.ends_with('}'))
+}
+
+/// The ProgIDs `ext` may be opened through: its own default first, then
+/// the ones listed under its `OpenWithProgids`.
+fn candidates(ext: &str) -> Vec<String> {
+	let mut candidates = Vec::new();
+	candidates.extend(registry::read_string(HKEY_CLASSES_ROOT, ext, None));
+
+	for prog_id in registry::value_names(HKEY_CLASSES_ROOT, &format!(r"{ext}\OpenWithProgids")) {
+		if !candidates
+			.iter()
+			.any(|known| known.eq_ignore_ascii_case(&prog_id))
+		{
+			candidates.push(prog_id);
+		}
+	}
+
+	candidates
+}
@@ -0,0 +1,177 @@
+//! # Association provider
+//!
+//! Which handlers an extension lists and which one is its default come from
+//! two lookups, `SHAssocEnumHandlers` and `AssocQueryStringW`. They sit
+//! behind [`AssocProvider`] so the logic built on them (sorting, dedup,
+//! skipping handlers that fail) can be checked against fixed answers rather
+//! than whatever this machine has installed. [`SystemAssoc`] is the shell
+//! itself, and the only provider outside tests.
+//!
+//! What a handler reports about itself (names, icon, executable) is still
+//! read from the handler, so a fake provider hands out fake `IAssocHandler`s.
+
+use std::fmt;
+
+use file_opening::{CancellationToken, FileOpenError};
+use windows::Win32::UI::Shell::{IAssocHandler, ASSOCF, ASSOCSTR, ASSOC_FILTER};
+
+use crate::{assoc, HandlerIter, LOG_TARGET};
+
+pub(crate) trait AssocProvider: Send + Sync + fmt::Debug {
+	/// The handlers for `ext` (e.g. `.txt`) under `filter`, checking `cancel`
+	/// before each one is fetched.
+	fn enum_handlers(
+		&self,
+		ext: &str,
+		filter: ASSOC_FILTER,
+		cancel: &CancellationToken,
+	) -> Result<Vec<IAssocHandler>, FileOpenError>;
+
+	/// One association string for `assoc`, an extension, ProgID or, with
+	/// `ASSOCF_OPEN_BYEXENAME`, an executable.
+	fn query_string(&self, flags: ASSOCF, kind: ASSOCSTR, assoc: &str) -> Option<String>;
+
+	/// Whether enumerations go through the per-thread handler cache, which is
+	/// keyed by extension alone. Only the system's may.
+	fn cacheable(&self) -> bool {
+		false
+	}
+}
+
+/// The shell's own associations.
+#[derive(Debug)]
+pub(crate) struct SystemAssoc;
+
+impl AssocProvider for SystemAssoc {
+	/// On cancellation the handlers fetched so far and the enumerator are
+	/// dropped, which releases every one of them.
+	fn enum_handlers(
+		&self,
+		ext: &str,
+		filter: ASSOC_FILTER,
+		cancel: &CancellationToken,
+	) -> Result<Vec<IAssocHandler>, FileOpenError> {
+		let _span =
+			tracing::debug_span!(target: LOG_TARGET, "SHAssocEnumHandlers", ext, filter = filter.0)
+				.entered();
+
+		let mut handlers = Vec::new();
+		let mut iter = HandlerIter::new(ext, filter)?;
+
+		loop {
+			cancel.check()?;
+			let Some(handler) = iter.next() else {
+				break;
+			};
+			handlers.push(handler);
+		}
+
+		tracing::debug!(target: LOG_TARGET, handlers = handlers.len(), "Enumerated handlers");
+		Ok(handlers)
+	}
+
+	fn query_string(&self, flags: ASSOCF, kind: ASSOCSTR, assoc: &str) -> Option<String> {
+		assoc::query_string(flags, kind, assoc)
+	}
+
+	fn cacheable(&self) -> bool {
+		true
+	}
+}
+
+/// Stand-ins for tests.
+#[cfg(test)]
+pub(crate) mod fake {
+	use std::cell::Cell;
+
+	use windows::core::{implement, Error, Result, HRESULT, HSTRING, PCWSTR, PWSTR};
+	use windows::Win32::Foundation::{E_FAIL, E_NOTIMPL, S_OK};
+	use windows::Win32::System::Com::IDataObject;
+	use windows::Win32::UI::Shell::{
+		IAssocHandlerInvoker, IAssocHandler_Impl, SHStrDupW, ASSOCF_NONE, ASSOCSTR_EXECUTABLE,
+	};
+
+	use super::*;
+
+	thread_local! {
+		/// How many `GetName` calls fake handlers have answered on this
+		/// thread, to tell how far a search went.
+		pub(crate) static NAMES_READ: Cell<usize> = const { Cell::new(0) };
+	}
+
+	/// A handler whose `GetName` reports `name`, or fails without one.
+	#[implement(IAssocHandler)]
+	pub(crate) struct FakeHandler(pub Option<String>);
+
+	impl IAssocHandler_Impl for FakeHandler_Impl {
+		fn GetName(&self) -> Result<PWSTR> {
+			NAMES_READ.with(|read| read.set(read.get() + 1));
+			let name = self.0.as_deref().ok_or_else(|| Error::from(E_FAIL))?;
+			unsafe { SHStrDupW(&HSTRING::from(name)) }
+		}
+
+		fn GetUIName(&self) -> Result<PWSTR> {
+			Err(E_NOTIMPL.into())
+		}
+
+		fn GetIconLocation(&self, _path: *mut PWSTR, _index: *mut i32) -> Result<()> {
+			Err(E_NOTIMPL.into())
+		}
+
+		fn IsRecommended(&self) -> HRESULT {
+			S_OK
+		}
+
+		fn MakeDefault(&self, _description: &PCWSTR) -> Result<()> {
+			Err(E_NOTIMPL.into())
+		}
+
+		fn Invoke(&self, _data_object: Option<&IDataObject>) -> Result<()> {
+			Err(E_NOTIMPL.into())
+		}
+
+		fn CreateInvoker(
+			&self,
+			_data_object: Option<&IDataObject>,
+		) -> Result<IAssocHandlerInvoker> {
+			Err(E_NOTIMPL.into())
+		}
+	}
+
+	/// Lists `handlers` by name for every extension, and answers
+	/// `ASSOCSTR_EXECUTABLE` for any extension with `default`.
+	///
+	/// `None` names a handler that fails. With `failing` every enumeration
+	/// fails instead, as `SHAssocEnumHandlers` itself can.
+	#[derive(Debug, Default)]
+	pub(crate) struct FakeAssoc {
+		pub handlers: Vec<Option<String>>,
+		pub default: Option<String>,
+		pub failing: bool,
+	}
+
+	impl AssocProvider for FakeAssoc {
+		fn enum_handlers(
+			&self,
+			_ext: &str,
+			_filter: ASSOC_FILTER,
+			cancel: &CancellationToken,
+		) -> std::result::Result<Vec<IAssocHandler>, FileOpenError> {
+			cancel.check()?;
+			if self.failing {
+				return Err(FileOpenError::EnumHandlers(E_FAIL.0));
+			}
+			Ok(self
+				.handlers
+				.iter()
+				.map(|name| FakeHandler(name.clone()).into())
+				.collect())
+		}
+
+		fn query_string(&self, flags: ASSOCF, kind: ASSOCSTR, assoc: &str) -> Option<String> {
+			let default_executable =
+				flags == ASSOCF_NONE && kind == ASSOCSTR_EXECUTABLE && assoc.starts_with('.');
+			self.default.clone().filter(|_| default_executable)
+		}
+	}
+}
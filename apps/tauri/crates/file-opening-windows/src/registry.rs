@@ -0,0 +1,218 @@
+//! # Registry reads
+//!
+//! The few association facts the shell APIs don't expose (MIME mappings,
+//! per-user choices, verb names) are read straight from the registry.
+
+use windows::core::{HSTRING, PCWSTR, PWSTR};
+use windows::Win32::Foundation::{ERROR_SUCCESS, FILETIME, WIN32_ERROR};
+use windows::Win32::System::Registry::{
+	RegCloseKey, RegEnumKeyExW, RegEnumValueW, RegGetValueW, RegOpenKeyExW, RegQueryInfoKeyW, HKEY,
+	KEY_READ, RRF_RT_ANY, RRF_RT_REG_BINARY, RRF_RT_REG_DWORD, RRF_RT_REG_SZ,
+};
+
+/// Reads a `REG_SZ` value, or `None` if the key or value is missing.
+///
+/// `value` of `None` reads the key's default value.
+pub(crate) fn read_string(root: HKEY, subkey: &str, value: Option<&str>) -> Option<String> {
+	let subkey = HSTRING::from(subkey);
+	let value = value.map(HSTRING::from);
+	let value = value
+		.as_ref()
+		.map_or(PCWSTR::null(), |v| PCWSTR(v.as_ptr()));
+
+	unsafe {
+		let mut size = 0u32;
+		let status = RegGetValueW(
+			root,
+			&subkey,
+			value,
+			RRF_RT_REG_SZ,
+			None,
+			None,
+			Some(&mut size),
+		);
+		if status != ERROR_SUCCESS || size == 0 {
+			return None;
+		}
+
+		let mut buffer = vec![0u16; (size as usize).div_ceil(2)];
+		let status = RegGetValueW(
+			root,
+			&subkey,
+			value,
+			RRF_RT_REG_SZ,
+			None,
+			Some(buffer.as_mut_ptr().cast()),
+			Some(&mut size),
+		);
+		if status != ERROR_SUCCESS {
+			return None;
+		}
+
+		let end = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+		String::from_utf16(&buffer[..end])
+			.ok()
+			.filter(|value| !value.is_empty())
+	}
+}
+
+/// Reads the raw bytes of a `REG_DWORD` or `REG_BINARY` value, or `None` if
+/// the key or value is missing or of another type.
+pub(crate) fn read_bytes(root: HKEY, subkey: &str, value: &str) -> Option<Vec<u8>> {
+	let subkey = HSTRING::from(subkey);
+	let value = HSTRING::from(value);
+	let kinds = RRF_RT_REG_DWORD | RRF_RT_REG_BINARY;
+
+	unsafe {
+		let mut size = 0u32;
+		let status = RegGetValueW(root, &subkey, &value, kinds, None, None, Some(&mut size));
+		if status != ERROR_SUCCESS {
+			return None;
+		}
+
+		let mut buffer = vec![0u8; size as usize];
+		let status = RegGetValueW(
+			root,
+			&subkey,
+			&value,
+			kinds,
+			None,
+			Some(buffer.as_mut_ptr().cast()),
+			Some(&mut size),
+		);
+		if status != ERROR_SUCCESS {
+			return None;
+		}
+
+		buffer.truncate(size as usize);
+		Some(buffer)
+	}
+}
+
+/// Whether `subkey` has a value named `value`, of any type.
+pub(crate) fn has_value(root: HKEY, subkey: &str, value: &str) -> bool {
+	let status = unsafe {
+		RegGetValueW(
+			root,
+			&HSTRING::from(subkey),
+			&HSTRING::from(value),
+			RRF_RT_ANY,
+			None,
+			None,
+			None,
+		)
+	};
+	status == ERROR_SUCCESS
+}
+
+/// Whether `subkey` exists, whatever it holds.
+pub(crate) fn has_key(root: HKEY, subkey: &str) -> bool {
+	let mut key = HKEY::default();
+	let status = unsafe { RegOpenKeyExW(root, &HSTRING::from(subkey), 0, KEY_READ, &mut key) };
+	if status != ERROR_SUCCESS {
+		return false;
+	}
+
+	unsafe {
+		let _ = RegCloseKey(key);
+	}
+	true
+}
+
+/// When `subkey` or one of its values was last written, as a `FILETIME`.
+pub(crate) fn last_write_time(root: HKEY, subkey: &str) -> Option<u64> {
+	let mut key = HKEY::default();
+	let status = unsafe { RegOpenKeyExW(root, &HSTRING::from(subkey), 0, KEY_READ, &mut key) };
+	if status != ERROR_SUCCESS {
+		return None;
+	}
+
+	let mut time = FILETIME::default();
+	let status = unsafe {
+		let status = RegQueryInfoKeyW(
+			key,
+			PWSTR::null(),
+			None,
+			None,
+			None,
+			None,
+			None,
+			None,
+			None,
+			None,
+			None,
+			Some(&mut time),
+		);
+		let _ = RegCloseKey(key);
+		status
+	};
+
+	(status == ERROR_SUCCESS)
+		.then(|| (u64::from(time.dwHighDateTime) << 32) | u64::from(time.dwLowDateTime))
+}
+
+/// Names of the direct subkeys of `subkey`, or nothing if it's missing.
+pub(crate) fn subkey_names(root: HKEY, subkey: &str) -> Vec<String> {
+	// Key names are limited to 255 characters.
+	enumerate(root, subkey, 256, |key, index, buffer, len| unsafe {
+		RegEnumKeyExW(
+			key,
+			index,
+			PWSTR(buffer.as_mut_ptr()),
+			len,
+			None,
+			PWSTR::null(),
+			None,
+			None,
+		)
+	})
+}
+
+/// Names of the values of `subkey`, or nothing if it's missing.
+pub(crate) fn value_names(root: HKEY, subkey: &str) -> Vec<String> {
+	// Value names are limited to 16383 characters.
+	enumerate(root, subkey, 16384, |key, index, buffer, len| unsafe {
+		RegEnumValueW(
+			key,
+			index,
+			PWSTR(buffer.as_mut_ptr()),
+			len,
+			None,
+			None,
+			None,
+			None,
+		)
+	})
+}
+
+/// Opens `subkey` and collects the names `next` reports for index 0, 1, ...
+/// until it stops succeeding.
+fn enumerate(
+	root: HKEY,
+	subkey: &str,
+	capacity: usize,
+	next: impl Fn(HKEY, u32, &mut [u16], &mut u32) -> WIN32_ERROR,
+) -> Vec<String> {
+	let mut key = HKEY::default();
+	let status = unsafe { RegOpenKeyExW(root, &HSTRING::from(subkey), 0, KEY_READ, &mut key) };
+	if status != ERROR_SUCCESS {
+		return Vec::new();
+	}
+
+	let mut names = Vec::new();
+	let mut buffer = vec![0u16; capacity];
+	for index in 0.. {
+		let mut len = buffer.len() as u32;
+		if next(key, index, &mut buffer, &mut len) != ERROR_SUCCESS {
+			break;
+		}
+		if let Ok(name) = String::from_utf16(&buffer[..len as usize]) {
+			names.push(name);
+		}
+	}
+
+	unsafe {
+		let _ = RegCloseKey(key);
+	}
+	names
+}
@@ -0,0 +1,74 @@
+//! # Risky file types
+//!
+//! Opening some types, executables and scripts among them, makes the shell
+//! show its "Open File - Security Warning" prompt first. Spacedrive asks for
+//! its own confirmation instead, so it needs to know before launching: a
+//! type is risky when it's on the shell's built-in list of dangerous types
+//! (`AssocIsDangerous`) or its class sets `FTA_AlwaysUnsafe` in `EditFlags`.
+
+use windows::core::HSTRING;
+use windows::Win32::System::Registry::HKEY_CLASSES_ROOT;
+use windows::Win32::UI::Shell::{AssocIsDangerous, FTA_AlwaysUnsafe, ASSOCF_NONE, ASSOCSTR_PROGID};
+
+use crate::{assoc, registry};
+
+/// Whether opening files of type `ext` (with its dot) warns the user first.
+///
+/// Flags that can't be read count as safe, so a broken registration doesn't
+/// turn every file of its type into a confirmation prompt.
+pub(crate) fn is_risky(ext: &str) -> bool {
+	if unsafe { AssocIsDangerous(&HSTRING::from(ext)) }.as_bool() {
+		return true;
+	}
+
+	let mut classes = vec![ext.to_string()];
+	classes.extend(assoc::query_string(ASSOCF_NONE, ASSOCSTR_PROGID, ext));
+
+	classes.iter().any(|class| {
+		registry::read_bytes(HKEY_CLASSES_ROOT, class, "EditFlags")
+			.and_then(|bytes| parse_edit_flags(&bytes))
+			.is_some_and(always_unsafe)
+	})
+}
+
+/// `EditFlags` as a number: a `REG_DWORD`, or a `REG_BINARY` holding the
+/// same four little-endian bytes. Anything shorter can't be read.
+fn parse_edit_flags(bytes: &[u8]) -> Option<u32> {
+	let bytes: [u8; 4] = bytes.get(..4)?.try_into().ok()?;
+	Some(u32::from_le_bytes(bytes))
+}
+
+fn always_unsafe(flags: u32) -> bool {
+	flags & FTA_AlwaysUnsafe.0 as u32 != 0
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn edit_flags_parse_from_dwords_and_binary_values() {
+		// `EditFlags`=dword:00020000 and =hex:00,00,02,00 mean the same.
+		assert_eq!(
+			parse_edit_flags(&0x0002_0000u32.to_le_bytes()),
+			Some(0x0002_0000)
+		);
+		assert_eq!(
+			parse_edit_flags(&[0x00, 0x00, 0x02, 0x00]),
+			Some(0x0002_0000)
+		);
+		assert_eq!(parse_edit_flags(&[0x00, 0x00]), None);
+		assert_eq!(parse_edit_flags(&[]), None);
+
+		assert!(always_unsafe(0x0002_0000));
+		// FTA_OpenIsSafe | FTA_NoEdit
+		assert!(!always_unsafe(0x0001_0008));
+		assert!(always_unsafe(0x0003_0000));
+	}
+
+	#[test]
+	fn executables_are_risky_and_text_is_not() {
+		assert!(is_risky(".exe"));
+		assert!(!is_risky(".txt"));
+	}
+}
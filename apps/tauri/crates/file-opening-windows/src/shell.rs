@@ -0,0 +1,227 @@
+//! # Shell item helpers
+//!
+//! Owned PIDLs and data-object construction shared by the launch paths that
+//! hand items to the shell by identity rather than by path string.
+
+use std::ffi::{OsStr, OsString};
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
+use std::path::{Path, PathBuf};
+
+use windows::core::{Result, HSTRING};
+use windows::Win32::System::Com::IBindCtx;
+use windows::Win32::System::Com::IDataObject;
+use windows::Win32::UI::Shell::Common::ITEMIDLIST;
+use windows::Win32::UI::Shell::{
+	BHID_DataObject, ILCreateFromPathW, ILFindLastID, ILFree, IShellItem, IShellItemArray,
+	SHCreateItemFromParsingName, SHCreateShellItemArrayFromDataObject,
+	SHCreateShellItemArrayFromIDLists, SHGetIDListFromObject, SHOpenFolderAndSelectItems,
+	SHParseDisplayName, SIGDN_DESKTOPABSOLUTEPARSING,
+};
+
+use crate::take_co_string;
+
+/// An absolute PIDL freed with `ILFree` on drop.
+pub(crate) struct OwnedPidl(*mut ITEMIDLIST);
+
+impl OwnedPidl {
+	pub(crate) fn from_path(path: &Path) -> Option<Self> {
+		let pidl = unsafe { ILCreateFromPathW(&HSTRING::from(path)) };
+		(!pidl.is_null()).then_some(Self(pidl))
+	}
+
+	/// Parses through the desktop folder, which unlike `ILCreateFromPathW`
+	/// reports why a path couldn't be resolved.
+	pub(crate) fn parse(path: &Path) -> Result<Self> {
+		let mut pidl = std::ptr::null_mut();
+		unsafe { SHParseDisplayName(&HSTRING::from(path), None::<&IBindCtx>, &mut pidl, 0, None)? };
+		Ok(Self(pidl))
+	}
+
+	/// Resolves a parsing name, which unlike a path can lead into a
+	/// namespace extension such as a ZIP folder.
+	pub(crate) fn from_parsing_name(name: &str) -> Result<Self> {
+		let item: IShellItem = unsafe { SHCreateItemFromParsingName(&HSTRING::from(name), None)? };
+		Ok(Self(unsafe { SHGetIDListFromObject(&item)? }))
+	}
+
+	pub(crate) fn as_ptr(&self) -> *const ITEMIDLIST {
+		self.0
+	}
+}
+
+impl Drop for OwnedPidl {
+	fn drop(&mut self) {
+		unsafe { ILFree(Some(self.0)) }
+	}
+}
+
+/// Opens `folder` in Explorer with every one of `items`, its children,
+/// selected.
+pub(crate) fn select_in_folder<'a>(
+	folder: &Path,
+	items: impl IntoIterator<Item = &'a Path>,
+) -> Result<()> {
+	let folder = OwnedPidl::parse(folder)?;
+	let items = items
+		.into_iter()
+		.map(OwnedPidl::parse)
+		.collect::<Result<Vec<_>>>()?;
+
+	// Selections are relative to the folder, so each is the last ID of the
+	// item's full PIDL, which `items` keeps alive until the call returns.
+	let children = items
+		.iter()
+		.map(|item| unsafe { ILFindLastID(item.as_ptr()) } as *const ITEMIDLIST)
+		.collect::<Vec<_>>();
+
+	unsafe { SHOpenFolderAndSelectItems(folder.as_ptr(), Some(&children), 0) }
+}
+
+/// Builds one `IDataObject` describing every path, suitable for a single
+/// `IAssocHandler::Invoke`.
+///
+/// The shell only produces a data object for items sharing a parent folder,
+/// so callers group paths by directory first.
+pub(crate) fn data_object_for_paths(paths: &[&Path]) -> Result<IDataObject> {
+	data_object_for_items(&item_array_for_paths(paths)?)
+}
+
+/// The `IDataObject` describing `items`.
+pub(crate) fn data_object_for_items(items: &IShellItemArray) -> Result<IDataObject> {
+	unsafe { items.BindToHandler(None, &BHID_DataObject) }
+}
+
+/// Builds one `IShellItemArray` describing every path, under the same
+/// shared-parent rule as [`data_object_for_paths`].
+pub(crate) fn item_array_for_paths(paths: &[&Path]) -> Result<IShellItemArray> {
+	let pidls = paths
+		.iter()
+		.map(|path| OwnedPidl::from_path(path).ok_or_else(windows::core::Error::from_win32))
+		.collect::<Result<Vec<_>>>()?;
+
+	let raw = pidls.iter().map(OwnedPidl::as_ptr).collect::<Vec<_>>();
+
+	unsafe { SHCreateShellItemArrayFromIDLists(&raw) }
+}
+
+/// The items `data` describes, for a data object that didn't come from
+/// [`data_object_for_paths`].
+pub(crate) fn item_array_for_data_object(data: &IDataObject) -> Result<IShellItemArray> {
+	unsafe { SHCreateShellItemArrayFromDataObject(data) }
+}
+
+/// The parsing name of the first of `items`: its path for a file, a path
+/// that continues past the filesystem for one inside a ZIP folder.
+pub(crate) fn first_parsing_name(items: &IShellItemArray) -> Result<PathBuf> {
+	unsafe {
+		let name = items
+			.GetItemAt(0)?
+			.GetDisplayName(SIGDN_DESKTOPABSOLUTEPARSING)?;
+		Ok(PathBuf::from(take_co_string(name).unwrap_or_default()))
+	}
+}
+
+/// Joins arguments into one command line that `CommandLineToArgvW` (and so
+/// the C runtime of the receiving app) splits back into the same values.
+///
+/// Backslashes are only special when they precede a quote, so runs of them
+/// are doubled there and left alone everywhere else. Arguments are quoted as
+/// UTF-16, so a path that isn't valid Unicode reaches the app unchanged.
+pub(crate) fn join_arguments<'a>(args: impl IntoIterator<Item = &'a OsStr>) -> OsString {
+	let mut line = Vec::new();
+	for (i, arg) in args.into_iter().enumerate() {
+		if i > 0 {
+			line.push(SPACE);
+		}
+		quote_argument(&arg.encode_wide().collect::<Vec<_>>(), &mut line);
+	}
+	OsString::from_wide(&line)
+}
+
+const SPACE: u16 = b' ' as u16;
+const TAB: u16 = b'\t' as u16;
+const QUOTE: u16 = b'"' as u16;
+const BACKSLASH: u16 = b'\\' as u16;
+
+fn quote_argument(arg: &[u16], line: &mut Vec<u16>) {
+	if !arg.is_empty() && !arg.iter().any(|c| matches!(*c, SPACE | TAB | QUOTE)) {
+		line.extend_from_slice(arg);
+		return;
+	}
+
+	let mut backslashes = 0;
+	line.push(QUOTE);
+
+	for &c in arg {
+		match c {
+			BACKSLASH => backslashes += 1,
+			QUOTE => {
+				line.resize(line.len() + backslashes * 2 + 1, BACKSLASH);
+				line.push(QUOTE);
+				backslashes = 0;
+			}
+			_ => {
+				line.resize(line.len() + backslashes, BACKSLASH);
+				line.push(c);
+				backslashes = 0;
+			}
+		}
+	}
+
+	// The closing quote would otherwise be escaped by a trailing backslash.
+	line.resize(line.len() + backslashes * 2, BACKSLASH);
+	line.push(QUOTE);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parsing_names_reach_beyond_the_filesystem() {
+		let temp = std::env::temp_dir();
+		assert!(OwnedPidl::from_parsing_name(&temp.to_string_lossy()).is_ok());
+
+		// This PC, which has no path at all.
+		assert!(OwnedPidl::from_parsing_name("::{20D04FE0-3AEA-1069-A2D8-08002B30309D}").is_ok());
+
+		let missing = temp.join("sd-no-such-folder").join("inside.zip");
+		assert!(OwnedPidl::from_parsing_name(&missing.to_string_lossy()).is_err());
+	}
+
+	fn join(args: &[&str]) -> OsString {
+		join_arguments(args.iter().map(OsStr::new))
+	}
+
+	#[test]
+	fn quotes_only_when_needed() {
+		assert_eq!(
+			join(&["-n42", r"C:\Users\me\My Notes.txt"]),
+			r#"-n42 "C:\Users\me\My Notes.txt""#
+		);
+		assert_eq!(join(&["", "plain"]), r#""" plain"#);
+	}
+
+	#[test]
+	fn escapes_quotes_and_trailing_backslashes() {
+		assert_eq!(
+			join(&[r#"say "hi""#, r"C:\dir with space\"]),
+			r#""say \"hi\"" "C:\dir with space\\""#
+		);
+		assert_eq!(join(&[r#"a\"b"#]), r#""a\\\"b""#);
+	}
+
+	#[test]
+	fn unpaired_surrogates_survive_quoting() {
+		// `a b\uD800.txt`, which has no UTF-8 form.
+		let name = OsString::from_wide(&[0x61, 0x20, 0x62, 0xD800, 0x2E, 0x74, 0x78, 0x74]);
+		let mut quoted = vec![QUOTE];
+		quoted.extend(name.encode_wide());
+		quoted.push(QUOTE);
+
+		assert_eq!(
+			join_arguments([name.as_os_str()]),
+			OsString::from_wide(&quoted)
+		);
+	}
+}
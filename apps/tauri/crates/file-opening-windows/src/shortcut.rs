@@ -0,0 +1,85 @@
+//! # Shell links
+//!
+//! A `.lnk` file only stores where its target was when it was made. The
+//! target is read back with `IShellLinkW` without calling `Resolve`, which
+//! would go searching for a moved target and can show UI while it does;
+//! a target that is gone is reported as such instead.
+
+use std::path::{Path, PathBuf};
+use windows::core::{Interface, Result, HSTRING};
+use windows::Win32::Foundation::MAX_PATH;
+use windows::Win32::System::Com::{
+	CoCreateInstance, IPersistFile, CLSCTX_INPROC_SERVER, STGM_READ,
+};
+use windows::Win32::UI::Shell::{IShellLinkW, ShellLink};
+
+/// Whether `path` names a shell link by its extension.
+pub(crate) fn is_shortcut(path: &Path) -> bool {
+	path.extension()
+		.is_some_and(|ext| ext.eq_ignore_ascii_case("lnk"))
+}
+
+/// The existing file or folder the shell link at `path` points at.
+///
+/// `None` for files that aren't readable shell links, for links to items
+/// outside the file system (e.g. Control Panel pages), and for links whose
+/// target no longer exists.
+pub(crate) fn resolve(path: &Path) -> Result<Option<PathBuf>> {
+	unsafe {
+		let link: IShellLinkW = CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER)?;
+		let file: IPersistFile = link.cast()?;
+
+		if file.Load(&HSTRING::from(path), STGM_READ).is_err() {
+			return Ok(None);
+		}
+
+		// Without SLGP_RAWPATH, environment variables in the stored path are
+		// already expanded.
+		let mut buffer = [0u16; MAX_PATH as usize];
+		if link.GetPath(&mut buffer, std::ptr::null_mut(), 0).is_err() {
+			return Ok(None);
+		}
+
+		let end = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+		let target = PathBuf::from(String::from_utf16_lossy(&buffer[..end]));
+
+		Ok((!target.as_os_str().is_empty() && target.exists()).then_some(target))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::fs;
+
+	fn write_link(link: &Path, target: &Path) {
+		unsafe {
+			let shell_link: IShellLinkW =
+				CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER).unwrap();
+			shell_link.SetPath(&HSTRING::from(target)).unwrap();
+			let file: IPersistFile = shell_link.cast().unwrap();
+			file.Save(&HSTRING::from(link), true).unwrap();
+		}
+	}
+
+	#[test]
+	fn resolves_links_until_their_target_is_gone() {
+		crate::com::ensure_initialized().unwrap();
+
+		let dir = std::env::temp_dir().join(format!("sd-shortcut-{}", std::process::id()));
+		fs::create_dir_all(&dir).unwrap();
+		let target = dir.join("notes.txt");
+		let link = dir.join("notes.lnk");
+		fs::write(&target, "").unwrap();
+		write_link(&link, &target);
+
+		assert!(is_shortcut(&link));
+		assert_eq!(resolve(&link).unwrap(), Some(target.clone()));
+
+		fs::remove_file(&target).unwrap();
+		assert_eq!(resolve(&link).unwrap(), None);
+		assert_eq!(resolve(&dir.join("missing.lnk")).unwrap(), None);
+
+		fs::remove_dir_all(&dir).unwrap();
+	}
+}
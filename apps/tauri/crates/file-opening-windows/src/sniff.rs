@@ -0,0 +1,43 @@
+//! # Content sniffing
+//!
+//! Shell associations are keyed by extension, so a file without one has no
+//! handlers at all. Sniffing the first few KB for a magic number gives a
+//! stand-in extension to enumerate handlers for instead.
+
+use std::fs::File;
+use std::io::Read;
+use std::os::windows::fs::MetadataExt;
+use std::path::Path;
+
+use windows::Win32::Storage::FileSystem::{
+	FILE_ATTRIBUTE_OFFLINE, FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS,
+};
+
+/// Enough for every signature `infer` knows, including the ZIP-based
+/// document formats that need to look past the local file header.
+const SNIFF_LEN: u64 = 8192;
+
+/// Guesses a dotted extension (e.g. `.png`) from the file's leading bytes.
+///
+/// Only plain local files are read. Pipes and devices can block a reader
+/// forever, and cloud placeholders would be downloaded just to be sniffed.
+pub(crate) fn sniffed_extension(path: &Path) -> Option<String> {
+	if path.to_string_lossy().starts_with(r"\\.\") {
+		return None;
+	}
+
+	let metadata = path.metadata().ok()?;
+	let skipped = FILE_ATTRIBUTE_OFFLINE.0 | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS.0;
+	if !metadata.is_file() || metadata.file_attributes() & skipped != 0 {
+		return None;
+	}
+
+	let mut head = Vec::with_capacity(SNIFF_LEN as usize);
+	File::open(path)
+		.ok()?
+		.take(SNIFF_LEN)
+		.read_to_end(&mut head)
+		.ok()?;
+
+	infer::get(&head).map(|kind| format!(".{}", kind.extension()))
+}
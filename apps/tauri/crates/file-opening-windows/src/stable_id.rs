@@ -0,0 +1,119 @@
+//! # Stable app ids
+//!
+//! A handler's `GetName` is whatever the shell found it under: an
+//! executable path, a bare exe name, or an AUMID. Spacedrive saves the app
+//! picked per file type and matches apps across the types of a selection,
+//! so `OpenWithApp::id` is the same for every type an app opens: a hash of
+//! its executable path, which doesn't depend on how the shell spelled it, or
+//! the AUMID of a packaged app.
+//!
+//! ProgIDs would survive an app moving to a new folder on update, but most
+//! apps register one per type (`VSCode.txt`, `VSCode.md`), so they're only
+//! a lookup hint: `OpenWithApp::progid`, which `open_with_app` still takes.
+//!
+//! Handlers whose executable is gone have neither and keep their name, which
+//! is what [`assoc::missing_handler_executable`] goes by.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use windows::Win32::UI::Shell::IAssocHandler;
+
+use crate::{assoc, packaged};
+
+/// Prefix of the ids made from an executable's path.
+const EXE_PREFIX: &str = "exe:";
+
+/// The executables behind the hashed ids handed out so far, so an app can
+/// be started from its id alone, without a file type to enumerate.
+static EXECUTABLES: Mutex<BTreeMap<String, PathBuf>> = Mutex::new(BTreeMap::new());
+
+/// The id of a handler whose `GetName` is `name`, given what was already
+/// resolved for it.
+pub(crate) fn of(name: &str, exe_path: Option<&Path>, aumid: Option<&str>) -> String {
+	match (exe_path, aumid) {
+		(Some(exe), _) => {
+			let id = hashed(exe);
+			EXECUTABLES
+				.lock()
+				.unwrap_or_else(|e| e.into_inner())
+				.insert(id.clone(), exe.to_path_buf());
+			id
+		}
+		(None, Some(aumid)) => aumid.to_string(),
+		(None, None) => name.to_string(),
+	}
+}
+
+/// [`of`] for an enumerated handler for `ext`, resolving what it needs.
+pub(crate) fn of_handler(ext: &str, name: &str, handler: &IAssocHandler) -> String {
+	let exe_path = assoc::handler_executable(name);
+	let aumid = match exe_path {
+		Some(_) => None,
+		None => packaged::app_user_model_id(ext, name, handler),
+	};
+
+	of(name, exe_path.as_deref(), aumid.as_deref())
+}
+
+/// Whether `app_id` is the hashed id of `exe`.
+pub(crate) fn is_hash_of(app_id: &str, exe: &Path) -> bool {
+	app_id.starts_with(EXE_PREFIX) && hashed(exe) == app_id
+}
+
+/// The executable behind a hashed id handed out by this process.
+pub(crate) fn executable(app_id: &str) -> Option<PathBuf> {
+	EXECUTABLES
+		.lock()
+		.unwrap_or_else(|e| e.into_inner())
+		.get(app_id)
+		.cloned()
+}
+
+/// `exe:` and a 64-bit FNV-1a hash of the lowercased path, which unlike
+/// `std`'s hashers is guaranteed to stay the same across releases.
+fn hashed(exe: &Path) -> String {
+	let hash = exe
+		.to_string_lossy()
+		.to_lowercase()
+		.bytes()
+		.fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+			(hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+		});
+	format!("{EXE_PREFIX}{hash:016x}")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn executables_come_first_then_the_aumid() {
+		let exe = Path::new(r"C:\Program Files\Editor\editor.exe");
+
+		let hashed = of("editor.exe", Some(exe), None);
+		assert!(hashed.starts_with(EXE_PREFIX) && hashed.len() == EXE_PREFIX.len() + 16);
+		assert_eq!(
+			hashed,
+			of(
+				"EDITOR.EXE",
+				Some(Path::new(r"c:\program files\editor\EDITOR.exe")),
+				None
+			),
+			"the same for any spelling of the path"
+		);
+		assert!(is_hash_of(&hashed, exe));
+		assert_eq!(executable(&hashed).as_deref(), Some(exe));
+
+		let aumid = "Microsoft.WindowsNotepad_8wekyb3d8bbwe!App";
+		assert_eq!(of(aumid, None, None), aumid);
+		assert_eq!(of("Notepad", None, Some(aumid)), aumid);
+	}
+
+	#[test]
+	fn hashes_dont_change_between_releases() {
+		assert_eq!(hashed(Path::new("")), "exe:cbf29ce484222325");
+		assert_eq!(hashed(Path::new("a")), "exe:af63dc4c8601ec8c");
+	}
+}
@@ -0,0 +1,182 @@
+//! # Failure reporting
+//!
+//! Turns ShellExecute return codes and `HRESULT`s into `OpenResult`s that
+//! carry both a readable message and the code itself, so a user's bug report
+//! says more than "failed with code 31".
+
+use std::path::Path;
+use windows::core::{Error, HRESULT};
+use windows::Win32::Foundation::*;
+use windows::Win32::UI::Shell::*;
+
+use file_opening::OpenResult;
+
+use crate::lock;
+
+/// Maps a failed `ShellExecuteW` return value (32 or less) to a result.
+///
+/// `last_error` is the thread's last error right after the call, which is
+/// more specific than the return value when the shell set one.
+pub(crate) fn from_shell_execute(code: isize, last_error: WIN32_ERROR, file: &Path) -> OpenResult {
+	// A dismissed UAC or Open With prompt comes back as SE_ERR_ACCESSDENIED
+	// with ERROR_CANCELLED left as the thread's last error.
+	if last_error == ERROR_CANCELLED {
+		return OpenResult::Cancelled;
+	}
+	if last_error == ERROR_ELEVATION_REQUIRED {
+		return requires_elevation(file);
+	}
+
+	let path = || file.to_string_lossy().to_string();
+	let code = u32::try_from(code).unwrap_or(u32::MAX);
+
+	if code == SE_ERR_SHARE || is_sharing_violation(last_error) {
+		return locked(file);
+	}
+
+	match code {
+		SE_ERR_ACCESSDENIED => OpenResult::PermissionDenied { path: path() },
+		SE_ERR_FNF | SE_ERR_PNF => OpenResult::FileNotFound { path: path() },
+		_ => {
+			let (description, error) = describe_shell_execute(code);
+			let error = if last_error.0 != 0 { last_error } else { error };
+			let hresult = HRESULT::from_win32(error.0);
+
+			OpenResult::PlatformError {
+				message: format!("{description} ({hresult})"),
+				code: hresult.0,
+			}
+		}
+	}
+}
+
+/// Maps a failed shell or handler call to what the user should be told.
+///
+/// Follows the cross-platform contract documented on `OpenResult::Cancelled`.
+pub(crate) fn from_error(error: &Error, path: &Path) -> OpenResult {
+	if error.code() == HRESULT::from_win32(ERROR_CANCELLED.0) {
+		OpenResult::Cancelled
+	} else if error.code() == HRESULT::from_win32(ERROR_ELEVATION_REQUIRED.0) {
+		requires_elevation(path)
+	} else if error.code() == E_ACCESSDENIED {
+		OpenResult::PermissionDenied {
+			path: path.to_string_lossy().to_string(),
+		}
+	} else if [ERROR_SHARING_VIOLATION, ERROR_LOCK_VIOLATION]
+		.iter()
+		.any(|&violation| error.code() == HRESULT::from_win32(violation.0))
+	{
+		locked(path)
+	} else {
+		// Displays as the system's FormatMessageW text plus the HRESULT.
+		OpenResult::PlatformError {
+			message: error.to_string(),
+			code: error.code().0,
+		}
+	}
+}
+
+fn is_sharing_violation(error: WIN32_ERROR) -> bool {
+	error == ERROR_SHARING_VIOLATION || error == ERROR_LOCK_VIOLATION
+}
+
+/// What CreateProcessW reports for an executable whose manifest says
+/// `requireAdministrator`. Only the shell knows to prompt for it.
+fn requires_elevation(path: &Path) -> OpenResult {
+	OpenResult::RequiresElevation {
+		path: path.to_string_lossy().to_string(),
+	}
+}
+
+/// `Locked`, naming the process that holds `path` if it can be found.
+fn locked(path: &Path) -> OpenResult {
+	OpenResult::Locked {
+		by: lock::holder(path),
+	}
+}
+
+/// The documented meaning of a `SE_ERR_*` code and the Win32 error it
+/// corresponds to.
+fn describe_shell_execute(code: u32) -> (&'static str, WIN32_ERROR) {
+	match code {
+		0 | SE_ERR_OOM => ("The system is out of memory or resources", ERROR_OUTOFMEMORY),
+		SE_ERR_FNF => ("The file was not found", ERROR_FILE_NOT_FOUND),
+		SE_ERR_PNF => ("The path was not found", ERROR_PATH_NOT_FOUND),
+		SE_ERR_ACCESSDENIED => ("Access was denied", ERROR_ACCESS_DENIED),
+		// Documented as ERROR_BAD_FORMAT, which has no SE_ERR_ alias.
+		11 => ("The executable file is invalid", ERROR_BAD_FORMAT),
+		SE_ERR_SHARE => ("A sharing violation occurred", ERROR_SHARING_VIOLATION),
+		SE_ERR_ASSOCINCOMPLETE => (
+			"The file type's association is incomplete or invalid",
+			ERROR_NO_ASSOCIATION,
+		),
+		SE_ERR_NOASSOC => (
+			"No application is associated with this file type",
+			ERROR_NO_ASSOCIATION,
+		),
+		SE_ERR_DDETIMEOUT => ("The DDE request timed out", ERROR_DDE_FAIL),
+		SE_ERR_DDEFAIL => ("The DDE transaction failed", ERROR_DDE_FAIL),
+		SE_ERR_DDEBUSY => (
+			"The DDE transaction could not complete because other DDE transactions were in progress",
+			ERROR_DDE_FAIL,
+		),
+		SE_ERR_DLLNOTFOUND => ("A required library was not found", ERROR_DLL_NOT_FOUND),
+		_ => ("ShellExecute failed", ERROR_GEN_FAILURE),
+	}
+}
+
+/// Whether `result` reports that the file's type has no usable association.
+pub(crate) fn is_no_association(result: &OpenResult) -> bool {
+	matches!(
+		result,
+		OpenResult::PlatformError { code, .. }
+			if *code == HRESULT::from_win32(ERROR_NO_ASSOCIATION.0).0
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn decodes_shell_execute_codes() {
+		let file = Path::new(r"C:\notes.xyz");
+
+		let no_association = from_shell_execute(SE_ERR_NOASSOC as isize, WIN32_ERROR(0), file);
+		assert!(is_no_association(&no_association));
+		match no_association {
+			OpenResult::PlatformError { message, code } => {
+				assert!(message.starts_with("No application is associated"));
+				assert_eq!(code, HRESULT::from_win32(ERROR_NO_ASSOCIATION.0).0);
+			}
+			other => panic!("unexpected {other:?}"),
+		}
+
+		assert!(matches!(
+			from_shell_execute(SE_ERR_FNF as isize, WIN32_ERROR(0), file),
+			OpenResult::FileNotFound { .. }
+		));
+		assert!(matches!(
+			from_shell_execute(SE_ERR_ACCESSDENIED as isize, ERROR_CANCELLED, file),
+			OpenResult::Cancelled
+		));
+		assert!(matches!(
+			from_shell_execute(SE_ERR_SHARE as isize, WIN32_ERROR(0), file),
+			OpenResult::Locked { by: None }
+		));
+		assert!(matches!(
+			from_error(
+				&Error::from(HRESULT::from_win32(ERROR_LOCK_VIOLATION.0)),
+				file
+			),
+			OpenResult::Locked { by: None }
+		));
+		assert!(matches!(
+			from_error(
+				&Error::from(HRESULT::from_win32(ERROR_ELEVATION_REQUIRED.0)),
+				file
+			),
+			OpenResult::RequiresElevation { .. }
+		));
+	}
+}
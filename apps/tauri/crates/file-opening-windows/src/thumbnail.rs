@@ -0,0 +1,43 @@
+//! # Thumbnails
+//!
+//! `IShellItemImageFactory` hands out the same previews Explorer shows,
+//! through the thumbnail cache and whichever thumbnail provider the file's
+//! type registered. `SIIGBF_THUMBNAILONLY` keeps it from falling back to the
+//! type's icon, so a file without a preview comes back as `None` and the UI
+//! can tell it apart from one that has one.
+
+use windows::core::HSTRING;
+use windows::Win32::Foundation::SIZE;
+use windows::Win32::Graphics::Gdi::DeleteObject;
+use windows::Win32::UI::Shell::{
+	IShellItemImageFactory, SHCreateItemFromParsingName, SIIGBF_RESIZETOFIT, SIIGBF_THUMBNAILONLY,
+};
+
+use crate::icon;
+
+/// The thumbnail of the file at `path` as PNG, scaled to fit within `size`
+/// pixels on each side with its aspect ratio kept.
+pub(crate) fn for_path(path: &std::path::Path, size: u32) -> Option<Vec<u8>> {
+	// The shell rejects an empty box, and the extent has to fit an i32.
+	let edge = size.clamp(1, i32::MAX as u32) as i32;
+
+	unsafe {
+		let factory: IShellItemImageFactory =
+			SHCreateItemFromParsingName(&HSTRING::from(path), None).ok()?;
+		let bitmap = factory
+			.GetImage(
+				SIZE { cx: edge, cy: edge },
+				SIIGBF_THUMBNAILONLY | SIIGBF_RESIZETOFIT,
+			)
+			.ok()?;
+
+		let pixels = icon::bitmap_rgba(bitmap);
+		// The bitmap is ours to free.
+		let _ = DeleteObject(bitmap);
+
+		let (width, height, mut pixels) = pixels?;
+		// Thumbnails of opaque formats like JPEG leave alpha zeroed.
+		icon::apply_mask(&mut pixels, None);
+		icon::encode_png(width, height, &pixels)
+	}
+}
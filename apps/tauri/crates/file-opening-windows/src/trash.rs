@@ -0,0 +1,138 @@
+//! # Recycle Bin
+//!
+//! Items are deleted with `IFileOperation`, the engine behind Explorer's own
+//! Delete, so the user gets the usual confirmation, progress and error
+//! dialogs and can undo it from Explorer. `FOF_ALLOWUNDO` is what sends them
+//! to the Recycle Bin; an item it can't take (a drive without one, or a file
+//! too large for it) gets the shell's prompt to delete it permanently, which
+//! the user may decline.
+//!
+//! All items go through one operation, so a batch shows one dialog. The
+//! operation only reports whether anything was aborted, not what, so each
+//! item's result is read off whether it's still there afterwards.
+
+use std::path::Path;
+
+use file_opening::OpenResult;
+use windows::core::{Result, HSTRING};
+use windows::Win32::Foundation::{ERROR_CANCELLED, HWND};
+use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_ALL};
+use windows::Win32::UI::Shell::{
+	FileOperation, IFileOperation, IShellItem, SHCreateItemFromParsingName,
+	COPYENGINE_E_USER_CANCELLED, FOFX_RECYCLEONDELETE, FOF_ALLOWUNDO,
+};
+
+use crate::{status, LOG_TARGET};
+
+/// Moves `paths` to the Recycle Bin, with a result for each in order.
+pub(crate) fn recycle(owner: HWND, paths: &[&Path]) -> Vec<OpenResult> {
+	let _span = tracing::debug_span!(target: LOG_TARGET, "recycle", paths = paths.len()).entered();
+
+	let mut results = paths
+		.iter()
+		.map(|path| (!exists(path)).then(|| not_found(path)))
+		.collect::<Vec<_>>();
+
+	let mut items = Vec::new();
+	for (path, result) in paths.iter().zip(&mut results) {
+		if result.is_some() {
+			continue;
+		}
+		let item: Result<IShellItem> =
+			unsafe { SHCreateItemFromParsingName(&HSTRING::from(*path), None) };
+		match item {
+			Ok(item) => items.push(item),
+			Err(e) => *result = Some(status::from_error(&e, path)),
+		}
+	}
+
+	let outcome = if items.is_empty() {
+		Ok(false)
+	} else {
+		perform(owner, &items)
+	};
+
+	paths
+		.iter()
+		.zip(results)
+		.map(|(path, result)| {
+			result.unwrap_or_else(|| match &outcome {
+				Err(e) => status::from_error(e, path),
+				Ok(_) if !exists(path) => OpenResult::success(),
+				Ok(true) => OpenResult::Cancelled,
+				Ok(false) => OpenResult::PlatformError {
+					message: "The item wasn't moved to the Recycle Bin".to_string(),
+					code: 0,
+				},
+			})
+		})
+		.collect()
+}
+
+/// Deletes `items` in one operation, returning whether the user aborted any
+/// of it.
+fn perform(owner: HWND, items: &[IShellItem]) -> Result<bool> {
+	unsafe {
+		let operation: IFileOperation = CoCreateInstance(&FileOperation, None, CLSCTX_ALL)?;
+		operation.SetOperationFlags(FOF_ALLOWUNDO | FOFX_RECYCLEONDELETE)?;
+		if !owner.is_invalid() {
+			operation.SetOwnerWindow(owner)?;
+		}
+		for item in items {
+			operation.DeleteItem(item, None)?;
+		}
+
+		let performed = operation.PerformOperations();
+		let aborted = operation
+			.GetAnyOperationsAborted()
+			.is_ok_and(|aborted| aborted.as_bool());
+		match performed {
+			Ok(()) => Ok(aborted),
+			Err(e)
+				if e.code() == COPYENGINE_E_USER_CANCELLED
+					|| e.code() == ERROR_CANCELLED.to_hresult() =>
+			{
+				Ok(true)
+			}
+			Err(e) => Err(e),
+		}
+	}
+}
+
+/// Whether something is at `path`, a dangling link included.
+fn exists(path: &Path) -> bool {
+	path.symlink_metadata().is_ok()
+}
+
+fn not_found(path: &Path) -> OpenResult {
+	OpenResult::FileNotFound {
+		path: path.to_string_lossy().to_string(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::com;
+
+	#[test]
+	fn recycled_files_are_gone_and_missing_ones_reported() {
+		com::ensure_initialized().unwrap();
+		let dir = std::env::temp_dir().join(format!("sd-recycle-{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		let (first, second) = (dir.join("first.txt"), dir.join("second.txt"));
+		std::fs::write(&first, b"recycled").unwrap();
+		std::fs::write(&second, b"recycled").unwrap();
+		let missing = dir.join("missing.txt");
+
+		let results = recycle(HWND::default(), &[&first, &missing, &second]);
+		assert!(matches!(results[0], OpenResult::Success { .. }));
+		assert!(
+			matches!(&results[1], OpenResult::FileNotFound { path } if path.ends_with("missing.txt"))
+		);
+		assert!(matches!(results[2], OpenResult::Success { .. }));
+		assert!(!first.exists() && !second.exists());
+
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+}
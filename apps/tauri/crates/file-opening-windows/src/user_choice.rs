@@ -0,0 +1,332 @@
+//! # Per-user defaults
+//!
+//! The app a user picked with "Always use this app" is saved under
+//! `HKCU\...\Explorer\FileExts\<ext>\UserChoice` as a ProgID plus a hash of
+//! it, the extension, the user's SID and the minute the key was written. The
+//! shell ignores a choice whose hash doesn't match, so one an installer wrote
+//! by hand doesn't count, and doesn't here either. Without a valid choice the
+//! extension's registered ProgID is the default, per-user classes over
+//! per-machine ones as `HKEY_CLASSES_ROOT` merges them.
+//!
+//! `AssocQueryStringW` and `SHAssocEnumHandlers` (recommended filter
+//! included) resolve the default the same way; this is for callers that need
+//! the layers read directly.
+
+use windows::core::PWSTR;
+use windows::Win32::Foundation::{CloseHandle, LocalFree, HANDLE, HLOCAL};
+use windows::Win32::Security::Authorization::ConvertSidToStringSidW;
+use windows::Win32::Security::Cryptography::{BCryptHash, BCRYPT_MD5_ALG_HANDLE};
+use windows::Win32::Security::{GetTokenInformation, TokenUser, TOKEN_QUERY, TOKEN_USER};
+use windows::Win32::System::Registry::{HKEY_CLASSES_ROOT, HKEY_CURRENT_USER};
+use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+
+use crate::{registry, LOG_TARGET};
+
+const FILE_EXTS: &str = r"Software\Microsoft\Windows\CurrentVersion\Explorer\FileExts";
+
+/// Mixed into every choice's hash; the shell keeps it in shell32's resources.
+const EXPERIENCE: &str =
+	"User Choice set via Windows User Experience {D18B6DD5-6124-4341-9318-804003BAFA0B}";
+
+/// A minute in `FILETIME` units, the resolution of the hash's timestamp.
+const MINUTE: u64 = 60 * 10_000_000;
+
+/// The ProgID the user chose for `ext` (dotted), if there's a choice and its
+/// hash checks out.
+pub(crate) fn prog_id(ext: &str) -> Option<String> {
+	let subkey = choice_key(ext);
+	let prog_id = registry::read_string(HKEY_CURRENT_USER, &subkey, Some("ProgId"))?;
+	let hash = registry::read_string(HKEY_CURRENT_USER, &subkey, Some("Hash"))?;
+	let written = registry::last_write_time(HKEY_CURRENT_USER, &subkey)?;
+
+	if choice_hash(ext, &current_user_sid()?, &prog_id, written)? != hash {
+		tracing::debug!(target: LOG_TARGET, ext, prog_id, "Ignoring a UserChoice with a bad hash");
+		return None;
+	}
+	Some(prog_id)
+}
+
+/// The ProgID `ext` (dotted) is registered with, what applies without a
+/// valid choice.
+pub(crate) fn registered_prog_id(ext: &str) -> Option<String> {
+	registry::read_string(HKEY_CLASSES_ROOT, ext, None)
+}
+
+/// `HKCU`'s key for the user's choice for `ext`.
+pub(crate) fn choice_key(ext: &str) -> String {
+	format!(r"{FILE_EXTS}\{ext}\UserChoice")
+}
+
+/// The hash the shell expects for choosing `prog_id` for `ext`, by the user
+/// `sid`, in a key last written at `written` (a `FILETIME`).
+pub(crate) fn choice_hash(ext: &str, sid: &str, prog_id: &str, written: u64) -> Option<String> {
+	let minute = written - written % MINUTE;
+	let input = format!("{ext}{sid}{prog_id}{minute:016x}{EXPERIENCE}").to_lowercase();
+	// UTF-16 with its terminator, as the shell hashes it.
+	let bytes = input
+		.encode_utf16()
+		.chain(Some(0))
+		.flat_map(u16::to_le_bytes)
+		.collect::<Vec<_>>();
+
+	let mut digest = [0u8; 16];
+	unsafe { BCryptHash(BCRYPT_MD5_ALG_HANDLE, None, &bytes, &mut digest) }
+		.ok()
+		.ok()?;
+	let md5 = [dword(&digest, 0), dword(&digest, 1)];
+
+	let hash = scramble(&bytes, md5);
+	let mut out = [0u8; 8];
+	out[..4].copy_from_slice(&hash[0].to_le_bytes());
+	out[4..].copy_from_slice(&hash[1].to_le_bytes());
+	Some(base64(&out))
+}
+
+/// Two checksums over `bytes` in 8-byte blocks, each scrambled after every
+/// DWORD with multipliers seeded from `md5`. A trailing partial block is
+/// ignored.
+fn scramble(bytes: &[u8], md5: [u32; 2]) -> [u32; 2] {
+	let c0 = [
+		[md5[0] | 1, 0xCF98B111, 0x87085B9F, 0x12CEB96D, 0x257E1D83],
+		[md5[1] | 1, 0xA27416F5, 0xD38396FF, 0x7C932B89, 0xBFA49F69],
+	];
+	let c1 = [
+		[md5[0] | 1, 0xEF0569FB, 0x689B6B9F, 0x79F8A395, 0xC3EFEA97],
+		[md5[1] | 1, 0xC31713DB, 0xDDCD1F0F, 0x59C3AF2D, 0x35BD1EC9],
+	];
+
+	let (mut h0, mut h1, mut h0_total, mut h1_total) = (0u32, 0u32, 0u32, 0u32);
+	for block in 0..bytes.len() / 8 {
+		for (half, (c0, c1)) in c0.iter().zip(&c1).enumerate() {
+			let input = dword(bytes, block * 2 + half);
+
+			h0 = h0.wrapping_add(input).wrapping_mul(c0[0]);
+			for &c in &c0[1..] {
+				h0 = h0.rotate_left(16).wrapping_mul(c);
+			}
+			h0_total = h0_total.wrapping_add(h0);
+
+			h1 = h1.wrapping_add(input);
+			h1 = h1
+				.rotate_left(16)
+				.wrapping_mul(c1[1])
+				.wrapping_add(h1.wrapping_mul(c1[0]));
+			h1 = (h1 >> 16)
+				.wrapping_mul(c1[2])
+				.wrapping_add(h1.wrapping_mul(c1[3]));
+			h1 = h1.rotate_left(16).wrapping_mul(c1[4]).wrapping_add(h1);
+			h1_total = h1_total.wrapping_add(h1);
+		}
+	}
+
+	[h0 ^ h1, h0_total ^ h1_total]
+}
+
+/// The little-endian DWORD at `index` in `bytes`.
+fn dword(bytes: &[u8], index: usize) -> u32 {
+	let start = index * 4;
+	u32::from_le_bytes(bytes[start..start + 4].try_into().unwrap())
+}
+
+fn base64(bytes: &[u8]) -> String {
+	const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+	let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+	for chunk in bytes.chunks(3) {
+		let group = chunk
+			.iter()
+			.enumerate()
+			.fold(0u32, |group, (i, &b)| group | (b as u32) << (16 - 8 * i));
+		for i in 0..4 {
+			if i <= chunk.len() {
+				out.push(ALPHABET[(group >> (18 - 6 * i) & 0x3F) as usize] as char);
+			} else {
+				out.push('=');
+			}
+		}
+	}
+	out
+}
+
+/// The string form of the SID this process runs as, e.g. `S-1-5-21-…-1001`.
+fn current_user_sid() -> Option<String> {
+	unsafe {
+		let mut token = HANDLE::default();
+		OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token).ok()?;
+		let sid = token_user_sid(token);
+		let _ = CloseHandle(token);
+		sid
+	}
+}
+
+unsafe fn token_user_sid(token: HANDLE) -> Option<String> {
+	let mut size = 0u32;
+	let _ = GetTokenInformation(token, TokenUser, None, 0, &mut size);
+	// u64s so the buffer is aligned for the TOKEN_USER at its start.
+	let mut buffer = vec![0u64; (size as usize).div_ceil(8)];
+	GetTokenInformation(
+		token,
+		TokenUser,
+		Some(buffer.as_mut_ptr().cast()),
+		size,
+		&mut size,
+	)
+	.ok()?;
+
+	let user = &*buffer.as_ptr().cast::<TOKEN_USER>();
+	let mut string = PWSTR::null();
+	ConvertSidToStringSidW(user.User.Sid, &mut string).ok()?;
+	let sid = string.to_string().ok();
+	let _ = LocalFree(HLOCAL(string.0.cast()));
+	sid
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{com, WindowsFileOpener};
+	use file_opening::FileOpener;
+	use std::path::{Path, PathBuf};
+	use windows::core::{HSTRING, PCWSTR};
+	use windows::Win32::System::Registry::{RegDeleteTreeW, RegSetKeyValueW, REG_SZ};
+	use windows::Win32::UI::Shell::{SHChangeNotify, SHCNE_ASSOCCHANGED, SHCNF_IDLIST};
+
+	#[test]
+	fn hashes_match_the_shells() {
+		let sid = "S-1-5-21-1004336348-1177238915-682003330-1001";
+		let written = 133_400_000_123_456_789;
+		let hash = |prog_id| choice_hash(".sdtest", sid, prog_id, written).unwrap();
+
+		assert_eq!(hash("SdTest.Document"), "csHA0mOikpU=");
+		// Case and the seconds don't count.
+		assert_eq!(hash("SDTEST.DOCUMENT"), "csHA0mOikpU=");
+		assert_eq!(
+			choice_hash(
+				".sdtest",
+				sid,
+				"SdTest.Document",
+				written - written % MINUTE
+			),
+			Some("csHA0mOikpU=".to_string())
+		);
+		assert_ne!(hash("SdTest.Other"), "csHA0mOikpU=");
+
+		assert_eq!(base64(b"ab"), "YWI=");
+		assert_eq!(base64(b"abcd"), "YWJjZA==");
+	}
+
+	/// Keys under `HKCU`, deleted on drop.
+	struct Keys(Vec<String>);
+
+	impl Drop for Keys {
+		fn drop(&mut self) {
+			for key in &self.0 {
+				let _ = unsafe { RegDeleteTreeW(HKEY_CURRENT_USER, &HSTRING::from(key)) };
+			}
+			notify_association_changed();
+		}
+	}
+
+	fn set_string(subkey: &str, name: Option<&str>, value: &str) {
+		let data = value.encode_utf16().chain(Some(0)).collect::<Vec<_>>();
+		let name = name.map(HSTRING::from);
+
+		let status = unsafe {
+			RegSetKeyValueW(
+				HKEY_CURRENT_USER,
+				&HSTRING::from(subkey),
+				name.as_ref()
+					.map_or(PCWSTR::null(), |name| PCWSTR(name.as_ptr())),
+				REG_SZ.0,
+				Some(data.as_ptr().cast()),
+				(data.len() * size_of::<u16>()) as u32,
+			)
+		};
+		assert!(status.is_ok(), "writing {subkey}: {status:?}");
+	}
+
+	fn notify_association_changed() {
+		unsafe { SHChangeNotify(SHCNE_ASSOCCHANGED, SHCNF_IDLIST, None, None) };
+	}
+
+	/// Chooses `prog_id` for `ext` with `hash`, or the right hash if `None`.
+	fn write_choice(ext: &str, prog_id: &str, hash: Option<&str>) {
+		let key = choice_key(ext);
+		set_string(&key, Some("ProgId"), prog_id);
+		let sid = current_user_sid().unwrap();
+
+		// Writing the hash updates the key's time too; go again if that
+		// lands in the next minute.
+		loop {
+			let written = registry::last_write_time(HKEY_CURRENT_USER, &key).unwrap();
+			let right = choice_hash(ext, &sid, prog_id, written).unwrap();
+			set_string(&key, Some("Hash"), hash.unwrap_or(&right));
+
+			let now = registry::last_write_time(HKEY_CURRENT_USER, &key).unwrap();
+			if hash.is_some() || now / MINUTE == written / MINUTE {
+				break;
+			}
+		}
+		notify_association_changed();
+	}
+
+	#[test]
+	fn valid_choices_override_the_registered_default() {
+		com::ensure_initialized().unwrap();
+		let ext = format!(".sdchoice{}", std::process::id());
+		let chosen = format!("Spacedrive.TestChosen{ext}");
+		let registered = format!("Spacedrive.TestRegistered{ext}");
+		let classes = |name: &str| format!(r"Software\Classes\{name}");
+		let _keys = Keys(vec![
+			classes(&ext),
+			classes(&chosen),
+			classes(&registered),
+			format!(r"{FILE_EXTS}\{ext}"),
+		]);
+
+		let exe = std::env::current_exe().unwrap();
+		let cmd = PathBuf::from(std::env::var("SystemRoot").unwrap()).join(r"System32\cmd.exe");
+		for (prog_id, app) in [(&chosen, &exe), (&registered, &cmd)] {
+			let command = format!(r#""{}" "%1""#, app.display());
+			set_string(
+				&format!(r"{}\shell\open\command", classes(prog_id)),
+				None,
+				&command,
+			);
+			set_string(
+				&format!(r"{}\OpenWithProgids", classes(&ext)),
+				Some(prog_id),
+				"",
+			);
+		}
+		set_string(&classes(&ext), None, &registered);
+		notify_association_changed();
+
+		let opener = WindowsFileOpener::default();
+		let effective = || {
+			opener
+				.effective_default_app(&ext)
+				.unwrap()
+				.and_then(|app| app.exe_path)
+		};
+
+		assert_eq!(prog_id(&ext), None);
+		assert_eq!(effective(), Some(cmd.clone()));
+
+		write_choice(&ext, &chosen, Some("c2R0ZXN0AA=="));
+		assert_eq!(prog_id(&ext), None);
+		assert_eq!(effective(), Some(cmd));
+
+		write_choice(&ext, &chosen, None);
+		assert_eq!(prog_id(&ext).as_deref(), Some(chosen.as_str()));
+		assert_eq!(effective(), Some(exe.clone()));
+
+		// The shell honors it too, so the hash is the one it computes.
+		opener.clear_cache();
+		let default = opener
+			.get_default_app(Path::new(&format!("notes{ext}")))
+			.unwrap()
+			.unwrap();
+		assert_eq!(default.exe_path, Some(exe));
+	}
+}
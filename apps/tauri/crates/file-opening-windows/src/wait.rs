@@ -0,0 +1,94 @@
+//! # Waiting on launches
+//!
+//! `ShellExecuteExW` with `SEE_MASK_NOCLOSEPROCESS` hands back the process it
+//! started, if it started one. It doesn't when the shell resolved the launch
+//! some other way (DDE, a packaged app, a COM handler), and a single-instance
+//! app that forwards the file to its running instance starts a process that
+//! exits straight away, so the exit code is only meaningful for apps that
+//! open each file in a process of their own.
+
+use std::ffi::OsStr;
+use std::path::Path;
+
+use file_opening::{OpenResult, ProcessOutcome};
+use windows::core::{w, HSTRING, PCWSTR};
+use windows::Win32::Foundation::{CloseHandle, HWND};
+use windows::Win32::System::Threading::{
+	GetExitCodeProcess, GetProcessId, WaitForSingleObject, INFINITE,
+};
+use windows::Win32::UI::Shell::{
+	ShellExecuteExW, SEE_MASK_CLASSNAME, SEE_MASK_NOASYNC, SEE_MASK_NOCLOSEPROCESS,
+	SHELLEXECUTEINFOW,
+};
+use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+
+use crate::status;
+
+/// What to hand `ShellExecuteExW`.
+pub(crate) struct Launch<'a> {
+	/// The document, or the program when `parameters` carry the document.
+	pub file: &'a Path,
+	pub parameters: Option<&'a OsStr>,
+	/// A ProgID whose `open` command is used instead of the file's own.
+	pub class: Option<&'a str>,
+}
+
+/// Starts `launch` and blocks until the process it started exits.
+///
+/// Failures are reported against `document`.
+pub(crate) fn run(owner: HWND, launch: &Launch, document: &Path) -> ProcessOutcome {
+	let file = HSTRING::from(launch.file);
+	let parameters = launch.parameters.map(HSTRING::from);
+	let class = launch.class.map(HSTRING::from);
+
+	let mut mask = SEE_MASK_NOCLOSEPROCESS | SEE_MASK_NOASYNC;
+	if class.is_some() {
+		mask |= SEE_MASK_CLASSNAME;
+	}
+	let mut info = SHELLEXECUTEINFOW {
+		cbSize: std::mem::size_of::<SHELLEXECUTEINFOW>() as u32,
+		hwnd: owner,
+		fMask: mask,
+		lpVerb: w!("open"),
+		lpFile: PCWSTR(file.as_ptr()),
+		lpParameters: parameters
+			.as_ref()
+			.map_or(PCWSTR::null(), |p| PCWSTR(p.as_ptr())),
+		lpClass: class
+			.as_ref()
+			.map_or(PCWSTR::null(), |c| PCWSTR(c.as_ptr())),
+		nShow: SW_SHOWNORMAL.0,
+		..Default::default()
+	};
+
+	if let Err(e) = unsafe { ShellExecuteExW(&mut info) } {
+		return ProcessOutcome {
+			result: status::from_error(&e, document),
+			exit_code: None,
+		};
+	}
+
+	let process = info.hProcess;
+	if process.is_invalid() {
+		return ProcessOutcome {
+			result: OpenResult::success(),
+			exit_code: None,
+		};
+	}
+
+	let mut code = 0u32;
+	let (pid, exit_code) = unsafe {
+		let pid = GetProcessId(process);
+		WaitForSingleObject(process, INFINITE);
+		let exited = GetExitCodeProcess(process, &mut code);
+		let _ = CloseHandle(process);
+		(pid, exited.ok().map(|()| code as i32))
+	};
+
+	ProcessOutcome {
+		result: OpenResult::Success {
+			pid: (pid != 0).then_some(pid),
+		},
+		exit_code,
+	}
+}
@@ -0,0 +1,142 @@
+//! # Association change watcher
+//!
+//! Installing an app or picking a new default mid-session changes what the
+//! shell enumerates, so cached handlers must not outlive it. A background
+//! thread owns a hidden top-level window (message-only windows never see
+//! broadcasts) that listens for `WM_SETTINGCHANGE` and for the shell's
+//! `SHCNE_ASSOCCHANGED` notification, and invalidates the handler cache on
+//! either before calling every subscribed callback on that same thread.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Once};
+use windows::core::*;
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::UI::Shell::*;
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use crate::cache;
+
+const WM_ASSOC_CHANGED: u32 = WM_APP + 1;
+
+static START: Once = Once::new();
+
+type Callback = Arc<dyn Fn() + Send + Sync>;
+
+static SUBSCRIBERS: Mutex<Vec<(u64, Callback)>> = Mutex::new(Vec::new());
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Registers `callback` for association changes, returning the id to pass to
+/// [`unsubscribe`].
+pub(crate) fn subscribe(callback: Callback) -> u64 {
+	ensure_started();
+
+	let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+	SUBSCRIBERS
+		.lock()
+		.unwrap_or_else(|e| e.into_inner())
+		.push((id, callback));
+	id
+}
+
+pub(crate) fn unsubscribe(id: u64) {
+	SUBSCRIBERS
+		.lock()
+		.unwrap_or_else(|e| e.into_inner())
+		.retain(|(subscriber, _)| *subscriber != id);
+}
+
+fn notify_subscribers() {
+	// Called outside the lock so a callback can drop its own handle.
+	let callbacks = SUBSCRIBERS
+		.lock()
+		.unwrap_or_else(|e| e.into_inner())
+		.iter()
+		.map(|(_, callback)| Arc::clone(callback))
+		.collect::<Vec<_>>();
+
+	for callback in callbacks {
+		callback();
+	}
+}
+
+/// Starts the watcher thread the first time it's called.
+///
+/// If the window can't be created the cache simply goes without automatic
+/// invalidation; `clear_cache` still works.
+pub(crate) fn ensure_started() {
+	START.call_once(|| {
+		let _ = std::thread::Builder::new()
+			.name("file-opening-assoc-watcher".to_string())
+			.spawn(|| unsafe {
+				let _ = run();
+			});
+	});
+}
+
+unsafe fn run() -> Result<()> {
+	let instance = GetModuleHandleW(None)?;
+	let class_name = w!("SpacedriveFileOpeningWatcher");
+
+	let class = WNDCLASSW {
+		lpfnWndProc: Some(window_proc),
+		hInstance: instance.into(),
+		lpszClassName: class_name,
+		..Default::default()
+	};
+	if RegisterClassW(&class) == 0 {
+		return Err(Error::from_win32());
+	}
+
+	let hwnd = CreateWindowExW(
+		WINDOW_EX_STYLE::default(),
+		class_name,
+		w!(""),
+		WINDOW_STYLE::default(),
+		0,
+		0,
+		0,
+		0,
+		None,
+		None,
+		instance,
+		None,
+	)?;
+
+	// Association changes are posted against the desktop folder. The PIDL is
+	// never freed because the registration lives as long as the process.
+	let desktop = SHGetKnownFolderIDList(&FOLDERID_Desktop, KF_FLAG_DEFAULT.0 as u32, None)?;
+	let entry = SHChangeNotifyEntry {
+		pidl: desktop,
+		fRecursive: true.into(),
+	};
+	SHChangeNotifyRegister(
+		hwnd,
+		SHCNRF_ShellLevel,
+		SHCNE_ASSOCCHANGED.0 as i32,
+		WM_ASSOC_CHANGED,
+		1,
+		&entry,
+	);
+
+	let mut message = MSG::default();
+	while GetMessageW(&mut message, None, 0, 0).as_bool() {
+		DispatchMessageW(&message);
+	}
+
+	Ok(())
+}
+
+unsafe extern "system" fn window_proc(
+	hwnd: HWND,
+	message: u32,
+	wparam: WPARAM,
+	lparam: LPARAM,
+) -> LRESULT {
+	if message == WM_SETTINGCHANGE || message == WM_ASSOC_CHANGED {
+		cache::invalidate();
+		notify_subscribers();
+	}
+
+	DefWindowProcW(hwnd, message, wparam, lparam)
+}
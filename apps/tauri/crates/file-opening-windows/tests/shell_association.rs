@@ -0,0 +1,624 @@
+//! # Real shell associations
+//!
+//! Registers throwaway extensions for the current user whose open command
+//! is this test binary, then goes through the backend and the actual shell:
+//! the binary's `stub_handler` test is what gets launched, and it leaves a
+//! sentinel file next to the file it was opened with, naming the verb that
+//! launched it.
+//!
+//! Everything lives under `HKCU\Software\Classes`, so no elevation is needed,
+//! and it is removed again even if the test panics. It's still the user's
+//! own registry, and the shell launches real processes, so every test but
+//! the stub is ignored by default and only CI runs them:
+//!
+//! ```text
+//! cargo test -p file-opening-windows --test shell_association -- --ignored
+//! ```
+
+#![cfg(windows)]
+
+use std::ffi::OsString;
+use std::fs;
+use std::os::windows::ffi::OsStringExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use file_opening::{FileOpener, LaunchSpec, OpenResult, OpenWithApp};
+use file_opening_windows::WindowsFileOpener;
+use windows::core::{HSTRING, PCWSTR};
+use windows::Win32::System::Registry::{
+	RegDeleteTreeW, RegSetKeyValueW, HKEY_CURRENT_USER, REG_SZ,
+};
+use windows::Win32::UI::Shell::{SHChangeNotify, SHCNE_ASSOCCHANGED, SHCNF_IDLIST};
+
+/// Prefix of the extensions registered for the run, unique per process.
+const EXTENSION_PREFIX: &str = "sdtest";
+
+/// How long the shell gets to start the stub and the stub to write.
+const LAUNCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A per-run association, unregistered on drop.
+struct TempAssociation {
+	extension: String,
+	prog_id: String,
+}
+
+impl TempAssociation {
+	/// Makes `handler` open files with `.sdtest<pid>` and then `suffix`,
+	/// returning that extension.
+	fn register(handler: &Path, suffix: &str) -> Self {
+		let extension = format!(".{EXTENSION_PREFIX}{}{suffix}", std::process::id());
+		// Built before anything is written, so a registration that panics
+		// halfway is still cleaned up.
+		let association = Self {
+			prog_id: format!("Spacedrive.Test{extension}"),
+			extension,
+		};
+
+		set_string(&association.extension_key(), None, &association.prog_id);
+		set_string(
+			&format!(r"{}\OpenWithProgids", association.extension_key()),
+			Some(&association.prog_id),
+			"",
+		);
+		association.add_verb(handler, "open");
+
+		notify_association_changed();
+		association
+	}
+
+	/// Registers `verb` as launching `handler` too.
+	fn add_verb(&self, handler: &Path, verb: &str) {
+		// The verb rides along as one more test filter, matching nothing.
+		let command = format!(
+			r#""{}" stub_handler --exact --nocapture "%1" verb-{verb}"#,
+			handler.display()
+		);
+		set_string(
+			&format!(r"{}\shell\{verb}\command", self.prog_id_key()),
+			None,
+			&command,
+		);
+	}
+
+	/// Makes `verb` what a double-click runs instead of `open`.
+	fn set_default_verb(&self, verb: &str) {
+		set_string(&format!(r"{}\shell", self.prog_id_key()), None, verb);
+		notify_association_changed();
+	}
+
+	fn extension_key(&self) -> String {
+		format!(r"Software\Classes\{}", self.extension)
+	}
+
+	fn prog_id_key(&self) -> String {
+		format!(r"Software\Classes\{}", self.prog_id)
+	}
+}
+
+impl Drop for TempAssociation {
+	fn drop(&mut self) {
+		for key in [self.extension_key(), self.prog_id_key()] {
+			// Missing keys are fine: registration may not have got that far.
+			let _ = unsafe { RegDeleteTreeW(HKEY_CURRENT_USER, &HSTRING::from(key)) };
+		}
+		notify_association_changed();
+	}
+}
+
+/// Writes a `REG_SZ` value under `HKCU\subkey`, creating the key if needed.
+fn set_string(subkey: &str, name: Option<&str>, value: &str) {
+	let data = value.encode_utf16().chain(Some(0)).collect::<Vec<_>>();
+	let name = name.map(HSTRING::from);
+
+	let status = unsafe {
+		RegSetKeyValueW(
+			HKEY_CURRENT_USER,
+			&HSTRING::from(subkey),
+			name.as_ref()
+				.map_or(PCWSTR::null(), |name| PCWSTR(name.as_ptr())),
+			REG_SZ.0,
+			Some(data.as_ptr().cast()),
+			(data.len() * size_of::<u16>()) as u32,
+		)
+	};
+	assert!(status.is_ok(), "writing {subkey}: {status:?}");
+}
+
+/// Tells the shell to drop what it cached about associations.
+fn notify_association_changed() {
+	unsafe { SHChangeNotify(SHCNE_ASSOCCHANGED, SHCNF_IDLIST, None, None) };
+}
+
+/// Where the stub records that it was launched with `file`.
+fn sentinel_for(file: &Path) -> PathBuf {
+	let mut sentinel = file.as_os_str().to_owned();
+	sentinel.push(".opened");
+	PathBuf::from(sentinel)
+}
+
+/// The handler the association launches, filtered down to this test and
+/// given the file as an argument.
+///
+/// Run any other way, including as part of the normal suite, there's no
+/// file among the arguments and it passes without doing anything.
+#[test]
+fn stub_handler() {
+	let args = std::env::args_os().skip(1).collect::<Vec<_>>();
+	let Some(file) = args.iter().map(PathBuf::from).find(|arg| {
+		arg.is_file()
+			&& arg.file_name().is_some_and(|name| {
+				name.to_string_lossy()
+					.contains(&format!(".{EXTENSION_PREFIX}"))
+			})
+	}) else {
+		return;
+	};
+
+	let verb = args
+		.iter()
+		.filter_map(|arg| arg.to_str()?.strip_prefix("verb-"))
+		.next()
+		.unwrap_or_default();
+	fs::write(sentinel_for(&file), verb).unwrap();
+}
+
+/// Whether `app` is this test binary.
+fn is_stub(app: &OpenWithApp) -> bool {
+	let handler = std::env::current_exe().unwrap();
+	app.exe_path
+		.as_deref()
+		.is_some_and(|exe| exe.as_os_str().eq_ignore_ascii_case(handler.as_os_str()))
+}
+
+/// Creates an empty `sample<extension>` in a directory of its own for `test`.
+fn sample_file(test: &str, extension: &str) -> PathBuf {
+	let dir = std::env::temp_dir().join(format!("sd-{test}-{}", std::process::id()));
+	fs::create_dir_all(&dir).unwrap();
+	let file = dir.join(format!("sample{extension}"));
+	fs::write(&file, b"").unwrap();
+	file
+}
+
+/// Waits for the stub to have been launched with `file`, returning the verb
+/// it was launched through and clearing the sentinel for the next launch.
+fn wait_for_launch(file: &Path) -> String {
+	let sentinel = sentinel_for(file);
+	let started = Instant::now();
+	loop {
+		// Read until the stub has finished writing, not just created it.
+		if let Some(verb) = fs::read_to_string(&sentinel)
+			.ok()
+			.filter(|verb| !verb.is_empty())
+		{
+			let _ = fs::remove_file(&sentinel);
+			return verb;
+		}
+		assert!(
+			started.elapsed() < LAUNCH_TIMEOUT,
+			"the stub wasn't launched within {LAUNCH_TIMEOUT:?}"
+		);
+		std::thread::sleep(Duration::from_millis(100));
+	}
+}
+
+#[test]
+#[ignore = "writes to the user's registry; run with --ignored"]
+fn registered_handlers_are_listed_and_launched() {
+	let handler = std::env::current_exe().unwrap();
+	let association = TempAssociation::register(&handler, "");
+	let file = sample_file("shell-association", &association.extension);
+	let opener = WindowsFileOpener::default();
+
+	let apps = opener.get_apps_for_file(&file).unwrap();
+	let stub = apps
+		.iter()
+		.find(|app| is_stub(app))
+		.unwrap_or_else(|| panic!("the stub isn't among {apps:?}"));
+
+	let default = opener.get_default_app(&file).unwrap();
+	assert!(
+		default.as_ref().is_some_and(is_stub),
+		"the stub isn't the default: {default:?}"
+	);
+
+	assert!(matches!(
+		opener.open_with_app(&file, &stub.id),
+		Ok(OpenResult::Success { .. })
+	));
+	wait_for_launch(&file);
+
+	let _ = fs::remove_dir_all(file.parent().unwrap());
+}
+
+#[test]
+#[ignore = "writes to the user's registry; run with --ignored"]
+fn apps_keep_one_id_across_types() {
+	let handler = std::env::current_exe().unwrap();
+	// Two ProgIDs launching the same executable, as apps that register one
+	// per type have.
+	let (text, markdown) = (
+		TempAssociation::register(&handler, "ida"),
+		TempAssociation::register(&handler, "idb"),
+	);
+	let files = [
+		sample_file("id-text", &text.extension),
+		sample_file("id-markdown", &markdown.extension),
+	];
+	let opener = WindowsFileOpener::default();
+	let stub_for = |file: &Path| {
+		opener
+			.get_apps_for_file(file)
+			.unwrap()
+			.into_iter()
+			.find(is_stub)
+			.expect("the stub isn't listed")
+	};
+
+	let (first, second) = (stub_for(&files[0]), stub_for(&files[1]));
+	assert_eq!(first.progid.as_deref(), Some(text.prog_id.as_str()));
+	assert_eq!(second.progid.as_deref(), Some(markdown.prog_id.as_str()));
+	assert_eq!(first.id, second.id);
+	assert!(opener
+		.get_apps_for_files(&files)
+		.unwrap()
+		.iter()
+		.any(is_stub));
+
+	// An id saved for one type opens the app for the other.
+	assert!(matches!(
+		opener.open_with_app(&files[1], &first.id),
+		Ok(OpenResult::Success { .. })
+	));
+	wait_for_launch(&files[1]);
+
+	for file in &files {
+		let _ = fs::remove_dir_all(file.parent().unwrap());
+	}
+}
+
+#[test]
+#[ignore = "writes to the user's registry; run with --ignored"]
+fn always_using_the_default_opens_and_keeps_it() {
+	let handler = std::env::current_exe().unwrap();
+	let association = TempAssociation::register(&handler, "always");
+	let file = sample_file("always-use", &association.extension);
+	let opener = WindowsFileOpener::default();
+	let stub = opener
+		.get_apps_for_file(&file)
+		.unwrap()
+		.into_iter()
+		.find(is_stub)
+		.expect("the stub isn't listed");
+
+	// Already the default, so Settings is never opened.
+	let opened = opener
+		.open_with_app_and_set_default(&file, &stub.id)
+		.unwrap();
+	assert!(matches!(opened.result, OpenResult::Success { .. }));
+	assert!(
+		matches!(opened.default, Some(OpenResult::Success { .. })),
+		"{:?}",
+		opened.default
+	);
+	wait_for_launch(&file);
+
+	let _ = fs::remove_dir_all(file.parent().unwrap());
+}
+
+#[test]
+#[ignore = "writes to the user's registry; run with --ignored"]
+fn files_of_several_types_are_opened_with_one_app() {
+	let handler = std::env::current_exe().unwrap();
+	let first_type = TempAssociation::register(&handler, "manya");
+	let second_type = TempAssociation::register(&handler, "manyb");
+	let first = sample_file("open-many", &first_type.extension);
+	let second = sample_file("open-many", &second_type.extension);
+	let opener = WindowsFileOpener::default();
+	let stub = opener
+		.get_apps_for_file(&first)
+		.unwrap()
+		.into_iter()
+		.find(is_stub)
+		.expect("the stub isn't listed");
+
+	assert!(matches!(
+		opener.open_many_with_app(&[&first, &second], &stub.id),
+		Ok(OpenResult::Success { .. })
+	));
+	wait_for_launch(&first);
+	wait_for_launch(&second);
+
+	let missing = first.with_file_name("missing.txt");
+	assert!(matches!(
+		opener.open_many_with_app(&[&first, &missing], &stub.id),
+		Ok(OpenResult::FileNotFound { .. })
+	));
+
+	let _ = fs::remove_dir_all(first.parent().unwrap());
+}
+
+#[test]
+#[ignore = "writes to the user's registry; run with --ignored"]
+fn opening_many_keeps_verbs_and_overrides() {
+	let handler = std::env::current_exe().unwrap();
+	let plain_type = TempAssociation::register(&handler, "batch");
+	let played_type = TempAssociation::register(&handler, "batchplay");
+	played_type.add_verb(&handler, "play");
+	played_type.set_default_verb("play");
+	let overridden_type = TempAssociation::register(&handler, "batchover");
+	let plain = sample_file("open-many-default", &plain_type.extension);
+	let played = sample_file("open-many-default", &played_type.extension);
+	let overridden = sample_file("open-many-default", &overridden_type.extension);
+	let opener = WindowsFileOpener::default();
+	let stub = opener
+		.get_apps_for_file(&overridden)
+		.unwrap()
+		.into_iter()
+		.find(is_stub)
+		.expect("the stub isn't listed");
+	opener
+		.default_overrides()
+		.unwrap()
+		.set(&overridden_type.extension, &stub.id);
+
+	let results = opener
+		.open_many_with_default(&[&plain, &played, &overridden])
+		.unwrap();
+	assert!(
+		matches!(results[0], OpenResult::Success { .. }),
+		"{results:?}"
+	);
+	assert!(
+		matches!(results[1], OpenResult::Success { .. }),
+		"{results:?}"
+	);
+	assert!(
+		matches!(results[2], OpenResult::OpenedWithOverride { .. }),
+		"{results:?}"
+	);
+	assert_eq!(wait_for_launch(&plain), "open");
+	assert_eq!(wait_for_launch(&played), "play");
+	wait_for_launch(&overridden);
+
+	let _ = fs::remove_dir_all(plain.parent().unwrap());
+}
+
+#[test]
+#[ignore = "writes to the user's registry; run with --ignored"]
+fn dry_runs_name_the_handler_without_launching_it() {
+	let handler = std::env::current_exe().unwrap();
+	let association = TempAssociation::register(&handler, "dry");
+	let file = sample_file("dry-run", &association.extension);
+	let opener = WindowsFileOpener::dry_run();
+	let stub = opener
+		.get_apps_for_file(&file)
+		.unwrap()
+		.into_iter()
+		.find(is_stub)
+		.expect("the stub isn't listed");
+
+	assert!(matches!(
+		opener.open_with_app(&file, &stub.id),
+		Ok(OpenResult::Success { .. })
+	));
+	assert!(matches!(
+		opener.launch(&LaunchSpec::new(&file, &stub.id)),
+		Ok(OpenResult::Success { .. })
+	));
+	let launches = opener.dry_run_launches();
+	assert_eq!(
+		launches
+			.iter()
+			.map(|launch| launch.operation)
+			.collect::<Vec<_>>(),
+		["open_with_app", "launch"]
+	);
+	let exe = launches[1].handler.as_deref().unwrap();
+	assert!(exe.eq_ignore_ascii_case(&handler.to_string_lossy()));
+
+	// Long enough for a real launch to have written its sentinel.
+	std::thread::sleep(Duration::from_secs(2));
+	assert!(!sentinel_for(&file).exists());
+
+	let _ = fs::remove_dir_all(file.parent().unwrap());
+}
+
+#[test]
+#[ignore = "writes to the user's registry; run with --ignored"]
+fn apps_can_be_started_with_exactly_the_given_arguments() {
+	let handler = std::env::current_exe().unwrap();
+	let association = TempAssociation::register(&handler, "args");
+	let file = sample_file("launch-with-args", &association.extension);
+	let opener = WindowsFileOpener::default();
+	let stub = opener
+		.get_apps_for_file(&file)
+		.unwrap()
+		.into_iter()
+		.find(is_stub)
+		.expect("the stub isn't listed");
+
+	let file_arg = file.to_string_lossy();
+	let args = [
+		"stub_handler",
+		"--exact",
+		"--nocapture",
+		&file_arg,
+		"verb-args",
+	];
+	assert!(matches!(
+		opener.launch_app_with_args(&stub.id, &args),
+		Ok(OpenResult::Success { .. })
+	));
+	assert_eq!(wait_for_launch(&file), "args");
+
+	let _ = fs::remove_dir_all(file.parent().unwrap());
+}
+
+#[test]
+#[ignore = "writes to the user's registry; run with --ignored"]
+fn compound_extensions_win_over_their_last_segment() {
+	let handler = std::env::current_exe().unwrap();
+	// Like `.tar.gz`: whatever owns `.gz` on this machine must not be used.
+	let association = TempAssociation::register(&handler, ".gz");
+	let file = sample_file("compound-extension", &association.extension);
+	let opener = WindowsFileOpener::default();
+
+	let apps = opener.get_apps_for_file(&file).unwrap();
+	assert!(apps.iter().any(is_stub), "the stub isn't among {apps:?}");
+
+	let default = opener.get_default_app(&file).unwrap();
+	assert!(
+		default.as_ref().is_some_and(is_stub),
+		"the stub isn't the default: {default:?}"
+	);
+
+	assert!(matches!(
+		opener.open_with_default(&file),
+		Ok(OpenResult::Success { .. })
+	));
+	wait_for_launch(&file);
+
+	let _ = fs::remove_dir_all(file.parent().unwrap());
+}
+
+#[test]
+#[ignore = "writes to the user's registry; run with --ignored"]
+fn the_default_verb_is_used_unless_open_is_forced() {
+	let handler = std::env::current_exe().unwrap();
+	let association = TempAssociation::register(&handler, "play");
+	association.add_verb(&handler, "play");
+	association.set_default_verb("play");
+	let file = sample_file("default-verb", &association.extension);
+
+	// Media players register this way, and a double-click plays.
+	assert!(matches!(
+		WindowsFileOpener::default().open_with_default(&file),
+		Ok(OpenResult::Success { .. })
+	));
+	assert_eq!(wait_for_launch(&file), "play");
+
+	let forced = WindowsFileOpener::builder().force_open_verb(true).build();
+	assert!(matches!(
+		forced.open_with_default(&file),
+		Ok(OpenResult::Success { .. })
+	));
+	assert_eq!(wait_for_launch(&file), "open");
+
+	let _ = fs::remove_dir_all(file.parent().unwrap());
+}
+
+#[test]
+#[ignore = "writes to the user's registry; run with --ignored"]
+fn edit_uses_the_edit_verb_when_there_is_one() {
+	let handler = std::env::current_exe().unwrap();
+	let editable = TempAssociation::register(&handler, "edit");
+	editable.add_verb(&handler, "edit");
+	let plain = TempAssociation::register(&handler, "noedit");
+	let opener = WindowsFileOpener::default();
+
+	let file = sample_file("edit-verb", &editable.extension);
+	assert!(opener.can_edit(&file).unwrap());
+	assert!(matches!(opener.edit(&file), Ok(OpenResult::Success { .. })));
+	assert_eq!(wait_for_launch(&file), "edit");
+
+	// Only `open`: the action is hidden, and nothing is launched.
+	let without = sample_file("no-edit-verb", &plain.extension);
+	assert!(!opener.can_edit(&without).unwrap());
+	assert!(matches!(
+		opener.edit(&without),
+		Ok(OpenResult::Unsupported { operation }) if operation == "edit"
+	));
+
+	// Nothing registered for the type at all.
+	let unknown = without.with_extension("sdnothing");
+	fs::write(&unknown, b"").unwrap();
+	assert!(matches!(
+		opener.edit(&unknown),
+		Ok(OpenResult::NoHandler { verb }) if verb == "edit"
+	));
+
+	let _ = fs::remove_dir_all(file.parent().unwrap());
+	let _ = fs::remove_dir_all(without.parent().unwrap());
+}
+
+#[test]
+#[ignore = "writes to the user's registry; run with --ignored"]
+fn new_instances_are_started_as_processes_of_their_own() {
+	let handler = std::env::current_exe().unwrap();
+	let association = TempAssociation::register(&handler, "new");
+	let file = sample_file("new-instance", &association.extension);
+	let opener = WindowsFileOpener::default();
+	let stub = opener
+		.get_apps_for_file(&file)
+		.unwrap()
+		.into_iter()
+		.find(is_stub)
+		.expect("the stub isn't listed");
+
+	// Started straight from its executable, so the arguments the ProgID's
+	// command would add have to be passed here.
+	let spec = LaunchSpec {
+		args: ["stub_handler", "--exact", "--nocapture", "verb-new"]
+			.map(String::from)
+			.to_vec(),
+		new_instance: true,
+		..LaunchSpec::new(&file, &stub.id)
+	};
+	assert!(matches!(
+		opener.launch(&spec),
+		Ok(OpenResult::Success { pid: Some(_) })
+	));
+	assert_eq!(wait_for_launch(&file), "new");
+
+	let _ = fs::remove_dir_all(file.parent().unwrap());
+}
+
+#[test]
+#[ignore = "writes to the user's registry; run with --ignored"]
+fn names_that_are_not_unicode_reach_the_app_intact() {
+	let handler = std::env::current_exe().unwrap();
+	let association = TempAssociation::register(&handler, "wide");
+	let sample = sample_file("wide-name", &association.extension);
+	// `sample\u{D800}.sdtest…`: a lone surrogate, which NTFS allows but UTF-8
+	// can't hold. Any lossy conversion on the way names a file that isn't
+	// there, and the stub never writes its sentinel.
+	let mut name = OsString::from_wide(&[0x73, 0xD800]);
+	name.push(&association.extension);
+	let file = sample.with_file_name(name);
+	fs::write(&file, b"").unwrap();
+	let opener = WindowsFileOpener::default();
+	let stub = opener
+		.get_apps_for_file(&file)
+		.unwrap()
+		.into_iter()
+		.find(is_stub)
+		.expect("the stub isn't listed");
+
+	assert!(matches!(
+		opener.open_with_default(&file),
+		Ok(OpenResult::Success { .. })
+	));
+	wait_for_launch(&file);
+
+	assert!(matches!(
+		opener.open_with_app(&file, &stub.id),
+		Ok(OpenResult::Success { .. })
+	));
+	wait_for_launch(&file);
+
+	let spec = LaunchSpec {
+		args: ["stub_handler", "--exact", "--nocapture"]
+			.map(String::from)
+			.to_vec(),
+		new_instance: true,
+		..LaunchSpec::new(&file, &stub.id)
+	};
+	assert!(matches!(
+		opener.launch(&spec),
+		Ok(OpenResult::Success { .. })
+	));
+	wait_for_launch(&file);
+
+	let _ = fs::remove_dir_all(file.parent().unwrap());
+}
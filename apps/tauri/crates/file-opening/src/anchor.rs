@@ -0,0 +1,309 @@
+//! # Opening at a location
+//!
+//! Editors and viewers that can jump to a line, page or fragment each take
+//! it on their command line in a convention of their own, and nothing about
+//! an association says which. [`FORMATS`] lists the ones Spacedrive knows,
+//! keyed by executable name, so "jump to match" can open search results in
+//! place; any other app gets the file as usual.
+//!
+//! Some conventions name the file as part of the anchor (`--goto
+//! notes.txt:12`, or a `file:` URL with a fragment for browsers), so the
+//! anchor replaces the file on the command line instead of preceding it.
+
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// A file and where in it to open
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenTarget {
+	pub path: PathBuf,
+	/// 1-based line, for editors
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub line: Option<u32>,
+	/// 1-based page, for document viewers
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub page: Option<u32>,
+	/// URL fragment, for browsers, without the `#`
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub fragment: Option<String>,
+}
+
+impl OpenTarget {
+	/// `path` with no location, which opens like any other file.
+	pub fn new(path: impl Into<PathBuf>) -> Self {
+		Self {
+			path: path.into(),
+			..Self::default()
+		}
+	}
+}
+
+/// What an app's command line can carry a location as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+	Line,
+	Page,
+	Fragment,
+}
+
+/// How one family of apps takes an [`Anchor`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnchorFormat {
+	/// Executable names without their extension, lowercase
+	pub programs: &'static [&'static str],
+	pub anchor: Anchor,
+	/// Arguments with `{file}`, `{line}`, `{page}`, `{fragment}` and `{url}`
+	/// (a `file:` URL with the fragment) filled in
+	pub args: &'static [&'static str],
+	/// Whether `args` name the file themselves, rather than going before it
+	pub replaces_file: bool,
+}
+
+/// The apps whose anchors are known. The first format for a program whose
+/// anchor the target has is used.
+pub const FORMATS: &[AnchorFormat] = &[
+	AnchorFormat {
+		programs: &["code", "code - insiders", "codium", "vscodium", "cursor"],
+		anchor: Anchor::Line,
+		args: &["--goto", "{file}:{line}"],
+		replaces_file: true,
+	},
+	AnchorFormat {
+		programs: &["sublime_text", "subl"],
+		anchor: Anchor::Line,
+		args: &["{file}:{line}"],
+		replaces_file: true,
+	},
+	AnchorFormat {
+		programs: &["notepad++"],
+		anchor: Anchor::Line,
+		args: &["-n{line}"],
+		replaces_file: false,
+	},
+	AnchorFormat {
+		programs: &["kate", "kwrite"],
+		anchor: Anchor::Line,
+		args: &["--line", "{line}"],
+		replaces_file: false,
+	},
+	AnchorFormat {
+		programs: &["gedit", "gnome-text-editor", "vim", "gvim", "nvim"],
+		anchor: Anchor::Line,
+		args: &["+{line}"],
+		replaces_file: false,
+	},
+	AnchorFormat {
+		programs: &["acrord32", "acrobat"],
+		anchor: Anchor::Page,
+		args: &["/A", "page={page}"],
+		replaces_file: false,
+	},
+	AnchorFormat {
+		programs: &["sumatrapdf"],
+		anchor: Anchor::Page,
+		args: &["-page", "{page}"],
+		replaces_file: false,
+	},
+	AnchorFormat {
+		programs: &["okular"],
+		anchor: Anchor::Page,
+		args: &["--page", "{page}"],
+		replaces_file: false,
+	},
+	AnchorFormat {
+		programs: &["evince"],
+		anchor: Anchor::Page,
+		args: &["--page-index={page}"],
+		replaces_file: false,
+	},
+	AnchorFormat {
+		programs: &[
+			"chrome",
+			"google-chrome",
+			"chromium",
+			"msedge",
+			"microsoft-edge",
+			"firefox",
+			"brave",
+			"vivaldi",
+			"opera",
+		],
+		anchor: Anchor::Fragment,
+		args: &["{url}"],
+		replaces_file: true,
+	},
+];
+
+/// The command line that opens `target` at its location in the app `exe`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnchorArgs {
+	pub args: Vec<String>,
+	/// Whether `args` are the whole command line, the file included
+	pub replaces_file: bool,
+}
+
+/// The arguments that open `target` at its location in `exe`, from
+/// [`FORMATS`]. `None` when the app isn't listed or takes no location the
+/// target has, in which case it's opened without one.
+pub fn anchor_args(exe: &Path, target: &OpenTarget) -> Option<AnchorArgs> {
+	let program = exe.file_stem()?.to_str()?.to_lowercase();
+
+	FORMATS
+		.iter()
+		.filter(|format| format.programs.contains(&program.as_str()))
+		.find_map(|format| {
+			let value = match format.anchor {
+				Anchor::Line => target.line?.to_string(),
+				Anchor::Page => target.page?.to_string(),
+				Anchor::Fragment => target
+					.fragment
+					.as_deref()?
+					.trim_start_matches('#')
+					.to_string(),
+			};
+			let args = format
+				.args
+				.iter()
+				.map(|arg| fill(arg, &target.path, format.anchor, &value))
+				.collect();
+			Some(AnchorArgs {
+				args,
+				replaces_file: format.replaces_file,
+			})
+		})
+}
+
+/// `template` with its placeholders replaced.
+fn fill(template: &str, path: &Path, anchor: Anchor, value: &str) -> String {
+	let placeholder = match anchor {
+		Anchor::Line => "{line}",
+		Anchor::Page => "{page}",
+		Anchor::Fragment => "{fragment}",
+	};
+	let mut filled = template.replace(placeholder, value);
+	if filled.contains("{url}") {
+		let url = format!("{}#{value}", file_url(path));
+		filled = filled.replace("{url}", &url);
+	}
+	filled.replace("{file}", &path.to_string_lossy())
+}
+
+/// `path` as a `file:` URL, percent-encoded, e.g. `file:///C:/My%20Notes.html`.
+fn file_url(path: &Path) -> String {
+	let path = path.to_string_lossy().replace('\\', "/");
+	let (prefix, rest) = match path.strip_prefix("//") {
+		// A UNC path's server becomes the URL's host.
+		Some(unc) => ("file://", unc),
+		None if path.starts_with('/') => ("file://", path.as_str()),
+		None => ("file:///", path.as_str()),
+	};
+
+	let mut url = prefix.to_string();
+	for byte in rest.bytes() {
+		if byte.is_ascii_alphanumeric() || b"-._~/:".contains(&byte) {
+			url.push(byte as char);
+		} else {
+			let _ = write!(url, "%{byte:02X}");
+		}
+	}
+	url
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn target(path: &str) -> OpenTarget {
+		OpenTarget::new(path)
+	}
+
+	#[test]
+	fn known_apps_get_their_own_convention() {
+		let at_line = OpenTarget {
+			line: Some(12),
+			..target("/src/main.rs")
+		};
+		assert_eq!(
+			anchor_args(Path::new("/usr/bin/code"), &at_line),
+			Some(AnchorArgs {
+				args: vec!["--goto".to_string(), "/src/main.rs:12".to_string()],
+				replaces_file: true,
+			})
+		);
+		assert_eq!(
+			anchor_args(Path::new("/opt/npp/Notepad++.exe"), &at_line).map(|a| a.args),
+			Some(vec!["-n12".to_string()])
+		);
+
+		let at_page = OpenTarget {
+			page: Some(3),
+			..target("/docs/manual.pdf")
+		};
+		assert_eq!(
+			anchor_args(Path::new("/opt/Acrobat/AcroRd32.exe"), &at_page),
+			Some(AnchorArgs {
+				args: vec!["/A".to_string(), "page=3".to_string()],
+				replaces_file: false,
+			})
+		);
+
+		let at_fragment = OpenTarget {
+			fragment: Some("#usage".to_string()),
+			..target("/docs/read me.html")
+		};
+		assert_eq!(
+			anchor_args(Path::new("/usr/bin/firefox"), &at_fragment).map(|a| a.args),
+			Some(vec!["file:///docs/read%20me.html#usage".to_string()])
+		);
+	}
+
+	#[test]
+	fn anchors_an_app_cannot_take_are_ignored() {
+		let at_page = OpenTarget {
+			page: Some(3),
+			..target("/docs/manual.pdf")
+		};
+		// An editor, but the target only has a page.
+		assert_eq!(anchor_args(Path::new("/usr/bin/code"), &at_page), None);
+		assert_eq!(
+			anchor_args(Path::new("/usr/bin/sd-unknown"), &at_page),
+			None
+		);
+		assert_eq!(
+			anchor_args(Path::new("/usr/bin/okular"), &target("/docs/manual.pdf")),
+			None
+		);
+	}
+
+	#[test]
+	fn every_format_fills_its_placeholders() {
+		let everything = OpenTarget {
+			line: Some(1),
+			page: Some(1),
+			fragment: Some("top".to_string()),
+			..target("/notes.txt")
+		};
+		for format in FORMATS {
+			for program in format.programs {
+				assert_eq!(program.to_lowercase(), *program);
+				let args = anchor_args(Path::new(program), &everything).unwrap();
+				assert!(args.args.iter().all(|arg| !arg.contains('{')), "{program}");
+			}
+		}
+	}
+
+	#[test]
+	fn file_urls_are_percent_encoded() {
+		assert_eq!(
+			file_url(Path::new(r"C:\My Notes\a#b.html")),
+			"file:///C:/My%20Notes/a%23b.html"
+		);
+		assert_eq!(
+			file_url(Path::new(r"\\server\share\x.html")),
+			"file://server/share/x.html"
+		);
+		assert_eq!(file_url(Path::new("/tmp/x.html")), "file:///tmp/x.html");
+	}
+}
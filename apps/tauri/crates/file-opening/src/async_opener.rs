@@ -0,0 +1,919 @@
+//! # Async adapters
+//!
+//! Every backend blocks: Windows walks COM enumerators, Linux scans the XDG
+//! directories, macOS waits on Launch Services. [`AsyncFileOpener`] moves that
+//! work onto tokio's blocking pool so async command handlers stay responsive.
+//!
+//! A blocking worker can be any pool thread, so backends set up per-thread
+//! state (the COM apartment on Windows) inside each call rather than when the
+//! opener is constructed. Backend caches that are per thread, like the Windows
+//! handler cache, warm up separately on each pool thread.
+//!
+//! ## Example
+//! ```rust,ignore
+//! use file_opening::{AsyncFileOpener, FileOpener};
+//! use std::sync::Arc;
+//!
+//! let opener: Arc<dyn FileOpener> = Arc::new(PlatformOpener);
+//! let apps = opener.get_apps_for_file(path).await?;
+//! ```
+
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{
+	CancellationToken, DefaultedOpen, ExtensionlessFiles, FileAssociation, FileOpenError,
+	FileOpener, LaunchSpec, OpenResult, OpenTarget, OpenWithApp, ProcessOutcome, TimedFileOpener,
+	UriPolicy,
+};
+
+/// Extensions looked up per blocking task by `list_associations`.
+const ASSOCIATION_CHUNK: usize = 32;
+
+/// Async counterparts of the [`FileOpener`] methods, implemented for any
+/// `Arc`-shared opener.
+pub trait AsyncFileOpener {
+	fn get_apps_for_file(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<Vec<OpenWithApp>, FileOpenError>> + Send;
+
+	fn get_all_apps_for_file(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<Vec<OpenWithApp>, FileOpenError>> + Send;
+
+	/// Dropping the future doesn't stop the listing; cancelling `cancel` does.
+	fn get_apps_for_file_cancellable(
+		&self,
+		path: PathBuf,
+		cancel: CancellationToken,
+	) -> impl Future<Output = Result<Vec<OpenWithApp>, FileOpenError>> + Send;
+
+	fn get_all_apps_for_file_cancellable(
+		&self,
+		path: PathBuf,
+		cancel: CancellationToken,
+	) -> impl Future<Output = Result<Vec<OpenWithApp>, FileOpenError>> + Send;
+
+	fn count_apps_for_file(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<usize, FileOpenError>> + Send;
+
+	fn count_apps_for_file_up_to(
+		&self,
+		path: PathBuf,
+		cap: usize,
+	) -> impl Future<Output = Result<usize, FileOpenError>> + Send;
+
+	fn get_apps_for_file_paged(
+		&self,
+		path: PathBuf,
+		offset: usize,
+		limit: usize,
+	) -> impl Future<Output = Result<(Vec<OpenWithApp>, usize), FileOpenError>> + Send;
+
+	fn get_apps_for_files(
+		&self,
+		paths: Vec<PathBuf>,
+	) -> impl Future<Output = Result<Vec<OpenWithApp>, FileOpenError>> + Send;
+
+	fn get_common_apps(
+		&self,
+		paths: Vec<PathBuf>,
+		extensionless: ExtensionlessFiles,
+	) -> impl Future<Output = Result<Vec<OpenWithApp>, FileOpenError>> + Send;
+
+	fn get_apps_for_mime_type(
+		&self,
+		mime: String,
+	) -> impl Future<Output = Result<Vec<OpenWithApp>, FileOpenError>> + Send;
+
+	/// Built from `list_association_extensions` and `get_association`, a
+	/// chunk of extensions per blocking task, so a long table doesn't tie up
+	/// a blocking thread for its whole length.
+	fn list_associations(
+		&self,
+	) -> impl Future<Output = Result<Vec<FileAssociation>, FileOpenError>> + Send;
+
+	fn get_default_app(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<Option<OpenWithApp>, FileOpenError>> + Send;
+
+	fn can_open(&self, path: PathBuf) -> impl Future<Output = Result<bool, FileOpenError>> + Send;
+
+	fn recent_apps_for(
+		&self,
+		extension: String,
+		limit: usize,
+	) -> impl Future<Output = Result<Vec<OpenWithApp>, FileOpenError>> + Send;
+
+	fn open_with_default(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn is_risky(&self, path: PathBuf) -> impl Future<Output = Result<bool, FileOpenError>> + Send;
+
+	fn open_elevated(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn print(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn can_print(&self, path: PathBuf) -> impl Future<Output = Result<bool, FileOpenError>> + Send;
+
+	fn edit(&self, path: PathBuf)
+		-> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn can_edit(&self, path: PathBuf) -> impl Future<Output = Result<bool, FileOpenError>> + Send;
+
+	fn open_with_verb(
+		&self,
+		path: PathBuf,
+		verb: String,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn list_verbs(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<Vec<String>, FileOpenError>> + Send;
+
+	fn open_many_with_default(
+		&self,
+		paths: Vec<PathBuf>,
+	) -> impl Future<Output = Result<Vec<OpenResult>, FileOpenError>> + Send;
+
+	fn open_with_app(
+		&self,
+		path: PathBuf,
+		app_id: String,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn open_with_app_and_set_default(
+		&self,
+		path: PathBuf,
+		app_id: String,
+	) -> impl Future<Output = Result<DefaultedOpen, FileOpenError>> + Send;
+
+	/// See [`TimedFileOpener`]. The wait itself occupies a blocking thread.
+	fn open_with_default_timeout(
+		&self,
+		path: PathBuf,
+		timeout: Duration,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn open_with_app_timeout(
+		&self,
+		path: PathBuf,
+		app_id: String,
+		timeout: Duration,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn open_with_app_args(
+		&self,
+		path: PathBuf,
+		app_id: String,
+		args: Vec<String>,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn open_with_executable(
+		&self,
+		path: PathBuf,
+		exe: PathBuf,
+		args: Vec<String>,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn open_and_wait(
+		&self,
+		path: PathBuf,
+		app_id: Option<String>,
+	) -> impl Future<Output = Result<ProcessOutcome, FileOpenError>> + Send;
+
+	fn launch_app(
+		&self,
+		app_id: String,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn launch_app_with_args(
+		&self,
+		app_id: String,
+		args: Vec<String>,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn open_at(
+		&self,
+		target: OpenTarget,
+		app_id: String,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn open_shell_item(
+		&self,
+		parsing_name: String,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn launch(
+		&self,
+		spec: LaunchSpec,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn open_with_default_or_prompt(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn open_readonly(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn open_bytes(
+		&self,
+		bytes: Vec<u8>,
+		suggested_name: String,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn cleanup_temp_files(&self) -> impl Future<Output = Result<usize, FileOpenError>> + Send;
+
+	fn open_with_dialog(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn open_files_with_app(
+		&self,
+		paths: Vec<PathBuf>,
+		app_id: String,
+	) -> impl Future<Output = Result<Vec<OpenResult>, FileOpenError>> + Send;
+
+	fn open_many_with_app(
+		&self,
+		paths: Vec<PathBuf>,
+		app_id: String,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn open_uri(
+		&self,
+		uri: String,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn open_uri_with_policy(
+		&self,
+		uri: String,
+		policy: UriPolicy,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn reveal(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn reveal_many(
+		&self,
+		paths: Vec<PathBuf>,
+	) -> impl Future<Output = Result<Vec<OpenResult>, FileOpenError>> + Send;
+
+	fn move_to_trash(
+		&self,
+		paths: Vec<PathBuf>,
+	) -> impl Future<Output = Result<Vec<OpenResult>, FileOpenError>> + Send;
+
+	fn resolve_shortcut(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<Option<PathBuf>, FileOpenError>> + Send;
+
+	fn set_default_app(
+		&self,
+		extension: String,
+		app_id: String,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn show_properties(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn open_mail_with_attachment(
+		&self,
+		attachments: Vec<PathBuf>,
+		subject: Option<String>,
+		body: Option<String>,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send;
+
+	fn get_thumbnail(
+		&self,
+		path: PathBuf,
+		size: u32,
+	) -> impl Future<Output = Result<Option<Vec<u8>>, FileOpenError>> + Send;
+
+	fn get_thumbnail_timeout(
+		&self,
+		path: PathBuf,
+		size: u32,
+		timeout: Duration,
+	) -> impl Future<Output = Result<Option<Vec<u8>>, FileOpenError>> + Send;
+}
+
+// Calls go through `FileOpener::method(&*opener, ..)` because method syntax on
+// the `Arc` would resolve back to this trait, and likewise for the
+// `TimedFileOpener` methods that share names with this trait's.
+impl<T: FileOpener + ?Sized + 'static> AsyncFileOpener for Arc<T> {
+	fn get_apps_for_file(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<Vec<OpenWithApp>, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::get_apps_for_file(&*opener, &path))
+	}
+
+	fn get_all_apps_for_file(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<Vec<OpenWithApp>, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::get_all_apps_for_file(&*opener, &path))
+	}
+
+	fn get_apps_for_file_cancellable(
+		&self,
+		path: PathBuf,
+		cancel: CancellationToken,
+	) -> impl Future<Output = Result<Vec<OpenWithApp>, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::get_apps_for_file_cancellable(&*opener, &path, &cancel))
+	}
+
+	fn get_all_apps_for_file_cancellable(
+		&self,
+		path: PathBuf,
+		cancel: CancellationToken,
+	) -> impl Future<Output = Result<Vec<OpenWithApp>, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || {
+			FileOpener::get_all_apps_for_file_cancellable(&*opener, &path, &cancel)
+		})
+	}
+
+	fn count_apps_for_file(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<usize, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::count_apps_for_file(&*opener, &path))
+	}
+
+	fn count_apps_for_file_up_to(
+		&self,
+		path: PathBuf,
+		cap: usize,
+	) -> impl Future<Output = Result<usize, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::count_apps_for_file_up_to(&*opener, &path, cap))
+	}
+
+	fn get_apps_for_file_paged(
+		&self,
+		path: PathBuf,
+		offset: usize,
+		limit: usize,
+	) -> impl Future<Output = Result<(Vec<OpenWithApp>, usize), FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::get_apps_for_file_paged(&*opener, &path, offset, limit))
+	}
+
+	fn get_apps_for_files(
+		&self,
+		paths: Vec<PathBuf>,
+	) -> impl Future<Output = Result<Vec<OpenWithApp>, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::get_apps_for_files(&*opener, &paths))
+	}
+
+	fn get_common_apps(
+		&self,
+		paths: Vec<PathBuf>,
+		extensionless: ExtensionlessFiles,
+	) -> impl Future<Output = Result<Vec<OpenWithApp>, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || {
+			let paths = paths.iter().map(PathBuf::as_path).collect::<Vec<_>>();
+			FileOpener::get_common_apps(&*opener, &paths, extensionless)
+		})
+	}
+
+	fn get_apps_for_mime_type(
+		&self,
+		mime: String,
+	) -> impl Future<Output = Result<Vec<OpenWithApp>, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::get_apps_for_mime_type(&*opener, &mime))
+	}
+
+	fn list_associations(
+		&self,
+	) -> impl Future<Output = Result<Vec<FileAssociation>, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+
+		async move {
+			let extensions = {
+				let opener = Arc::clone(&opener);
+				run_blocking(move || FileOpener::list_association_extensions(&*opener)).await?
+			};
+
+			let mut associations = Vec::with_capacity(extensions.len());
+			for chunk in extensions.chunks(ASSOCIATION_CHUNK) {
+				let opener = Arc::clone(&opener);
+				let chunk = chunk.to_vec();
+				associations.extend(
+					run_blocking(move || {
+						chunk
+							.iter()
+							.map(|extension| FileOpener::get_association(&*opener, extension))
+							.collect::<Result<Vec<_>, _>>()
+					})
+					.await?,
+				);
+			}
+
+			Ok(associations)
+		}
+	}
+
+	fn get_default_app(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<Option<OpenWithApp>, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::get_default_app(&*opener, &path))
+	}
+
+	fn can_open(&self, path: PathBuf) -> impl Future<Output = Result<bool, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::can_open(&*opener, &path))
+	}
+
+	fn recent_apps_for(
+		&self,
+		extension: String,
+		limit: usize,
+	) -> impl Future<Output = Result<Vec<OpenWithApp>, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::recent_apps_for(&*opener, &extension, limit))
+	}
+
+	fn open_with_default(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::open_with_default(&*opener, &path))
+	}
+
+	fn is_risky(&self, path: PathBuf) -> impl Future<Output = Result<bool, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::is_risky(&*opener, &path))
+	}
+
+	fn open_elevated(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::open_elevated(&*opener, &path))
+	}
+
+	fn print(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::print(&*opener, &path))
+	}
+
+	fn can_print(&self, path: PathBuf) -> impl Future<Output = Result<bool, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::can_print(&*opener, &path))
+	}
+
+	fn edit(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::edit(&*opener, &path))
+	}
+
+	fn can_edit(&self, path: PathBuf) -> impl Future<Output = Result<bool, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::can_edit(&*opener, &path))
+	}
+
+	fn open_with_verb(
+		&self,
+		path: PathBuf,
+		verb: String,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::open_with_verb(&*opener, &path, &verb))
+	}
+
+	fn list_verbs(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<Vec<String>, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::list_verbs(&*opener, &path))
+	}
+
+	fn open_many_with_default(
+		&self,
+		paths: Vec<PathBuf>,
+	) -> impl Future<Output = Result<Vec<OpenResult>, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || {
+			let paths = paths.iter().map(PathBuf::as_path).collect::<Vec<_>>();
+			FileOpener::open_many_with_default(&*opener, &paths)
+		})
+	}
+
+	fn open_with_app(
+		&self,
+		path: PathBuf,
+		app_id: String,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::open_with_app(&*opener, &path, &app_id))
+	}
+
+	fn open_with_app_and_set_default(
+		&self,
+		path: PathBuf,
+		app_id: String,
+	) -> impl Future<Output = Result<DefaultedOpen, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::open_with_app_and_set_default(&*opener, &path, &app_id))
+	}
+
+	fn open_with_default_timeout(
+		&self,
+		path: PathBuf,
+		timeout: Duration,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || TimedFileOpener::open_with_default_timeout(&opener, &path, timeout))
+	}
+
+	fn open_with_app_timeout(
+		&self,
+		path: PathBuf,
+		app_id: String,
+		timeout: Duration,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || {
+			TimedFileOpener::open_with_app_timeout(&opener, &path, &app_id, timeout)
+		})
+	}
+
+	fn open_with_app_args(
+		&self,
+		path: PathBuf,
+		app_id: String,
+		args: Vec<String>,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || {
+			let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+			FileOpener::open_with_app_args(&*opener, &path, &app_id, &args)
+		})
+	}
+
+	fn open_with_executable(
+		&self,
+		path: PathBuf,
+		exe: PathBuf,
+		args: Vec<String>,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || {
+			let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+			FileOpener::open_with_executable(&*opener, &path, &exe, &args)
+		})
+	}
+
+	/// Holds a blocking-pool thread until the process exits.
+	fn open_and_wait(
+		&self,
+		path: PathBuf,
+		app_id: Option<String>,
+	) -> impl Future<Output = Result<ProcessOutcome, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::open_and_wait(&*opener, &path, app_id.as_deref()))
+	}
+
+	fn launch_app(
+		&self,
+		app_id: String,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::launch_app(&*opener, &app_id))
+	}
+
+	fn launch_app_with_args(
+		&self,
+		app_id: String,
+		args: Vec<String>,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || {
+			let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+			FileOpener::launch_app_with_args(&*opener, &app_id, &args)
+		})
+	}
+
+	fn open_at(
+		&self,
+		target: OpenTarget,
+		app_id: String,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::open_at(&*opener, &target, &app_id))
+	}
+
+	fn open_shell_item(
+		&self,
+		parsing_name: String,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::open_shell_item(&*opener, &parsing_name))
+	}
+
+	fn launch(
+		&self,
+		spec: LaunchSpec,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::launch(&*opener, &spec))
+	}
+
+	fn open_with_default_or_prompt(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::open_with_default_or_prompt(&*opener, &path))
+	}
+
+	fn open_readonly(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::open_readonly(&*opener, &path))
+	}
+
+	fn open_bytes(
+		&self,
+		bytes: Vec<u8>,
+		suggested_name: String,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::open_bytes(&*opener, &bytes, &suggested_name))
+	}
+
+	fn cleanup_temp_files(&self) -> impl Future<Output = Result<usize, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || Ok(FileOpener::cleanup_temp_files(&*opener)))
+	}
+
+	fn open_with_dialog(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::open_with_dialog(&*opener, &path))
+	}
+
+	fn open_files_with_app(
+		&self,
+		paths: Vec<PathBuf>,
+		app_id: String,
+	) -> impl Future<Output = Result<Vec<OpenResult>, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::open_files_with_app(&*opener, &paths, &app_id))
+	}
+
+	fn open_many_with_app(
+		&self,
+		paths: Vec<PathBuf>,
+		app_id: String,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || {
+			let paths = paths.iter().map(PathBuf::as_path).collect::<Vec<_>>();
+			FileOpener::open_many_with_app(&*opener, &paths, &app_id)
+		})
+	}
+
+	fn open_uri(
+		&self,
+		uri: String,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::open_uri(&*opener, &uri))
+	}
+
+	fn open_uri_with_policy(
+		&self,
+		uri: String,
+		policy: UriPolicy,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::open_uri_with_policy(&*opener, &uri, &policy))
+	}
+
+	fn reveal(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::reveal(&*opener, &path))
+	}
+
+	fn reveal_many(
+		&self,
+		paths: Vec<PathBuf>,
+	) -> impl Future<Output = Result<Vec<OpenResult>, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || {
+			let paths = paths.iter().map(PathBuf::as_path).collect::<Vec<_>>();
+			FileOpener::reveal_many(&*opener, &paths)
+		})
+	}
+
+	fn move_to_trash(
+		&self,
+		paths: Vec<PathBuf>,
+	) -> impl Future<Output = Result<Vec<OpenResult>, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || {
+			let paths = paths.iter().map(PathBuf::as_path).collect::<Vec<_>>();
+			FileOpener::move_to_trash(&*opener, &paths)
+		})
+	}
+
+	fn resolve_shortcut(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<Option<PathBuf>, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::resolve_shortcut(&*opener, &path))
+	}
+
+	fn set_default_app(
+		&self,
+		extension: String,
+		app_id: String,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::set_default_app(&*opener, &extension, &app_id))
+	}
+
+	fn show_properties(
+		&self,
+		path: PathBuf,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::show_properties(&*opener, &path))
+	}
+
+	fn open_mail_with_attachment(
+		&self,
+		attachments: Vec<PathBuf>,
+		subject: Option<String>,
+		body: Option<String>,
+	) -> impl Future<Output = Result<OpenResult, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || {
+			let attachments = attachments.iter().map(PathBuf::as_path).collect::<Vec<_>>();
+			FileOpener::open_mail_with_attachment(
+				&*opener,
+				&attachments,
+				subject.as_deref(),
+				body.as_deref(),
+			)
+		})
+	}
+
+	fn get_thumbnail(
+		&self,
+		path: PathBuf,
+		size: u32,
+	) -> impl Future<Output = Result<Option<Vec<u8>>, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || FileOpener::get_thumbnail(&*opener, &path, size))
+	}
+
+	fn get_thumbnail_timeout(
+		&self,
+		path: PathBuf,
+		size: u32,
+		timeout: Duration,
+	) -> impl Future<Output = Result<Option<Vec<u8>>, FileOpenError>> + Send {
+		let opener = Arc::clone(self);
+		run_blocking(move || TimedFileOpener::get_thumbnail_timeout(&opener, &path, size, timeout))
+	}
+}
+
+async fn run_blocking<R, F>(work: F) -> Result<R, FileOpenError>
+where
+	R: Send + 'static,
+	F: FnOnce() -> Result<R, FileOpenError> + Send + 'static,
+{
+	tokio::task::spawn_blocking(work)
+		.await
+		.map_err(|e| FileOpenError::Other(format!("file opening task failed: {e}")))?
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::path::Path;
+	use std::thread;
+
+	/// Reports the id of the thread each call ran on through the app name.
+	struct ThreadReporter;
+
+	impl FileOpener for ThreadReporter {
+		fn get_apps_for_file(&self, _path: &Path) -> Result<Vec<OpenWithApp>, FileOpenError> {
+			Ok(vec![OpenWithApp::new(
+				"reporter",
+				format!("{:?}", thread::current().id()),
+			)])
+		}
+
+		fn get_default_app(&self, _path: &Path) -> Result<Option<OpenWithApp>, FileOpenError> {
+			Ok(None)
+		}
+
+		fn open_with_default(&self, _path: &Path) -> Result<OpenResult, FileOpenError> {
+			Err(FileOpenError::NoExtension)
+		}
+
+		fn open_with_app(&self, _path: &Path, _app_id: &str) -> Result<OpenResult, FileOpenError> {
+			Ok(OpenResult::success())
+		}
+
+		fn reveal(&self, _path: &Path) -> Result<OpenResult, FileOpenError> {
+			Ok(OpenResult::success())
+		}
+
+		fn list_association_extensions(&self) -> Result<Vec<String>, FileOpenError> {
+			Ok((0..70).map(|i| format!("EXT{i}")).collect())
+		}
+	}
+
+	#[tokio::test]
+	async fn runs_on_blocking_pool() {
+		let opener: Arc<dyn FileOpener> = Arc::new(ThreadReporter);
+
+		let apps = opener
+			.get_apps_for_file(PathBuf::from("a.txt"))
+			.await
+			.unwrap();
+		assert_ne!(apps[0].name, format!("{:?}", thread::current().id()));
+	}
+
+	#[tokio::test]
+	async fn forwards_errors() {
+		let opener = Arc::new(ThreadReporter);
+
+		assert!(matches!(
+			opener.open_with_default(PathBuf::from("a.txt")).await,
+			Err(FileOpenError::NoExtension)
+		));
+	}
+
+	#[tokio::test]
+	async fn lists_associations_across_chunks() {
+		let opener = Arc::new(ThreadReporter);
+
+		let associations = AsyncFileOpener::list_associations(&opener).await.unwrap();
+
+		assert_eq!(associations.len(), 70);
+		assert_eq!(associations[0].extension, ".ext0");
+		assert_eq!(associations[69].extension, ".ext69");
+		assert!(associations.iter().all(
+			|association| association.default_app.is_none() && association.all_apps.len() == 1
+		));
+	}
+}
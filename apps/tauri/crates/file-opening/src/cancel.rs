@@ -0,0 +1,60 @@
+//! # Cancellation
+//!
+//! Enumerating handlers and extracting their icons can take long enough that
+//! a file browser has moved on to another selection by the time it's done.
+//! Dropping an async call doesn't stop the blocking work behind it, so stale
+//! requests are cancelled through a token the work checks as it goes.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::FileOpenError;
+
+/// A flag shared between a caller and the work it may abandon.
+///
+/// Clones share the flag, so the caller keeps one and hands the other to the
+/// `*_cancellable` methods of [`FileOpener`](crate::FileOpener).
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Asks work holding this token to stop at its next check.
+	pub fn cancel(&self) {
+		self.0.store(true, Ordering::Release);
+	}
+
+	pub fn is_cancelled(&self) -> bool {
+		self.0.load(Ordering::Acquire)
+	}
+
+	/// [`FileOpenError::Cancelled`] once the token has been cancelled.
+	pub fn check(&self) -> Result<(), FileOpenError> {
+		if self.is_cancelled() {
+			return Err(FileOpenError::Cancelled);
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn clones_share_cancellation() {
+		let token = CancellationToken::new();
+		let held_by_work = token.clone();
+		assert!(held_by_work.check().is_ok());
+
+		token.cancel();
+		assert!(held_by_work.is_cancelled());
+		assert!(matches!(
+			held_by_work.check(),
+			Err(FileOpenError::Cancelled)
+		));
+	}
+}
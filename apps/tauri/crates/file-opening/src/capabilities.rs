@@ -0,0 +1,52 @@
+//! # Capabilities
+//!
+//! Backends differ in what they can do beyond opening and listing, and a menu
+//! built from a fixed list would offer actions that only come back as
+//! [`OpenResult::Unsupported`](crate::OpenResult::Unsupported). Asking the
+//! opener up front lets the caller leave them out instead.
+
+use serde::{Deserialize, Serialize};
+
+/// Which optional operations an opener performs, from
+/// [`FileOpener::capabilities`](crate::FileOpener::capabilities)
+///
+/// `false` means the operation is unsupported or always empty, whatever the
+/// file; `true` only says the backend implements it, not that every file's
+/// type has a handler for it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenerCapabilities {
+	/// `reveal` and `reveal_many`
+	pub reveal: bool,
+	/// `open_elevated`
+	pub elevated: bool,
+	/// `open_with_verb` with verbs other than `open`, and `list_verbs`
+	pub verbs: bool,
+	/// `open_with_dialog`
+	pub dialog: bool,
+	/// `open_with_executable`
+	pub executable: bool,
+	/// `launch_app` and `launch_app_with_args`
+	pub launch: bool,
+	/// `open_and_wait`
+	pub wait: bool,
+	/// `open_shell_item`
+	pub shell_items: bool,
+	/// `print` and `can_print`
+	pub print: bool,
+	/// `edit` and `can_edit`
+	pub edit: bool,
+	/// `show_properties`
+	pub properties: bool,
+	/// `OpenWithApp::icon` and `icons` are filled in
+	pub icons: bool,
+	/// `get_thumbnail`
+	pub thumbnails: bool,
+	/// `set_default_app`
+	pub set_default: bool,
+	/// `open_uri` and `open_uri_with_policy`
+	pub uri: bool,
+	/// `move_to_trash`
+	pub trash: bool,
+	/// `open_mail_with_attachment`
+	pub mail: bool,
+}
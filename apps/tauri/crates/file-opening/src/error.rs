@@ -0,0 +1,57 @@
+use thiserror::Error;
+
+/// Failure to query or launch a file handler.
+///
+/// Outcomes the user should see (missing file, missing app) are reported
+/// through [`OpenResult`](crate::OpenResult) instead; this type covers the
+/// cases where the backend itself couldn't do its job. Windows `HRESULT`s are
+/// carried as their raw `i32` so this crate stays platform-agnostic.
+#[derive(Debug, Error)]
+pub enum FileOpenError {
+	/// The file's type couldn't be determined: it has no extension, and
+	/// sniffing its content (where the backend does) didn't help.
+	#[error("file has no extension")]
+	NoExtension,
+
+	#[error("COM initialization failed (HRESULT {0:#010x})")]
+	ComInit(i32),
+
+	#[error("failed to enumerate handlers (HRESULT {0:#010x})")]
+	EnumHandlers(i32),
+
+	#[error("ShellExecute failed with code {code}")]
+	ShellExecute { code: i32 },
+
+	#[error("invalid path {path}: {reason}")]
+	InvalidPath { path: String, reason: &'static str },
+
+	/// Caught before any lookup; see [`check_app_id`](crate::check_app_id).
+	#[error("invalid app id {app_id:?}: {reason}")]
+	InvalidAppId {
+		app_id: String,
+		reason: &'static str,
+	},
+
+	#[error("refusing to open {uri}: {reason}")]
+	UriNotAllowed { uri: String, reason: &'static str },
+
+	#[error("operation was cancelled")]
+	Cancelled,
+
+	/// The backend can't do `operation` at all, on this platform or in this
+	/// build.
+	#[error("{operation} is not supported here")]
+	Unsupported { operation: String },
+
+	#[error(transparent)]
+	Io(#[from] std::io::Error),
+
+	#[error("{0}")]
+	Other(String),
+}
+
+impl From<FileOpenError> for String {
+	fn from(error: FileOpenError) -> Self {
+		error.to_string()
+	}
+}
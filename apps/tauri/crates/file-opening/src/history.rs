@@ -0,0 +1,187 @@
+//! # Open With history
+//!
+//! The "Open With" menu puts the apps a type was recently opened with first.
+//! Backends record every successful `open_with_app` after the launch has
+//! returned, so keeping the history never delays it. Like
+//! [`DefaultOverrides`](crate::DefaultOverrides) it only lives in the
+//! opener: the caller saves [`OpenHistory::entries`] and hands them back to
+//! [`OpenHistory::restore`] on startup.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::OpenResult;
+
+/// Apps remembered per extension; more than a menu section would show.
+const PER_EXTENSION: usize = 16;
+
+/// One app a type was opened with, as saved and restored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+	/// Lowercased, without the leading dot.
+	pub extension: String,
+	pub app_id: String,
+	/// When it was last used, in milliseconds since the Unix epoch.
+	pub used_at: u64,
+}
+
+/// Which apps files were opened with, per extension, most recent first.
+#[derive(Debug, Default)]
+pub struct OpenHistory {
+	// Newest first, at most PER_EXTENSION apps each, every app once.
+	by_extension: RwLock<HashMap<String, Vec<HistoryEntry>>>,
+}
+
+impl OpenHistory {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Notes that `path` was opened with `app_id`, if `result` says it was.
+	///
+	/// Files without an extension aren't recorded, since nothing groups them.
+	pub fn record(&self, path: &Path, app_id: &str, result: &OpenResult) {
+		if !matches!(result, OpenResult::Success { .. }) {
+			return;
+		}
+		let Some(extension) = crate::mime::derive_extension(path) else {
+			return;
+		};
+
+		self.insert(HistoryEntry {
+			extension: key(&extension),
+			app_id: app_id.to_string(),
+			used_at: now(),
+		});
+	}
+
+	/// The IDs of the apps files with `extension` (with or without the dot,
+	/// matched case-insensitively) were last opened with, newest first.
+	pub fn recent(&self, extension: &str, limit: usize) -> Vec<String> {
+		self.by_extension
+			.read()
+			.unwrap_or_else(|e| e.into_inner())
+			.get(&key(extension))
+			.map(|entries| {
+				entries
+					.iter()
+					.take(limit)
+					.map(|entry| entry.app_id.clone())
+					.collect()
+			})
+			.unwrap_or_default()
+	}
+
+	/// Everything recorded, for the caller to save.
+	pub fn entries(&self) -> Vec<HistoryEntry> {
+		self.by_extension
+			.read()
+			.unwrap_or_else(|e| e.into_inner())
+			.values()
+			.flatten()
+			.cloned()
+			.collect()
+	}
+
+	/// Adds saved entries back, e.g. on startup. Where an app is both saved
+	/// and already recorded, the later use wins.
+	pub fn restore(&self, entries: impl IntoIterator<Item = HistoryEntry>) {
+		for entry in entries {
+			self.insert(HistoryEntry {
+				extension: key(&entry.extension),
+				..entry
+			});
+		}
+	}
+
+	fn insert(&self, entry: HistoryEntry) {
+		let mut by_extension = self.by_extension.write().unwrap_or_else(|e| e.into_inner());
+		let entries = by_extension.entry(entry.extension.clone()).or_default();
+
+		if let Some(known) = entries
+			.iter()
+			.position(|known| known.app_id == entry.app_id)
+		{
+			if entries[known].used_at > entry.used_at {
+				return;
+			}
+			entries.remove(known);
+		}
+
+		let at = entries
+			.iter()
+			.position(|known| known.used_at <= entry.used_at)
+			.unwrap_or(entries.len());
+		entries.insert(at, entry);
+		entries.truncate(PER_EXTENSION);
+	}
+}
+
+impl Clone for OpenHistory {
+	fn clone(&self) -> Self {
+		Self {
+			by_extension: RwLock::new(
+				self.by_extension
+					.read()
+					.unwrap_or_else(|e| e.into_inner())
+					.clone(),
+			),
+		}
+	}
+}
+
+fn key(extension: &str) -> String {
+	crate::mime::normalize_extension(extension)
+}
+
+fn now() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn entry(app_id: &str, used_at: u64) -> HistoryEntry {
+		HistoryEntry {
+			extension: "txt".to_string(),
+			app_id: app_id.to_string(),
+			used_at,
+		}
+	}
+
+	#[test]
+	fn latest_use_comes_first_and_failures_are_not_recorded() {
+		let history = OpenHistory::new();
+		history.record(Path::new("a.txt"), "notepad", &OpenResult::success());
+		history.record(Path::new("b.TXT"), "code", &OpenResult::success());
+		history.record(Path::new("c.txt"), "vim", &OpenResult::Cancelled);
+		history.record(Path::new("README"), "code", &OpenResult::success());
+
+		assert_eq!(history.recent(".txt", 10), ["code", "notepad"]);
+		assert_eq!(history.recent("txt", 1), ["code"]);
+		assert!(history.recent("md", 10).is_empty());
+	}
+
+	#[test]
+	fn restored_entries_merge_by_time() {
+		let history = OpenHistory::new();
+		history.restore([entry("notepad", 10), entry("code", 30), entry("vim", 20)]);
+		assert_eq!(history.recent("txt", 10), ["code", "vim", "notepad"]);
+
+		// An older save doesn't undo a newer use.
+		history.restore([entry("notepad", 40), entry("code", 5)]);
+		assert_eq!(history.recent("txt", 10), ["notepad", "code", "vim"]);
+
+		let saved = history.entries();
+		let reloaded = OpenHistory::new();
+		reloaded.restore(saved);
+		assert_eq!(reloaded.recent("txt", 10), ["notepad", "code", "vim"]);
+	}
+}
@@ -2,83 +2,1811 @@ use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
+#[cfg(feature = "tokio")]
+mod async_opener;
+mod cancel;
+mod capabilities;
+mod error;
+mod history;
+mod logging;
+#[cfg(feature = "mock")]
+mod mock;
+mod noop;
+mod overrides;
+mod reveal;
+mod temp;
+mod timeout;
+mod uri;
+mod watch;
+
+pub mod anchor;
+pub mod mime;
+
+pub use anchor::OpenTarget;
+#[cfg(feature = "tokio")]
+pub use async_opener::AsyncFileOpener;
+pub use cancel::CancellationToken;
+pub use capabilities::OpenerCapabilities;
+pub use error::FileOpenError;
+pub use history::{HistoryEntry, OpenHistory};
+pub use logging::{LoggedCall, LoggingFileOpener};
+#[cfg(feature = "mock")]
+pub use mock::{MockCall, MockFileOpener};
+pub use noop::NoopFileOpener;
+pub use overrides::DefaultOverrides;
+pub use reveal::{reveal_target, RevealTarget};
+pub use timeout::TimedFileOpener;
+pub use uri::UriPolicy;
+pub use watch::WatchHandle;
+
 /// Represents an application that can open a file
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OpenWithApp {
-	/// Platform-specific identifier:
+	/// Platform-specific identifier, stable across display languages and app
+	/// updates so it can be saved:
 	/// - macOS: bundle ID (com.apple.Preview)
-	/// - Windows: application name
+	/// - Windows: `exe:` and a hash of the app's executable path, else the
+	///   AUMID of a packaged app, the same for every type the app opens.
+	///   `open_with_app` also takes the app's `progid` and the handler names
+	///   earlier versions used.
 	/// - Linux: desktop entry ID (org.gnome.Evince.desktop)
 	pub id: String,
 
 	/// Human-readable display name
 	pub name: String,
 
-	/// Optional: app icon as base64-encoded PNG (for future use)
-	#[serde(skip_serializing_if = "Option::is_none")]
-	pub icon: Option<String>,
+	/// Full path to the binary the app launches.
+	///
+	/// Used for tooltips and for telling apart apps that share a name. `None`
+	/// when the platform can't resolve one, e.g. for packaged Windows apps, and
+	/// omitted from the JSON so older payloads still deserialize.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub exe_path: Option<PathBuf>,
+
+	/// Whether the platform suggests this app for the file's type.
+	///
+	/// Always `true` from `get_apps_for_file`. `get_all_apps_for_file` also
+	/// returns apps that merely registered as able to open files, with this
+	/// `false`, so the UI can list them separately. Missing from older
+	/// payloads, which only ever held recommended apps.
+	#[serde(default = "recommended_by_default")]
+	pub recommended: bool,
+
+	/// Whether this is the app `open_with_default` would launch
+	#[serde(default)]
+	pub is_default: bool,
+
+	/// How the app is installed, which decides how it gets launched.
+	#[serde(default)]
+	pub kind: AppKind,
+
+	/// What sort of app this is, as a stable lowercase id such as `browser`
+	/// or `mail`, for grouping menus; see [`group_by_category`].
+	///
+	/// Only set where the platform registers one: on Windows, apps listed
+	/// under a Default Programs client category. `None` everywhere else.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub category: Option<String>,
+
+	/// The registration the app was found through, e.g. `txtfile` or
+	/// `Applications\notepad.exe`, which unlike `name` doesn't change with
+	/// the display language.
+	///
+	/// Windows only, `None` elsewhere and where no ProgID matches the handler.
+	/// `open_with_app` accepts it as the `app_id`.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub progid: Option<String>,
+
+	/// The CLSID, in braces, of the COM object that runs the app's open
+	/// verb through `DelegateExecute`. Windows only, and `None` for apps that
+	/// are launched by command line.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub clsid: Option<String>,
+
+	/// App icon as PNG-encoded bytes.
+	///
+	/// Serialized as a base64 string so the frontend can drop it straight into
+	/// a `data:image/png;base64,` URL. `None` when the platform couldn't
+	/// extract an icon for this app.
+	#[serde(default, with = "png_base64", skip_serializing_if = "Option::is_none")]
+	pub icon: Option<Vec<u8>>,
+
+	/// The icon at every size the caller asked the backend for, smallest
+	/// first, so the UI can pick one for its DPI. `icon` is kept as the primary
+	/// size for callers that only want one.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub icons: Vec<AppIcon>,
+
+	/// The letter to underline as the app's mnemonic in an "Open With" menu,
+	/// from [`assign_accelerators`]. Backends leave it `None`.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub accelerator: Option<char>,
+}
+
+impl OpenWithApp {
+	/// An app known only by `id` and `name`, with the defaults a payload
+	/// holding just those deserializes to: recommended, not the default,
+	/// of [`AppKind::Unknown`] and nothing else set.
+	pub fn new(id: impl Into<String>, name: impl Into<String>) -> Self {
+		Self {
+			id: id.into(),
+			name: name.into(),
+			exe_path: None,
+			recommended: recommended_by_default(),
+			is_default: false,
+			kind: AppKind::default(),
+			category: None,
+			progid: None,
+			clsid: None,
+			icon: None,
+			icons: Vec::new(),
+			accelerator: None,
+		}
+	}
+}
+
+/// How an app is installed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AppKind {
+	/// A classic app launched through its executable.
+	Desktop,
+	/// A Windows UWP/MSIX app, launched by its AppUserModelID and usually
+	/// without an `exe_path`.
+	Packaged,
+	/// The backend can't tell; always the case outside Windows, and for
+	/// Windows handlers that expose neither an executable nor an AUMID.
+	#[default]
+	Unknown,
+}
+
+/// What [`FileOpener::get_common_apps`] does with selected files that have
+/// no extension.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtensionlessFiles {
+	/// Leave them out, so the rest of the selection still gets its apps.
+	#[default]
+	Ignore,
+	/// Treat them as opening with nothing, so the selection has no common
+	/// apps.
+	NoCommonApps,
+}
+
+/// One rendition of an app icon.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppIcon {
+	/// Edge length in pixels. May differ from the size asked for when the
+	/// platform only has a nearby one.
+	pub size: u32,
+
+	/// PNG-encoded bytes with transparency preserved, serialized as base64
+	/// like [`OpenWithApp::icon`].
+	#[serde(with = "png_base64::required")]
+	pub png: Vec<u8>,
+}
+
+/// One file to open in a specific app, with how to start the app
+///
+/// Backends honor what their platform's launch mechanism can carry and
+/// ignore the rest without failing:
+/// - Linux: every field. `args` are substituted into the desktop entry's
+///   `Exec=` line ahead of its file field code, and the app starts in
+///   `working_dir` with `env` added to the inherited environment.
+///   `new_instance` spawns the `Exec=` line rather than going through GLib,
+///   which D-Bus activates apps that support it into their running instance.
+/// - Windows: `args` are quoted into the command line of apps that have an
+///   executable, which start in `working_dir`, with `CreateProcessW` for
+///   `new_instance`. Packaged and COM-only handlers are started through
+///   `IAssocHandler::Invoke`, which only takes the file, so they get none of
+///   these. `env` is always ignored: apps inherit Spacedrive's. A spec that
+///   sets none of the others goes through `open_with_app`.
+/// - macOS: `args` and `env` are handed over as `open --args` would, which
+///   only reaches an app that isn't running yet, and `new_instance` as
+///   `open -n`. `working_dir` is ignored, since Launch Services starts every
+///   app in `/`.
+///
+/// A `working_dir` that isn't an existing directory fails the launch on
+/// every backend, ignored or not, so callers find out on the platform
+/// they're testing on.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LaunchSpec {
+	pub path: PathBuf,
+	pub app_id: String,
+	/// Extra arguments, which go before the file on the command line.
+	#[serde(default)]
+	pub args: Vec<String>,
+	/// Where the app starts, for apps that resolve relative paths against
+	/// their working directory. Inherited from Spacedrive when `None`.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub working_dir: Option<PathBuf>,
+	/// Variables to set for the app on top of the ones it inherits.
+	#[serde(default)]
+	pub env: HashMap<String, String>,
+	/// Start a separate instance of the app rather than handing the file to
+	/// one that's already running. Best effort: an app that forwards every
+	/// launch to its first instance still does, and handlers with no
+	/// executable of their own are launched as usual.
+	#[serde(default)]
+	pub new_instance: bool,
+}
+
+impl LaunchSpec {
+	/// Opens `path` with `app_id` with nothing else changed, as
+	/// `open_with_app` does.
+	pub fn new(path: impl Into<PathBuf>, app_id: impl Into<String>) -> Self {
+		Self {
+			path: path.into(),
+			app_id: app_id.into(),
+			..Self::default()
+		}
+	}
+
+	/// Fails with [`FileOpenError::InvalidPath`] unless `working_dir` is
+	/// unset or an existing directory.
+	pub fn check_working_dir(&self) -> Result<(), FileOpenError> {
+		let Some(dir) = &self.working_dir else {
+			return Ok(());
+		};
+
+		let reason = match std::fs::metadata(dir) {
+			Ok(metadata) if metadata.is_dir() => return Ok(()),
+			Ok(_) => "working directory is not a directory",
+			Err(_) => "working directory does not exist",
+		};
+		Err(FileOpenError::InvalidPath {
+			path: dir.to_string_lossy().to_string(),
+			reason,
+		})
+	}
+
+	/// `args` as the `&str`s [`FileOpener::open_with_app_args`] takes.
+	pub fn arg_strs(&self) -> Vec<&str> {
+		self.args.iter().map(String::as_str).collect()
+	}
+}
+
+/// What one file type opens with, for showing associations in bulk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileAssociation {
+	/// The extension in `.ext` form, lowercased
+	pub extension: String,
+	/// What `open_with_default` would launch for the type
+	pub default_app: Option<OpenWithApp>,
+	/// Every app registered for the type, as `get_all_apps_for_file` lists them
+	pub all_apps: Vec<OpenWithApp>,
+}
+
+fn recommended_by_default() -> bool {
+	true
+}
+
+mod png_base64 {
+	use base64::{engine::general_purpose::STANDARD, Engine};
+	use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+	pub fn serialize<S: Serializer>(
+		icon: &Option<Vec<u8>>,
+		serializer: S,
+	) -> Result<S::Ok, S::Error> {
+		match icon {
+			Some(bytes) => serializer.serialize_some(&STANDARD.encode(bytes)),
+			None => serializer.serialize_none(),
+		}
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(
+		deserializer: D,
+	) -> Result<Option<Vec<u8>>, D::Error> {
+		Option::<String>::deserialize(deserializer)?
+			.map(|encoded| STANDARD.decode(encoded).map_err(D::Error::custom))
+			.transpose()
+	}
+
+	/// The same encoding for a field that is always present.
+	pub mod required {
+		use super::*;
+
+		pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+			serializer.serialize_str(&STANDARD.encode(bytes))
+		}
+
+		pub fn deserialize<'de, D: Deserializer<'de>>(
+			deserializer: D,
+		) -> Result<Vec<u8>, D::Error> {
+			STANDARD
+				.decode(String::deserialize(deserializer)?)
+				.map_err(D::Error::custom)
+		}
+	}
 }
 
 /// Result of attempting to open a file
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "status", rename_all = "snake_case")]
 pub enum OpenResult {
-	Success,
-	FileNotFound { path: String },
-	AppNotFound { app_id: String },
-	PermissionDenied { path: String },
-	PlatformError { message: String },
+	/// `pid` is the process that was started for the file, where the backend
+	/// can tell. It's `None` when the file went to an instance that was
+	/// already running, when the launch didn't go through a new process of
+	/// its own (a COM handler, a packaged app), and on backends that don't
+	/// report one. Windows reports it from `open_with_default` and
+	/// `open_and_wait`.
+	Success {
+		#[serde(default)]
+		pid: Option<u32>,
+	},
+	FileNotFound {
+		path: String,
+	},
+	AppNotFound {
+		app_id: String,
+	},
+	/// The app is still registered, but the executable it launches is gone,
+	/// typically because it was uninstalled without cleaning up
+	AppUnavailable {
+		app_id: String,
+		exe_path: String,
+	},
+	PermissionDenied {
+		path: String,
+	},
+	/// Another process holds the file in a way that keeps the app from
+	/// opening it, e.g. a spreadsheet open in Excel
+	///
+	/// `by` is that process's display name, when the backend could find out.
+	/// Only the Windows backend reports this, going by the sharing violation
+	/// from the launch and asking the Restart Manager for the holder.
+	Locked {
+		#[serde(default)]
+		by: Option<String>,
+	},
+	/// The file is on a network location that didn't answer in time, e.g. a
+	/// share whose server is offline
+	///
+	/// Only the Windows backend checks for this, within the timeout its
+	/// builder sets; elsewhere such launches fail or block as the OS does.
+	Unreachable {
+		path: String,
+	},
+	/// Nothing at all is registered to open the file's type, so there was no
+	/// app to find `app_id` among
+	///
+	/// Unlike `AppNotFound`, no particular app is missing: the user should be
+	/// offered a choice, e.g. through `open_with_dialog`. `extension` is the
+	/// type, as `.ext`. Only the Windows backend tells the two apart.
+	NoAppsForType {
+		extension: String,
+	},
+	/// The app only runs as administrator (its manifest asks for it) and was
+	/// started in a way that can't raise a UAC prompt
+	///
+	/// `path` is the file the launch was for. Only the Windows backend
+	/// reports this, for launches that start an executable directly, e.g.
+	/// `launch` with `new_instance`. Its builder can have such launches
+	/// retried with elevation instead, so the user gets the prompt; otherwise
+	/// the caller decides, e.g. by offering `open_elevated`.
+	RequiresElevation {
+		path: String,
+	},
+	/// Nothing is registered to perform `verb` (e.g. `print`) on this file's
+	/// type, so the action should be hidden rather than reported as a failure
+	NoHandler {
+		verb: String,
+	},
+	/// No mail client is set up that could take the message, e.g. none is
+	/// the default, or the default can't be handed attachments
+	///
+	/// Only produced by [`FileOpener::open_mail_with_attachment`].
+	NoMailClient,
+	/// The backend can't perform `operation` at all, so the action should be
+	/// hidden rather than reported as a failure
+	///
+	/// `operation` is the trait method's name, e.g. `print`. Which launches
+	/// each backend currently performs, the rest returning this:
+	///
+	/// | Operation                   | Windows | macOS       | Linux       |
+	/// |-----------------------------|---------|-------------|-------------|
+	/// | `open_elevated`             | yes     | no          | no          |
+	/// | `print`                     | yes     | no          | no          |
+	/// | `edit`                      | yes     | no          | no          |
+	/// | `open_with_verb`            | yes     | `open` only | `open` only |
+	/// | `open_with_dialog`          | yes     | no          | no          |
+	/// | `open_with_executable`      | yes     | no          | no          |
+	/// | `launch_app`                | yes     | no          | no          |
+	/// | `launch_app_with_args`      | yes     | no          | no          |
+	/// | `open_shell_item`           | yes     | no          | no          |
+	/// | `move_to_trash`             | yes     | no          | no          |
+	/// | `open_and_wait`             | yes     | no          | no          |
+	/// | `show_properties`           | yes     | no          | no          |
+	/// | `open_mail_with_attachment` | yes     | no          | no          |
+	/// | `set_default_app`           | yes     | no          | no          |
+	///
+	/// Queries without a launch, like `list_verbs` or `get_thumbnail`, return
+	/// empty results instead. [`FileOpener::capabilities`] reports the same at
+	/// runtime.
+	Unsupported {
+		operation: String,
+	},
+	PlatformError {
+		message: String,
+		/// The platform's own code for the failure, for logs and bug reports
+		///
+		/// - Windows: an `HRESULT`; ShellExecute's `SE_ERR_*` codes are reported
+		///   as the matching Win32 error in HRESULT form.
+		/// - macOS: the `NSError` code.
+		/// - Linux: the OS error number, e.g. from a failed spawn.
+		///
+		/// `0` when there is no underlying code.
+		#[serde(default)]
+		code: i32,
+	},
+	/// The user dismissed a system prompt, so there is nothing to report back
+	/// to them
+	///
+	/// Backends only produce this when the user explicitly backed out:
+	/// - Windows: `ERROR_CANCELLED` from ShellExecute or a handler, e.g. a
+	///   dismissed UAC or Open With dialog. A bare `SE_ERR_ACCESSDENIED` /
+	///   `E_ACCESSDENIED` is a real refusal and maps to `PermissionDenied`.
+	/// - macOS: `NSUserCancelledError` from Launch Services.
+	/// - Linux: never, since launching a desktop entry doesn't prompt.
+	Cancelled,
+	/// The launch didn't report back before the caller's deadline
+	///
+	/// Only produced by [`TimedFileOpener`]. It doesn't mean the app failed
+	/// to start, only that confirmation wasn't received in time; the app may
+	/// still open the file, e.g. once the user answers a prompt it showed.
+	TimedOut,
+	/// The system only let the change be started: it showed the user a page
+	/// where they have to finish it themselves
+	///
+	/// Produced by [`FileOpener::set_default_app`] where the OS blocks silent
+	/// association changes. Not a failure, but nothing has changed yet either.
+	RequiresUserAction,
+	/// The file's type had no handler, so the user was asked for one and the
+	/// file was opened with the app they picked
+	///
+	/// Only produced by [`FileOpener::open_with_default_or_prompt`].
+	OpenedAfterPrompt,
+	/// `open_with_default` launched the app a [`DefaultOverrides`] rule
+	/// picked for the type, not the OS default
+	OpenedWithOverride {
+		app_id: String,
+	},
+	/// [`FileOpener::open_readonly`] launched the app, and `enforced` says
+	/// whether the original is actually out of its reach
+	///
+	/// `true` when the app was given a read-only copy, so nothing it does can
+	/// change the original. `false` when it had to be given the original
+	/// itself, e.g. a directory, and keeping it unchanged is up to the app.
+	OpenedReadOnly {
+		enforced: bool,
+	},
+}
+
+impl OpenResult {
+	/// `Success` with no process to report
+	pub const fn success() -> Self {
+		Self::Success { pid: None }
+	}
+
+	/// `Success` with the id of the process that was started
+	pub const fn success_with_pid(pid: u32) -> Self {
+		Self::Success { pid: Some(pid) }
+	}
+}
+
+/// How a launch from [`FileOpener::open_and_wait`] ended
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessOutcome {
+	/// How the launch itself went
+	pub result: OpenResult,
+	/// What the started process exited with, once it has
+	///
+	/// `None` when the launch failed, and when it started no process of its
+	/// own to wait for: the file was handed to an instance that was already
+	/// running, or to a packaged app or DDE server.
+	pub exit_code: Option<i32>,
+}
+
+/// How [`FileOpener::open_with_app_and_set_default`] went, the open and the
+/// default change each on its own
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefaultedOpen {
+	/// How opening the file went
+	pub result: OpenResult,
+	/// How making the app the default went: `Success` when it now is,
+	/// `RequiresUserAction` when the OS left the change to the user
+	///
+	/// `None` when the file couldn't be opened with the app, in which case
+	/// the default wasn't touched.
+	pub default: Option<OpenResult>,
 }
 
 /// Trait for platform-specific file opening implementations
+///
+/// Object safe, and `Send + Sync` so a single `Arc<dyn FileOpener>` can serve
+/// every thread. Backends built on thread-affine handles (COM on Windows)
+/// keep them per calling thread instead of in the opener.
 pub trait FileOpener: Send + Sync {
 	/// Get list of applications that can open this file
-	fn get_apps_for_file(&self, path: &Path) -> Result<Vec<OpenWithApp>, String>;
+	///
+	/// Fails with [`FileOpenError::NoExtension`] when the file has no
+	/// extension and its type couldn't be told from its content either, and
+	/// is empty when the type is known but nothing is registered for it, so
+	/// the UI can offer to pick an app for the one and report that none is
+	/// installed for the other. The other listing methods follow suit.
+	fn get_apps_for_file(&self, path: &Path) -> Result<Vec<OpenWithApp>, FileOpenError>;
+
+	/// Get every application registered for this file's type
+	///
+	/// A superset of `get_apps_for_file` for "More apps…"-style lists: entries
+	/// the platform doesn't suggest are included with `recommended: false`.
+	/// Backends that can't tell the two apart return the recommended list.
+	fn get_all_apps_for_file(&self, path: &Path) -> Result<Vec<OpenWithApp>, FileOpenError> {
+		self.get_apps_for_file(path)
+	}
+
+	/// `get_apps_for_file` that gives up with [`FileOpenError::Cancelled`]
+	/// once `cancel` is cancelled
+	///
+	/// Backends check the token between handlers and between icons; the
+	/// default only checks before and after the whole listing.
+	fn get_apps_for_file_cancellable(
+		&self,
+		path: &Path,
+		cancel: &CancellationToken,
+	) -> Result<Vec<OpenWithApp>, FileOpenError> {
+		cancel.check()?;
+		let apps = self.get_apps_for_file(path)?;
+		cancel.check()?;
+		Ok(apps)
+	}
+
+	/// `get_all_apps_for_file` under a [`CancellationToken`], as
+	/// [`get_apps_for_file_cancellable`](Self::get_apps_for_file_cancellable)
+	fn get_all_apps_for_file_cancellable(
+		&self,
+		path: &Path,
+		cancel: &CancellationToken,
+	) -> Result<Vec<OpenWithApp>, FileOpenError> {
+		cancel.check()?;
+		let apps = self.get_all_apps_for_file(path)?;
+		cancel.check()?;
+		Ok(apps)
+	}
+
+	/// How many apps `get_apps_for_file` would list
+	///
+	/// For badges and "Open With (3)" labels. Backends that can count without
+	/// building the list, names and icons included, override this.
+	fn count_apps_for_file(&self, path: &Path) -> Result<usize, FileOpenError> {
+		self.get_apps_for_file(path).map(|apps| apps.len())
+	}
+
+	/// One page of `get_apps_for_file`: up to `limit` apps from `offset` on,
+	/// with the total the pages add up to
+	///
+	/// Pages slice the same ranked order, so stepping through them lists
+	/// every app once. Backends that build listings lazily only extract
+	/// icons for the apps on the page.
+	fn get_apps_for_file_paged(
+		&self,
+		path: &Path,
+		offset: usize,
+		limit: usize,
+	) -> Result<(Vec<OpenWithApp>, usize), FileOpenError> {
+		let apps = self.get_apps_for_file(path)?;
+		let total = apps.len();
+		Ok((apps.into_iter().skip(offset).take(limit).collect(), total))
+	}
+
+	/// `count_apps_for_file`, but counting no further than `cap`
+	///
+	/// For "is there anything to open this with" checks, `cap` of 1, and
+	/// badges that top out at "9+". Backends that count lazily stop looking
+	/// at handlers once `cap` is reached.
+	fn count_apps_for_file_up_to(&self, path: &Path, cap: usize) -> Result<usize, FileOpenError> {
+		self.count_apps_for_file(path).map(|count| count.min(cap))
+	}
 
 	/// Get list of apps that can open all provided files (intersection)
-	fn get_apps_for_files(&self, paths: &[PathBuf]) -> Result<Vec<OpenWithApp>, String> {
-		if paths.is_empty() {
-			return Ok(vec![]);
-		}
+	fn get_apps_for_files(&self, paths: &[PathBuf]) -> Result<Vec<OpenWithApp>, FileOpenError> {
+		intersect(paths.iter().map(|path| self.get_apps_for_file(path)))
+	}
 
-		// Get apps for first file
-		let mut common_apps = self
-			.get_apps_for_file(&paths[0])?
-			.into_iter()
-			.map(|app| (app.id.clone(), app))
-			.collect::<HashMap<_, _>>();
+	/// Apps that can open every file of a multi-selection, for its "Open
+	/// With" menu
+	///
+	/// Apps are listed once per extension rather than once per file, and
+	/// matched by `id` so an app offered for several of the extensions shows
+	/// up once. An app is only the default if it is for every extension.
+	/// `extensionless` decides whether files without an extension are left
+	/// out or leave the selection with no apps; a selection of nothing but
+	/// such files has none either way.
+	fn get_common_apps(
+		&self,
+		paths: &[&Path],
+		extensionless: ExtensionlessFiles,
+	) -> Result<Vec<OpenWithApp>, FileOpenError> {
+		// The first file of each extension stands in for the rest of them.
+		let mut representatives: Vec<(String, &Path)> = Vec::new();
+		for path in paths {
+			let Some(ext) = mime::derive_extension(path) else {
+				match extensionless {
+					ExtensionlessFiles::Ignore => continue,
+					ExtensionlessFiles::NoCommonApps => return Ok(vec![]),
+				}
+			};
 
-		// Intersect with remaining files
-		for path in &paths[1..] {
-			let apps = self
-				.get_apps_for_file(path)?
+			if !representatives.iter().any(|(known, _)| *known == ext) {
+				representatives.push((ext, path));
+			}
+		}
+
+		intersect(
+			representatives
 				.into_iter()
-				.map(|app| app.id)
-				.collect::<HashSet<_>>();
+				.map(|(_, path)| self.get_apps_for_file(path)),
+		)
+	}
+
+	/// Get list of applications registered for a MIME type, e.g. `image/png`
+	///
+	/// For callers that know a file's type without having a path, such as a
+	/// download that hasn't landed yet. Unknown types, and backends that can't
+	/// map MIME types, return an empty list.
+	fn get_apps_for_mime_type(&self, mime: &str) -> Result<Vec<OpenWithApp>, FileOpenError> {
+		let _ = mime;
+		Ok(vec![])
+	}
 
-			common_apps.retain(|id, _| apps.contains(id));
+	/// The extensions [`FileOpener::list_associations`] covers, without
+	/// looking any of them up
+	///
+	/// Backends that can't enumerate the system's file types return an empty
+	/// list.
+	fn list_association_extensions(&self) -> Result<Vec<String>, FileOpenError> {
+		Ok(vec![])
+	}
+
+	/// What files with `extension` (with or without the leading dot) open with
+	///
+	/// The default looks the type up through a file name that doesn't exist,
+	/// which is all any backend needs to go by.
+	fn get_association(&self, extension: &str) -> Result<FileAssociation, FileOpenError> {
+		let extension = format!(".{}", mime::normalize_extension(extension));
+		let path = PathBuf::from(format!("file{extension}"));
+
+		Ok(FileAssociation {
+			default_app: self.get_default_app(&path)?,
+			all_apps: self.get_all_apps_for_file(&path)?,
+			extension,
+		})
+	}
+
+	/// The association table one extension at a time, each looked up only
+	/// when it's reached
+	///
+	/// For rendering the table as it comes in and stopping early. An entry
+	/// that fails to look up (a malformed registration, say) is an `Err` of
+	/// its own and the ones after it still follow; only failing to list the
+	/// extensions at all ends the iterator after that one error.
+	fn associations_iter(
+		&self,
+	) -> Box<dyn Iterator<Item = Result<FileAssociation, FileOpenError>> + '_> {
+		match self.list_association_extensions() {
+			Ok(extensions) => Box::new(
+				extensions
+					.into_iter()
+					.map(|extension| self.get_association(&extension)),
+			),
+			Err(e) => Box::new(std::iter::once(Err(e))),
 		}
+	}
+
+	/// The whole association table, one entry per extension, failing on the
+	/// first entry that does
+	///
+	/// There can be hundreds of extensions, each costing an enumeration, so
+	/// UIs should prefer [`FileOpener::associations_iter`] or
+	/// `AsyncFileOpener::list_associations`, which works through them in
+	/// chunks.
+	fn list_associations(&self) -> Result<Vec<FileAssociation>, FileOpenError> {
+		self.associations_iter().collect()
+	}
+
+	/// Get the application `open_with_default` would launch, without launching it
+	///
+	/// Returns `Ok(None)` when no default is registered for the file's type. The
+	/// returned `id` is accepted by `open_with_app`.
+	fn get_default_app(&self, path: &Path) -> Result<Option<OpenWithApp>, FileOpenError>;
 
-		let mut result: Vec<_> = common_apps.into_values().collect();
-		result.sort_by(|a, b| a.name.cmp(&b.name));
-		Ok(result)
+	/// Whether anything could open this file, without launching anything
+	///
+	/// For enabling or greying out an "Open" action. Only the file's type is
+	/// looked at, not whether it exists. Backends answer this more cheaply
+	/// than listing every app; the default falls back to doing just that.
+	fn can_open(&self, path: &Path) -> Result<bool, FileOpenError> {
+		Ok(!self.get_apps_for_file(path)?.is_empty())
 	}
 
 	/// Open file with system default application
-	fn open_with_default(&self, path: &Path) -> Result<OpenResult, String>;
+	///
+	/// A directory opens in whatever the system uses for folders, which may be
+	/// a third-party file manager rather than the one `reveal` uses; on macOS
+	/// a bundle such as an `.app` is launched instead. A missing path,
+	/// directory or not, gives `FileNotFound`.
+	///
+	/// Backends with [`default_overrides`](Self::default_overrides) launch an
+	/// overriding app instead of the OS default where a rule matches.
+	fn open_with_default(&self, path: &Path) -> Result<OpenResult, FileOpenError>;
+
+	/// The rules that replace the OS default in `open_with_default`, for
+	/// backends that support them
+	fn default_overrides(&self) -> Option<&DefaultOverrides> {
+		None
+	}
+
+	/// Open files with `extension` (with or without the leading dot) with
+	/// `app_id` from `open_with_default` on, leaving the OS default alone
+	///
+	/// The rule lasts as long as the opener; persisting it is up to the
+	/// caller.
+	fn add_override(&self, extension: &str, app_id: &str) -> Result<(), FileOpenError> {
+		overrides_or_unsupported(self, "add_override")?.set(extension, app_id);
+		Ok(())
+	}
+
+	/// Drop the override for `extension`, so the OS default applies again
+	fn clear_override(&self, extension: &str) -> Result<(), FileOpenError> {
+		overrides_or_unsupported(self, "clear_override")?.clear(extension);
+		Ok(())
+	}
+
+	/// Whether opening the file makes the system warn the user first, so the
+	/// caller can ask for confirmation itself before launching
+	///
+	/// On Windows that is the shell's "Open File - Security Warning" for
+	/// types it considers dangerous, such as executables and scripts.
+	/// Backends that show no such warning, and types whose flags can't be
+	/// read, report `false`.
+	fn is_risky(&self, path: &Path) -> Result<bool, FileOpenError> {
+		let _ = path;
+		Ok(false)
+	}
+
+	/// The history of apps files were opened with, for backends that record
+	/// one
+	///
+	/// Those record every successful `open_with_app`, `open_with_app_args`
+	/// and `launch` after it returns.
+	fn open_history(&self) -> Option<&OpenHistory> {
+		None
+	}
+
+	/// The apps files with `extension` were most recently opened with,
+	/// newest first and at most `limit` of them, for the top of the "Open
+	/// With" menu
+	///
+	/// Only apps still registered for the type are returned. Backends without
+	/// an [`open_history`](Self::open_history) return an empty list.
+	fn recent_apps_for(
+		&self,
+		extension: &str,
+		limit: usize,
+	) -> Result<Vec<OpenWithApp>, FileOpenError> {
+		let Some(history) = self.open_history() else {
+			return Ok(vec![]);
+		};
+		let recent = history.recent(extension, usize::MAX);
+		if recent.is_empty() || limit == 0 {
+			return Ok(vec![]);
+		}
+
+		// Apps are listed by path; the file itself needn't exist.
+		let probe = PathBuf::from(format!("recent.{}", mime::normalize_extension(extension)));
+		let apps = self.get_all_apps_for_file(&probe)?;
+
+		Ok(recent
+			.iter()
+			.filter_map(|id| {
+				apps.iter().find(|app| {
+					app.id == *id
+						|| app
+							.progid
+							.as_deref()
+							.is_some_and(|progid| progid.eq_ignore_ascii_case(id))
+				})
+			})
+			.take(limit)
+			.cloned()
+			.collect())
+	}
+
+	/// Open file with its default application at elevated privileges
+	///
+	/// On Windows this raises a UAC prompt, and dismissing it yields
+	/// `Cancelled`. Backends without an elevation model don't support it.
+	fn open_elevated(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+		let _ = path;
+		Ok(not_supported("open_elevated"))
+	}
+
+	/// Send the file to the printer through its type's registered `print` verb
+	///
+	/// Returns `NoHandler` when the type has no print handler. Backends without
+	/// shell verbs don't support it.
+	fn print(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+		let _ = path;
+		Ok(not_supported("print"))
+	}
+
+	/// Whether the file's type registers a `print` verb, for enabling a
+	/// "Print" action without launching anything
+	fn can_print(&self, path: &Path) -> Result<bool, FileOpenError> {
+		let _ = path;
+		Ok(false)
+	}
+
+	/// Open the file for editing through its type's registered `edit` verb,
+	/// for an "Edit" action distinct from "Open"
+	///
+	/// Returns `Unsupported` when the type opens with an app but has no edit
+	/// verb, and `NoHandler` when nothing is registered for the type at all.
+	/// Backends without shell verbs don't support it.
+	fn edit(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+		let _ = path;
+		Ok(not_supported("edit"))
+	}
+
+	/// Whether the file's type registers an `edit` verb, for showing an
+	/// "Edit" action without launching anything
+	fn can_edit(&self, path: &Path) -> Result<bool, FileOpenError> {
+		let _ = path;
+		Ok(false)
+	}
+
+	/// Open file through a named shell verb, e.g. `edit`, `play` or `preview`
+	///
+	/// `verb` is the registered name, as returned by `list_verbs`, not its
+	/// localized menu text. Returns `NoHandler` when the file's type doesn't
+	/// define it. Backends without shell verbs only understand `open`.
+	fn open_with_verb(&self, path: &Path, verb: &str) -> Result<OpenResult, FileOpenError> {
+		if verb == "open" {
+			return self.open_with_default(path);
+		}
+		Ok(not_supported("open_with_verb"))
+	}
+
+	/// Names of the shell verbs the file's type defines, in registration order
+	///
+	/// For building a right-click submenu; each name is accepted by
+	/// `open_with_verb`. Backends without shell verbs return an empty list.
+	fn list_verbs(&self, path: &Path) -> Result<Vec<String>, FileOpenError> {
+		let _ = path;
+		Ok(vec![])
+	}
+
+	/// Open several files, each with its system default application
+	///
+	/// Results are aligned 1:1 with `paths`. Backends may batch files that
+	/// share a handler into one launch so the target app isn't flooded with
+	/// separate invocations.
+	fn open_many_with_default(&self, paths: &[&Path]) -> Result<Vec<OpenResult>, FileOpenError> {
+		paths
+			.iter()
+			.map(|path| self.open_with_default(path))
+			.collect()
+	}
 
 	/// Open file with specific application
-	fn open_with_app(&self, path: &Path, app_id: &str) -> Result<OpenResult, String>;
+	fn open_with_app(&self, path: &Path, app_id: &str) -> Result<OpenResult, FileOpenError>;
+
+	/// Open file with specific application, passing extra command-line arguments
+	///
+	/// `args` go before the file on the command line, so something like
+	/// `["-n42"]` can jump to a line. Backends that can only hand the app the
+	/// file itself ignore `args` and behave like `open_with_app`.
+	fn open_with_app_args(
+		&self,
+		path: &Path,
+		app_id: &str,
+		args: &[&str],
+	) -> Result<OpenResult, FileOpenError> {
+		let _ = args;
+		self.open_with_app(path, app_id)
+	}
+
+	/// Open file with `app_id` and make it the default for the file's type,
+	/// as "Always use this app" in an Open With dialog does
+	///
+	/// The default is only changed once the file has opened, and the two
+	/// outcomes are reported apart, since the OS may refuse or defer the
+	/// change after the app launched fine. The default goes through
+	/// `open_with_app` and then `set_default_app`; fails with
+	/// [`FileOpenError::NoExtension`] for files without an extension, whose
+	/// type there's no default for.
+	fn open_with_app_and_set_default(
+		&self,
+		path: &Path,
+		app_id: &str,
+	) -> Result<DefaultedOpen, FileOpenError> {
+		let extension = mime::derive_extension(path).ok_or(FileOpenError::NoExtension)?;
+
+		let result = self.open_with_app(path, app_id)?;
+		let default = match result {
+			OpenResult::Success { .. } => Some(self.set_default_app(&extension, app_id)?),
+			_ => None,
+		};
+		Ok(DefaultedOpen { result, default })
+	}
+
+	/// Open file with a program that isn't necessarily registered for it,
+	/// e.g. an `.exe` the user picked
+	///
+	/// Associations aren't consulted: `exe` is started with `args` and then
+	/// the file on its command line. `AppNotFound`, with `exe` as the app
+	/// ID, means it doesn't exist or isn't a program. Only the Windows
+	/// backend supports it.
+	fn open_with_executable(
+		&self,
+		path: &Path,
+		exe: &Path,
+		args: &[&str],
+	) -> Result<OpenResult, FileOpenError> {
+		let _ = (path, exe, args);
+		Ok(not_supported("open_with_executable"))
+	}
+
+	/// Open file with `app_id`, or its default application for `None`, and
+	/// block until the process that was started exits
+	///
+	/// For scripting, or to know when the user closed the file. Only works
+	/// when the launch starts a process of its own: apps that hand the file
+	/// to an instance already running, like most browsers and editors, exit
+	/// right away or start nothing, and `exit_code` says nothing about the
+	/// file then. Backends that can't wait don't support it.
+	fn open_and_wait(
+		&self,
+		path: &Path,
+		app_id: Option<&str>,
+	) -> Result<ProcessOutcome, FileOpenError> {
+		let _ = (path, app_id);
+		Ok(ProcessOutcome {
+			result: not_supported("open_and_wait"),
+			exit_code: None,
+		})
+	}
+
+	/// Start an app on its own, without a file
+	///
+	/// `app_id` is an [`OpenWithApp::id`] or `progid`. `AppNotFound` when
+	/// neither resolves to something startable, and `Cancelled` when the app
+	/// asks for elevation and the prompt is dismissed.
+	fn launch_app(&self, app_id: &str) -> Result<OpenResult, FileOpenError> {
+		let _ = app_id;
+		Ok(not_supported("launch_app"))
+	}
+
+	/// Start an app with exactly `args` on its command line, no file added
+	///
+	/// For conventions that name the file inside an argument, like
+	/// `--goto notes.txt:12`; see [`open_at`](Self::open_at). Same ids and
+	/// results as `launch_app`. Packaged apps, which take no command line,
+	/// are `Unsupported`.
+	fn launch_app_with_args(
+		&self,
+		app_id: &str,
+		args: &[&str],
+	) -> Result<OpenResult, FileOpenError> {
+		let _ = (app_id, args);
+		Ok(not_supported("launch_app_with_args"))
+	}
+
+	/// Open a shell item with its default verb, for items that have no
+	/// filesystem path of their own
+	///
+	/// `parsing_name` is what Windows calls the item's parsing name: a plain
+	/// path, a path that continues inside a ZIP folder
+	/// (`C:\Downloads\photos.zip\2024\beach.jpg`), or a namespace path
+	/// rooted at a CLSID (`::{20D04FE0-3AEA-1069-A2D8-08002B30309D}` for This
+	/// PC). Fails with `InvalidPath` when no item has that name.
+	fn open_shell_item(&self, parsing_name: &str) -> Result<OpenResult, FileOpenError> {
+		let _ = parsing_name;
+		Ok(not_supported("open_shell_item"))
+	}
+
+	/// Open a file as `spec` describes
+	///
+	/// The one call for every way of starting an app, with each backend
+	/// applying its platform's conventions; see [`LaunchSpec`] for which
+	/// fields each one honors. The default passes `args` to
+	/// `open_with_app_args` and ignores the rest, after checking
+	/// `working_dir` as every backend does.
+	fn launch(&self, spec: &LaunchSpec) -> Result<OpenResult, FileOpenError> {
+		spec.check_working_dir()?;
+		self.open_with_app_args(&spec.path, &spec.app_id, &spec.arg_strs())
+	}
+
+	/// Open a file with `app_id` at the line, page or fragment `target`
+	/// names, for apps known to take one on their command line
+	///
+	/// The app's executable is looked up in [`anchor::FORMATS`]; apps not
+	/// listed there, apps without an executable and targets without a
+	/// location those apps take are opened as `open_with_app` would, without
+	/// the location. So are conventions that need
+	/// [`launch_app_with_args`](Self::launch_app_with_args) on a backend that
+	/// doesn't support it.
+	fn open_at(&self, target: &OpenTarget, app_id: &str) -> Result<OpenResult, FileOpenError> {
+		let path = &target.path;
+		let exe = self
+			.get_all_apps_for_file(path)
+			.unwrap_or_default()
+			.into_iter()
+			.find(|app| app.id == app_id)
+			.and_then(|app| app.exe_path);
+		let Some(anchored) = exe.and_then(|exe| anchor::anchor_args(&exe, target)) else {
+			return self.open_with_app(path, app_id);
+		};
+
+		if !anchored.replaces_file {
+			return self.launch(&LaunchSpec {
+				args: anchored.args,
+				..LaunchSpec::new(path, app_id)
+			});
+		}
+
+		// Nothing else would notice the file is missing.
+		if !path.exists() {
+			return Ok(OpenResult::FileNotFound {
+				path: path.to_string_lossy().to_string(),
+			});
+		}
+		let args = anchored.args.iter().map(String::as_str).collect::<Vec<_>>();
+		match self.launch_app_with_args(app_id, &args)? {
+			OpenResult::Unsupported { .. } => self.open_with_app(path, app_id),
+			result => Ok(result),
+		}
+	}
+
+	/// Open a file with its default app, asking the user to pick one if its
+	/// type has no handler
+	///
+	/// Where the type has no association, the "Open With" dialog is shown as
+	/// by [`open_with_dialog`](Self::open_with_dialog), and picking an app
+	/// there is reported as `OpenedAfterPrompt`. Dismissing it is `Cancelled`,
+	/// and `NoHandler` means no app could be picked. Backends without such a
+	/// dialog behave like `open_with_default`.
+	fn open_with_default_or_prompt(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+		self.open_with_default(path)
+	}
+
+	/// Open file with its default app without letting the app change it,
+	/// as far as the platform allows
+	///
+	/// Files are copied to a read-only temporary file that is opened instead,
+	/// cleaned up by [`cleanup_temp_files`](Self::cleanup_temp_files); edits
+	/// the app makes anyway land in the copy. Shortcuts are resolved first so
+	/// the copy is of their target. Anything that isn't a plain file is opened
+	/// in place. The result says which happened, as `OpenedReadOnly`.
+	///
+	/// This is not a sandbox: the app still runs as the user and can read and
+	/// write whatever else they can. Isolating it depends on the app.
+	fn open_readonly(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+		let target = self.resolve_shortcut(path)?;
+		let path = target.as_deref().unwrap_or(path);
+
+		if !path.exists() {
+			return Ok(OpenResult::FileNotFound {
+				path: path.to_string_lossy().to_string(),
+			});
+		}
+
+		let (result, enforced) = match path.is_file() {
+			true => (self.open_with_default(&temp::copy_read_only(path)?)?, true),
+			false => (self.open_with_default(path)?, false),
+		};
+
+		Ok(match result {
+			OpenResult::Success { .. } => OpenResult::OpenedReadOnly { enforced },
+			other => other,
+		})
+	}
+
+	/// Open content that has no file of its own with its default app
+	///
+	/// `bytes` are written to a temporary file named `suggested_name`, whose
+	/// extension decides the app, and that file is opened. Apps read it after
+	/// this returns, so it stays until
+	/// [`cleanup_temp_files`](Self::cleanup_temp_files). Until then it is an
+	/// ordinary file any process of the same user can read, which matters for
+	/// decrypted content.
+	fn open_bytes(&self, bytes: &[u8], suggested_name: &str) -> Result<OpenResult, FileOpenError> {
+		let path = temp::write(bytes, suggested_name)?;
+		self.open_with_default(&path)
+	}
+
+	/// Delete the temporary files [`open_bytes`](Self::open_bytes) and
+	/// [`open_readonly`](Self::open_readonly) wrote in this process,
+	/// returning how many were deleted
+	///
+	/// An app that still has its file open keeps it on Windows, where it is
+	/// retried on the next call; elsewhere the file goes from under the app.
+	/// Files are removed without being overwritten first.
+	fn cleanup_temp_files(&self) -> usize {
+		temp::cleanup()
+	}
+
+	/// Let the user pick the app from the system's "Open With" dialog
+	///
+	/// The fallback for when `open_with_app` reports `AppNotFound`, and the
+	/// way to open a file whose type has no association at all. Blocks until
+	/// the dialog is closed; dismissing it is `Cancelled`. Backends without
+	/// such a dialog don't support it.
+	fn open_with_dialog(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+		let _ = path;
+		Ok(not_supported("open_with_dialog"))
+	}
 
 	/// Open multiple files with specific application
 	fn open_files_with_app(
 		&self,
 		paths: &[PathBuf],
 		app_id: &str,
-	) -> Result<Vec<OpenResult>, String> {
+	) -> Result<Vec<OpenResult>, FileOpenError> {
 		paths
 			.iter()
 			.map(|path| self.open_with_app(path, app_id))
 			.collect()
 	}
+
+	/// Open several files with one application in a single launch, so it gets
+	/// them together (one window with a tab each, say) rather than one by one
+	///
+	/// The files may have different extensions as long as the app is
+	/// registered for each. The result covers all of them: `Success` when
+	/// every file reached the app, otherwise the first thing that went wrong.
+	/// Backends without a batch launch open the files one at a time.
+	fn open_many_with_app(
+		&self,
+		paths: &[&Path],
+		app_id: &str,
+	) -> Result<OpenResult, FileOpenError> {
+		let mut aggregate = OpenResult::success();
+		for path in paths {
+			let result = self.open_with_app(path, app_id)?;
+			if matches!(aggregate, OpenResult::Success { .. }) {
+				aggregate = result;
+			}
+		}
+		Ok(aggregate)
+	}
+
+	/// Open a URI with its registered protocol handler, under the default
+	/// [`UriPolicy`]
+	fn open_uri(&self, uri: &str) -> Result<OpenResult, FileOpenError> {
+		self.open_uri_with_policy(uri, &UriPolicy::default())
+	}
+
+	/// Open a URI with its registered protocol handler if `policy` allows it
+	///
+	/// Disallowed URIs fail with [`FileOpenError::UriNotAllowed`] before
+	/// anything is launched; see the [`UriPolicy`] docs for the rules.
+	fn open_uri_with_policy(
+		&self,
+		uri: &str,
+		policy: &UriPolicy,
+	) -> Result<OpenResult, FileOpenError> {
+		policy.check(uri)?;
+		Ok(not_supported("open_uri"))
+	}
+
+	/// Show the item in the system file manager without opening it
+	///
+	/// Files are selected inside their parent folder and directories are opened
+	/// with nothing selected. If `path` is gone but its folder still exists the
+	/// folder is shown instead; `FileNotFound` means neither exists. Backends
+	/// decide through [`reveal_target`].
+	fn reveal(&self, path: &Path) -> Result<OpenResult, FileOpenError>;
+
+	/// Reveal several files at once, with results in the order of `paths`
+	///
+	/// Backends that can select several items in one file manager window do
+	/// so for files sharing a parent folder, rather than opening a window per
+	/// file. The default reveals each path on its own.
+	fn reveal_many(&self, paths: &[&Path]) -> Result<Vec<OpenResult>, FileOpenError> {
+		paths.iter().map(|path| self.reveal(path)).collect()
+	}
+
+	/// Move files to the trash (the Recycle Bin on Windows), with results in
+	/// the order of `paths`
+	///
+	/// Unlike deleting, this can be undone: items stay restorable until the
+	/// trash is emptied. The system's own confirmation and progress UI is
+	/// shown where it has one, and an item the user declined to trash there
+	/// is `Cancelled`; a missing one is `FileNotFound`. The default reports
+	/// every path as `Unsupported`.
+	fn move_to_trash(&self, paths: &[&Path]) -> Result<Vec<OpenResult>, FileOpenError> {
+		Ok(paths
+			.iter()
+			.map(|_| not_supported("move_to_trash"))
+			.collect())
+	}
+
+	/// Resolve a shortcut file to the item it points at
+	///
+	/// `Ok(None)` when `path` isn't a shortcut the backend reads, when the
+	/// shortcut points outside the file system, and when its target is gone.
+	/// Only Windows `.lnk` shell links are read; `.url` internet shortcuts are
+	/// out of scope, and other platforms always return `Ok(None)` since their
+	/// symlinks are followed by the file system itself.
+	fn resolve_shortcut(&self, path: &Path) -> Result<Option<PathBuf>, FileOpenError> {
+		let _ = path;
+		Ok(None)
+	}
+
+	/// Show the system's properties dialog for a file or directory
+	///
+	/// Only the dialog is opened; the call returns without waiting for the
+	/// user to close it. Backends without such a dialog don't support it.
+	fn show_properties(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+		let _ = path;
+		Ok(not_supported("show_properties"))
+	}
+
+	/// Open a new message in the default mail client with `attachments`
+	/// attached, for the user to address and send
+	///
+	/// `subject` and `body` prefill the message, and `attachments` may be
+	/// empty. Missing attachments are reported as for a launch, and folders
+	/// fail with [`FileOpenError::InvalidPath`]. `NoMailClient` means there
+	/// was no client to hand the message to. How many files, and how large,
+	/// is up to the mechanism and the client; the Windows backend documents
+	/// its limits.
+	fn open_mail_with_attachment(
+		&self,
+		attachments: &[&Path],
+		subject: Option<&str>,
+		body: Option<&str>,
+	) -> Result<OpenResult, FileOpenError> {
+		let _ = (attachments, subject, body);
+		Ok(not_supported("open_mail_with_attachment"))
+	}
+
+	/// The thumbnail the system would show for the file, as PNG fitting
+	/// within `size` × `size` pixels
+	///
+	/// Only previews the system generates from file content (images, video
+	/// frames, documents) count: `Ok(None)` for files that have none,
+	/// including missing ones, rather than the type's icon. Extraction runs
+	/// whatever codec the file needs and may take long; callers without a
+	/// deadline of their own should use
+	/// [`get_thumbnail_timeout`](TimedFileOpener::get_thumbnail_timeout).
+	/// Backends without thumbnails always return `Ok(None)`, and builds that
+	/// leave them out, like the Windows backend without its `icons` feature,
+	/// fail with [`FileOpenError::Unsupported`].
+	fn get_thumbnail(&self, path: &Path, size: u32) -> Result<Option<Vec<u8>>, FileOpenError> {
+		let _ = (path, size);
+		Ok(None)
+	}
+
+	/// Make `app_id` the default for files with `extension` (with or without
+	/// the leading dot)
+	///
+	/// Only ever with the user's consent. Windows refuses silent changes (the
+	/// default is stored with a hash only the shell can produce, so that
+	/// installers can't hijack file types), so its backend opens the Default
+	/// apps page in Settings on the chosen app and returns
+	/// `RequiresUserAction`; the change lands when the user makes it there, and
+	/// `watch_association_changes` reports it. `Success` means the app already
+	/// was the default.
+	fn set_default_app(&self, extension: &str, app_id: &str) -> Result<OpenResult, FileOpenError> {
+		let _ = (extension, app_id);
+		Ok(not_supported("set_default_app"))
+	}
+
+	/// Drop any association data the backend has cached
+	///
+	/// Backends that cache invalidate on their own when the system reports an
+	/// association change; this is for callers that know better, e.g. right
+	/// after an app install they triggered. Backends without a cache ignore it.
+	fn clear_cache(&self) {}
+
+	/// Drop the app icons the backend has cached, leaving other cached data
+	///
+	/// Icons are cached per app rather than per file type and dropped along
+	/// with everything else on an association change or `clear_cache`; this
+	/// is for when only icons went stale, e.g. after an app updated in place
+	/// without re-registering. Backends without an icon cache ignore it.
+	fn clear_icon_cache(&self) {}
+
+	/// Reset every cache the backend keeps, right away
+	///
+	/// For callers that know associations changed, e.g. after installing an
+	/// app or picking a default themselves, and don't want to wait for the
+	/// system's change notification (which some changes never send). The next
+	/// lookup reads the system afresh. Backends document which state this
+	/// covers; by default it's [`clear_cache`](Self::clear_cache) and
+	/// [`clear_icon_cache`](Self::clear_icon_cache) together.
+	fn refresh(&self) {
+		self.clear_cache();
+		self.clear_icon_cache();
+	}
+
+	/// Call `callback` whenever the system reports that file associations
+	/// changed, e.g. after an app was installed or a default was picked
+	///
+	/// The callback runs on a backend thread, not the caller's, and may fire
+	/// for changes that leave every association as it was. It stays
+	/// registered until the returned handle is dropped. Backends that can't
+	/// detect changes return a handle whose callback never fires.
+	fn watch_association_changes(
+		&self,
+		callback: Box<dyn Fn() + Send + Sync>,
+	) -> Result<WatchHandle, FileOpenError> {
+		let _ = callback;
+		Ok(WatchHandle::inactive())
+	}
+
+	/// Which optional operations this backend performs, for building menus
+	/// that only offer what will work
+	///
+	/// The default describes a backend that implements only the required
+	/// methods, revealing included; backends override it as they add more.
+	fn capabilities(&self) -> OpenerCapabilities {
+		OpenerCapabilities {
+			reveal: true,
+			..OpenerCapabilities::default()
+		}
+	}
+}
+
+/// Orders apps the way an "Open With" menu should list them
+///
+/// The default app comes first, then the other recommended apps, then the
+/// rest by name. The sort is stable, so recommended apps keep the order the
+/// platform listed them in, which is usually its own preference order.
+pub fn sort_by_relevance(apps: &mut [OpenWithApp]) {
+	apps.sort_by(|a, b| {
+		b.is_default
+			.cmp(&a.is_default)
+			.then(b.recommended.cmp(&a.recommended))
+			.then_with(|| {
+				if a.recommended && b.recommended {
+					std::cmp::Ordering::Equal
+				} else {
+					a.name.cmp(&b.name)
+				}
+			})
+	});
+}
+
+/// One section of a grouped "Open With" menu
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppGroup {
+	/// The shared [`OpenWithApp::category`], or `None` for the apps without one
+	pub category: Option<String>,
+	pub apps: Vec<OpenWithApp>,
+}
+
+/// Splits a list of apps into sections by category
+///
+/// Sections come in the order of their first app, so a list already sorted
+/// with [`sort_by_relevance`] keeps the default's section on top, and apps
+/// keep their order within each. Uncategorized apps share one trailing
+/// section. When no app has a category the result is that one section,
+/// which the UI can show as a plain list.
+pub fn group_by_category(apps: Vec<OpenWithApp>) -> Vec<AppGroup> {
+	let mut groups: Vec<AppGroup> = Vec::new();
+	let mut uncategorized = Vec::new();
+
+	for app in apps {
+		let Some(category) = app.category.clone() else {
+			uncategorized.push(app);
+			continue;
+		};
+
+		match groups
+			.iter_mut()
+			.find(|group| group.category.as_deref() == Some(category.as_str()))
+		{
+			Some(group) => group.apps.push(app),
+			None => groups.push(AppGroup {
+				category: Some(category),
+				apps: vec![app],
+			}),
+		}
+	}
+
+	if !uncategorized.is_empty() {
+		groups.push(AppGroup {
+			category: None,
+			apps: uncategorized,
+		});
+	}
+
+	groups
+}
+
+/// Picks a mnemonic for each app in one menu, in list order: the first
+/// letter or digit of its name that no app before it took
+///
+/// Letters compare case-insensitively, as menu mnemonics do, and each keeps
+/// the case it has in the name so the UI can underline it there. An app whose
+/// every letter is taken gets `None`. Lists sorted with [`sort_by_relevance`]
+/// give the default the first letter of its name.
+pub fn assign_accelerators(apps: &mut [OpenWithApp]) {
+	let mut taken = HashSet::new();
+
+	for app in apps {
+		app.accelerator = app
+			.name
+			.chars()
+			.filter(|c| c.is_alphanumeric())
+			.find(|c| taken.insert(c.to_lowercase().collect::<String>()));
+	}
+}
+
+/// The apps present in every one of `lists`, matched by `id`, ranked as by
+/// [`sort_by_relevance`].
+///
+/// An app only stays the default if it is the default in every list.
+fn intersect(
+	mut lists: impl Iterator<Item = Result<Vec<OpenWithApp>, FileOpenError>>,
+) -> Result<Vec<OpenWithApp>, FileOpenError> {
+	let Some(first) = lists.next() else {
+		return Ok(vec![]);
+	};
+	let mut common_apps = first?
+		.into_iter()
+		.map(|app| (app.id.clone(), app))
+		.collect::<HashMap<_, _>>();
+
+	for apps in lists {
+		let apps = apps?
+			.into_iter()
+			.map(|app| (app.id, app.is_default))
+			.collect::<HashMap<_, _>>();
+
+		common_apps.retain(|id, app| match apps.get(id) {
+			Some(&is_default) => {
+				app.is_default &= is_default;
+				true
+			}
+			None => false,
+		});
+	}
+
+	let mut result: Vec<_> = common_apps.into_values().collect();
+	result.sort_by(|a, b| a.name.cmp(&b.name));
+	sort_by_relevance(&mut result);
+	Ok(result)
+}
+
+fn overrides_or_unsupported<'a>(
+	opener: &'a (impl FileOpener + ?Sized),
+	operation: &str,
+) -> Result<&'a DefaultOverrides, FileOpenError> {
+	opener
+		.default_overrides()
+		.ok_or_else(|| FileOpenError::Unsupported {
+			operation: operation.to_string(),
+		})
+}
+
+/// Longest app id accepted, in bytes: the longest ids are Windows handler
+/// names, which are executable paths.
+pub const MAX_APP_ID_LEN: usize = 32_767;
+
+/// Fails with [`FileOpenError::InvalidAppId`] for an id nothing could have
+/// handed out: blank, longer than [`MAX_APP_ID_LEN`], or with a NUL in it
+///
+/// Backends check before looking the app up, so a bad id from the frontend
+/// fails at once instead of after an enumeration that can't match it.
+pub fn check_app_id(app_id: &str) -> Result<(), FileOpenError> {
+	let reason = if app_id.trim().is_empty() {
+		"app id is empty"
+	} else if app_id.len() > MAX_APP_ID_LEN {
+		"app id is too long"
+	} else if app_id.contains('\0') {
+		"app id contains a NUL character"
+	} else {
+		return Ok(());
+	};
+
+	let mut shown = app_id.to_string();
+	// Never echo a whole oversized id back.
+	if shown.len() > 64 {
+		let mut end = 64;
+		while !shown.is_char_boundary(end) {
+			end -= 1;
+		}
+		shown.truncate(end);
+		shown.push('…');
+	}
+	Err(FileOpenError::InvalidAppId {
+		app_id: shown,
+		reason,
+	})
+}
+
+fn not_supported(operation: &str) -> OpenResult {
+	OpenResult::Unsupported {
+		operation: operation.to_string(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn malformed_app_ids_are_rejected() {
+		for app_id in ["", "  ", "note\0pad"] {
+			assert!(
+				matches!(
+					check_app_id(app_id),
+					Err(FileOpenError::InvalidAppId { .. })
+				),
+				"{app_id:?}"
+			);
+		}
+
+		let long = "a".repeat(MAX_APP_ID_LEN + 1);
+		let Err(FileOpenError::InvalidAppId { app_id, .. }) = check_app_id(&long) else {
+			panic!("an oversized id was accepted");
+		};
+		assert!(app_id.len() < 80);
+
+		for app_id in [
+			"txtfile",
+			r"C:\Windows\notepad.exe",
+			"org.gnome.Evince.desktop",
+		] {
+			assert!(check_app_id(app_id).is_ok(), "{app_id}");
+		}
+	}
+
+	#[test]
+	fn icon_serializes_as_base64() {
+		let app = OpenWithApp {
+			icon: Some(vec![0x89, b'P', b'N', b'G']),
+			..OpenWithApp::new("notepad.exe", "Notepad")
+		};
+
+		let json = serde_json::to_value(&app).unwrap();
+		assert_eq!(json["icon"], "iVBORw==");
+
+		let decoded: OpenWithApp = serde_json::from_value(json).unwrap();
+		assert_eq!(decoded.icon, app.icon);
+	}
+
+	#[test]
+	fn exe_path_is_optional() {
+		let app = OpenWithApp {
+			exe_path: Some(PathBuf::from(r"C:\Windows\notepad.exe")),
+			..OpenWithApp::new("notepad.exe", "Notepad")
+		};
+
+		let json = serde_json::to_value(&app).unwrap();
+		assert_eq!(json["exe_path"], r"C:\Windows\notepad.exe");
+
+		let decoded: OpenWithApp = serde_json::from_value(json).unwrap();
+		assert_eq!(decoded.exe_path, app.exe_path);
+
+		let legacy: OpenWithApp =
+			serde_json::from_str(r#"{"id":"notepad.exe","name":"Notepad"}"#).unwrap();
+		assert!(legacy.exe_path.is_none());
+		assert!(legacy.recommended);
+	}
+
+	#[test]
+	fn default_app_sorts_first() {
+		let app = |name: &str, recommended, is_default| OpenWithApp {
+			recommended,
+			is_default,
+			..OpenWithApp::new(name, name)
+		};
+		let mut apps = vec![
+			app("Photos", true, false),
+			app("Zed", false, false),
+			app("Paint", true, true),
+			app("Blender", false, false),
+			app("Krita", true, false),
+		];
+
+		sort_by_relevance(&mut apps);
+
+		let names = apps.iter().map(|app| app.name.as_str()).collect::<Vec<_>>();
+		assert_eq!(names, ["Paint", "Photos", "Krita", "Blender", "Zed"]);
+	}
+
+	#[test]
+	fn groups_follow_the_ranked_order() {
+		let app = |name: &str, category: Option<&str>| OpenWithApp {
+			category: category.map(str::to_string),
+			..OpenWithApp::new(name, name)
+		};
+		let sections = |groups: &[AppGroup]| {
+			groups
+				.iter()
+				.map(|group| {
+					let names = group.apps.iter().map(|app| app.name.as_str());
+					format!(
+						"{}: {}",
+						group.category.as_deref().unwrap_or("-"),
+						names.collect::<Vec<_>>().join(", ")
+					)
+				})
+				.collect::<Vec<_>>()
+		};
+
+		let grouped = group_by_category(vec![
+			app("Notepad", None),
+			app("Edge", Some("browser")),
+			app("Outlook", Some("mail")),
+			app("Firefox", Some("browser")),
+			app("Code", None),
+		]);
+		assert_eq!(
+			sections(&grouped),
+			[
+				"browser: Edge, Firefox",
+				"mail: Outlook",
+				"-: Notepad, Code"
+			]
+		);
+
+		// Nothing categorized: one flat section.
+		let flat = group_by_category(vec![app("Notepad", None), app("Code", None)]);
+		assert_eq!(flat.len(), 1);
+		assert!(flat[0].category.is_none());
+		assert!(group_by_category(Vec::new()).is_empty());
+	}
+
+	#[test]
+	fn accelerators_skip_letters_already_taken() {
+		let mut apps = ["Paint", "Photos", "paint 3D", "Pa", "VS Code"].map(|name| OpenWithApp {
+			accelerator: Some('x'),
+			..OpenWithApp::new(name, name)
+		});
+
+		assign_accelerators(&mut apps);
+
+		let accelerators = apps.iter().map(|app| app.accelerator).collect::<Vec<_>>();
+		assert_eq!(
+			accelerators,
+			[Some('P'), Some('h'), Some('a'), None, Some('V')]
+		);
+	}
+
+	#[test]
+	fn missing_icon_is_omitted() {
+		let decoded: OpenWithApp =
+			serde_json::from_str(r#"{"id":"com.apple.Preview","name":"Preview"}"#).unwrap();
+		assert!(decoded.icon.is_none());
+		assert!(serde_json::to_value(&decoded)
+			.unwrap()
+			.get("icon")
+			.is_none());
+	}
+
+	#[test]
+	fn successes_carry_the_pid_when_known() {
+		assert_eq!(
+			serde_json::to_value(OpenResult::success_with_pid(42)).unwrap(),
+			serde_json::json!({ "status": "success", "pid": 42 })
+		);
+
+		let result: OpenResult = serde_json::from_str(r#"{ "status": "success" }"#).unwrap();
+		assert!(matches!(result, OpenResult::Success { pid: None }));
+	}
+
+	#[test]
+	fn association_failures_do_not_end_the_iterator() {
+		struct Table;
+
+		impl FileOpener for Table {
+			fn get_apps_for_file(&self, path: &Path) -> Result<Vec<OpenWithApp>, FileOpenError> {
+				match path.extension().and_then(|ext| ext.to_str()) {
+					Some("broken") => Err(FileOpenError::EnumHandlers(-1)),
+					_ => Ok(vec![]),
+				}
+			}
+
+			fn get_default_app(&self, path: &Path) -> Result<Option<OpenWithApp>, FileOpenError> {
+				self.get_apps_for_file(path).map(|_| None)
+			}
+
+			fn open_with_default(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+				NoopFileOpener.open_with_default(path)
+			}
+
+			fn open_with_app(
+				&self,
+				path: &Path,
+				app_id: &str,
+			) -> Result<OpenResult, FileOpenError> {
+				NoopFileOpener.open_with_app(path, app_id)
+			}
+
+			fn reveal(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+				NoopFileOpener.reveal(path)
+			}
+
+			fn list_association_extensions(&self) -> Result<Vec<String>, FileOpenError> {
+				Ok(["txt", "broken", "png"].map(String::from).to_vec())
+			}
+		}
+
+		let entries = Table.associations_iter().collect::<Vec<_>>();
+		assert_eq!(entries.len(), 3);
+		assert!(matches!(&entries[0], Ok(entry) if entry.extension == ".txt"));
+		assert!(matches!(entries[1], Err(FileOpenError::EnumHandlers(_))));
+		assert!(matches!(&entries[2], Ok(entry) if entry.extension == ".png"));
+
+		// The eager form gives up at the first failure.
+		assert!(Table.list_associations().is_err());
+		assert!(NoopFileOpener.associations_iter().next().is_none());
+	}
+
+	#[test]
+	fn unsupported_launches_say_which_operation() {
+		let opener = NoopFileOpener;
+		let result = opener.print(Path::new("notes.txt")).unwrap();
+		assert!(matches!(&result, OpenResult::Unsupported { operation } if operation == "print"));
+		assert_eq!(
+			serde_json::to_value(&result).unwrap(),
+			serde_json::json!({ "status": "unsupported", "operation": "print" })
+		);
+
+		// Without shell verbs only `open` is understood.
+		assert!(matches!(
+			opener.open_with_verb(Path::new("notes.txt"), "edit"),
+			Ok(OpenResult::Unsupported { .. })
+		));
+
+		assert!(matches!(
+			opener.open_mail_with_attachment(&[Path::new("notes.txt")], Some("Notes"), None),
+			Ok(OpenResult::Unsupported { operation }) if operation == "open_mail_with_attachment"
+		));
+		assert!(matches!(
+			opener.add_override("txt", "code"),
+			Err(FileOpenError::Unsupported { operation }) if operation == "add_override"
+		));
+	}
+
+	#[test]
+	fn launch_specs_only_need_a_path_and_app() {
+		let spec: LaunchSpec =
+			serde_json::from_str(r#"{"path":"/tmp/notes.txt","app_id":"code"}"#).unwrap();
+		assert_eq!(spec, LaunchSpec::new("/tmp/notes.txt", "code"));
+		assert!(spec.arg_strs().is_empty());
+	}
+
+	#[test]
+	fn working_dirs_must_be_existing_directories() {
+		let file = std::env::temp_dir().join(format!("sd-launch-cwd-{}", std::process::id()));
+		std::fs::write(&file, b"").unwrap();
+
+		let with_dir = |dir: PathBuf| LaunchSpec {
+			working_dir: Some(dir),
+			..LaunchSpec::new("notes.txt", "code")
+		};
+
+		assert!(LaunchSpec::new("notes.txt", "code")
+			.check_working_dir()
+			.is_ok());
+		assert!(with_dir(std::env::temp_dir()).check_working_dir().is_ok());
+		assert!(matches!(
+			with_dir(file.clone()).check_working_dir(),
+			Err(FileOpenError::InvalidPath { reason, .. }) if reason.contains("not a directory")
+		));
+		assert!(matches!(
+			with_dir(file.join("missing")).check_working_dir(),
+			Err(FileOpenError::InvalidPath { reason, .. }) if reason.contains("does not exist")
+		));
+
+		std::fs::remove_file(file).unwrap();
+	}
 }
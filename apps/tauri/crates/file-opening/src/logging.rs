@@ -0,0 +1,692 @@
+//! # Call logging
+//!
+//! [`LoggingFileOpener`] wraps another opener and forwards every call to it
+//! unchanged, emitting a `tracing` event (target `file_opening`, level
+//! `debug`) with the method, its arguments and what came back. It can also
+//! keep those calls in memory, for tests and for a debug view that shows
+//! what the UI asked of the backend.
+//!
+//! Arguments and outcomes are their `Debug` form, cut short at 512 bytes so
+//! icons and thumbnails don't flood the log. Nothing is formatted while the
+//! event is disabled and there's no call log.
+
+use std::fmt::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::{
+	CancellationToken, DefaultOverrides, DefaultedOpen, ExtensionlessFiles, FileAssociation,
+	FileOpenError, FileOpener, LaunchSpec, OpenHistory, OpenResult, OpenTarget, OpenWithApp,
+	OpenerCapabilities, ProcessOutcome, UriPolicy, WatchHandle,
+};
+
+const LOG_TARGET: &str = "file_opening";
+
+/// Longest argument list or outcome kept, in bytes.
+const MAX_DESCRIPTION: usize = 512;
+
+/// One call that went through a [`LoggingFileOpener`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoggedCall {
+	/// The [`FileOpener`] method, e.g. `open_with_app`
+	pub method: &'static str,
+	pub args: String,
+	/// What the inner opener returned
+	pub outcome: String,
+}
+
+/// A [`FileOpener`] that logs every call and forwards it to `inner`.
+///
+/// Accessors that only hand out the inner opener's state
+/// (`default_overrides`, `open_history` and `capabilities`) are forwarded
+/// without being logged.
+pub struct LoggingFileOpener<T: FileOpener + ?Sized> {
+	inner: Arc<T>,
+	log: Option<Mutex<Vec<LoggedCall>>>,
+}
+
+impl<T: FileOpener + ?Sized> LoggingFileOpener<T> {
+	pub fn new(inner: Arc<T>) -> Self {
+		Self { inner, log: None }
+	}
+
+	/// Keep every call in memory as well, for [`calls`](Self::calls).
+	pub fn with_call_log(mut self) -> Self {
+		self.log = Some(Mutex::new(Vec::new()));
+		self
+	}
+
+	pub fn inner(&self) -> &Arc<T> {
+		&self.inner
+	}
+
+	/// Calls so far, oldest first; empty without
+	/// [`with_call_log`](Self::with_call_log).
+	pub fn calls(&self) -> Vec<LoggedCall> {
+		self.log.as_ref().map_or_else(Vec::new, |log| {
+			log.lock().unwrap_or_else(|e| e.into_inner()).clone()
+		})
+	}
+
+	/// Runs `call` against the inner opener and logs it as `method(args)`.
+	fn logged<R: fmt::Debug>(
+		&self,
+		method: &'static str,
+		args: fmt::Arguments<'_>,
+		call: impl FnOnce(&T) -> R,
+	) -> R {
+		let result = call(&self.inner);
+		if self.recording() {
+			self.record(method, describe(args), describe(format_args!("{result:?}")));
+		}
+		result
+	}
+
+	/// Whether calls go anywhere, so they're only described when they do.
+	fn recording(&self) -> bool {
+		self.log.is_some() || tracing::enabled!(target: LOG_TARGET, tracing::Level::DEBUG)
+	}
+
+	fn record(&self, method: &'static str, args: String, outcome: String) {
+		tracing::debug!(
+			target: LOG_TARGET,
+			method,
+			args = %args,
+			outcome = %outcome,
+			"FileOpener call"
+		);
+		if let Some(log) = &self.log {
+			log.lock()
+				.unwrap_or_else(|e| e.into_inner())
+				.push(LoggedCall {
+					method,
+					args,
+					outcome,
+				});
+		}
+	}
+}
+
+impl<T: FileOpener + ?Sized> fmt::Debug for LoggingFileOpener<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("LoggingFileOpener")
+			.field("call_log", &self.log.is_some())
+			.finish_non_exhaustive()
+	}
+}
+
+impl<T: FileOpener + ?Sized> FileOpener for LoggingFileOpener<T> {
+	fn get_apps_for_file(&self, path: &Path) -> Result<Vec<OpenWithApp>, FileOpenError> {
+		self.logged("get_apps_for_file", format_args!("{path:?}"), |inner| {
+			inner.get_apps_for_file(path)
+		})
+	}
+
+	fn get_all_apps_for_file(&self, path: &Path) -> Result<Vec<OpenWithApp>, FileOpenError> {
+		self.logged("get_all_apps_for_file", format_args!("{path:?}"), |inner| {
+			inner.get_all_apps_for_file(path)
+		})
+	}
+
+	fn get_apps_for_file_cancellable(
+		&self,
+		path: &Path,
+		cancel: &CancellationToken,
+	) -> Result<Vec<OpenWithApp>, FileOpenError> {
+		self.logged(
+			"get_apps_for_file_cancellable",
+			format_args!("{path:?}"),
+			|inner| inner.get_apps_for_file_cancellable(path, cancel),
+		)
+	}
+
+	fn get_all_apps_for_file_cancellable(
+		&self,
+		path: &Path,
+		cancel: &CancellationToken,
+	) -> Result<Vec<OpenWithApp>, FileOpenError> {
+		self.logged(
+			"get_all_apps_for_file_cancellable",
+			format_args!("{path:?}"),
+			|inner| inner.get_all_apps_for_file_cancellable(path, cancel),
+		)
+	}
+
+	fn count_apps_for_file(&self, path: &Path) -> Result<usize, FileOpenError> {
+		self.logged("count_apps_for_file", format_args!("{path:?}"), |inner| {
+			inner.count_apps_for_file(path)
+		})
+	}
+
+	fn get_apps_for_file_paged(
+		&self,
+		path: &Path,
+		offset: usize,
+		limit: usize,
+	) -> Result<(Vec<OpenWithApp>, usize), FileOpenError> {
+		self.logged(
+			"get_apps_for_file_paged",
+			format_args!("{path:?}, {offset}, {limit}"),
+			|inner| inner.get_apps_for_file_paged(path, offset, limit),
+		)
+	}
+
+	fn count_apps_for_file_up_to(&self, path: &Path, cap: usize) -> Result<usize, FileOpenError> {
+		self.logged(
+			"count_apps_for_file_up_to",
+			format_args!("{path:?}, {cap}"),
+			|inner| inner.count_apps_for_file_up_to(path, cap),
+		)
+	}
+
+	fn get_apps_for_files(&self, paths: &[PathBuf]) -> Result<Vec<OpenWithApp>, FileOpenError> {
+		self.logged("get_apps_for_files", format_args!("{paths:?}"), |inner| {
+			inner.get_apps_for_files(paths)
+		})
+	}
+
+	fn get_common_apps(
+		&self,
+		paths: &[&Path],
+		extensionless: ExtensionlessFiles,
+	) -> Result<Vec<OpenWithApp>, FileOpenError> {
+		self.logged(
+			"get_common_apps",
+			format_args!("{paths:?}, {extensionless:?}"),
+			|inner| inner.get_common_apps(paths, extensionless),
+		)
+	}
+
+	fn get_apps_for_mime_type(&self, mime: &str) -> Result<Vec<OpenWithApp>, FileOpenError> {
+		self.logged(
+			"get_apps_for_mime_type",
+			format_args!("{mime:?}"),
+			|inner| inner.get_apps_for_mime_type(mime),
+		)
+	}
+
+	fn list_association_extensions(&self) -> Result<Vec<String>, FileOpenError> {
+		self.logged("list_association_extensions", format_args!(""), |inner| {
+			inner.list_association_extensions()
+		})
+	}
+
+	fn get_association(&self, extension: &str) -> Result<FileAssociation, FileOpenError> {
+		self.logged("get_association", format_args!("{extension:?}"), |inner| {
+			inner.get_association(extension)
+		})
+	}
+
+	/// Each association is logged as it's yielded.
+	fn associations_iter(
+		&self,
+	) -> Box<dyn Iterator<Item = Result<FileAssociation, FileOpenError>> + '_> {
+		Box::new(self.inner.associations_iter().inspect(|association| {
+			if self.recording() {
+				self.record(
+					"associations_iter",
+					String::new(),
+					describe(format_args!("{association:?}")),
+				);
+			}
+		}))
+	}
+
+	fn list_associations(&self) -> Result<Vec<FileAssociation>, FileOpenError> {
+		self.logged("list_associations", format_args!(""), |inner| {
+			inner.list_associations()
+		})
+	}
+
+	fn get_default_app(&self, path: &Path) -> Result<Option<OpenWithApp>, FileOpenError> {
+		self.logged("get_default_app", format_args!("{path:?}"), |inner| {
+			inner.get_default_app(path)
+		})
+	}
+
+	fn can_open(&self, path: &Path) -> Result<bool, FileOpenError> {
+		self.logged("can_open", format_args!("{path:?}"), |inner| {
+			inner.can_open(path)
+		})
+	}
+
+	fn open_with_default(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+		self.logged("open_with_default", format_args!("{path:?}"), |inner| {
+			inner.open_with_default(path)
+		})
+	}
+
+	fn default_overrides(&self) -> Option<&DefaultOverrides> {
+		self.inner.default_overrides()
+	}
+
+	fn add_override(&self, extension: &str, app_id: &str) -> Result<(), FileOpenError> {
+		self.logged(
+			"add_override",
+			format_args!("{extension:?}, {app_id:?}"),
+			|inner| inner.add_override(extension, app_id),
+		)
+	}
+
+	fn clear_override(&self, extension: &str) -> Result<(), FileOpenError> {
+		self.logged("clear_override", format_args!("{extension:?}"), |inner| {
+			inner.clear_override(extension)
+		})
+	}
+
+	fn is_risky(&self, path: &Path) -> Result<bool, FileOpenError> {
+		self.logged("is_risky", format_args!("{path:?}"), |inner| {
+			inner.is_risky(path)
+		})
+	}
+
+	fn open_history(&self) -> Option<&OpenHistory> {
+		self.inner.open_history()
+	}
+
+	fn recent_apps_for(
+		&self,
+		extension: &str,
+		limit: usize,
+	) -> Result<Vec<OpenWithApp>, FileOpenError> {
+		self.logged(
+			"recent_apps_for",
+			format_args!("{extension:?}, {limit}"),
+			|inner| inner.recent_apps_for(extension, limit),
+		)
+	}
+
+	fn open_elevated(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+		self.logged("open_elevated", format_args!("{path:?}"), |inner| {
+			inner.open_elevated(path)
+		})
+	}
+
+	fn print(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+		self.logged("print", format_args!("{path:?}"), |inner| inner.print(path))
+	}
+
+	fn can_print(&self, path: &Path) -> Result<bool, FileOpenError> {
+		self.logged("can_print", format_args!("{path:?}"), |inner| {
+			inner.can_print(path)
+		})
+	}
+
+	fn edit(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+		self.logged("edit", format_args!("{path:?}"), |inner| inner.edit(path))
+	}
+
+	fn can_edit(&self, path: &Path) -> Result<bool, FileOpenError> {
+		self.logged("can_edit", format_args!("{path:?}"), |inner| {
+			inner.can_edit(path)
+		})
+	}
+
+	fn open_with_verb(&self, path: &Path, verb: &str) -> Result<OpenResult, FileOpenError> {
+		self.logged(
+			"open_with_verb",
+			format_args!("{path:?}, {verb:?}"),
+			|inner| inner.open_with_verb(path, verb),
+		)
+	}
+
+	fn list_verbs(&self, path: &Path) -> Result<Vec<String>, FileOpenError> {
+		self.logged("list_verbs", format_args!("{path:?}"), |inner| {
+			inner.list_verbs(path)
+		})
+	}
+
+	fn open_many_with_default(&self, paths: &[&Path]) -> Result<Vec<OpenResult>, FileOpenError> {
+		self.logged(
+			"open_many_with_default",
+			format_args!("{paths:?}"),
+			|inner| inner.open_many_with_default(paths),
+		)
+	}
+
+	fn open_with_app(&self, path: &Path, app_id: &str) -> Result<OpenResult, FileOpenError> {
+		self.logged(
+			"open_with_app",
+			format_args!("{path:?}, {app_id:?}"),
+			|inner| inner.open_with_app(path, app_id),
+		)
+	}
+
+	fn open_with_app_args(
+		&self,
+		path: &Path,
+		app_id: &str,
+		args: &[&str],
+	) -> Result<OpenResult, FileOpenError> {
+		self.logged(
+			"open_with_app_args",
+			format_args!("{path:?}, {app_id:?}, {args:?}"),
+			|inner| inner.open_with_app_args(path, app_id, args),
+		)
+	}
+
+	fn open_with_app_and_set_default(
+		&self,
+		path: &Path,
+		app_id: &str,
+	) -> Result<DefaultedOpen, FileOpenError> {
+		self.logged(
+			"open_with_app_and_set_default",
+			format_args!("{path:?}, {app_id:?}"),
+			|inner| inner.open_with_app_and_set_default(path, app_id),
+		)
+	}
+
+	fn open_with_executable(
+		&self,
+		path: &Path,
+		exe: &Path,
+		args: &[&str],
+	) -> Result<OpenResult, FileOpenError> {
+		self.logged(
+			"open_with_executable",
+			format_args!("{path:?}, {exe:?}, {args:?}"),
+			|inner| inner.open_with_executable(path, exe, args),
+		)
+	}
+
+	fn open_and_wait(
+		&self,
+		path: &Path,
+		app_id: Option<&str>,
+	) -> Result<ProcessOutcome, FileOpenError> {
+		self.logged(
+			"open_and_wait",
+			format_args!("{path:?}, {app_id:?}"),
+			|inner| inner.open_and_wait(path, app_id),
+		)
+	}
+
+	fn launch_app(&self, app_id: &str) -> Result<OpenResult, FileOpenError> {
+		self.logged("launch_app", format_args!("{app_id:?}"), |inner| {
+			inner.launch_app(app_id)
+		})
+	}
+
+	fn launch_app_with_args(
+		&self,
+		app_id: &str,
+		args: &[&str],
+	) -> Result<OpenResult, FileOpenError> {
+		self.logged(
+			"launch_app_with_args",
+			format_args!("{app_id:?}, {args:?}"),
+			|inner| inner.launch_app_with_args(app_id, args),
+		)
+	}
+
+	fn open_shell_item(&self, parsing_name: &str) -> Result<OpenResult, FileOpenError> {
+		self.logged(
+			"open_shell_item",
+			format_args!("{parsing_name:?}"),
+			|inner| inner.open_shell_item(parsing_name),
+		)
+	}
+
+	fn launch(&self, spec: &LaunchSpec) -> Result<OpenResult, FileOpenError> {
+		self.logged("launch", format_args!("{spec:?}"), |inner| {
+			inner.launch(spec)
+		})
+	}
+
+	fn open_at(&self, target: &OpenTarget, app_id: &str) -> Result<OpenResult, FileOpenError> {
+		self.logged("open_at", format_args!("{target:?}, {app_id:?}"), |inner| {
+			inner.open_at(target, app_id)
+		})
+	}
+
+	fn open_with_default_or_prompt(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+		self.logged(
+			"open_with_default_or_prompt",
+			format_args!("{path:?}"),
+			|inner| inner.open_with_default_or_prompt(path),
+		)
+	}
+
+	fn open_readonly(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+		self.logged("open_readonly", format_args!("{path:?}"), |inner| {
+			inner.open_readonly(path)
+		})
+	}
+
+	/// Logs the size of `bytes` rather than the bytes.
+	fn open_bytes(&self, bytes: &[u8], suggested_name: &str) -> Result<OpenResult, FileOpenError> {
+		self.logged(
+			"open_bytes",
+			format_args!("{} bytes, {suggested_name:?}", bytes.len()),
+			|inner| inner.open_bytes(bytes, suggested_name),
+		)
+	}
+
+	fn cleanup_temp_files(&self) -> usize {
+		self.logged("cleanup_temp_files", format_args!(""), |inner| {
+			inner.cleanup_temp_files()
+		})
+	}
+
+	fn open_with_dialog(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+		self.logged("open_with_dialog", format_args!("{path:?}"), |inner| {
+			inner.open_with_dialog(path)
+		})
+	}
+
+	fn open_files_with_app(
+		&self,
+		paths: &[PathBuf],
+		app_id: &str,
+	) -> Result<Vec<OpenResult>, FileOpenError> {
+		self.logged(
+			"open_files_with_app",
+			format_args!("{paths:?}, {app_id:?}"),
+			|inner| inner.open_files_with_app(paths, app_id),
+		)
+	}
+
+	fn open_many_with_app(
+		&self,
+		paths: &[&Path],
+		app_id: &str,
+	) -> Result<OpenResult, FileOpenError> {
+		self.logged(
+			"open_many_with_app",
+			format_args!("{paths:?}, {app_id:?}"),
+			|inner| inner.open_many_with_app(paths, app_id),
+		)
+	}
+
+	fn open_uri(&self, uri: &str) -> Result<OpenResult, FileOpenError> {
+		self.logged("open_uri", format_args!("{uri:?}"), |inner| {
+			inner.open_uri(uri)
+		})
+	}
+
+	fn open_uri_with_policy(
+		&self,
+		uri: &str,
+		policy: &UriPolicy,
+	) -> Result<OpenResult, FileOpenError> {
+		self.logged(
+			"open_uri_with_policy",
+			format_args!("{uri:?}, {policy:?}"),
+			|inner| inner.open_uri_with_policy(uri, policy),
+		)
+	}
+
+	fn reveal(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+		self.logged("reveal", format_args!("{path:?}"), |inner| {
+			inner.reveal(path)
+		})
+	}
+
+	fn reveal_many(&self, paths: &[&Path]) -> Result<Vec<OpenResult>, FileOpenError> {
+		self.logged("reveal_many", format_args!("{paths:?}"), |inner| {
+			inner.reveal_many(paths)
+		})
+	}
+
+	fn move_to_trash(&self, paths: &[&Path]) -> Result<Vec<OpenResult>, FileOpenError> {
+		self.logged("move_to_trash", format_args!("{paths:?}"), |inner| {
+			inner.move_to_trash(paths)
+		})
+	}
+
+	fn resolve_shortcut(&self, path: &Path) -> Result<Option<PathBuf>, FileOpenError> {
+		self.logged("resolve_shortcut", format_args!("{path:?}"), |inner| {
+			inner.resolve_shortcut(path)
+		})
+	}
+
+	fn show_properties(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+		self.logged("show_properties", format_args!("{path:?}"), |inner| {
+			inner.show_properties(path)
+		})
+	}
+
+	fn open_mail_with_attachment(
+		&self,
+		attachments: &[&Path],
+		subject: Option<&str>,
+		body: Option<&str>,
+	) -> Result<OpenResult, FileOpenError> {
+		self.logged(
+			"open_mail_with_attachment",
+			format_args!("{attachments:?}, {subject:?}, {body:?}"),
+			|inner| inner.open_mail_with_attachment(attachments, subject, body),
+		)
+	}
+
+	fn get_thumbnail(&self, path: &Path, size: u32) -> Result<Option<Vec<u8>>, FileOpenError> {
+		self.logged("get_thumbnail", format_args!("{path:?}, {size}"), |inner| {
+			inner.get_thumbnail(path, size)
+		})
+	}
+
+	fn set_default_app(&self, extension: &str, app_id: &str) -> Result<OpenResult, FileOpenError> {
+		self.logged(
+			"set_default_app",
+			format_args!("{extension:?}, {app_id:?}"),
+			|inner| inner.set_default_app(extension, app_id),
+		)
+	}
+
+	fn clear_cache(&self) {
+		self.logged("clear_cache", format_args!(""), |inner| inner.clear_cache())
+	}
+
+	fn clear_icon_cache(&self) {
+		self.logged("clear_icon_cache", format_args!(""), |inner| {
+			inner.clear_icon_cache()
+		})
+	}
+
+	fn refresh(&self) {
+		self.logged("refresh", format_args!(""), |inner| inner.refresh())
+	}
+
+	fn watch_association_changes(
+		&self,
+		callback: Box<dyn Fn() + Send + Sync>,
+	) -> Result<WatchHandle, FileOpenError> {
+		self.logged("watch_association_changes", format_args!(""), |inner| {
+			inner.watch_association_changes(callback)
+		})
+	}
+
+	fn capabilities(&self) -> OpenerCapabilities {
+		self.inner.capabilities()
+	}
+}
+
+/// `args` formatted, cut short after [`MAX_DESCRIPTION`] bytes.
+fn describe(args: fmt::Arguments<'_>) -> String {
+	let mut out = Bounded(String::new());
+	// The only error is `Bounded` running out of room, which stops the
+	// formatting early instead of building the whole thing.
+	if out.write_fmt(args).is_err() {
+		out.0.push('…');
+	}
+	out.0
+}
+
+/// A `String` that refuses writes past [`MAX_DESCRIPTION`].
+struct Bounded(String);
+
+impl Write for Bounded {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		let room = MAX_DESCRIPTION - self.0.len();
+		if s.len() <= room {
+			self.0.push_str(s);
+			return Ok(());
+		}
+
+		let mut end = room;
+		while !s.is_char_boundary(end) {
+			end -= 1;
+		}
+		self.0.push_str(&s[..end]);
+		Err(fmt::Error)
+	}
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+	use super::*;
+	use crate::MockFileOpener;
+	use std::collections::HashMap;
+
+	fn mock() -> Arc<MockFileOpener> {
+		Arc::new(MockFileOpener::new(HashMap::from([(
+			"txt".to_string(),
+			vec![
+				OpenWithApp::new("notepad", "notepad"),
+				OpenWithApp::new("code", "code"),
+			],
+		)])))
+	}
+
+	#[test]
+	fn calls_are_forwarded_and_logged() {
+		let (direct, wrapped) = (mock(), mock());
+		let logging = LoggingFileOpener::new(Arc::clone(&wrapped)).with_call_log();
+		let path = Path::new("notes.txt");
+
+		let ids = |apps: Vec<OpenWithApp>| apps.into_iter().map(|app| app.id).collect::<Vec<_>>();
+		assert_eq!(
+			ids(logging.get_apps_for_file(path).unwrap()),
+			ids(direct.get_apps_for_file(path).unwrap())
+		);
+		assert_eq!(
+			format!("{:?}", logging.open_with_app(path, "paint")),
+			format!("{:?}", direct.open_with_app(path, "paint"))
+		);
+		assert_eq!(wrapped.calls(), direct.calls());
+
+		let calls = logging.calls();
+		assert_eq!(calls.len(), 2);
+		assert_eq!(calls[1].method, "open_with_app");
+		assert_eq!(calls[1].args, r#""notes.txt", "paint""#);
+		assert!(
+			calls[1].outcome.contains("AppNotFound"),
+			"{}",
+			calls[1].outcome
+		);
+	}
+
+	#[test]
+	fn long_descriptions_are_cut_short() {
+		let long = "é".repeat(MAX_DESCRIPTION);
+		let described = describe(format_args!("{long}"));
+		assert!(described.len() <= MAX_DESCRIPTION + '…'.len_utf8());
+		assert!(described.ends_with('…'));
+
+		assert_eq!(
+			describe(format_args!("{:?}", Path::new("a.txt"))),
+			r#""a.txt""#
+		);
+	}
+}
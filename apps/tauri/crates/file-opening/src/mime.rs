@@ -0,0 +1,220 @@
+//! # MIME types and extensions
+//!
+//! A built-in table of common types, for backends to fall back on when the
+//! system's own database doesn't know a type (Windows registers few MIME
+//! types beyond what its own apps handle), and the one normalized form
+//! extensions are compared and keyed in.
+//!
+//! Backends consult the system first; `file_opening_windows::mime` has the
+//! same functions reading the registry, with this table behind them.
+
+use std::path::Path;
+
+/// (extension, MIME type) pairs. Where a type has several extensions the
+/// first is the one it maps back to.
+const TABLE: &[(&str, &str)] = &[
+	("7z", "application/x-7z-compressed"),
+	("aac", "audio/aac"),
+	("avi", "video/x-msvideo"),
+	("avif", "image/avif"),
+	("bmp", "image/bmp"),
+	("css", "text/css"),
+	("csv", "text/csv"),
+	("doc", "application/msword"),
+	(
+		"docx",
+		"application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+	),
+	("epub", "application/epub+zip"),
+	("flac", "audio/flac"),
+	("gif", "image/gif"),
+	("gz", "application/gzip"),
+	("heic", "image/heic"),
+	("htm", "text/html"),
+	("html", "text/html"),
+	("ico", "image/vnd.microsoft.icon"),
+	("ics", "text/calendar"),
+	("jpg", "image/jpeg"),
+	("jpeg", "image/jpeg"),
+	("js", "text/javascript"),
+	("json", "application/json"),
+	("md", "text/markdown"),
+	("mkv", "video/x-matroska"),
+	("mov", "video/quicktime"),
+	("mp3", "audio/mpeg"),
+	("mp4", "video/mp4"),
+	("odt", "application/vnd.oasis.opendocument.text"),
+	("ogg", "audio/ogg"),
+	("opus", "audio/opus"),
+	("pdf", "application/pdf"),
+	("png", "image/png"),
+	("ppt", "application/vnd.ms-powerpoint"),
+	(
+		"pptx",
+		"application/vnd.openxmlformats-officedocument.presentationml.presentation",
+	),
+	("rar", "application/vnd.rar"),
+	("rtf", "application/rtf"),
+	("svg", "image/svg+xml"),
+	("tar", "application/x-tar"),
+	("tif", "image/tiff"),
+	("tiff", "image/tiff"),
+	("txt", "text/plain"),
+	("wav", "audio/wav"),
+	("webm", "video/webm"),
+	("webp", "image/webp"),
+	("xls", "application/vnd.ms-excel"),
+	(
+		"xlsx",
+		"application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+	),
+	("xml", "application/xml"),
+	("zip", "application/zip"),
+];
+
+/// `extension` lowercased and without leading dots, e.g. `tar.gz` for
+/// `.TAR.GZ`: the form extensions are compared and saved in.
+pub fn normalize_extension(extension: &str) -> String {
+	extension.trim().trim_start_matches('.').to_lowercase()
+}
+
+/// The extension of `path`'s file name, lowercased and dotted, e.g. `.txt`
+/// for `Notes.TXT`: the one rule every lookup by a file's extension uses
+///
+/// Only the last segment counts, so `archive.tar.gz` gives `.gz`. `None` when
+/// there's nothing to go by: no dot (`README`), only a leading one
+/// (`.bashrc`), a trailing one (`notes.`), or an extension that isn't valid
+/// Unicode and so can't name an association.
+pub fn derive_extension(path: &Path) -> Option<String> {
+	let extension = path.extension()?.to_str()?;
+	(!extension.is_empty()).then(|| format!(".{}", extension.to_lowercase()))
+}
+
+/// The usual extension for `mime`, normalized, from the built-in table
+///
+/// Parameters such as `; charset=utf-8` are ignored, and types compare
+/// case-insensitively.
+pub fn extension_for_mime(mime: &str) -> Option<String> {
+	let mime = essence(mime);
+	TABLE
+		.iter()
+		.find(|(_, known)| *known == mime)
+		.map(|(extension, _)| extension.to_string())
+}
+
+/// The MIME type of files with `extension` (with or without the leading
+/// dot), from the built-in table
+pub fn mime_for_extension(extension: &str) -> Option<String> {
+	let extension = normalize_extension(extension);
+	TABLE
+		.iter()
+		.find(|(known, _)| *known == extension)
+		.map(|(_, mime)| mime.to_string())
+}
+
+/// `mime` without parameters, lowercased.
+fn essence(mime: &str) -> String {
+	mime.split(';')
+		.next()
+		.unwrap_or_default()
+		.trim()
+		.to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn types_and_extensions_round_trip() {
+		assert_eq!(extension_for_mime("image/png").as_deref(), Some("png"));
+		assert_eq!(mime_for_extension(".png").as_deref(), Some("image/png"));
+		assert_eq!(mime_for_extension("PNG").as_deref(), Some("image/png"));
+
+		// Types with several extensions map back to the first.
+		assert_eq!(
+			extension_for_mime("Text/HTML; charset=utf-8").as_deref(),
+			Some("htm")
+		);
+		for (extension, mime) in TABLE {
+			let back = extension_for_mime(mime).unwrap();
+			assert_eq!(
+				mime_for_extension(&back).as_deref(),
+				Some(*mime),
+				"{extension}"
+			);
+		}
+
+		assert_eq!(extension_for_mime("application/x-made-up"), None);
+		assert_eq!(mime_for_extension("sd-not-a-type"), None);
+	}
+
+	#[test]
+	fn extensions_normalize_to_one_form() {
+		assert_eq!(normalize_extension(".TXT"), "txt");
+		assert_eq!(normalize_extension("txt"), "txt");
+		assert_eq!(normalize_extension("..Tar.GZ "), "tar.gz");
+	}
+
+	#[test]
+	fn extensions_come_from_the_last_segment() {
+		let derive = |name: &str| derive_extension(Path::new(name));
+		assert_eq!(derive("Notes.TXT").as_deref(), Some(".txt"));
+		assert_eq!(derive("dir.d/archive.tar.gz").as_deref(), Some(".gz"));
+		assert_eq!(derive("..hidden").as_deref(), Some(".hidden"));
+		for name in ["README", ".bashrc", "notes.", ".."] {
+			assert_eq!(derive(name), None, "{name}");
+		}
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn extensions_that_are_not_unicode_are_none() {
+		use std::ffi::OsStr;
+		use std::os::unix::ffi::OsStrExt;
+
+		let path = Path::new(OsStr::from_bytes(b"photo.jp\xffg"));
+		assert_eq!(derive_extension(path), None);
+	}
+
+	mod properties {
+		use super::*;
+		use proptest::prelude::*;
+
+		proptest! {
+			#[test]
+			fn derived_extensions_are_dotted_and_lowercase(name in "[^/\\\\\\x00]{0,24}") {
+				if let Some(extension) = derive_extension(Path::new(&name)) {
+					prop_assert!(extension.len() > 1);
+					prop_assert!(extension.starts_with('.'));
+					prop_assert_eq!(extension.matches('.').count(), 1);
+					prop_assert_eq!(extension.to_lowercase(), extension);
+				}
+			}
+
+			#[test]
+			fn dotfiles_have_no_extension(name in "\\.[^./\\\\\\x00]{1,16}") {
+				prop_assert_eq!(derive_extension(Path::new(&name)), None);
+			}
+
+			#[test]
+			fn the_last_segment_is_the_extension(
+				stem in "[^/\\\\\\x00]*[^./\\\\\\x00][^/\\\\\\x00]*",
+				extension in "[^./\\\\\\x00]{1,8}",
+			) {
+				prop_assert_eq!(
+					derive_extension(Path::new(&format!("{stem}.{extension}"))),
+					Some(format!(".{}", extension.to_lowercase()))
+				);
+			}
+
+			#[test]
+			fn case_is_ignored(name in "[a-zA-Z0-9 _.-]{1,24}") {
+				prop_assert_eq!(
+					derive_extension(Path::new(&name.to_uppercase())),
+					derive_extension(Path::new(&name.to_lowercase()))
+				);
+			}
+		}
+	}
+}
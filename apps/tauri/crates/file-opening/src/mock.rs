@@ -0,0 +1,814 @@
+//! # Mock opener
+//!
+//! [`MockFileOpener`] stands in for a platform backend in tests: apps are
+//! seeded per extension, every launch is recorded instead of performed, and
+//! a responder decides what each launch returns.
+//!
+//! ## Example
+//! ```rust,ignore
+//! let opener = MockFileOpener::new(HashMap::from([("txt".to_string(), vec![notepad])]));
+//! open_in_preferred_editor(&opener, Path::new("notes.txt"));
+//!
+//! assert!(opener.was_called(&MockCall::OpenWithApp {
+//!     path: "notes.txt".into(),
+//!     app_id: "notepad".to_string(),
+//! }));
+//! ```
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::{
+	mime, DefaultOverrides, FileOpenError, FileOpener, LaunchSpec, OpenHistory, OpenResult,
+	OpenWithApp, OpenerCapabilities, ProcessOutcome, UriPolicy,
+};
+
+type Responder = dyn Fn(&MockCall) -> Result<OpenResult, FileOpenError> + Send + Sync;
+
+/// A launch recorded by [`MockFileOpener`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MockCall {
+	OpenWithDefault {
+		path: PathBuf,
+	},
+	OpenElevated {
+		path: PathBuf,
+	},
+	Print {
+		path: PathBuf,
+	},
+	Edit {
+		path: PathBuf,
+	},
+	OpenWithVerb {
+		path: PathBuf,
+		verb: String,
+	},
+	OpenWithApp {
+		path: PathBuf,
+		app_id: String,
+	},
+	OpenWithAppArgs {
+		path: PathBuf,
+		app_id: String,
+		args: Vec<String>,
+	},
+	Launch {
+		spec: LaunchSpec,
+	},
+	OpenWithExecutable {
+		path: PathBuf,
+		exe: PathBuf,
+		args: Vec<String>,
+	},
+	LaunchApp {
+		app_id: String,
+	},
+	LaunchAppWithArgs {
+		app_id: String,
+		args: Vec<String>,
+	},
+	OpenShellItem {
+		parsing_name: String,
+	},
+	OpenAndWait {
+		path: PathBuf,
+		app_id: Option<String>,
+	},
+	OpenWithDialog {
+		path: PathBuf,
+	},
+	OpenUri {
+		uri: String,
+	},
+	Reveal {
+		path: PathBuf,
+	},
+	MoveToTrash {
+		path: PathBuf,
+	},
+	ShowProperties {
+		path: PathBuf,
+	},
+	OpenMailWithAttachment {
+		attachments: Vec<PathBuf>,
+		subject: Option<String>,
+		body: Option<String>,
+	},
+	SetDefaultApp {
+		extension: String,
+		app_id: String,
+	},
+}
+
+impl MockCall {
+	/// The app a launch with a specific app asked for.
+	fn app_id(&self) -> Option<&str> {
+		match self {
+			Self::OpenWithApp { app_id, .. } | Self::OpenWithAppArgs { app_id, .. } => Some(app_id),
+			Self::Launch { spec } => Some(&spec.app_id),
+			Self::OpenAndWait { app_id, .. } => app_id.as_deref(),
+			_ => None,
+		}
+	}
+}
+
+/// In-memory [`FileOpener`] for tests.
+///
+/// Without a responder every launch succeeds, except that opening with an app
+/// not seeded for the file's extension yields `AppNotFound`, as does starting
+/// an app on its own that isn't seeded for any extension. The default app
+/// for an extension is the seeded app marked `is_default`, and any extension
+/// with seeded apps counts as printable. Listing apps for a file without an
+/// extension fails with `NoExtension`. Successful launches with an app go
+/// into its [`OpenHistory`]. Default overrides are honoured as
+/// by a real backend, so an overridden `open_with_default` records an
+/// `OpenWithApp`.
+pub struct MockFileOpener {
+	apps: HashMap<String, Vec<OpenWithApp>>,
+	responder: Option<Box<Responder>>,
+	calls: Mutex<Vec<MockCall>>,
+	overrides: DefaultOverrides,
+	history: OpenHistory,
+}
+
+impl MockFileOpener {
+	/// Seeds the opener with apps per extension, given without the dot and
+	/// matched case-insensitively.
+	pub fn new(apps: HashMap<String, Vec<OpenWithApp>>) -> Self {
+		Self {
+			apps: apps
+				.into_iter()
+				.map(|(ext, apps)| (mime::normalize_extension(&ext), apps))
+				.collect(),
+			responder: None,
+			calls: Mutex::new(Vec::new()),
+			overrides: DefaultOverrides::new(),
+			history: OpenHistory::new(),
+		}
+	}
+
+	/// Decides the outcome of every launch, e.g. to return `Cancelled` or an
+	/// error for one path.
+	pub fn with_responder(
+		mut self,
+		responder: impl Fn(&MockCall) -> Result<OpenResult, FileOpenError> + Send + Sync + 'static,
+	) -> Self {
+		self.responder = Some(Box::new(responder));
+		self
+	}
+
+	/// Every launch so far, oldest first.
+	pub fn calls(&self) -> Vec<MockCall> {
+		self.calls.lock().unwrap_or_else(|e| e.into_inner()).clone()
+	}
+
+	pub fn was_called(&self, call: &MockCall) -> bool {
+		self.calls().contains(call)
+	}
+
+	fn apps_for(&self, path: &Path) -> &[OpenWithApp] {
+		mime::derive_extension(path)
+			.and_then(|ext| self.apps.get(&mime::normalize_extension(&ext)))
+			.map_or(&[], Vec::as_slice)
+	}
+
+	fn record(&self, call: MockCall, path: Option<&Path>) -> Result<OpenResult, FileOpenError> {
+		self.calls
+			.lock()
+			.unwrap_or_else(|e| e.into_inner())
+			.push(call.clone());
+
+		let result = self.respond(&call, path);
+		if let (Ok(result), Some(path), Some(app_id)) = (&result, path, call.app_id()) {
+			self.history.record(path, app_id, result);
+		}
+		result
+	}
+
+	fn respond(&self, call: &MockCall, path: Option<&Path>) -> Result<OpenResult, FileOpenError> {
+		if let Some(responder) = &self.responder {
+			return responder(call);
+		}
+
+		if let MockCall::LaunchApp { app_id } | MockCall::LaunchAppWithArgs { app_id, .. } = call {
+			let known = self.apps.values().flatten().any(|app| app.id == *app_id);
+			return Ok(if known {
+				OpenResult::success()
+			} else {
+				OpenResult::AppNotFound {
+					app_id: app_id.clone(),
+				}
+			});
+		}
+
+		let Some(app_id) = call.app_id() else {
+			return Ok(OpenResult::success());
+		};
+
+		let known =
+			path.is_some_and(|path| self.apps_for(path).iter().any(|app| app.id == *app_id));
+		Ok(if known {
+			OpenResult::success()
+		} else {
+			OpenResult::AppNotFound {
+				app_id: app_id.to_string(),
+			}
+		})
+	}
+}
+
+impl FileOpener for MockFileOpener {
+	fn get_apps_for_file(&self, path: &Path) -> Result<Vec<OpenWithApp>, FileOpenError> {
+		if mime::derive_extension(path).is_none() {
+			return Err(FileOpenError::NoExtension);
+		}
+
+		Ok(self.apps_for(path).to_vec())
+	}
+
+	fn get_default_app(&self, path: &Path) -> Result<Option<OpenWithApp>, FileOpenError> {
+		Ok(self
+			.apps_for(path)
+			.iter()
+			.find(|app| app.is_default)
+			.cloned())
+	}
+
+	fn open_with_default(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+		if let Some(result) = self.overrides.open(self, path)? {
+			return Ok(result);
+		}
+
+		self.record(
+			MockCall::OpenWithDefault {
+				path: path.to_path_buf(),
+			},
+			Some(path),
+		)
+	}
+
+	fn open_elevated(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+		self.record(
+			MockCall::OpenElevated {
+				path: path.to_path_buf(),
+			},
+			Some(path),
+		)
+	}
+
+	fn print(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+		self.record(
+			MockCall::Print {
+				path: path.to_path_buf(),
+			},
+			Some(path),
+		)
+	}
+
+	fn can_print(&self, path: &Path) -> Result<bool, FileOpenError> {
+		Ok(!self.apps_for(path).is_empty())
+	}
+
+	fn edit(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+		self.record(
+			MockCall::Edit {
+				path: path.to_path_buf(),
+			},
+			Some(path),
+		)
+	}
+
+	fn can_edit(&self, path: &Path) -> Result<bool, FileOpenError> {
+		Ok(!self.apps_for(path).is_empty())
+	}
+
+	fn open_with_verb(&self, path: &Path, verb: &str) -> Result<OpenResult, FileOpenError> {
+		self.record(
+			MockCall::OpenWithVerb {
+				path: path.to_path_buf(),
+				verb: verb.to_string(),
+			},
+			Some(path),
+		)
+	}
+
+	fn open_with_app(&self, path: &Path, app_id: &str) -> Result<OpenResult, FileOpenError> {
+		self.record(
+			MockCall::OpenWithApp {
+				path: path.to_path_buf(),
+				app_id: app_id.to_string(),
+			},
+			Some(path),
+		)
+	}
+
+	fn open_with_app_args(
+		&self,
+		path: &Path,
+		app_id: &str,
+		args: &[&str],
+	) -> Result<OpenResult, FileOpenError> {
+		self.record(
+			MockCall::OpenWithAppArgs {
+				path: path.to_path_buf(),
+				app_id: app_id.to_string(),
+				args: args.iter().map(|arg| arg.to_string()).collect(),
+			},
+			Some(path),
+		)
+	}
+
+	fn launch(&self, spec: &LaunchSpec) -> Result<OpenResult, FileOpenError> {
+		spec.check_working_dir()?;
+		self.record(MockCall::Launch { spec: spec.clone() }, Some(&spec.path))
+	}
+
+	fn open_with_executable(
+		&self,
+		path: &Path,
+		exe: &Path,
+		args: &[&str],
+	) -> Result<OpenResult, FileOpenError> {
+		self.record(
+			MockCall::OpenWithExecutable {
+				path: path.to_path_buf(),
+				exe: exe.to_path_buf(),
+				args: args.iter().map(|arg| arg.to_string()).collect(),
+			},
+			Some(path),
+		)
+	}
+
+	/// Successful launches exit with `0` right away.
+	fn open_and_wait(
+		&self,
+		path: &Path,
+		app_id: Option<&str>,
+	) -> Result<ProcessOutcome, FileOpenError> {
+		let result = self.record(
+			MockCall::OpenAndWait {
+				path: path.to_path_buf(),
+				app_id: app_id.map(str::to_string),
+			},
+			Some(path),
+		)?;
+		Ok(ProcessOutcome {
+			exit_code: matches!(result, OpenResult::Success { .. }).then_some(0),
+			result,
+		})
+	}
+
+	fn launch_app(&self, app_id: &str) -> Result<OpenResult, FileOpenError> {
+		self.record(
+			MockCall::LaunchApp {
+				app_id: app_id.to_string(),
+			},
+			None,
+		)
+	}
+
+	fn launch_app_with_args(
+		&self,
+		app_id: &str,
+		args: &[&str],
+	) -> Result<OpenResult, FileOpenError> {
+		self.record(
+			MockCall::LaunchAppWithArgs {
+				app_id: app_id.to_string(),
+				args: args.iter().map(|arg| arg.to_string()).collect(),
+			},
+			None,
+		)
+	}
+
+	fn open_shell_item(&self, parsing_name: &str) -> Result<OpenResult, FileOpenError> {
+		self.record(
+			MockCall::OpenShellItem {
+				parsing_name: parsing_name.to_string(),
+			},
+			None,
+		)
+	}
+
+	fn default_overrides(&self) -> Option<&DefaultOverrides> {
+		Some(&self.overrides)
+	}
+
+	fn open_history(&self) -> Option<&OpenHistory> {
+		Some(&self.history)
+	}
+
+	fn open_with_dialog(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+		self.record(
+			MockCall::OpenWithDialog {
+				path: path.to_path_buf(),
+			},
+			Some(path),
+		)
+	}
+
+	fn open_uri_with_policy(
+		&self,
+		uri: &str,
+		policy: &UriPolicy,
+	) -> Result<OpenResult, FileOpenError> {
+		// Rejected URIs never reach a platform, so they aren't recorded either.
+		policy.check(uri)?;
+		self.record(
+			MockCall::OpenUri {
+				uri: uri.to_string(),
+			},
+			None,
+		)
+	}
+
+	fn reveal(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+		self.record(
+			MockCall::Reveal {
+				path: path.to_path_buf(),
+			},
+			Some(path),
+		)
+	}
+
+	/// One call per path, so a responder can fail or cancel a single one.
+	fn move_to_trash(&self, paths: &[&Path]) -> Result<Vec<OpenResult>, FileOpenError> {
+		paths
+			.iter()
+			.map(|path| {
+				self.record(
+					MockCall::MoveToTrash {
+						path: path.to_path_buf(),
+					},
+					Some(path),
+				)
+			})
+			.collect()
+	}
+
+	fn show_properties(&self, path: &Path) -> Result<OpenResult, FileOpenError> {
+		self.record(
+			MockCall::ShowProperties {
+				path: path.to_path_buf(),
+			},
+			Some(path),
+		)
+	}
+
+	fn open_mail_with_attachment(
+		&self,
+		attachments: &[&Path],
+		subject: Option<&str>,
+		body: Option<&str>,
+	) -> Result<OpenResult, FileOpenError> {
+		self.record(
+			MockCall::OpenMailWithAttachment {
+				attachments: attachments.iter().map(|path| path.to_path_buf()).collect(),
+				subject: subject.map(str::to_string),
+				body: body.map(str::to_string),
+			},
+			None,
+		)
+	}
+
+	fn set_default_app(&self, extension: &str, app_id: &str) -> Result<OpenResult, FileOpenError> {
+		self.record(
+			MockCall::SetDefaultApp {
+				extension: extension.to_string(),
+				app_id: app_id.to_string(),
+			},
+			None,
+		)
+	}
+
+	/// Everything it records a call for; its apps have no icons and it has
+	/// no thumbnails.
+	fn capabilities(&self) -> OpenerCapabilities {
+		OpenerCapabilities {
+			reveal: true,
+			elevated: true,
+			verbs: true,
+			dialog: true,
+			executable: true,
+			launch: true,
+			wait: true,
+			shell_items: true,
+			print: true,
+			edit: true,
+			properties: true,
+			icons: false,
+			thumbnails: false,
+			set_default: true,
+			uri: true,
+			trash: true,
+			mail: true,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{ExtensionlessFiles, OpenTarget};
+
+	fn app(id: &str, is_default: bool) -> OpenWithApp {
+		OpenWithApp {
+			is_default,
+			..OpenWithApp::new(id, id)
+		}
+	}
+
+	fn opener() -> MockFileOpener {
+		MockFileOpener::new(HashMap::from([(
+			"TXT".to_string(),
+			vec![app("notepad", true), app("code", false)],
+		)]))
+	}
+
+	#[test]
+	fn serves_seeded_apps_and_records_launches() {
+		let opener = opener();
+		let path = Path::new("notes.txt");
+
+		assert_eq!(opener.get_apps_for_file(path).unwrap().len(), 2);
+		assert_eq!(
+			opener.get_default_app(path).unwrap().map(|app| app.id),
+			Some("notepad".to_string())
+		);
+		assert!(opener
+			.get_apps_for_file(Path::new("a.png"))
+			.unwrap()
+			.is_empty());
+		assert!(matches!(
+			opener.get_apps_for_file(Path::new("Makefile")),
+			Err(FileOpenError::NoExtension)
+		));
+
+		assert!(matches!(
+			opener.open_with_app(path, "code"),
+			Ok(OpenResult::Success { .. })
+		));
+		assert!(matches!(
+			opener.open_with_app(path, "paint"),
+			Ok(OpenResult::AppNotFound { .. })
+		));
+		assert!(opener.was_called(&MockCall::OpenWithApp {
+			path: path.to_path_buf(),
+			app_id: "code".to_string(),
+		}));
+		assert_eq!(opener.calls().len(), 2);
+	}
+
+	#[test]
+	fn always_using_an_app_opens_then_sets_the_default() {
+		let opener = opener();
+		let path = Path::new("notes.txt");
+
+		let opened = opener.open_with_app_and_set_default(path, "code").unwrap();
+		assert!(matches!(opened.result, OpenResult::Success { .. }));
+		assert!(matches!(opened.default, Some(OpenResult::Success { .. })));
+		assert_eq!(
+			opener.calls()[1],
+			MockCall::SetDefaultApp {
+				extension: ".txt".to_string(),
+				app_id: "code".to_string(),
+			}
+		);
+
+		// An app that didn't open isn't made the default.
+		let missing = opener.open_with_app_and_set_default(path, "paint").unwrap();
+		assert!(matches!(missing.result, OpenResult::AppNotFound { .. }));
+		assert!(missing.default.is_none());
+		assert_eq!(opener.calls().len(), 3);
+	}
+
+	#[test]
+	fn opening_many_with_an_app_reports_the_first_failure() {
+		let opener = opener();
+		let (notes, todo) = (Path::new("notes.txt"), Path::new("todo.TXT"));
+
+		assert!(matches!(
+			opener.open_many_with_app(&[notes, todo], "code"),
+			Ok(OpenResult::Success { .. })
+		));
+
+		// Every file is still tried.
+		let result = opener.open_many_with_app(&[notes, Path::new("a.png"), todo], "code");
+		assert!(matches!(result, Ok(OpenResult::AppNotFound { app_id }) if app_id == "code"));
+		assert_eq!(opener.calls().len(), 5);
+	}
+
+	#[test]
+	fn opening_at_a_line_uses_the_app_convention() {
+		let editor = |id: &str, exe: &str| OpenWithApp {
+			exe_path: Some(PathBuf::from(exe)),
+			..app(id, false)
+		};
+		let opener = MockFileOpener::new(HashMap::from([(
+			"txt".to_string(),
+			vec![
+				app("notepad", true),
+				editor("code", "/usr/bin/code"),
+				editor("npp", "/opt/npp/notepad++.exe"),
+			],
+		)]));
+		let dir = std::env::temp_dir().join(format!("sd-open-at-{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		let path = dir.join("notes.txt");
+		std::fs::write(&path, b"").unwrap();
+		let target = OpenTarget {
+			line: Some(7),
+			..OpenTarget::new(&path)
+		};
+
+		for app_id in ["code", "npp", "notepad"] {
+			assert!(matches!(
+				opener.open_at(&target, app_id),
+				Ok(OpenResult::Success { .. })
+			));
+		}
+		let calls = opener.calls();
+		assert_eq!(calls.len(), 3);
+		assert_eq!(
+			calls[0],
+			MockCall::LaunchAppWithArgs {
+				app_id: "code".to_string(),
+				args: vec!["--goto".to_string(), format!("{}:7", path.display())],
+			}
+		);
+		assert!(matches!(&calls[1], MockCall::Launch { spec } if spec.args == ["-n7"]));
+		assert_eq!(
+			calls[2],
+			MockCall::OpenWithApp {
+				path: path.clone(),
+				app_id: "notepad".to_string(),
+			}
+		);
+
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn responder_decides_outcomes() {
+		let opener = opener().with_responder(|call| match call {
+			MockCall::OpenWithDefault { path } if path.ends_with("locked.txt") => {
+				Ok(OpenResult::PermissionDenied {
+					path: path.to_string_lossy().to_string(),
+				})
+			}
+			MockCall::Reveal { .. } => Err(FileOpenError::Other("no file manager".to_string())),
+			_ => Ok(OpenResult::Cancelled),
+		});
+
+		assert!(matches!(
+			opener.open_with_default(Path::new("locked.txt")),
+			Ok(OpenResult::PermissionDenied { .. })
+		));
+		assert!(matches!(
+			opener.open_with_default(Path::new("notes.txt")),
+			Ok(OpenResult::Cancelled)
+		));
+		assert!(opener.reveal(Path::new("notes.txt")).is_err());
+		assert!(opener.open_uri("ms-msdt:/id").is_err());
+		assert_eq!(opener.calls().len(), 3);
+	}
+
+	#[test]
+	fn overrides_win_until_cleared() {
+		let opener = opener();
+		let path = Path::new("notes.txt");
+
+		opener.add_override("txt", "code").unwrap();
+		assert!(matches!(
+			opener.open_with_default(path),
+			Ok(OpenResult::OpenedWithOverride { app_id }) if app_id == "code"
+		));
+		assert_eq!(
+			opener.calls(),
+			[MockCall::OpenWithApp {
+				path: path.to_path_buf(),
+				app_id: "code".to_string(),
+			}]
+		);
+
+		opener.clear_override(".TXT").unwrap();
+		assert!(matches!(
+			opener.open_with_default(path),
+			Ok(OpenResult::Success { .. })
+		));
+		assert!(opener.was_called(&MockCall::OpenWithDefault {
+			path: path.to_path_buf(),
+		}));
+	}
+
+	#[test]
+	fn launches_are_recorded_whole() {
+		let opener = opener();
+		let spec = LaunchSpec {
+			args: vec!["--goto".to_string()],
+			working_dir: Some(std::env::temp_dir()),
+			..LaunchSpec::new("notes.txt", "code")
+		};
+
+		assert!(matches!(
+			opener.launch(&spec),
+			Ok(OpenResult::Success { .. })
+		));
+		assert!(matches!(
+			opener.launch(&LaunchSpec::new("notes.txt", "paint")),
+			Ok(OpenResult::AppNotFound { .. })
+		));
+		assert_eq!(opener.calls()[0], MockCall::Launch { spec });
+	}
+
+	#[test]
+	fn read_only_opens_hand_over_a_copy() {
+		let _serial = crate::temp::tests::serial();
+		let dir = std::env::temp_dir().join(format!("sd-mock-readonly-{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		let file = dir.join("notes.txt");
+		std::fs::write(&file, "notes").unwrap();
+		let opener = opener();
+
+		assert!(matches!(
+			opener.open_readonly(&file),
+			Ok(OpenResult::OpenedReadOnly { enforced: true })
+		));
+		let [MockCall::OpenWithDefault { path: copy }] = &opener.calls()[..] else {
+			panic!("expected one launch, got {:?}", opener.calls());
+		};
+		assert_ne!(copy, &file);
+		assert!(std::fs::metadata(copy).unwrap().permissions().readonly());
+
+		// Directories can't be copied, so the app gets the original.
+		assert!(matches!(
+			opener.open_readonly(&dir),
+			Ok(OpenResult::OpenedReadOnly { enforced: false })
+		));
+		assert!(opener.was_called(&MockCall::OpenWithDefault { path: dir.clone() }));
+
+		opener.cleanup_temp_files();
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn common_apps_are_counted_once_per_extension() {
+		let opener = MockFileOpener::new(HashMap::from([
+			(
+				"txt".to_string(),
+				vec![app("notepad", true), app("code", false)],
+			),
+			(
+				"md".to_string(),
+				vec![app("code", true), app("typora", false)],
+			),
+			("png".to_string(), vec![app("paint", true)]),
+		]));
+
+		let selection = [
+			Path::new("a.txt"),
+			Path::new("b.TXT"),
+			Path::new("c.md"),
+			Path::new("Makefile"),
+		];
+		let common = opener
+			.get_common_apps(&selection, ExtensionlessFiles::Ignore)
+			.unwrap();
+		assert_eq!(common.len(), 1);
+		assert_eq!(common[0].id, "code");
+		assert!(!common[0].is_default, "code is only the default for .md");
+
+		let common = opener
+			.get_common_apps(&selection, ExtensionlessFiles::NoCommonApps)
+			.unwrap();
+		assert!(common.is_empty());
+
+		let mixed = [Path::new("a.txt"), Path::new("b.png")];
+		let common = opener
+			.get_common_apps(&mixed, ExtensionlessFiles::Ignore)
+			.unwrap();
+		assert!(common.is_empty());
+	}
+
+	#[test]
+	fn recent_apps_are_the_ones_still_listed() {
+		let opener = opener();
+		opener.open_with_app(Path::new("a.txt"), "notepad").unwrap();
+		opener.open_with_app(Path::new("b.txt"), "code").unwrap();
+		// Not seeded for .txt, so the launch fails and isn't remembered.
+		opener.open_with_app(Path::new("c.txt"), "vim").unwrap();
+		opener.history.restore([crate::HistoryEntry {
+			extension: "txt".to_string(),
+			app_id: "uninstalled".to_string(),
+			used_at: u64::MAX,
+		}]);
+
+		let recent = opener.recent_apps_for(".TXT", 5).unwrap();
+		let ids = recent.iter().map(|app| app.id.as_str()).collect::<Vec<_>>();
+		assert_eq!(ids, ["code", "notepad"]);
+		assert_eq!(opener.recent_apps_for("txt", 1).unwrap().len(), 1);
+		assert!(opener.recent_apps_for("md", 5).unwrap().is_empty());
+	}
+}
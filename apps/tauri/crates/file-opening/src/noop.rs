@@ -0,0 +1,43 @@
+//! # No-op opener
+//!
+//! For targets without a backend, so code that opens files still compiles
+//! and degrades to "not supported" at runtime.
+
+use std::path::Path;
+
+use crate::{
+	not_supported, FileOpenError, FileOpener, OpenResult, OpenWithApp, OpenerCapabilities,
+};
+
+/// A [`FileOpener`] that knows no apps and supports no launches.
+///
+/// Queries come back empty and every launch returns
+/// [`OpenResult::Unsupported`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopFileOpener;
+
+impl FileOpener for NoopFileOpener {
+	fn get_apps_for_file(&self, _path: &Path) -> Result<Vec<OpenWithApp>, FileOpenError> {
+		Ok(vec![])
+	}
+
+	fn get_default_app(&self, _path: &Path) -> Result<Option<OpenWithApp>, FileOpenError> {
+		Ok(None)
+	}
+
+	fn open_with_default(&self, _path: &Path) -> Result<OpenResult, FileOpenError> {
+		Ok(not_supported("open_with_default"))
+	}
+
+	fn open_with_app(&self, _path: &Path, _app_id: &str) -> Result<OpenResult, FileOpenError> {
+		Ok(not_supported("open_with_app"))
+	}
+
+	fn reveal(&self, _path: &Path) -> Result<OpenResult, FileOpenError> {
+		Ok(not_supported("reveal"))
+	}
+
+	fn capabilities(&self) -> OpenerCapabilities {
+		OpenerCapabilities::default()
+	}
+}
@@ -0,0 +1,106 @@
+//! # Default app overrides
+//!
+//! Spacedrive lets users pick an app for a type without touching the OS
+//! association, e.g. `.log` files in an editor while the system default
+//! stays Notepad. The rules only live in the opener: they are lost with it,
+//! so the caller persists them and adds them back on startup.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::RwLock;
+
+use crate::{FileOpenError, FileOpener, OpenResult};
+
+/// Per-extension apps that `open_with_default` uses instead of the OS
+/// default, for backends that return them from
+/// [`FileOpener::default_overrides`].
+#[derive(Debug, Default)]
+pub struct DefaultOverrides {
+	rules: RwLock<HashMap<String, String>>,
+}
+
+impl DefaultOverrides {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Opens files with `extension` (with or without the leading dot,
+	/// matched case-insensitively) with `app_id` from now on.
+	pub fn set(&self, extension: &str, app_id: &str) {
+		self.rules
+			.write()
+			.unwrap_or_else(|e| e.into_inner())
+			.insert(key(extension), app_id.to_string());
+	}
+
+	/// Returns files with `extension` to the OS default.
+	pub fn clear(&self, extension: &str) {
+		self.rules
+			.write()
+			.unwrap_or_else(|e| e.into_inner())
+			.remove(&key(extension));
+	}
+
+	/// The app overriding the default for `path`'s extension, if any.
+	pub fn app_for(&self, path: &Path) -> Option<String> {
+		let extension = crate::mime::derive_extension(path)?;
+		self.rules
+			.read()
+			.unwrap_or_else(|e| e.into_inner())
+			.get(&key(&extension))
+			.cloned()
+	}
+
+	/// Opens `path` with its override through `opener`, or `None` when its
+	/// extension has none and the OS default applies.
+	///
+	/// A successful launch is reported as `OpenedWithOverride`, so callers
+	/// can tell which way the file went.
+	pub fn open(
+		&self,
+		opener: &(impl FileOpener + ?Sized),
+		path: &Path,
+	) -> Result<Option<OpenResult>, FileOpenError> {
+		let Some(app_id) = self.app_for(path) else {
+			return Ok(None);
+		};
+
+		Ok(Some(match opener.open_with_app(path, &app_id)? {
+			OpenResult::Success { .. } => OpenResult::OpenedWithOverride { app_id },
+			other => other,
+		}))
+	}
+}
+
+impl Clone for DefaultOverrides {
+	fn clone(&self) -> Self {
+		Self {
+			rules: RwLock::new(self.rules.read().unwrap_or_else(|e| e.into_inner()).clone()),
+		}
+	}
+}
+
+fn key(extension: &str) -> String {
+	crate::mime::normalize_extension(extension)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn rules_match_extensions_in_any_form() {
+		let overrides = DefaultOverrides::new();
+		overrides.set(".LOG", "code");
+
+		assert_eq!(
+			overrides.app_for(Path::new("server.log")),
+			Some("code".to_string())
+		);
+		assert_eq!(overrides.app_for(Path::new("server.txt")), None);
+		assert_eq!(overrides.app_for(Path::new("log")), None);
+
+		overrides.clear("log");
+		assert_eq!(overrides.app_for(Path::new("server.log")), None);
+	}
+}
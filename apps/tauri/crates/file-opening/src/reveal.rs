@@ -0,0 +1,84 @@
+//! # Reveal targets
+//!
+//! Every backend's `reveal` follows the same rules for what to show, so the
+//! decision lives here and the backends only differ in how they show it.
+
+use std::path::{Path, PathBuf};
+
+/// What [`FileOpener::reveal`](crate::FileOpener::reveal) should show for a
+/// path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RevealTarget {
+	/// Open this folder with nothing selected: the path itself when it is a
+	/// directory, or the parent of an item that no longer exists.
+	Folder(PathBuf),
+	/// Open the parent folder with this item selected.
+	Select(PathBuf),
+	/// Neither the item nor its parent folder exists.
+	Missing,
+}
+
+/// Decides what revealing `path` shows.
+///
+/// A trailing separator doesn't change the outcome: `dir/` is the directory
+/// `dir`, and a missing `dir/` falls back to the folder containing `dir`.
+pub fn reveal_target(path: &Path) -> RevealTarget {
+	if path.is_dir() {
+		return RevealTarget::Folder(path.to_path_buf());
+	}
+
+	if path.exists() {
+		return RevealTarget::Select(path.to_path_buf());
+	}
+
+	match path.parent().filter(|parent| parent.is_dir()) {
+		Some(parent) => RevealTarget::Folder(parent.to_path_buf()),
+		None => RevealTarget::Missing,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::fs;
+
+	fn scratch_dir(name: &str) -> PathBuf {
+		let dir = std::env::temp_dir().join(format!("sd-reveal-{name}-{}", std::process::id()));
+		let _ = fs::remove_dir_all(&dir);
+		fs::create_dir_all(&dir).unwrap();
+		dir
+	}
+
+	#[test]
+	fn directories_open_and_files_are_selected() {
+		let dir = scratch_dir("existing");
+		let file = dir.join("notes.txt");
+		fs::write(&file, "").unwrap();
+
+		assert_eq!(reveal_target(&dir), RevealTarget::Folder(dir.clone()));
+		assert_eq!(reveal_target(&file), RevealTarget::Select(file.clone()));
+
+		let trailing = PathBuf::from(format!("{}{}", dir.display(), std::path::MAIN_SEPARATOR));
+		assert_eq!(
+			reveal_target(&trailing),
+			RevealTarget::Folder(trailing.clone())
+		);
+
+		fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn missing_items_fall_back_to_their_folder() {
+		let dir = scratch_dir("missing");
+		let gone = dir.join("gone");
+
+		assert_eq!(reveal_target(&gone), RevealTarget::Folder(dir.clone()));
+
+		let trailing = PathBuf::from(format!("{}{}", gone.display(), std::path::MAIN_SEPARATOR));
+		assert_eq!(reveal_target(&trailing), RevealTarget::Folder(dir.clone()));
+
+		assert_eq!(reveal_target(&gone.join("deeper")), RevealTarget::Missing);
+
+		fs::remove_dir_all(&dir).unwrap();
+	}
+}
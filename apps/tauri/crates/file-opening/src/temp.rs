@@ -0,0 +1,169 @@
+//! # Temporary copies
+//!
+//! Content that has no ordinary path of its own (a cloud cache entry, a file
+//! decrypted out of a vault) is opened by writing it to a temporary file
+//! first, since every system opener only takes paths. Each copy gets its own
+//! directory so the file keeps the exact suggested name, which is what the
+//! app shows in its title bar, and the directories are tracked for
+//! [`FileOpener::cleanup_temp_files`](crate::FileOpener::cleanup_temp_files).
+//! Files opened read-only are copied the same way, so the app only ever
+//! gets at the copy.
+//!
+//! The copies are plain files under the user's temp directory: readable by
+//! anything running as the same user, visible to indexers and backup tools
+//! while they exist, and left behind if the process dies before cleaning up.
+//! Removing them doesn't scrub the disk either.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Directories created for copies that haven't been cleaned up yet.
+static CREATED: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Writes `bytes` to a new temporary file named after `suggested_name`.
+pub(crate) fn write(bytes: &[u8], suggested_name: &str) -> io::Result<PathBuf> {
+	let path = new_dir()?.join(file_name(suggested_name));
+	fs::write(&path, bytes)?;
+	Ok(path)
+}
+
+/// Copies `path` to a new temporary file of the same name that is marked
+/// read-only.
+pub(crate) fn copy_read_only(path: &Path) -> io::Result<PathBuf> {
+	let name = path.file_name().unwrap_or_default().to_string_lossy();
+	let copy = new_dir()?.join(file_name(&name));
+	fs::copy(path, &copy)?;
+
+	let mut permissions = fs::metadata(&copy)?.permissions();
+	permissions.set_readonly(true);
+	fs::set_permissions(&copy, permissions)?;
+	Ok(copy)
+}
+
+/// Creates the tracked directory for one copy.
+fn new_dir() -> io::Result<PathBuf> {
+	let root = std::env::temp_dir().join("spacedrive-open");
+	fs::create_dir_all(&root)?;
+
+	let dir = root.join(format!(
+		"{}-{}",
+		std::process::id(),
+		NEXT_ID.fetch_add(1, Ordering::Relaxed)
+	));
+	create_private_dir(&dir)?;
+	created().push(dir.clone());
+	Ok(dir)
+}
+
+/// Removes every copy this process made, returning how many were removed.
+///
+/// Copies that can't be removed yet, e.g. because an app still has them
+/// open on Windows, stay tracked for the next call.
+pub(crate) fn cleanup() -> usize {
+	let mut created = created();
+	let before = created.len();
+
+	created.retain(|dir| match fs::remove_dir_all(dir) {
+		Ok(()) => false,
+		Err(e) => e.kind() != io::ErrorKind::NotFound,
+	});
+
+	before - created.len()
+}
+
+fn created() -> std::sync::MutexGuard<'static, Vec<PathBuf>> {
+	CREATED.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(unix)]
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+	use std::os::unix::fs::DirBuilderExt;
+
+	// The shared temp directory is world-readable; the copy shouldn't be.
+	fs::DirBuilder::new().mode(0o700).create(dir)
+}
+
+#[cfg(not(unix))]
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+	// The temp directory is already private to the user.
+	fs::create_dir(dir)
+}
+
+/// `suggested_name` reduced to a single, portable file name component.
+///
+/// Anything before the last separator is dropped, so a name can't place the
+/// copy outside its directory, and characters Windows rejects are replaced.
+fn file_name(suggested_name: &str) -> String {
+	let name = suggested_name
+		.rsplit(['/', '\\'])
+		.next()
+		.unwrap_or_default()
+		.chars()
+		.map(|c| match c {
+			'<' | '>' | ':' | '"' | '|' | '?' | '*' => '_',
+			c if c.is_control() => '_',
+			c => c,
+		})
+		.collect::<String>();
+
+	match name.trim_matches([' ', '.']) {
+		"" => "file".to_string(),
+		_ => name,
+	}
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use super::*;
+
+	/// Held by tests that make copies, since any test's cleanup removes them
+	/// all.
+	pub(crate) fn serial() -> std::sync::MutexGuard<'static, ()> {
+		static SERIAL: Mutex<()> = Mutex::new(());
+		SERIAL.lock().unwrap_or_else(|e| e.into_inner())
+	}
+
+	#[test]
+	fn suggested_names_stay_inside_the_copy_directory() {
+		assert_eq!(file_name("report.pdf"), "report.pdf");
+		assert_eq!(file_name("../../etc/passwd"), "passwd");
+		assert_eq!(file_name(r"C:\Users\me\notes.txt"), "notes.txt");
+		assert_eq!(file_name("what?.txt"), "what_.txt");
+		assert_eq!(file_name(".."), "file");
+		assert_eq!(file_name(""), "file");
+		assert_eq!(file_name("dir/"), "file");
+	}
+
+	#[test]
+	fn copies_are_removed_on_cleanup() {
+		let _serial = serial();
+		let path = write(b"hello", "hello.txt").unwrap();
+		assert_eq!(path.file_name().unwrap(), "hello.txt");
+		assert_eq!(fs::read(&path).unwrap(), b"hello");
+
+		assert!(cleanup() >= 1);
+		assert!(!path.exists());
+		assert!(!path.parent().unwrap().exists());
+	}
+
+	#[test]
+	fn read_only_copies_leave_the_original_alone() {
+		let _serial = serial();
+		let original = write(b"original", "notes.txt").unwrap();
+		let copy = copy_read_only(&original).unwrap();
+
+		assert_ne!(copy, original);
+		assert_eq!(copy.file_name().unwrap(), "notes.txt");
+		assert_eq!(fs::read(&copy).unwrap(), b"original");
+		assert!(fs::metadata(&copy).unwrap().permissions().readonly());
+		assert!(!fs::metadata(&original).unwrap().permissions().readonly());
+
+		cleanup();
+		assert!(!copy.exists());
+	}
+}
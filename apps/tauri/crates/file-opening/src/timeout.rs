@@ -0,0 +1,206 @@
+//! # Launch timeouts
+//!
+//! `ShellExecuteW` and `IAssocHandler::Invoke` only return once the target
+//! app has taken the file, which can wait on a modal prompt the app shows on
+//! its first start. [`TimedFileOpener`] runs the launch on its own thread and
+//! stops waiting after a deadline, so the caller gets an answer either way.
+//! Thumbnails get the same treatment, since a slow codec can hold up
+//! extraction just as long.
+//!
+//! The launch thread is not abandoned: it finishes whenever the blocked call
+//! returns, drops its result, and exits, tearing down its per-thread backend
+//! state (the COM apartment on Windows) with it.
+
+use std::path::Path;
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+use crate::{FileOpenError, FileOpener, OpenResult};
+
+/// Launches with a deadline, implemented for any `Arc`-shared opener.
+///
+/// A result of [`OpenResult::TimedOut`] means no answer arrived in time, not
+/// that the launch failed: the app may still open the file afterwards.
+pub trait TimedFileOpener {
+	fn open_with_default_timeout(
+		&self,
+		path: &Path,
+		timeout: Duration,
+	) -> Result<OpenResult, FileOpenError>;
+
+	fn open_with_app_timeout(
+		&self,
+		path: &Path,
+		app_id: &str,
+		timeout: Duration,
+	) -> Result<OpenResult, FileOpenError>;
+
+	/// `Ok(None)` when no thumbnail arrived in time, as for a file without one.
+	fn get_thumbnail_timeout(
+		&self,
+		path: &Path,
+		size: u32,
+		timeout: Duration,
+	) -> Result<Option<Vec<u8>>, FileOpenError>;
+}
+
+impl<T: FileOpener + ?Sized + 'static> TimedFileOpener for Arc<T> {
+	fn open_with_default_timeout(
+		&self,
+		path: &Path,
+		timeout: Duration,
+	) -> Result<OpenResult, FileOpenError> {
+		let opener = Arc::clone(self);
+		let path = path.to_path_buf();
+		run_with_timeout(timeout, move || opener.open_with_default(&path))
+			.map(|result| result.unwrap_or(OpenResult::TimedOut))
+	}
+
+	fn open_with_app_timeout(
+		&self,
+		path: &Path,
+		app_id: &str,
+		timeout: Duration,
+	) -> Result<OpenResult, FileOpenError> {
+		let opener = Arc::clone(self);
+		let path = path.to_path_buf();
+		let app_id = app_id.to_string();
+		run_with_timeout(timeout, move || opener.open_with_app(&path, &app_id))
+			.map(|result| result.unwrap_or(OpenResult::TimedOut))
+	}
+
+	fn get_thumbnail_timeout(
+		&self,
+		path: &Path,
+		size: u32,
+		timeout: Duration,
+	) -> Result<Option<Vec<u8>>, FileOpenError> {
+		let opener = Arc::clone(self);
+		let path = path.to_path_buf();
+		run_with_timeout(timeout, move || opener.get_thumbnail(&path, size)).map(Option::flatten)
+	}
+}
+
+/// Runs `work` on its own thread, with `None` if it hasn't finished by
+/// `timeout`.
+fn run_with_timeout<R, F>(timeout: Duration, work: F) -> Result<Option<R>, FileOpenError>
+where
+	R: Send + 'static,
+	F: FnOnce() -> Result<R, FileOpenError> + Send + 'static,
+{
+	let (sender, receiver) = mpsc::sync_channel(1);
+
+	std::thread::Builder::new()
+		.name("file-opening-timed".to_string())
+		.spawn(move || {
+			// The receiver is gone if the caller already gave up.
+			let _ = sender.send(work());
+		})?;
+
+	match receiver.recv_timeout(timeout) {
+		Ok(result) => result.map(Some),
+		Err(mpsc::RecvTimeoutError::Timeout) => Ok(None),
+		Err(mpsc::RecvTimeoutError::Disconnected) => {
+			Err(FileOpenError::Other("worker thread panicked".to_string()))
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::OpenWithApp;
+	use std::sync::atomic::{AtomicBool, Ordering};
+	use std::thread;
+
+	/// Opens after `delay`, noting when it's done.
+	struct SlowOpener {
+		delay: Duration,
+		finished: AtomicBool,
+	}
+
+	impl FileOpener for SlowOpener {
+		fn get_apps_for_file(&self, _path: &Path) -> Result<Vec<OpenWithApp>, FileOpenError> {
+			Ok(vec![])
+		}
+
+		fn get_default_app(&self, _path: &Path) -> Result<Option<OpenWithApp>, FileOpenError> {
+			Ok(None)
+		}
+
+		fn open_with_default(&self, _path: &Path) -> Result<OpenResult, FileOpenError> {
+			thread::sleep(self.delay);
+			self.finished.store(true, Ordering::SeqCst);
+			Ok(OpenResult::success())
+		}
+
+		fn open_with_app(&self, path: &Path, _app_id: &str) -> Result<OpenResult, FileOpenError> {
+			self.open_with_default(path)
+		}
+
+		fn reveal(&self, _path: &Path) -> Result<OpenResult, FileOpenError> {
+			Ok(OpenResult::success())
+		}
+
+		fn get_thumbnail(
+			&self,
+			_path: &Path,
+			_size: u32,
+		) -> Result<Option<Vec<u8>>, FileOpenError> {
+			thread::sleep(self.delay);
+			Ok(Some(vec![0x89, b'P', b'N', b'G']))
+		}
+	}
+
+	fn slow(delay: Duration) -> Arc<SlowOpener> {
+		Arc::new(SlowOpener {
+			delay,
+			finished: AtomicBool::new(false),
+		})
+	}
+
+	#[test]
+	fn fast_launches_report_their_result() {
+		let opener = slow(Duration::ZERO);
+
+		assert!(matches!(
+			opener.open_with_app_timeout(Path::new("a.txt"), "app", Duration::from_secs(5)),
+			Ok(OpenResult::Success { .. })
+		));
+	}
+
+	#[test]
+	fn slow_launches_time_out_but_still_finish() {
+		let opener = slow(Duration::from_millis(200));
+
+		assert!(matches!(
+			opener.open_with_default_timeout(Path::new("a.txt"), Duration::from_millis(10)),
+			Ok(OpenResult::TimedOut)
+		));
+
+		// The worker outlives the wait and completes the launch on its own.
+		let deadline = std::time::Instant::now() + Duration::from_secs(5);
+		while !opener.finished.load(Ordering::SeqCst) {
+			assert!(
+				std::time::Instant::now() < deadline,
+				"launch never finished"
+			);
+			thread::sleep(Duration::from_millis(10));
+		}
+	}
+
+	#[test]
+	fn slow_thumbnails_come_back_empty() {
+		let opener = slow(Duration::from_millis(200));
+		assert!(matches!(
+			opener.get_thumbnail_timeout(Path::new("a.jpg"), 256, Duration::from_millis(10)),
+			Ok(None)
+		));
+
+		let opener = slow(Duration::ZERO);
+		assert!(matches!(
+			opener.get_thumbnail_timeout(Path::new("a.jpg"), 256, Duration::from_secs(5)),
+			Ok(Some(_))
+		));
+	}
+}
@@ -0,0 +1,187 @@
+//! # URI policy
+//!
+//! Every scheme maps to some registered protocol handler, and several of them
+//! (`ms-msdt:`, `search-ms:`, vendor updaters) have been used to run code from
+//! a crafted link. [`FileOpener::open_uri`](crate::FileOpener::open_uri)
+//! therefore only launches schemes on an allowlist, checked before anything
+//! reaches the platform.
+//!
+//! `file:` URIs get one more check. One that resolves to a UNC path
+//! (`file://server/share`, `file:////server/share`) makes Windows connect to
+//! that host and offer the user's credentials, so remote files are refused
+//! unless the policy explicitly allows them.
+
+use crate::FileOpenError;
+
+/// Which URIs `open_uri` may launch.
+///
+/// The default allows `http`, `https`, `mailto` and local `file` URIs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UriPolicy {
+	schemes: Vec<String>,
+	allow_remote_files: bool,
+}
+
+impl Default for UriPolicy {
+	fn default() -> Self {
+		Self {
+			schemes: ["http", "https", "mailto", "file"]
+				.into_iter()
+				.map(String::from)
+				.collect(),
+			allow_remote_files: false,
+		}
+	}
+}
+
+impl UriPolicy {
+	/// A policy that allows nothing until schemes are added.
+	pub fn empty() -> Self {
+		Self {
+			schemes: Vec::new(),
+			allow_remote_files: false,
+		}
+	}
+
+	/// Also allow `scheme` (without the trailing `:`), e.g. a custom
+	/// `spacedrive` scheme.
+	pub fn allow_scheme(mut self, scheme: &str) -> Self {
+		self.schemes.push(scheme.to_ascii_lowercase());
+		self
+	}
+
+	/// Allow `file:` URIs that point at another host.
+	pub fn allow_remote_files(mut self, allow: bool) -> Self {
+		self.allow_remote_files = allow;
+		self
+	}
+
+	/// Checks `uri` against the policy, returning
+	/// [`FileOpenError::UriNotAllowed`] when it must not be launched.
+	pub fn check(&self, uri: &str) -> Result<(), FileOpenError> {
+		let reject = |reason| {
+			Err(FileOpenError::UriNotAllowed {
+				uri: uri.to_string(),
+				reason,
+			})
+		};
+
+		let Some((scheme, rest)) = uri.split_once(':') else {
+			return reject("not a URI");
+		};
+
+		// A one-letter "scheme" is a drive letter, which the shell would open
+		// as a path.
+		if scheme.len() < 2 || !is_valid_scheme(scheme) {
+			return reject("not a URI");
+		}
+
+		if !self.schemes.iter().any(|s| s.eq_ignore_ascii_case(scheme)) {
+			return reject("scheme is not allowed");
+		}
+
+		if scheme.eq_ignore_ascii_case("file") && !self.allow_remote_files && is_remote_file(rest) {
+			return reject("file URI points at a remote host");
+		}
+
+		Ok(())
+	}
+}
+
+/// RFC 3986: `ALPHA *( ALPHA / DIGIT / "+" / "-" / "." )`.
+fn is_valid_scheme(scheme: &str) -> bool {
+	let mut chars = scheme.chars();
+	chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+		&& chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+/// Whether the part of a `file:` URI after the scheme names another host.
+///
+/// Windows accepts backslashes and percent-encoded separators anywhere in a
+/// file URI, so both are normalised before looking for an authority or a
+/// leading `//` in the path.
+fn is_remote_file(rest: &str) -> bool {
+	let normalized = percent_decode(rest).replace('\\', "/");
+
+	let Some(after_slashes) = normalized.strip_prefix("//") else {
+		return false;
+	};
+
+	let (authority, path) = match after_slashes.find('/') {
+		Some(index) => after_slashes.split_at(index),
+		None => (after_slashes, ""),
+	};
+
+	(!authority.is_empty() && !authority.eq_ignore_ascii_case("localhost"))
+		|| path.starts_with("//")
+}
+
+fn percent_decode(value: &str) -> String {
+	let bytes = value.as_bytes();
+	let mut decoded = Vec::with_capacity(bytes.len());
+	let mut index = 0;
+
+	while index < bytes.len() {
+		let hex = bytes
+			.get(index + 1..index + 3)
+			.and_then(|hex| std::str::from_utf8(hex).ok())
+			.and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+		match (bytes[index], hex) {
+			(b'%', Some(byte)) => {
+				decoded.push(byte);
+				index += 3;
+			}
+			(byte, _) => {
+				decoded.push(byte);
+				index += 1;
+			}
+		}
+	}
+
+	String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn allowed(policy: &UriPolicy, uri: &str) -> bool {
+		policy.check(uri).is_ok()
+	}
+
+	#[test]
+	fn only_allowlisted_schemes_pass() {
+		let policy = UriPolicy::default();
+
+		assert!(allowed(&policy, "https://spacedrive.com"));
+		assert!(allowed(&policy, "MailTo:hello@spacedrive.com"));
+		assert!(!allowed(&policy, "ms-msdt:/id PCWDiagnostic"));
+		assert!(!allowed(&policy, "spacedrive://library"));
+		assert!(!allowed(&policy, r"C:\Windows\notepad.exe"));
+		assert!(!allowed(&policy, "no scheme here"));
+
+		let custom = policy.allow_scheme("spacedrive");
+		assert!(allowed(&custom, "spacedrive://library"));
+	}
+
+	#[test]
+	fn remote_file_uris_need_opt_in() {
+		let policy = UriPolicy::default();
+
+		assert!(allowed(&policy, "file:///C:/Users/me/notes.txt"));
+		assert!(allowed(&policy, "file://localhost/C:/notes.txt"));
+		for remote in [
+			"file://server/share/notes.txt",
+			"file:////server/share/notes.txt",
+			r"file:\\server\share",
+			"file:///%5C%5Cserver/share",
+			"file:%2F%2Fserver/share",
+		] {
+			assert!(!allowed(&policy, remote), "{remote} should be rejected");
+		}
+
+		let permissive = policy.allow_remote_files(true);
+		assert!(allowed(&permissive, "file://server/share/notes.txt"));
+	}
+}
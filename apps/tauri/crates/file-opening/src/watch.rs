@@ -0,0 +1,65 @@
+//! # Association change subscriptions
+//!
+//! [`FileOpener::watch_association_changes`](crate::FileOpener::watch_association_changes)
+//! hands back a [`WatchHandle`] that keeps the subscription alive, so callers
+//! manage it like any other guard instead of pairing register and unregister
+//! calls by hand.
+
+use std::fmt;
+
+/// Keeps an association change callback registered until dropped.
+pub struct WatchHandle {
+	unregister: Option<Box<dyn FnOnce() + Send>>,
+}
+
+impl WatchHandle {
+	/// A handle that runs `unregister` when dropped.
+	pub fn new(unregister: impl FnOnce() + Send + 'static) -> Self {
+		Self {
+			unregister: Some(Box::new(unregister)),
+		}
+	}
+
+	/// A handle for a callback that will never be called, from backends that
+	/// can't detect association changes.
+	pub fn inactive() -> Self {
+		Self { unregister: None }
+	}
+}
+
+impl Drop for WatchHandle {
+	fn drop(&mut self) {
+		if let Some(unregister) = self.unregister.take() {
+			unregister();
+		}
+	}
+}
+
+impl fmt::Debug for WatchHandle {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("WatchHandle")
+			.field("active", &self.unregister.is_some())
+			.finish()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::sync::Arc;
+
+	#[test]
+	fn unregisters_once_on_drop() {
+		let unregistered = Arc::new(AtomicUsize::new(0));
+
+		let counter = Arc::clone(&unregistered);
+		let handle = WatchHandle::new(move || {
+			counter.fetch_add(1, Ordering::SeqCst);
+		});
+		assert_eq!(unregistered.load(Ordering::SeqCst), 0);
+
+		drop(handle);
+		assert_eq!(unregistered.load(Ordering::SeqCst), 1);
+	}
+}
@@ -1,30 +1,67 @@
-use file_opening::{FileOpener, OpenResult, OpenWithApp};
+use file_opening::{
+	AsyncFileOpener, DefaultedOpen, ExtensionlessFiles, FileAssociation, FileOpener, HistoryEntry,
+	LaunchSpec, OpenHistory, OpenResult, OpenTarget, OpenWithApp, OpenerCapabilities,
+	ProcessOutcome, WatchHandle,
+};
+use serde::Serialize;
 use std::path::PathBuf;
-use tauri::State;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, State};
 
-#[cfg(target_os = "macos")]
-use file_opening_macos::MacFileOpener as PlatformOpener;
+/// How long a launch may go unconfirmed before the frontend is told it timed
+/// out, e.g. while the target app sits on a first-run prompt
+const LAUNCH_TIMEOUT: Duration = Duration::from_secs(15);
 
-#[cfg(target_os = "windows")]
-use file_opening_windows::WindowsFileOpener as PlatformOpener;
+/// How long the detail pane waits on a thumbnail before showing the icon
+const THUMBNAIL_TIMEOUT: Duration = Duration::from_secs(5);
 
-#[cfg(target_os = "linux")]
-use file_opening_linux::LinuxFileOpener as PlatformOpener;
+/// One page of the applications for a file, with how many there are in all
+#[derive(Debug, Serialize)]
+pub struct AppsPage {
+	apps: Vec<OpenWithApp>,
+	total: usize,
+}
 
 pub struct FileOpeningService {
-	opener: Box<dyn FileOpener>,
+	pub(crate) opener: Arc<dyn FileOpener>,
+	watch: Mutex<Option<WatchHandle>>,
 }
 
 impl FileOpeningService {
-	pub fn new() -> Self {
+	/// `owner` is the raw handle of the window that system dialogs raised by
+	/// a launch belong to; see [`file_opening_platform::opener_for_window`]
+	pub fn new(owner: Option<isize>) -> Self {
 		Self {
-			opener: Box::new(PlatformOpener),
+			opener: Arc::from(file_opening_platform::opener_for_window(owner)),
+			watch: Mutex::new(None),
+		}
+	}
+
+	/// Emit `file-associations-changed` to the frontend whenever the system
+	/// reports an association change, so cached "Open With" lists refresh
+	pub fn forward_association_changes(&self, app: AppHandle) {
+		let callback = Box::new(move || {
+			let _ = app.emit("file-associations-changed", ());
+		});
+
+		match self.opener.watch_association_changes(callback) {
+			Ok(handle) => *self.watch.lock().unwrap_or_else(|e| e.into_inner()) = Some(handle),
+			Err(e) => tracing::warn!("Failed to watch file associations: {}", e),
 		}
 	}
 }
 
+/// Drop every cached association, for when the app knows they just changed,
+/// e.g. after it installed an app or set a default
+#[tauri::command]
+pub fn refresh_file_associations(service: State<'_, FileOpeningService>) {
+	service.opener.refresh();
+}
+
 /// Get applications that can open the given file paths
-/// Returns intersection of compatible apps for multiple files
+/// Returns intersection of compatible apps for multiple files; files without
+/// an extension are left out rather than emptying the menu
 #[tauri::command]
 pub async fn get_apps_for_paths(
 	paths: Vec<PathBuf>,
@@ -34,7 +71,84 @@ pub async fn get_apps_for_paths(
 		return Ok(vec![]);
 	}
 
-	service.opener.get_apps_for_files(&paths)
+	service
+		.opener
+		.get_common_apps(paths, ExtensionlessFiles::Ignore)
+		.await
+		.map_err(Into::into)
+}
+
+/// Count the applications that can open the given file, stopping at `cap`
+/// when one is given, for badges and enabling "Open With"
+#[tauri::command]
+pub async fn count_apps_for_path(
+	path: PathBuf,
+	cap: Option<usize>,
+	service: State<'_, FileOpeningService>,
+) -> Result<usize, String> {
+	match cap {
+		Some(cap) => service.opener.count_apps_for_file_up_to(path, cap).await,
+		None => service.opener.count_apps_for_file(path).await,
+	}
+	.map_err(Into::into)
+}
+
+/// Get one page of the applications that can open the given file, for
+/// menus that show the first few behind a "More" expander
+#[tauri::command]
+pub async fn get_apps_for_path_paged(
+	path: PathBuf,
+	offset: usize,
+	limit: usize,
+	service: State<'_, FileOpeningService>,
+) -> Result<AppsPage, String> {
+	let (apps, total) = service
+		.opener
+		.get_apps_for_file_paged(path, offset, limit)
+		.await?;
+	Ok(AppsPage { apps, total })
+}
+
+/// Get every application registered for the given file, including ones the
+/// platform doesn't recommend (for "More apps…")
+#[tauri::command]
+pub async fn get_all_apps_for_path(
+	path: PathBuf,
+	service: State<'_, FileOpeningService>,
+) -> Result<Vec<OpenWithApp>, String> {
+	service
+		.opener
+		.get_all_apps_for_file(path)
+		.await
+		.map_err(Into::into)
+}
+
+/// Check whether any application could open the file, without launching one
+#[tauri::command]
+pub async fn can_open_path(
+	path: PathBuf,
+	service: State<'_, FileOpeningService>,
+) -> Result<bool, String> {
+	service.opener.can_open(path).await.map_err(Into::into)
+}
+
+/// Which optional operations this platform's opener performs, so the
+/// frontend only offers actions that will work
+#[tauri::command]
+pub async fn get_opener_capabilities(
+	service: State<'_, FileOpeningService>,
+) -> Result<OpenerCapabilities, String> {
+	Ok(service.opener.capabilities())
+}
+
+/// Check whether opening the file makes the system show a security warning,
+/// so the frontend can confirm with the user first
+#[tauri::command]
+pub async fn is_path_risky(
+	path: PathBuf,
+	service: State<'_, FileOpeningService>,
+) -> Result<bool, String> {
+	service.opener.is_risky(path).await.map_err(Into::into)
 }
 
 /// Open file with system default application
@@ -43,7 +157,279 @@ pub async fn open_path_default(
 	path: PathBuf,
 	service: State<'_, FileOpeningService>,
 ) -> Result<OpenResult, String> {
-	service.opener.open_with_default(&path)
+	service
+		.opener
+		.open_with_default_timeout(path, LAUNCH_TIMEOUT)
+		.await
+		.map_err(Into::into)
+}
+
+/// Open file with default application, letting the user pick one from the
+/// system's Open With dialog when its type has none
+#[tauri::command]
+pub async fn open_path_default_or_prompt(
+	path: PathBuf,
+	service: State<'_, FileOpeningService>,
+) -> Result<OpenResult, String> {
+	service
+		.opener
+		.open_with_default_or_prompt(path)
+		.await
+		.map_err(Into::into)
+}
+
+/// Open file with its default application through a read-only copy, so the
+/// app can't change the original
+#[tauri::command]
+pub async fn open_path_readonly(
+	path: PathBuf,
+	service: State<'_, FileOpeningService>,
+) -> Result<OpenResult, String> {
+	service.opener.open_readonly(path).await.map_err(Into::into)
+}
+
+/// Print file through its type's registered print handler
+#[tauri::command]
+pub async fn print_path(
+	path: PathBuf,
+	service: State<'_, FileOpeningService>,
+) -> Result<OpenResult, String> {
+	service.opener.print(path).await.map_err(Into::into)
+}
+
+/// Check whether the file's type can be printed, without printing it
+#[tauri::command]
+pub async fn can_print_path(
+	path: PathBuf,
+	service: State<'_, FileOpeningService>,
+) -> Result<bool, String> {
+	service.opener.can_print(path).await.map_err(Into::into)
+}
+
+/// Open file for editing through its type's registered edit handler
+#[tauri::command]
+pub async fn edit_path(
+	path: PathBuf,
+	service: State<'_, FileOpeningService>,
+) -> Result<OpenResult, String> {
+	service.opener.edit(path).await.map_err(Into::into)
+}
+
+/// Check whether the file's type has an edit handler, without launching it
+#[tauri::command]
+pub async fn can_edit_path(
+	path: PathBuf,
+	service: State<'_, FileOpeningService>,
+) -> Result<bool, String> {
+	service.opener.can_edit(path).await.map_err(Into::into)
+}
+
+/// Open file through a named shell verb such as `edit` or `play`
+#[tauri::command]
+pub async fn open_path_with_verb(
+	path: PathBuf,
+	verb: String,
+	service: State<'_, FileOpeningService>,
+) -> Result<OpenResult, String> {
+	service
+		.opener
+		.open_with_verb(path, verb)
+		.await
+		.map_err(Into::into)
+}
+
+/// List the shell verbs the file's type defines
+#[tauri::command]
+pub async fn list_verbs_for_path(
+	path: PathBuf,
+	service: State<'_, FileOpeningService>,
+) -> Result<Vec<String>, String> {
+	service.opener.list_verbs(path).await.map_err(Into::into)
+}
+
+/// Move files to the trash, where they can be restored from, rather than
+/// deleting them
+#[tauri::command]
+pub async fn move_paths_to_trash(
+	paths: Vec<PathBuf>,
+	service: State<'_, FileOpeningService>,
+) -> Result<Vec<OpenResult>, String> {
+	service
+		.opener
+		.move_to_trash(paths)
+		.await
+		.map_err(Into::into)
+}
+
+/// Resolve a `.lnk` shortcut to its target, or `None` if it has none
+#[tauri::command]
+pub async fn resolve_shortcut(
+	path: PathBuf,
+	service: State<'_, FileOpeningService>,
+) -> Result<Option<PathBuf>, String> {
+	service
+		.opener
+		.resolve_shortcut(path)
+		.await
+		.map_err(Into::into)
+}
+
+/// Show the system properties dialog for a file or directory
+#[tauri::command]
+pub async fn show_path_properties(
+	path: PathBuf,
+	service: State<'_, FileOpeningService>,
+) -> Result<OpenResult, String> {
+	service
+		.opener
+		.show_properties(path)
+		.await
+		.map_err(Into::into)
+}
+
+/// Start a new message in the default mail client with the files attached,
+/// for the user to address and send
+#[tauri::command]
+pub async fn open_mail_with_paths(
+	paths: Vec<PathBuf>,
+	subject: Option<String>,
+	body: Option<String>,
+	service: State<'_, FileOpeningService>,
+) -> Result<OpenResult, String> {
+	service
+		.opener
+		.open_mail_with_attachment(paths, subject, body)
+		.await
+		.map_err(Into::into)
+}
+
+/// Get the system thumbnail for a file as PNG bytes fitting within `size`
+/// pixels, or `None` if it has none or it took too long
+#[tauri::command]
+pub async fn get_path_thumbnail(
+	path: PathBuf,
+	size: u32,
+	service: State<'_, FileOpeningService>,
+) -> Result<Option<Vec<u8>>, String> {
+	service
+		.opener
+		.get_thumbnail_timeout(path, size, THUMBNAIL_TIMEOUT)
+		.await
+		.map_err(Into::into)
+}
+
+/// Reveal several files, selecting those that share a folder in one window
+#[tauri::command]
+pub async fn reveal_paths(
+	paths: Vec<PathBuf>,
+	service: State<'_, FileOpeningService>,
+) -> Result<Vec<OpenResult>, String> {
+	service.opener.reveal_many(paths).await.map_err(Into::into)
+}
+
+/// Ask the system to make an app the default for an extension
+///
+/// On Windows this opens Settings for the user to confirm, reported as
+/// `requires_user_action`.
+#[tauri::command]
+pub async fn set_default_app_for_extension(
+	extension: String,
+	app_id: String,
+	service: State<'_, FileOpeningService>,
+) -> Result<OpenResult, String> {
+	service
+		.opener
+		.set_default_app(extension, app_id)
+		.await
+		.map_err(Into::into)
+}
+
+/// Get the apps files with an extension were most recently opened with,
+/// newest first
+#[tauri::command]
+pub async fn get_recent_apps_for_extension(
+	extension: String,
+	limit: usize,
+	service: State<'_, FileOpeningService>,
+) -> Result<Vec<OpenWithApp>, String> {
+	service
+		.opener
+		.recent_apps_for(extension, limit)
+		.await
+		.map_err(Into::into)
+}
+
+/// Get the Open With history, for the frontend to save
+#[tauri::command]
+pub async fn get_open_history(
+	service: State<'_, FileOpeningService>,
+) -> Result<Vec<HistoryEntry>, String> {
+	Ok(service
+		.opener
+		.open_history()
+		.map(OpenHistory::entries)
+		.unwrap_or_default())
+}
+
+/// Add a saved Open With history back, e.g. on startup
+#[tauri::command]
+pub async fn restore_open_history(
+	entries: Vec<HistoryEntry>,
+	service: State<'_, FileOpeningService>,
+) -> Result<(), String> {
+	if let Some(history) = service.opener.open_history() {
+		history.restore(entries);
+	}
+	Ok(())
+}
+
+/// Open files with an extension in the given app instead of the OS default
+///
+/// Kept only while the app runs; the frontend re-adds saved overrides on
+/// startup.
+#[tauri::command]
+pub async fn add_default_app_override(
+	extension: String,
+	app_id: String,
+	service: State<'_, FileOpeningService>,
+) -> Result<(), String> {
+	service
+		.opener
+		.add_override(&extension, &app_id)
+		.map_err(Into::into)
+}
+
+/// Return files with an extension to the OS default
+#[tauri::command]
+pub async fn clear_default_app_override(
+	extension: String,
+	service: State<'_, FileOpeningService>,
+) -> Result<(), String> {
+	service
+		.opener
+		.clear_override(&extension)
+		.map_err(Into::into)
+}
+
+/// What every registered file type opens with, for the settings UI
+#[tauri::command]
+pub async fn list_file_associations(
+	service: State<'_, FileOpeningService>,
+) -> Result<Vec<FileAssociation>, String> {
+	service.opener.list_associations().await.map_err(Into::into)
+}
+
+/// Let the user pick an app for a file from the system's Open With dialog
+#[tauri::command]
+pub async fn open_path_with_dialog(
+	path: PathBuf,
+	service: State<'_, FileOpeningService>,
+) -> Result<OpenResult, String> {
+	service
+		.opener
+		.open_with_dialog(path)
+		.await
+		.map_err(Into::into)
 }
 
 /// Open file with specific application
@@ -53,7 +439,98 @@ pub async fn open_path_with_app(
 	app_id: String,
 	service: State<'_, FileOpeningService>,
 ) -> Result<OpenResult, String> {
-	service.opener.open_with_app(&path, &app_id)
+	service
+		.opener
+		.open_with_app_timeout(path, app_id, LAUNCH_TIMEOUT)
+		.await
+		.map_err(Into::into)
+}
+
+/// Open file with an app and make it the default for the file's type
+#[tauri::command]
+pub async fn open_path_with_app_as_default(
+	path: PathBuf,
+	app_id: String,
+	service: State<'_, FileOpeningService>,
+) -> Result<DefaultedOpen, String> {
+	service
+		.opener
+		.open_with_app_and_set_default(path, app_id)
+		.await
+		.map_err(Into::into)
+}
+
+/// Open file with an application, or its default for no `app_id`, and
+/// resolve once the process that was started exits
+#[tauri::command]
+pub async fn open_path_and_wait(
+	path: PathBuf,
+	app_id: Option<String>,
+	service: State<'_, FileOpeningService>,
+) -> Result<ProcessOutcome, String> {
+	service
+		.opener
+		.open_and_wait(path, app_id)
+		.await
+		.map_err(Into::into)
+}
+
+/// Start an application on its own, without opening a file
+#[tauri::command]
+pub async fn launch_app(
+	app_id: String,
+	service: State<'_, FileOpeningService>,
+) -> Result<OpenResult, String> {
+	service.opener.launch_app(app_id).await.map_err(Into::into)
+}
+
+/// Open a shell item that has no filesystem path, such as a file inside a
+/// ZIP folder, by its parsing name
+#[tauri::command]
+pub async fn open_shell_item(
+	parsing_name: String,
+	service: State<'_, FileOpeningService>,
+) -> Result<OpenResult, String> {
+	service
+		.opener
+		.open_shell_item(parsing_name)
+		.await
+		.map_err(Into::into)
+}
+
+/// Open a file with a specific application, in a new instance of it if
+/// asked and the platform can
+///
+/// Only `new_instance` is taken from the webview: arguments, working
+/// directory and environment would let it run anything it liked through the
+/// app, so they stay on the Rust side.
+#[tauri::command]
+pub async fn launch_path(
+	path: PathBuf,
+	app_id: String,
+	new_instance: bool,
+	service: State<'_, FileOpeningService>,
+) -> Result<OpenResult, String> {
+	let spec = LaunchSpec {
+		new_instance,
+		..LaunchSpec::new(path, app_id)
+	};
+	service.opener.launch(spec).await.map_err(Into::into)
+}
+
+/// Open a file with a specific application at a line, page or fragment, for
+/// apps known to take one; others open the file as usual
+#[tauri::command]
+pub async fn open_path_at(
+	target: OpenTarget,
+	app_id: String,
+	service: State<'_, FileOpeningService>,
+) -> Result<OpenResult, String> {
+	service
+		.opener
+		.open_at(target, app_id)
+		.await
+		.map_err(Into::into)
 }
 
 /// Open multiple files with specific application
@@ -63,5 +540,24 @@ pub async fn open_paths_with_app(
 	app_id: String,
 	service: State<'_, FileOpeningService>,
 ) -> Result<Vec<OpenResult>, String> {
-	service.opener.open_files_with_app(&paths, &app_id)
+	service
+		.opener
+		.open_files_with_app(paths, app_id)
+		.await
+		.map_err(Into::into)
+}
+
+/// Open multiple files with specific application in one launch, so it gets
+/// them together; one result covers them all
+#[tauri::command]
+pub async fn open_paths_together_with_app(
+	paths: Vec<PathBuf>,
+	app_id: String,
+	service: State<'_, FileOpeningService>,
+) -> Result<OpenResult, String> {
+	service
+		.opener
+		.open_many_with_app(paths, app_id)
+		.await
+		.map_err(Into::into)
 }
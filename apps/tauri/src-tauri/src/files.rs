@@ -1,19 +1,30 @@
+use file_opening::{AsyncFileOpener, OpenResult};
 use std::path::{Path, PathBuf};
+use tauri::State;
 use tracing::error;
 
+use crate::file_opening::FileOpeningService;
+
 /// Reveal a file in the native file manager (Finder on macOS, Explorer on Windows, etc.)
 #[tauri::command]
-pub async fn reveal_file(path: String) -> Result<(), String> {
+pub async fn reveal_file(
+	path: String,
+	service: State<'_, FileOpeningService>,
+) -> Result<(), String> {
 	let path = PathBuf::from(path);
 
-	if !path.exists() {
-		return Err(format!("Path does not exist: {}", path.display()));
-	}
+	let message = match service.opener.reveal(path.clone()).await {
+		Ok(OpenResult::Success { .. }) => return Ok(()),
+		Ok(OpenResult::FileNotFound { .. }) => {
+			return Err(format!("Path does not exist: {}", path.display()))
+		}
+		Ok(OpenResult::PlatformError { message, .. }) => message,
+		Ok(other) => format!("{:?}", other),
+		Err(e) => e.to_string(),
+	};
 
-	reveal_path(&path).map_err(|e| {
-		error!("Failed to reveal file: {:#?}", e);
-		format!("Failed to reveal file: {}", e)
-	})
+	error!("Failed to reveal file: {}", message);
+	Err(format!("Failed to reveal file: {}", message))
 }
 
 /// Get the physical path to a sidecar file
@@ -90,44 +101,3 @@ async fn find_library_folder(data_dir: &Path, library_id: &str) -> Result<PathBu
 
 	Err(format!("Library folder not found for ID: {}", library_id))
 }
-
-#[cfg(target_os = "macos")]
-fn reveal_path(path: &Path) -> Result<(), std::io::Error> {
-	std::process::Command::new("open")
-		.arg("-R")
-		.arg(path)
-		.spawn()?
-		.wait()?;
-	Ok(())
-}
-
-#[cfg(target_os = "windows")]
-fn reveal_path(path: &Path) -> Result<(), std::io::Error> {
-	std::process::Command::new("explorer")
-		.arg("/select,")
-		.arg(path)
-		.spawn()?
-		.wait()?;
-	Ok(())
-}
-
-#[cfg(target_os = "linux")]
-fn reveal_path(path: &Path) -> Result<(), std::io::Error> {
-	// On Linux, we'll try to open the parent directory
-	// Different desktop environments have different file managers
-	if let Some(parent) = path.parent() {
-		std::process::Command::new("xdg-open")
-			.arg(parent)
-			.spawn()?
-			.wait()?;
-	}
-	Ok(())
-}
-
-#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
-fn reveal_path(path: &Path) -> Result<(), std::io::Error> {
-	Err(std::io::Error::new(
-		std::io::ErrorKind::Unsupported,
-		"Reveal is not supported on this platform",
-	))
-}
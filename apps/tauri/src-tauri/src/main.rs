@@ -1979,9 +1979,45 @@ fn main() {
 			files::reveal_file,
 			files::get_sidecar_path,
 			file_opening::get_apps_for_paths,
+			file_opening::get_all_apps_for_path,
+			file_opening::get_apps_for_path_paged,
+			file_opening::count_apps_for_path,
+			file_opening::can_open_path,
 			file_opening::open_path_default,
+			file_opening::open_path_default_or_prompt,
+			file_opening::get_opener_capabilities,
+			file_opening::is_path_risky,
+			file_opening::open_path_readonly,
+			file_opening::print_path,
+			file_opening::can_print_path,
+			file_opening::refresh_file_associations,
+			file_opening::edit_path,
+			file_opening::can_edit_path,
+			file_opening::open_path_with_verb,
+			file_opening::list_verbs_for_path,
+			file_opening::show_path_properties,
+			file_opening::open_mail_with_paths,
+			file_opening::get_path_thumbnail,
+			file_opening::reveal_paths,
+			file_opening::move_paths_to_trash,
+			file_opening::resolve_shortcut,
+			file_opening::list_file_associations,
+			file_opening::set_default_app_for_extension,
+			file_opening::add_default_app_override,
+			file_opening::get_recent_apps_for_extension,
+			file_opening::get_open_history,
+			file_opening::restore_open_history,
+			file_opening::clear_default_app_override,
+			file_opening::open_path_with_dialog,
 			file_opening::open_path_with_app,
+			file_opening::open_path_with_app_as_default,
+			file_opening::launch_path,
+			file_opening::open_path_at,
+			file_opening::open_path_and_wait,
+			file_opening::launch_app,
+			file_opening::open_shell_item,
 			file_opening::open_paths_with_app,
+			file_opening::open_paths_together_with_app,
 			keybinds::register_keybind,
 			keybinds::unregister_keybind,
 			keybinds::get_registered_keybinds
@@ -2123,7 +2159,18 @@ fn main() {
 			app.manage(app_state);
 			app.manage(drag::DragCoordinator::new());
 			app.manage(keybinds::KeybindState::new());
-			app.manage(file_opening::FileOpeningService::new());
+			// Parented to the main window so Open With and UAC dialogs can't
+			// open behind it
+			#[cfg(target_os = "windows")]
+			let owner = app
+				.get_webview_window("main")
+				.and_then(|window| window.hwnd().ok())
+				.map(|hwnd| hwnd.0 as isize);
+			#[cfg(not(target_os = "windows"))]
+			let owner = None;
+			let file_opening = file_opening::FileOpeningService::new(owner);
+			file_opening.forward_association_changes(app.handle().clone());
+			app.manage(file_opening);
 
 			let _handle = app.handle().clone();
 
@@ -2221,6 +2268,15 @@ fn main() {
 			if let tauri::WindowEvent::CloseRequested { .. } = event {
 				// Get daemon state
 				let app = window.app_handle().clone();
+
+				// Don't leave copies of opened content (possibly decrypted) behind
+				if window.label() == "main" {
+					if let Some(file_opening) = app.try_state::<file_opening::FileOpeningService>()
+					{
+						file_opening.opener.cleanup_temp_files();
+					}
+				}
+
 				if let Some(state) = app.try_state::<Arc<RwLock<DaemonState>>>() {
 					let state = state.inner().clone();
 					tauri::async_runtime::spawn(async move {
@@ -3,7 +3,18 @@ import { open as shellOpen } from "@tauri-apps/plugin-shell";
 import { convertFileSrc as tauriConvertFileSrc, invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
-import type { Platform } from "@sd/interface/platform";
+import type {
+	AppsPage,
+	DefaultedOpen,
+	FileAssociation,
+	OpenHistoryEntry,
+	OpenResult,
+	OpenTarget,
+	OpenerCapabilities,
+	OpenWithApp,
+	Platform,
+	ProcessOutcome,
+} from "@sd/interface/platform";
 import { beginDrag, onDragBegan, onDragMoved, onDragEntered, onDragLeft, onDragEnded } from "./lib/drag";
 
 let _isDragging = false;
@@ -67,36 +78,168 @@ export const platform: Platform = {
 		);
 	},
 
+	async countAppsForPath(path: string, cap?: number) {
+		return await invoke<number>("count_apps_for_path", { path, cap: cap ?? null });
+	},
+
+	async getAppsForPathPaged(path: string, offset: number, limit: number) {
+		return await invoke<AppsPage>("get_apps_for_path_paged", { path, offset, limit });
+	},
+
+	async getAllAppsForPath(path: string) {
+		return await invoke<OpenWithApp[]>("get_all_apps_for_path", { path });
+	},
+
+	async listFileAssociations() {
+		return await invoke<FileAssociation[]>("list_file_associations");
+	},
+
+	async setDefaultAppForExtension(extension: string, appId: string) {
+		return await invoke<OpenResult>("set_default_app_for_extension", {
+			extension,
+			appId,
+		});
+	},
+
+	async canOpenPath(path: string) {
+		return await invoke<boolean>("can_open_path", { path });
+	},
+
 	async openPathDefault(path: string) {
-		return await invoke<
-			| { status: "success" }
-			| { status: "file_not_found"; path: string }
-			| { status: "app_not_found"; app_id: string }
-			| { status: "permission_denied"; path: string }
-			| { status: "platform_error"; message: string }
-		>("open_path_default", { path });
+		return await invoke<OpenResult>("open_path_default", { path });
+	},
+
+	async openPathDefaultOrPrompt(path: string) {
+		return await invoke<OpenResult>("open_path_default_or_prompt", { path });
+	},
+
+	async getOpenerCapabilities() {
+		return await invoke<OpenerCapabilities>("get_opener_capabilities");
+	},
+
+	async isPathRisky(path: string) {
+		return await invoke<boolean>("is_path_risky", { path });
+	},
+
+	async openPathReadOnly(path: string) {
+		return await invoke<OpenResult>("open_path_readonly", { path });
+	},
+
+	async printPath(path: string) {
+		return await invoke<OpenResult>("print_path", { path });
+	},
+
+	async canPrintPath(path: string) {
+		return await invoke<boolean>("can_print_path", { path });
+	},
+
+	async refreshFileAssociations() {
+		await invoke("refresh_file_associations");
+	},
+
+	async editPath(path: string) {
+		return await invoke<OpenResult>("edit_path", { path });
+	},
+
+	async canEditPath(path: string) {
+		return await invoke<boolean>("can_edit_path", { path });
+	},
+
+	async openPathWithVerb(path: string, verb: string) {
+		return await invoke<OpenResult>("open_path_with_verb", { path, verb });
+	},
+
+	async listVerbsForPath(path: string) {
+		return await invoke<string[]>("list_verbs_for_path", { path });
+	},
+
+	async resolveShortcut(path: string) {
+		return await invoke<string | null>("resolve_shortcut", { path });
+	},
+
+	async showPathProperties(path: string) {
+		return await invoke<OpenResult>("show_path_properties", { path });
+	},
+
+	async openMailWithPaths(paths: string[], subject?: string, body?: string) {
+		return await invoke<OpenResult>("open_mail_with_paths", {
+			paths,
+			subject: subject ?? null,
+			body: body ?? null,
+		});
+	},
+
+	async getPathThumbnail(path: string, size: number) {
+		const png = await invoke<number[] | null>("get_path_thumbnail", { path, size });
+		return png ? new Uint8Array(png) : null;
+	},
+
+	async revealPaths(paths: string[]) {
+		return await invoke<OpenResult[]>("reveal_paths", { paths });
+	},
+
+	async movePathsToTrash(paths: string[]) {
+		return await invoke<OpenResult[]>("move_paths_to_trash", { paths });
+	},
+
+	async getRecentAppsForExtension(extension: string, limit: number) {
+		return await invoke<OpenWithApp[]>("get_recent_apps_for_extension", { extension, limit });
+	},
+
+	async getOpenHistory() {
+		return await invoke<OpenHistoryEntry[]>("get_open_history");
+	},
+
+	async restoreOpenHistory(entries: OpenHistoryEntry[]) {
+		await invoke("restore_open_history", { entries });
+	},
+
+	async addDefaultAppOverride(extension: string, appId: string) {
+		await invoke("add_default_app_override", { extension, appId });
+	},
+
+	async clearDefaultAppOverride(extension: string) {
+		await invoke("clear_default_app_override", { extension });
+	},
+
+	async openPathWithDialog(path: string) {
+		return await invoke<OpenResult>("open_path_with_dialog", { path });
 	},
 
 	async openPathWithApp(path: string, appId: string) {
-		return await invoke<
-			| { status: "success" }
-			| { status: "file_not_found"; path: string }
-			| { status: "app_not_found"; app_id: string }
-			| { status: "permission_denied"; path: string }
-			| { status: "platform_error"; message: string }
-		>("open_path_with_app", { path, appId });
+		return await invoke<OpenResult>("open_path_with_app", { path, appId });
+	},
+
+	async openPathWithAppAsDefault(path: string, appId: string) {
+		return await invoke<DefaultedOpen>("open_path_with_app_as_default", { path, appId });
+	},
+
+	async launchPath(path: string, appId: string, newInstance: boolean) {
+		return await invoke<OpenResult>("launch_path", { path, appId, newInstance });
+	},
+
+	async openPathAt(target: OpenTarget, appId: string) {
+		return await invoke<OpenResult>("open_path_at", { target, appId });
+	},
+
+	async openPathAndWait(path: string, appId?: string) {
+		return await invoke<ProcessOutcome>("open_path_and_wait", { path, appId });
+	},
+
+	async launchApp(appId: string) {
+		return await invoke<OpenResult>("launch_app", { appId });
+	},
+
+	async openShellItem(parsingName: string) {
+		return await invoke<OpenResult>("open_shell_item", { parsingName });
 	},
 
 	async openPathsWithApp(paths: string[], appId: string) {
-		return await invoke<
-			Array<
-				| { status: "success" }
-				| { status: "file_not_found"; path: string }
-				| { status: "app_not_found"; app_id: string }
-				| { status: "permission_denied"; path: string }
-				| { status: "platform_error"; message: string }
-			>
-		>("open_paths_with_app", { paths, appId });
+		return await invoke<OpenResult[]>("open_paths_with_app", { paths, appId });
+	},
+
+	async openPathsTogetherWithApp(paths: string[], appId: string) {
+		return await invoke<OpenResult>("open_paths_together_with_app", { paths, appId });
 	},
 
 	async getSidecarPath(
@@ -131,6 +274,13 @@ export const platform: Platform = {
 		await invoke("set_current_library_id", { libraryId });
 	},
 
+	async onFileAssociationsChanged(callback: () => void) {
+		const unlisten = await listen("file-associations-changed", () => {
+			callback();
+		});
+		return unlisten;
+	},
+
 	async onLibraryIdChanged(callback: (libraryId: string) => void) {
 		const unlisten = await listen<string>("library-changed", (event) => {
 			callback(event.payload);
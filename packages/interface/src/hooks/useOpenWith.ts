@@ -1,9 +1,32 @@
-import { useQuery } from "@tanstack/react-query";
+import { useEffect } from "react";
+import { useQuery, useQueryClient } from "@tanstack/react-query";
 import { usePlatform, type OpenResult } from "../platform";
 import { toast } from "@sd/ui";
 
 export function useOpenWith(paths: string[]) {
 	const platform = usePlatform();
+	const queryClient = useQueryClient();
+
+	// Installed or removed apps change what can open these paths
+	useEffect(() => {
+		if (!platform.onFileAssociationsChanged) return;
+
+		let unlisten: (() => void) | undefined;
+		let disposed = false;
+		platform
+			.onFileAssociationsChanged(() => {
+				queryClient.invalidateQueries({ queryKey: ["openWith"] });
+			})
+			.then((fn) => {
+				if (disposed) fn();
+				else unlisten = fn;
+			});
+
+		return () => {
+			disposed = true;
+			unlisten?.();
+		};
+	}, [platform, queryClient]);
 
 	const { data: apps, isLoading } = useQuery({
 		queryKey: ["openWith", ...paths],
@@ -30,6 +53,20 @@ export function useOpenWith(paths: string[]) {
 		}
 	};
 
+	const openReadOnly = async (path: string) => {
+		if (!platform.openPathReadOnly) {
+			toast.error("Opening files read-only is not supported on this platform");
+			return;
+		}
+
+		try {
+			const result = await platform.openPathReadOnly(path);
+			handleOpenResult(result);
+		} catch (e) {
+			toast.error(`Failed to open file: ${e}`);
+		}
+	};
+
 	const openWithApp = async (path: string, appId: string) => {
 		if (!platform.openPathWithApp) {
 			toast.error("Opening files is not supported on this platform");
@@ -37,7 +74,30 @@ export function useOpenWith(paths: string[]) {
 		}
 
 		try {
-			const result = await platform.openPathWithApp(path, appId);
+			let result = await platform.openPathWithApp(path, appId);
+			// The app was uninstalled since it was listed, or nothing opens the
+			// type at all; let the user pick another
+			const gone =
+				result.status === "app_not_found" ||
+				result.status === "app_unavailable" ||
+				result.status === "no_apps_for_type";
+			if (gone && platform.openPathWithDialog) {
+				result = await platform.openPathWithDialog(path);
+			}
+			handleOpenResult(result);
+		} catch (e) {
+			toast.error(`Failed to open file: ${e}`);
+		}
+	};
+
+	const openWithDialog = async (path: string) => {
+		if (!platform.openPathWithDialog) {
+			toast.error("Choosing an app is not supported on this platform");
+			return;
+		}
+
+		try {
+			const result = await platform.openPathWithDialog(path);
 			handleOpenResult(result);
 		} catch (e) {
 			toast.error(`Failed to open file: ${e}`);
@@ -45,42 +105,106 @@ export function useOpenWith(paths: string[]) {
 	};
 
 	const openMultipleWithApp = async (paths: string[], appId: string) => {
-		if (!platform.openPathsWithApp) {
+		if (!platform.openPathsTogetherWithApp && !platform.openPathsWithApp) {
 			toast.error("Opening files is not supported on this platform");
 			return;
 		}
 
 		try {
-			const results = await platform.openPathsWithApp(paths, appId);
-			results.forEach(handleOpenResult);
+			// In one launch where possible, so the app opens them as tabs of one window
+			if (platform.openPathsTogetherWithApp) {
+				handleOpenResult(await platform.openPathsTogetherWithApp(paths, appId));
+			} else if (platform.openPathsWithApp) {
+				const results = await platform.openPathsWithApp(paths, appId);
+				results.forEach(handleOpenResult);
+			}
 		} catch (e) {
 			toast.error(`Failed to open files: ${e}`);
 		}
 	};
 
+	const launchApp = async (appId: string) => {
+		if (!platform.launchApp) {
+			toast.error("Opening applications is not supported on this platform");
+			return;
+		}
+
+		try {
+			const result = await platform.launchApp(appId);
+			handleOpenResult(result);
+		} catch (e) {
+			toast.error(`Failed to open application: ${e}`);
+		}
+	};
+
 	return {
 		apps: apps ?? [],
 		isLoading,
 		openWithDefault,
+		openReadOnly,
 		openWithApp,
+		openWithDialog,
 		openMultipleWithApp,
+		launchApp,
 	};
 }
 
 function handleOpenResult(result: OpenResult) {
 	switch (result.status) {
 		case "success":
+		case "opened_after_prompt":
+		case "opened_with_override":
 			// Silent success
 			break;
+		case "opened_read_only":
+			if (!result.enforced) {
+				toast.info("Opened the original; the app may still change it");
+			}
+			break;
+		case "cancelled":
+			// The user dismissed a prompt themselves
+			break;
+		case "timed_out":
+			// The app may still be starting, e.g. behind a prompt of its own
+			break;
+		case "requires_user_action":
+			toast.info("Finish the change in the window the system opened");
+			break;
 		case "file_not_found":
 			toast.error(`File not found: ${result.path}`);
 			break;
 		case "app_not_found":
 			toast.error(`Application not found: ${result.app_id}`);
 			break;
+		case "app_unavailable":
+			toast.error(`Application is no longer installed: ${result.exe_path}`);
+			break;
+		case "no_apps_for_type":
+			toast.error(`No application is installed for ${result.extension} files`);
+			break;
 		case "permission_denied":
 			toast.error(`Permission denied: ${result.path}`);
 			break;
+		case "locked":
+			toast.error(
+				result.by ? `${result.by} has this file open` : "This file is open in another application",
+			);
+			break;
+		case "unreachable":
+			toast.error(`Network location is not responding: ${result.path}`);
+			break;
+		case "requires_elevation":
+			toast.error("This application has to be run as administrator");
+			break;
+		case "no_handler":
+			toast.error(`No application can ${result.verb} this file`);
+			break;
+		case "no_mail_client":
+			toast.error("No email application is set up to send files");
+			break;
+		case "unsupported":
+			toast.info("This isn't available on this platform");
+			break;
 		case "platform_error":
 			toast.error(`Error: ${result.message}`);
 			break;
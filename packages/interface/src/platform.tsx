@@ -40,18 +40,125 @@ export type Platform = {
 	/** Reveal a file in the native file manager (Finder on macOS, Explorer on Windows, etc.) */
 	revealFile?(filePath: string): Promise<void>;
 
+	/** Reveal several files, selecting those in the same folder in one window */
+	revealPaths?(paths: string[]): Promise<OpenResult[]>;
+	/** Move files to the trash with the system's confirmation and progress UI, one result per path; not a permanent delete */
+	movePathsToTrash?(paths: string[]): Promise<OpenResult[]>;
+
 	/** Get applications that can open the given file paths (intersection for multiple files) */
 	getAppsForPaths?(paths: string[]): Promise<OpenWithApp[]>;
 
+	/** Count the applications that can open a file, stopping at `cap` if given, without listing them */
+	countAppsForPath?(path: string, cap?: number): Promise<number>;
+
+	/** Get one page of the applications for a file, in the same order as the full list */
+	getAppsForPathPaged?(path: string, offset: number, limit: number): Promise<AppsPage>;
+
+	/** Get every application registered for a file, including non-recommended ones */
+	getAllAppsForPath?(path: string): Promise<OpenWithApp[]>;
+
+	/** What every registered file type opens with, for bulk settings views */
+	listFileAssociations?(): Promise<FileAssociation[]>;
+
+	/** Make an app the default for an extension; Windows hands the final step to the user in Settings */
+	setDefaultAppForExtension?(extension: string, appId: string): Promise<OpenResult>;
+
+	/** Check whether any application could open a file, without launching one */
+	canOpenPath?(path: string): Promise<boolean>;
+
 	/** Open file with system default application */
 	openPathDefault?(path: string): Promise<OpenResult>;
 
+	/** Open file with its default app, asking the user for one if its type has none */
+	openPathDefaultOrPrompt?(path: string): Promise<OpenResult>;
+
+	/** Which optional operations the platform performs, for leaving out actions that won't work */
+	getOpenerCapabilities?(): Promise<OpenerCapabilities>;
+
+	/** Check whether opening a file makes the system show a security warning first */
+	isPathRisky?(path: string): Promise<boolean>;
+
+	/** Open file with its default app through a read-only copy, so the app can't change the original */
+	openPathReadOnly?(path: string): Promise<OpenResult>;
+
+	/** Print file through its type's registered print handler */
+	printPath?(path: string): Promise<OpenResult>;
+
+	/** Check whether a file's type can be printed, without printing it */
+	canPrintPath?(path: string): Promise<boolean>;
+
+	/** Drop cached associations after the app itself installed an app or changed a default */
+	refreshFileAssociations?(): Promise<void>;
+
+	/** Open file for editing through its type's registered edit handler */
+	editPath?(path: string): Promise<OpenResult>;
+
+	/** Check whether a file's type has an edit handler, so an "Edit" action can be shown */
+	canEditPath?(path: string): Promise<boolean>;
+
+	/** Open file through a named shell verb such as "edit" or "play" */
+	openPathWithVerb?(path: string, verb: string): Promise<OpenResult>;
+
+	/** List the shell verbs a file's type defines */
+	listVerbsForPath?(path: string): Promise<string[]>;
+
+	/** Resolve a Windows `.lnk` shortcut to its target, or null if it has none */
+	resolveShortcut?(path: string): Promise<string | null>;
+
+	/** Show the system properties dialog for a file or directory */
+	showPathProperties?(path: string): Promise<OpenResult>;
+
+	/** Start a new message in the default mail client with the files attached; `no_mail_client` when there's none to take it */
+	openMailWithPaths?(paths: string[], subject?: string, body?: string): Promise<OpenResult>;
+
+	/** Get the system thumbnail for a file as PNG bytes fitting within `size` pixels, or null if it has none */
+	getPathThumbnail?(path: string, size: number): Promise<Uint8Array | null>;
+
+	/** Apps files with an extension were most recently opened with, newest first */
+	getRecentAppsForExtension?(extension: string, limit: number): Promise<OpenWithApp[]>;
+
+	/** The Open With history, for saving across restarts */
+	getOpenHistory?(): Promise<OpenHistoryEntry[]>;
+
+	/** Add a saved Open With history back */
+	restoreOpenHistory?(entries: OpenHistoryEntry[]): Promise<void>;
+
+	/** Open files with an extension in an app of the user's choosing, leaving the OS default alone */
+	addDefaultAppOverride?(extension: string, appId: string): Promise<void>;
+
+	/** Return files with an extension to the OS default */
+	clearDefaultAppOverride?(extension: string): Promise<void>;
+
+	/** Let the user pick an app from the system's Open With dialog */
+	openPathWithDialog?(path: string): Promise<OpenResult>;
+
 	/** Open file with specific application */
 	openPathWithApp?(path: string, appId: string): Promise<OpenResult>;
 
+	/** Open file with an app and make it the default for the file's type, as "Always use this app" does */
+	openPathWithAppAsDefault?(path: string, appId: string): Promise<DefaultedOpen>;
+
+	/** Open a file with a specific application, in a new instance of it where the platform can start one; apps may still reuse a running one */
+	launchPath?(path: string, appId: string, newInstance: boolean): Promise<OpenResult>;
+
+	/** Open a file with an app at a line, page or fragment, e.g. to jump to a search match; apps that can't take one open the file as usual */
+	openPathAt?(target: OpenTarget, appId: string): Promise<OpenResult>;
+
+	/** Open a file and resolve once the process that was started exits, with its exit code */
+	openPathAndWait?(path: string, appId?: string): Promise<ProcessOutcome>;
+
+	/** Start an application on its own, without a file */
+	launchApp?(appId: string): Promise<OpenResult>;
+
+	/** Open a shell item without a filesystem path, e.g. a file inside a ZIP folder, by its Windows parsing name */
+	openShellItem?(parsingName: string): Promise<OpenResult>;
+
 	/** Open multiple files with specific application */
 	openPathsWithApp?(paths: string[], appId: string): Promise<OpenResult[]>;
 
+	/** Open multiple files with specific application in one launch, with one result for them all */
+	openPathsTogetherWithApp?(paths: string[], appId: string): Promise<OpenResult>;
+
 	/** Get the physical path to a sidecar file */
 	getSidecarPath?(
 		libraryId: string,
@@ -70,6 +177,9 @@ export type Platform = {
 	/** Set the current library ID in platform state and sync to all windows (Tauri only) */
 	setCurrentLibraryId?(libraryId: string): Promise<void>;
 
+	/** Listen for system file association changes, e.g. an app being installed (Tauri only) */
+	onFileAssociationsChanged?(callback: () => void): Promise<() => void>;
+
 	/** Listen for library ID changes across all windows (Tauri only) */
 	onLibraryIdChanged?(callback: (libraryId: string) => void): Promise<() => void>;
 
@@ -170,21 +280,125 @@ export type Platform = {
 
 /** Application that can open a file */
 export interface OpenWithApp {
-	/** Platform-specific identifier (bundle ID on macOS, app name on Windows, desktop entry on Linux) */
+	/** Stable platform-specific identifier, safe to save and the same for every file type the app opens (bundle ID on macOS, executable hash or AUMID on Windows, desktop entry on Linux) */
 	id: string;
 	/** Human-readable display name */
 	name: string;
-	/** Optional base64-encoded icon */
+	/** Full path to the app's binary, when the platform can resolve it */
+	exe_path?: string;
+	/** Whether the platform suggests this app for the file's type */
+	recommended: boolean;
+	/** Whether this is the app that opening the file by default would launch */
+	is_default: boolean;
+	/** How the app is installed; "packaged" apps are Windows UWP/MSIX apps */
+	kind?: "desktop" | "packaged" | "unknown";
+	/** Stable category id such as "browser" or "mail", for grouping menus */
+	category?: string;
+	/** Windows ProgID the app is registered under; accepted as an app id */
+	progid?: string;
+	/** Windows CLSID of the COM object behind the app's open verb */
+	clsid?: string;
+	/** Optional base64-encoded PNG icon */
 	icon?: string;
+	/** The icon at every size the platform extracted, smallest first */
+	icons?: { size: number; png: string }[];
+	/** The letter to underline as the app's mnemonic in a menu, when one was assigned */
+	accelerator?: string;
+}
+
+/** One app a file type was opened with, as saved by the Open With history */
+export interface OpenTarget {
+	path: string;
+	/** 1-based, for editors */
+	line?: number;
+	/** 1-based, for document viewers */
+	page?: number;
+	/** For browsers, without the "#" */
+	fragment?: string;
+}
+
+export interface OpenHistoryEntry {
+	/** Lowercased, without the leading dot */
+	extension: string;
+	app_id: string;
+	/** Milliseconds since the Unix epoch */
+	used_at: number;
+}
+
+/** One page of the applications for a file */
+export interface AppsPage {
+	apps: OpenWithApp[];
+	/** How many applications all the pages hold together */
+	total: number;
+}
+
+/** How opening a file with an app and making it the default went, each on its own */
+export interface DefaultedOpen {
+	result: OpenResult;
+	/** Null when the file didn't open, so the default was left alone */
+	default: OpenResult | null;
+}
+
+/** How a launch that was waited on ended */
+export interface ProcessOutcome {
+	result: OpenResult;
+	/** Null when no process of its own was started, e.g. the file went to an app already running */
+	exit_code: number | null;
+}
+
+/** Which optional operations the platform's opener performs */
+export interface OpenerCapabilities {
+	reveal: boolean;
+	elevated: boolean;
+	/** Whether verbs other than "open" are understood */
+	verbs: boolean;
+	dialog: boolean;
+	executable: boolean;
+	launch: boolean;
+	wait: boolean;
+	shell_items: boolean;
+	print: boolean;
+	edit: boolean;
+	properties: boolean;
+	/** Whether `OpenWithApp.icon` is filled in */
+	icons: boolean;
+	thumbnails: boolean;
+	set_default: boolean;
+	uri: boolean;
+	trash: boolean;
+	mail: boolean;
+}
+
+/** What one file type opens with */
+export interface FileAssociation {
+	/** The extension in ".ext" form, lowercased */
+	extension: string;
+	default_app: OpenWithApp | null;
+	all_apps: OpenWithApp[];
 }
 
 /** Result of opening a file */
 export type OpenResult =
-	| { status: "success" }
+	/** `pid` is the process started for the file, when there was a new one and the platform reports it */
+	| { status: "success"; pid: number | null }
 	| { status: "file_not_found"; path: string }
 	| { status: "app_not_found"; app_id: string }
+	| { status: "app_unavailable"; app_id: string; exe_path: string }
+	| { status: "no_apps_for_type"; extension: string }
 	| { status: "permission_denied"; path: string }
-	| { status: "platform_error"; message: string };
+	| { status: "locked"; by: string | null }
+	| { status: "unreachable"; path: string }
+	| { status: "requires_elevation"; path: string }
+	| { status: "no_handler"; verb: string }
+	| { status: "no_mail_client" }
+	| { status: "unsupported"; operation: string }
+	| { status: "platform_error"; message: string; code: number }
+	| { status: "cancelled" }
+	| { status: "timed_out" }
+	| { status: "requires_user_action" }
+	| { status: "opened_after_prompt" }
+	| { status: "opened_with_override"; app_id: string }
+	| { status: "opened_read_only"; enforced: boolean };
 
 /** Menu item state for native menus */
 export interface MenuItemState {